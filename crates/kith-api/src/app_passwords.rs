@@ -0,0 +1,107 @@
+//! Handlers for `/app-passwords` endpoints.
+//!
+//! | Method | Path | Notes |
+//! |--------|------|-------|
+//! | `GET`  | `/app-passwords` | Revoked passwords included |
+//! | `POST` | `/app-passwords` | Body: `{"label":"phone"}`; returns 201 + [`CreatedAppPassword`] |
+//! | `POST` | `/app-passwords/:id/revoke` | 404 if not found |
+
+use std::sync::Arc;
+
+use axum::{
+  Json,
+  extract::{Path, State},
+  http::StatusCode,
+  response::IntoResponse,
+};
+use kith_core::{
+  app_password::{AppPassword, NewAppPassword, generate_secret, hash_secret},
+  store::ContactStore,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+// ─── List ─────────────────────────────────────────────────────────────────────
+
+/// `GET /app-passwords`
+pub async fn list<S>(
+  State(store): State<Arc<S>>,
+) -> Result<Json<Vec<AppPassword>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let passwords = store
+    .list_app_passwords()
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(passwords))
+}
+
+// ─── Create ───────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct CreateBody {
+  pub label: String,
+}
+
+/// A new app password together with its secret. This is the only time the
+/// secret is returned.
+#[derive(Debug, Serialize)]
+pub struct CreatedAppPassword {
+  #[serde(flatten)]
+  pub app_password: AppPassword,
+  pub secret:       String,
+}
+
+/// `POST /app-passwords` — body: `{"label":"phone"}`
+pub async fn create<S>(
+  State(store): State<Arc<S>>,
+  Json(body): Json<CreateBody>,
+) -> Result<impl IntoResponse, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let label = body.label.trim();
+  if label.is_empty() {
+    return Err(ApiError::BadRequest("label must not be empty".into()));
+  }
+
+  let secret = generate_secret();
+  let app_password = store
+    .create_app_password(NewAppPassword {
+      label:       label.to_string(),
+      secret_hash: hash_secret(&secret),
+    })
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok((
+    StatusCode::CREATED,
+    Json(CreatedAppPassword {
+      app_password,
+      secret,
+    }),
+  ))
+}
+
+// ─── Revoke ───────────────────────────────────────────────────────────────────
+
+/// `POST /app-passwords/:id/revoke`
+pub async fn revoke<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+) -> Result<Json<AppPassword>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  store
+    .revoke_app_password(id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?
+    .map(Json)
+    .ok_or_else(|| ApiError::NotFound(format!("app password {id} not found")))
+}
@@ -0,0 +1,47 @@
+//! Handler for `GET /duplicates`: pairs of people who may be the same
+//! person (see [`kith_core::duplicates`]).
+
+use std::sync::Arc;
+
+use axum::{
+  Json,
+  extract::{Query, State},
+};
+use kith_core::{
+  duplicates::{self, DEFAULT_MIN_SCORE, DuplicateCandidate},
+  store::ContactStore,
+};
+use serde::Deserialize;
+
+use crate::error::ApiError;
+
+#[derive(Debug, Deserialize, Default)]
+pub struct DuplicateParams {
+  /// Lowest score reported, in `0.0..=1.0`.
+  pub min_score: Option<f64>,
+  pub limit:     Option<usize>,
+}
+
+/// `GET /duplicates[?min_score=...][&limit=...]` — highest score first.
+pub async fn list<S>(
+  State(store): State<Arc<S>>,
+  Query(params): Query<DuplicateParams>,
+) -> Result<Json<Vec<DuplicateCandidate>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let min_score = params.min_score.unwrap_or(DEFAULT_MIN_SCORE);
+  if !(0.0..=1.0).contains(&min_score) {
+    return Err(ApiError::BadRequest(
+      "min_score must be between 0 and 1".into(),
+    ));
+  }
+  let mut found = duplicates::find(store.as_ref(), min_score)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  if let Some(limit) = params.limit {
+    found.truncate(limit);
+  }
+  Ok(Json(found))
+}
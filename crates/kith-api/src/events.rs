@@ -0,0 +1,115 @@
+//! Handler for `GET /events`: a server-sent event stream of every write to
+//! the fact log.
+//!
+//! Each event is named after its [`Change`](kith_core::event::Change)
+//! (`fact-recorded`, `fact-superseded`, `fact-retracted` or
+//! `fact-reinstated`), carries its [`EventId`] as the SSE `id`, and a
+//! [`StoreEvent`] as JSON `data`. A client that reconnects with
+//! `Last-Event-ID` first gets every event it missed; without one the stream
+//! starts at the present.
+
+use std::{collections::VecDeque, sync::Arc};
+
+use axum::{
+  extract::State,
+  http::HeaderMap,
+  response::sse::{Event, KeepAlive, Sse},
+};
+use futures_util::{Stream, stream};
+use kith_core::{
+  event::{EventId, StoreEvent},
+  store::ContactStore,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::error::ApiError;
+
+/// How many missed events are read from the store at a time.
+const PAGE: usize = 256;
+
+/// `GET /events` — honours the `Last-Event-ID` header.
+pub async fn stream<S>(
+  State(store): State<Arc<S>>,
+  headers: HeaderMap,
+) -> Result<Sse<impl Stream<Item = Result<Event, axum::Error>>>, ApiError>
+where
+  S: ContactStore + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  // Subscribe before reading the position, so nothing written in between
+  // is lost.
+  let rx = store.subscribe();
+  let last_event_id = headers
+    .get("last-event-id")
+    .map(|v| v.to_str().unwrap_or_default());
+  let last = match last_event_id {
+    Some(id) => id
+      .parse()
+      .map_err(|e: kith_core::Error| ApiError::BadRequest(e.to_string()))?,
+    None => store
+      .sync_token()
+      .await
+      .map_err(|e| ApiError::Store(Box::new(e)))?
+      .into(),
+  };
+
+  let listener = Listener {
+    store,
+    rx,
+    last,
+    pending: VecDeque::new(),
+    caught_up: last_event_id.is_none(),
+  };
+  let events = stream::unfold(listener, |mut listener| async move {
+    let event = listener.next().await?;
+    let sse = Event::default()
+      .id(event.id.to_string())
+      .event(event.change.name())
+      .json_data(&event);
+    Some((sse, listener))
+  });
+  Ok(Sse::new(events).keep_alive(KeepAlive::default()))
+}
+
+/// One client's position in the event stream.
+struct Listener<S> {
+  store:     Arc<S>,
+  rx:        broadcast::Receiver<StoreEvent>,
+  /// The last event delivered.
+  last:      EventId,
+  pending:   VecDeque<StoreEvent>,
+  /// Whether every event up to the live ones in `rx` has been read from the
+  /// store.
+  caught_up: bool,
+}
+
+impl<S> Listener<S>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  /// The next event, or `None` once the stream should end.
+  async fn next(&mut self) -> Option<StoreEvent> {
+    loop {
+      if let Some(event) = self.pending.pop_front() {
+        self.last = event.id;
+        return Some(event);
+      }
+      if !self.caught_up {
+        // A failing store ends the stream; the client resumes from the last
+        // id it got.
+        let missed = self.store.events_since(self.last, PAGE).await.ok()?;
+        self.caught_up = missed.len() < PAGE;
+        self.pending.extend(missed);
+        continue;
+      }
+      match self.rx.recv().await {
+        // Already read from the store while catching up.
+        Ok(event) if event.id.is_at_or_before(&self.last) => {}
+        Ok(event) => self.pending.push_back(event),
+        Err(RecvError::Lagged(_)) => self.caught_up = false,
+        Err(RecvError::Closed) => return None,
+      }
+    }
+  }
+}
@@ -2,11 +2,13 @@
 //!
 //! | Method | Path | Notes |
 //! |--------|------|-------|
-//! | `GET`  | `/facts` | `?subject_id` required; optional `fact_type`, `as_of`, `include_inactive` |
+//! | `GET`  | `/facts` | `?subject_id` required; optional `fact_type`, `as_of`, `valid_at`, `include_inactive` |
 //! | `GET`  | `/facts/:id` | Single resolved fact |
+//! | `GET`  | `/facts/:id/lineage` | Every version of the fact, oldest first |
 //! | `POST` | `/facts` | Body: [`NewFactBody`]; returns 201 + stored fact |
 //! | `POST` | `/facts/:id/supersede` | Body: [`NewFactBody`]; returns new resolved fact |
 //! | `POST` | `/facts/:id/retract` | Body: `{"reason":"..."}` |
+//! | `POST` | `/facts/:id/reinstate` | Makes a superseded or retracted fact active again |
 
 use std::sync::Arc;
 
@@ -19,7 +21,7 @@ use axum::{
 use chrono::{DateTime, Utc};
 use kith_core::{
   fact::{Confidence, EffectiveDate, FactValue, NewFact, RecordingContext},
-  lifecycle::{FactStatus, ResolvedFact, Retraction},
+  lifecycle::{FactStatus, Reinstatement, ResolvedFact, Retraction},
   store::ContactStore,
 };
 use serde::Deserialize;
@@ -35,14 +37,18 @@ pub struct ListParams {
   pub subject_id:       Uuid,
   /// If set, restrict to facts with this type discriminant (e.g. `"email"`).
   pub fact_type:        Option<String>,
-  /// Point-in-time filter on `recorded_at`. Defaults to now.
+  /// Transaction-time filter: what the store knew at this instant. Defaults
+  /// to now.
   pub as_of:            Option<DateTime<Utc>>,
+  /// Valid-time filter: only facts whose effective range covers this
+  /// instant.
+  pub valid_at:         Option<DateTime<Utc>>,
   /// If `true`, also return superseded and retracted facts. Default `false`.
   #[serde(default)]
   pub include_inactive: bool,
 }
 
-/// `GET /facts?subject_id=<id>[&fact_type=...][&as_of=...][&include_inactive=true]`
+/// `GET /facts?subject_id=<id>[&fact_type=...][&as_of=...][&valid_at=...][&include_inactive=true]`
 pub async fn list<S>(
   State(store): State<Arc<S>>,
   Query(params): Query<ListParams>,
@@ -52,7 +58,12 @@ where
   S::Error: std::error::Error + Send + Sync + 'static,
 {
   let mut facts = store
-    .get_facts(params.subject_id, params.as_of, params.include_inactive)
+    .get_facts(
+      params.subject_id,
+      params.as_of,
+      params.valid_at,
+      params.include_inactive,
+    )
     .await
     .map_err(|e| ApiError::Store(Box::new(e)))?;
 
@@ -82,6 +93,28 @@ where
   Ok(Json(fact))
 }
 
+// ─── Lineage ──────────────────────────────────────────────────────────────────
+
+/// `GET /facts/:id/lineage` — the chain of versions `id` belongs to, from the
+/// original through to the version currently in force.
+pub async fn lineage<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+) -> Result<Json<Vec<ResolvedFact>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let lineage = store
+    .fact_lineage(id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  if lineage.is_empty() {
+    return Err(ApiError::NotFound(format!("fact {id} not found")));
+  }
+  Ok(Json(lineage))
+}
+
 // ─── Create ───────────────────────────────────────────────────────────────────
 
 /// JSON body accepted by `POST /facts` and `POST /facts/:id/supersede`.
@@ -178,3 +211,21 @@ where
     .map_err(|e| ApiError::Store(Box::new(e)))?;
   Ok(Json(retraction))
 }
+
+// ─── Reinstate ────────────────────────────────────────────────────────────────
+
+/// `POST /facts/:id/reinstate` — undo the fact's supersession or retraction.
+pub async fn reinstate_one<S>(
+  State(store): State<Arc<S>>,
+  Path(fact_id): Path<Uuid>,
+) -> Result<Json<Reinstatement>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let reinstatement = store
+    .reinstate(fact_id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(reinstatement))
+}
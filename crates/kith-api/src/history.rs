@@ -0,0 +1,86 @@
+//! Handlers for exporting and importing fact history as JSON Lines (see
+//! [`kith_core::history`]).
+//!
+//! | Method | Path | Notes |
+//! |--------|------|-------|
+//! | `GET`  | `/export` | The whole store |
+//! | `GET`  | `/subjects/:id/export` | One subject; 404 if not found |
+//! | `POST` | `/import` | Body: a history document; returns an `ImportReport` |
+
+use std::sync::Arc;
+
+use axum::{
+  Json,
+  extract::{Path, State},
+  http::header,
+  response::{IntoResponse, Response},
+};
+use chrono::Utc;
+use kith_core::{
+  history::{self, CONTENT_TYPE, History, ImportError, ImportReport},
+  store::ContactStore,
+};
+use uuid::Uuid;
+
+use crate::error::ApiError;
+
+// ─── Export ─────────────────────────────────────────────────────────────────
+
+/// `GET /export`
+pub async fn export_all<S>(
+  State(store): State<Arc<S>>,
+) -> Result<Response, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let history = store
+    .history(None)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  document(&history)
+}
+
+/// `GET /subjects/:id/export`
+pub async fn export_one<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+) -> Result<Response, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let history = store
+    .history(Some(id))
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  if history.subjects.is_empty() {
+    return Err(ApiError::NotFound(format!("subject {id}")));
+  }
+  document(&history)
+}
+
+fn document(history: &History) -> Result<Response, ApiError> {
+  let body = history
+    .to_jsonl(Utc::now())
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(([(header::CONTENT_TYPE, CONTENT_TYPE)], body).into_response())
+}
+
+// ─── Import ─────────────────────────────────────────────────────────────────
+
+/// `POST /import` — nothing is written unless the whole document is valid.
+pub async fn import<S>(
+  State(store): State<Arc<S>>,
+  body: String,
+) -> Result<Json<ImportReport>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  match history::import(store.as_ref(), &body).await {
+    Ok(report) => Ok(Json(report)),
+    Err(ImportError::Store(e)) => Err(ApiError::Store(Box::new(e))),
+    Err(e) => Err(ApiError::BadRequest(e.to_string())),
+  }
+}
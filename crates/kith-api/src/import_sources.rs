@@ -0,0 +1,90 @@
+//! Handlers for `/import-sources` endpoints: the sources imported facts came
+//! from, and undoing an import.
+//!
+//! | Method | Path | Notes |
+//! |--------|------|-------|
+//! | `GET`  | `/import-sources` | Every source with its fact counts |
+//! | `GET`  | `/import-sources/facts` | `?source_name` required; the active facts an undo would retract |
+//! | `POST` | `/import-sources/retract` | Body: [`RetractImportBody`]; empty list if nothing is active |
+
+use std::sync::Arc;
+
+use axum::{
+  Json,
+  extract::{Query, State},
+};
+use kith_core::{
+  lifecycle::{ResolvedFact, Retraction},
+  store::{ContactStore, ImportSource},
+};
+use serde::Deserialize;
+
+use crate::error::ApiError;
+
+// ─── List ─────────────────────────────────────────────────────────────────────
+
+/// `GET /import-sources` — ordered by name.
+pub async fn list<S>(
+  State(store): State<Arc<S>>,
+) -> Result<Json<Vec<ImportSource>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let sources = store
+    .import_sources()
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(sources))
+}
+
+// ─── Preview ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct FactsParams {
+  pub source_name: String,
+}
+
+/// `GET /import-sources/facts?source_name=...` — the still-active facts from
+/// the source, in the order they were recorded.
+pub async fn facts<S>(
+  State(store): State<Arc<S>>,
+  Query(params): Query<FactsParams>,
+) -> Result<Json<Vec<ResolvedFact>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let facts = store
+    .import_facts(&params.source_name)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(facts))
+}
+
+// ─── Retract ──────────────────────────────────────────────────────────────────
+
+/// JSON body accepted by `POST /import-sources/retract`.
+#[derive(Debug, Deserialize)]
+pub struct RetractImportBody {
+  pub source_name: String,
+  /// Recorded on every retraction.
+  pub reason:      Option<String>,
+}
+
+/// `POST /import-sources/retract` — undo an import by retracting every
+/// still-active fact from the source at once.
+pub async fn retract<S>(
+  State(store): State<Arc<S>>,
+  Json(body): Json<RetractImportBody>,
+) -> Result<Json<Vec<Retraction>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let retractions = store
+    .retract_import(body.source_name, body.reason)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(retractions))
+}
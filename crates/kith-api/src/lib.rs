@@ -9,8 +9,13 @@
 //! .nest("/api", kith_api::api_router(store.clone()))
 //! ```
 
+pub mod app_passwords;
+pub mod duplicates;
 pub mod error;
+pub mod events;
 pub mod facts;
+pub mod history;
+pub mod import_sources;
 pub mod search;
 pub mod subjects;
 
@@ -37,12 +42,38 @@ where
     // Subjects
     .route("/subjects", get(subjects::list::<S>).post(subjects::create::<S>))
     .route("/subjects/{id}", get(subjects::get_one::<S>))
+    .route("/subjects/{id}/export", get(history::export_one::<S>))
+    .route("/subjects/{id}/merge", post(subjects::merge::<S>))
+    .route("/subjects/{id}/unmerge", post(subjects::unmerge::<S>))
+    .route("/subjects/{id}/reinstate", post(subjects::reinstate::<S>))
     // Facts
     .route("/facts", get(facts::list::<S>).post(facts::create::<S>))
     .route("/facts/{id}", get(facts::get_one::<S>))
+    .route("/facts/{id}/lineage", get(facts::lineage::<S>))
     .route("/facts/{id}/supersede", post(facts::supersede_one::<S>))
     .route("/facts/{id}/retract", post(facts::retract_one::<S>))
+    .route("/facts/{id}/reinstate", post(facts::reinstate_one::<S>))
     // Search
     .route("/search", get(search::handler::<S>))
+    // Duplicates
+    .route("/duplicates", get(duplicates::list::<S>))
+    // History
+    .route("/export", get(history::export_all::<S>))
+    .route("/import", post(history::import::<S>))
+    // Import sources
+    .route("/import-sources", get(import_sources::list::<S>))
+    .route("/import-sources/facts", get(import_sources::facts::<S>))
+    .route(
+      "/import-sources/retract",
+      post(import_sources::retract::<S>),
+    )
+    // Change events
+    .route("/events", get(events::stream::<S>))
+    // App passwords
+    .route(
+      "/app-passwords",
+      get(app_passwords::list::<S>).post(app_passwords::create::<S>),
+    )
+    .route("/app-passwords/{id}/revoke", post(app_passwords::revoke::<S>))
     .with_state(store)
 }
@@ -21,7 +21,7 @@ use crate::error::ApiError;
 
 #[derive(Debug, Deserialize, Default)]
 pub struct SearchParams {
-  /// Free-text filter applied over serialised fact values.
+  /// Full-text filter; supports `prefix*` terms and `"quoted phrases"`.
   pub text:            Option<String>,
   /// Restrict to subjects of a specific kind.
   pub kind:            Option<SubjectKind>,
@@ -32,11 +32,14 @@ pub struct SearchParams {
   pub confidence:      Option<Confidence>,
   pub recorded_after:  Option<DateTime<Utc>>,
   pub recorded_before: Option<DateTime<Utc>>,
+  /// Ignore superseded and retracted facts.
+  #[serde(default)]
+  pub active_only:     bool,
   pub limit:           Option<usize>,
   pub offset:          Option<usize>,
 }
 
-/// `GET /search[?text=...][&kind=...][&fact_types=...][&tags=...][&limit=...][&offset=...]`
+/// `GET /search[?text=...][&kind=...][&fact_types=...][&tags=...][&active_only=...][&limit=...][&offset=...]`
 pub async fn handler<S>(
   State(store): State<Arc<S>>,
   Query(params): Query<SearchParams>,
@@ -59,6 +62,7 @@ where
     confidence:      params.confidence,
     recorded_after:  params.recorded_after,
     recorded_before: params.recorded_before,
+    active_only:     params.active_only,
     limit:           params.limit,
     offset:          params.offset,
   };
@@ -5,6 +5,9 @@
 //! | `GET`  | `/subjects` | Optional `?kind=person\|organization\|group` |
 //! | `POST` | `/subjects` | Body: `{"kind":"person"}` |
 //! | `GET`  | `/subjects/:id` | 404 if not found |
+//! | `POST` | `/subjects/:id/merge` | Body: `{"absorbed_id":"..."}` |
+//! | `POST` | `/subjects/:id/unmerge` | Body: `{"absorbed_id":"..."}` |
+//! | `POST` | `/subjects/:id/reinstate` | Restores a deleted contact; 400 if nothing is retracted |
 
 use std::sync::Arc;
 
@@ -15,7 +18,8 @@ use axum::{
   response::IntoResponse,
 };
 use kith_core::{
-  store::ContactStore,
+  lifecycle::{FactStatus, Reinstatement, SubjectMerge},
+  store::{Changeset, ContactStore},
   subject::{Subject, SubjectKind},
 };
 use serde::Deserialize;
@@ -87,3 +91,116 @@ where
     .ok_or_else(|| ApiError::NotFound(format!("subject {id} not found")))?;
   Ok(Json(subject))
 }
+
+// ─── Merge ────────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Deserialize)]
+pub struct MergeBody {
+  pub absorbed_id: Uuid,
+}
+
+/// `POST /subjects/:id/merge` — merge `absorbed_id` into subject `id`.
+pub async fn merge<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+  Json(body): Json<MergeBody>,
+) -> Result<Json<SubjectMerge>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let merge = store
+    .merge_subjects(id, body.absorbed_id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(merge))
+}
+
+/// `POST /subjects/:id/unmerge` — split `absorbed_id` back out of subject
+/// `id`.
+pub async fn unmerge<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+  Json(body): Json<MergeBody>,
+) -> Result<Json<SubjectMerge>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let merge = store
+    .unmerge_subjects(id, body.absorbed_id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  Ok(Json(merge))
+}
+
+// ─── Reinstate ────────────────────────────────────────────────────────────────
+
+/// `POST /subjects/:id/reinstate` — restore a deleted contact.
+///
+/// Reinstates every fact ended by the subject's latest retraction (a CardDAV
+/// DELETE retracts them all at once) and, in the same changeset, puts the
+/// subject back in the default address book if it has been left in none.
+pub async fn reinstate<S>(
+  State(store): State<Arc<S>>,
+  Path(id): Path<Uuid>,
+) -> Result<Json<Vec<Reinstatement>>, ApiError>
+where
+  S: ContactStore,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  store
+    .get_subject(id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?
+    .ok_or_else(|| ApiError::NotFound(format!("subject {id} not found")))?;
+
+  let retracted: Vec<_> = store
+    .get_facts(id, None, None, true)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?
+    .into_iter()
+    .filter_map(|rf| match rf.status {
+      FactStatus::Retracted { at, .. } => Some((rf.fact.fact_id, at)),
+      _ => None,
+    })
+    .collect();
+  let Some(latest) = retracted.iter().map(|(_, at)| *at).max() else {
+    return Err(ApiError::BadRequest(format!(
+      "subject {id} has no retracted facts"
+    )));
+  };
+
+  // A contact in no book is invisible over CardDAV.
+  let books = store
+    .subject_addressbooks(id)
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+  let addressbook_adds = if books.is_empty() {
+    store
+      .list_addressbooks()
+      .await
+      .map_err(|e| ApiError::Store(Box::new(e)))?
+      .into_iter()
+      .filter(|b| b.is_default)
+      .map(|b| (b.addressbook_id, id))
+      .collect()
+  } else {
+    Vec::new()
+  };
+
+  let applied = store
+    .apply_changeset(Changeset {
+      addressbook_adds,
+      reinstatements: retracted
+        .into_iter()
+        .filter(|(_, at)| *at == latest)
+        .map(|(fact_id, _)| fact_id)
+        .collect(),
+      ..Default::default()
+    })
+    .await
+    .map_err(|e| ApiError::Store(Box::new(e)))?;
+
+  Ok(Json(applied.reinstatements))
+}
@@ -1,37 +1,137 @@
-//! HTTP Basic-auth extractor and standalone verifier.
+//! Request authentication: HTTP Basic and bearer tokens.
+//!
+//! Basic auth takes either the account password or one of the account's app
+//! passwords (see [`kith_core::app_password`]). An app password alone, sent
+//! as `Authorization: Bearer <secret>`, also authenticates: it is looked up
+//! in each account's store in turn.
+//!
+//! Every check goes through the server's [`AuthGuard`]: locked-out clients
+//! and usernames get a 429, failures count towards a lockout, and verified
+//! account passwords are cached. Only app passwords get through a username
+//! lockout.
+
+use std::net::{IpAddr, SocketAddr};
 
 use argon2::{Argon2, PasswordHash, PasswordVerifier};
 use axum::{
-  extract::FromRequestParts,
-  http::{HeaderMap, request::Parts},
+  extract::{ConnectInfo, FromRequestParts},
+  http::{Extensions, HeaderMap, request::Parts},
 };
 use base64::{Engine as _, engine::general_purpose::STANDARD as B64};
-use kith_core::store::ContactStore;
+use kith_core::{
+  app_password::{AppPassword, SECRET_PREFIX, hash_secret},
+  store::ContactStore,
+};
+
+use crate::{
+  AppState, Users,
+  error::Error,
+  throttle::{AuthGuard, AuthLimits},
+};
 
-use crate::{AppState, error::Error};
+/// The account a request authenticated as.
+pub struct Authenticated<S: ContactStore>(pub AppState<S>);
+
+/// The address of the client that sent a request, if known.
+pub struct ClientIp(pub Option<IpAddr>);
+
+impl<S> FromRequestParts<Users<S>> for ClientIp
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  type Rejection = std::convert::Infallible;
 
-/// Credentials accepted as valid for this server instance.
-#[derive(Clone)]
-pub struct AuthConfig {
-  pub username:      String,
-  /// PHC string produced by argon2, e.g. `$argon2id$v=19$…`
-  pub password_hash: String,
+  async fn from_request_parts(
+    parts: &mut Parts,
+    users: &Users<S>,
+  ) -> Result<Self, Self::Rejection> {
+    Ok(ClientIp(client_ip(
+      &parts.headers,
+      &parts.extensions,
+      users.guard().limits(),
+    )))
+  }
 }
 
-/// Zero-size marker: present in the handler means the request was
-/// authenticated.
-pub struct Authenticated;
+/// The client's address: from [`AuthLimits::client_ip_header`] if set and
+/// present, otherwise from the connection. Proxies append the address they
+/// saw to `X-Forwarded-For` style lists, so only the last entry is trusted;
+/// the ones before it are whatever the client sent.
+pub fn client_ip(
+  headers: &HeaderMap,
+  extensions: &Extensions,
+  limits: &AuthLimits,
+) -> Option<IpAddr> {
+  limits
+    .client_ip_header
+    .as_ref()
+    .and_then(|name| headers.get_all(name.as_str()).iter().next_back())
+    .and_then(|v| v.to_str().ok())
+    .and_then(|v| v.rsplit(',').next())
+    .and_then(|v| v.trim().parse().ok())
+    .or_else(|| {
+      extensions
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip())
+    })
+}
+
+/// A successful login.
+pub struct Login<'a, S: ContactStore> {
+  pub state:        &'a AppState<S>,
+  /// The app password used, or `None` for the account password.
+  pub app_password: Option<AppPassword>,
+}
 
-/// Verify credentials directly from headers — used by manual dispatch handlers.
-pub fn verify_auth(
+/// Verify credentials directly from headers — used by manual dispatch
+/// handlers. Returns the state of the account they belong to.
+pub async fn verify_auth<'a, S>(
   headers: &HeaderMap,
-  config: &AuthConfig,
-) -> Result<(), Error> {
+  ip: Option<IpAddr>,
+  users: &'a Users<S>,
+) -> Result<&'a AppState<S>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  Ok(login(headers, ip, users).await?.state)
+}
+
+/// Like [`verify_auth`], but also says which credential was used.
+pub async fn login<'a, S>(
+  headers: &HeaderMap,
+  ip: Option<IpAddr>,
+  users: &'a Users<S>,
+) -> Result<Login<'a, S>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let guard = users.guard();
+  if let Some(wait) = guard.ip_lockout(ip) {
+    return Err(Error::TooManyRequests(wait));
+  }
+
   let header_val = headers
     .get(axum::http::header::AUTHORIZATION)
     .and_then(|v| v.to_str().ok())
     .ok_or(Error::Unauthorized)?;
 
+  if let Some(token) = header_val.strip_prefix("Bearer ") {
+    let token = token.trim();
+    for state in users.states() {
+      if let Some(app_password) = use_app_password(state, token).await? {
+        guard.record_success(ip, &state.user.username);
+        return Ok(Login {
+          state,
+          app_password: Some(app_password),
+        });
+      }
+    }
+    return Err(failure(guard, ip, None));
+  }
+
   let encoded = header_val
     .strip_prefix("Basic ")
     .ok_or(Error::Unauthorized)?;
@@ -42,21 +142,84 @@ pub fn verify_auth(
   let (username, password) =
     creds.split_once(':').ok_or(Error::Unauthorized)?;
 
-  if username != config.username {
+  let Some(state) = users.get(username) else {
+    return Err(failure(guard, ip, None));
+  };
+
+  // App passwords are cheap to check; the argon2 verify below is not. They
+  // get through a username lockout: a lockout caused by someone else
+  // guessing must not stop the owner's devices.
+  if password.starts_with(SECRET_PREFIX)
+    && let Some(app_password) = use_app_password(state, password).await?
+  {
+    guard.record_success(ip, username);
+    return Ok(Login {
+      state,
+      app_password: Some(app_password),
+    });
+  }
+
+  if let Some(wait) = guard.user_lockout(username) {
+    return Err(Error::TooManyRequests(wait));
+  }
+  if guard.is_verified(username, password) {
+    return Ok(Login {
+      state,
+      app_password: None,
+    });
+  }
+
+  guard
+    .begin_attempt(ip, username)
+    .map_err(Error::TooManyRequests)?;
+  let verified =
+    PasswordHash::new(&state.user.password_hash).is_ok_and(|hash| {
+      Argon2::default()
+        .verify_password(password.as_bytes(), &hash)
+        .is_ok()
+    });
+  if !verified {
+    // Already counted by `begin_attempt`.
+    tracing::warn!(?ip, username, "authentication failed");
     return Err(Error::Unauthorized);
   }
 
-  let parsed_hash = PasswordHash::new(&config.password_hash)
-    .map_err(|_| Error::Unauthorized)?;
+  guard.remember(username, password);
+  guard.record_success(ip, username);
+  Ok(Login {
+    state,
+    app_password: None,
+  })
+}
 
-  Argon2::default()
-    .verify_password(password.as_bytes(), &parsed_hash)
-    .map_err(|_| Error::Unauthorized)?;
+/// Count a failed login and return the error for it.
+fn failure(
+  guard: &AuthGuard,
+  ip: Option<IpAddr>,
+  username: Option<&str>,
+) -> Error {
+  tracing::warn!(?ip, username, "authentication failed");
+  guard.record_failure(ip, username);
+  Error::Unauthorized
+}
 
-  Ok(())
+/// The active app password of `state`'s account whose secret is `secret`.
+async fn use_app_password<S>(
+  state: &AppState<S>,
+  secret: &str,
+) -> Result<Option<AppPassword>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  state
+    .store
+    .use_app_password(&hash_secret(secret))
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))
 }
 
-impl<S> FromRequestParts<AppState<S>> for Authenticated
+impl<S> FromRequestParts<Users<S>> for Authenticated<S>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
@@ -65,10 +228,12 @@ where
 
   async fn from_request_parts(
     parts: &mut Parts,
-    state: &AppState<S>,
+    users: &Users<S>,
   ) -> Result<Self, Self::Rejection> {
-    verify_auth(&parts.headers, &state.auth)?;
-    Ok(Authenticated)
+    let ip =
+      client_ip(&parts.headers, &parts.extensions, users.guard().limits());
+    let state = verify_auth(&parts.headers, ip, users).await?;
+    Ok(Authenticated(state.clone()))
   }
 }
 
@@ -78,14 +243,18 @@ mod tests {
   use kith_store_sqlite::SqliteStore;
 
   use super::*;
-  use crate::{AppState, test_helpers::make_state};
+  use crate::{
+    AppState,
+    test_helpers::{make_state, make_user_state},
+  };
 
   async fn extract(
     req: Request<axum::body::Body>,
     state: &AppState<SqliteStore>,
-  ) -> Result<Authenticated, Error> {
+  ) -> Result<Authenticated<SqliteStore>, Error> {
     let (mut parts, _) = req.into_parts();
-    Authenticated::from_request_parts(&mut parts, state).await
+    let users = Users::new([state.clone()]);
+    Authenticated::from_request_parts(&mut parts, &users).await
   }
 
   fn basic(user: &str, pass: &str) -> String {
@@ -138,4 +307,40 @@ mod tests {
       Err(Error::Unauthorized)
     ));
   }
+
+  #[tokio::test]
+  async fn unknown_user() {
+    let state = make_state("secret").await;
+    let req = Request::builder()
+      .header(header::AUTHORIZATION, basic("nobody", "secret"))
+      .body(axum::body::Body::empty())
+      .unwrap();
+    assert!(matches!(
+      extract(req, &state).await,
+      Err(Error::Unauthorized)
+    ));
+  }
+
+  #[tokio::test]
+  async fn credentials_select_their_account() {
+    let alice = make_user_state("alice", "a-secret").await;
+    let bob = make_user_state("bob", "b-secret").await;
+    let users = Users::new([alice, bob]);
+    let headers = |user: &str, pass: &str| {
+      let mut headers = HeaderMap::new();
+      headers.insert(header::AUTHORIZATION, basic(user, pass).parse().unwrap());
+      headers
+    };
+
+    let state = verify_auth(&headers("bob", "b-secret"), None, &users)
+      .await
+      .unwrap();
+    assert_eq!(state.user.username, "bob");
+    // One account's password does not open another.
+    assert!(
+      verify_auth(&headers("bob", "a-secret"), None, &users)
+        .await
+        .is_err()
+    );
+  }
 }
@@ -0,0 +1,321 @@
+//! Birthday and anniversary events, rendered as iCalendar (RFC 5545).
+//!
+//! Every active `birthday` or `anniversary` fact of a person becomes one
+//! all-day VEVENT recurring yearly from the recorded date. Nothing is stored:
+//! events are generated from the facts on every request, named and
+//! identified by the fact they come from. An event's ETag covers that fact
+//! and the name fact its summary uses, so it changes exactly when the
+//! rendered event would.
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, Utc};
+use kith_core::{fact::FactValue, lifecycle::ContactView};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// Name of the birthday calendar in each account's calendar home.
+pub const BIRTHDAYS: &str = "birthdays";
+
+/// `Content-Type` of a calendar object or feed.
+pub const CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+const PRODID: &str = "-//kith//birthdays//EN";
+
+/// What an event celebrates.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Occasion {
+  Birthday,
+  Anniversary,
+}
+
+impl Occasion {
+  fn label(self) -> &'static str {
+    match self {
+      Occasion::Birthday => "birthday",
+      Occasion::Anniversary => "anniversary",
+    }
+  }
+}
+
+/// One yearly event, generated from a fact.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+  /// The birthday or anniversary fact; also the event's `UID`.
+  pub fact_id:     Uuid,
+  pub occasion:    Occasion,
+  /// The recorded date; the first occurrence.
+  pub date:        NaiveDate,
+  /// Display name of the person.
+  pub name:        String,
+  pub recorded_at: DateTime<Utc>,
+  /// Quoted ETag of the event resource.
+  pub etag:        String,
+}
+
+impl Event {
+  /// Resource name of the event within the calendar.
+  pub fn resource_name(&self) -> String { format!("{}.ics", self.fact_id) }
+
+  /// The event as a calendar object of its own.
+  pub fn to_ics(&self) -> String {
+    let mut out = String::new();
+    begin_calendar(&mut out);
+    self.write_vevent(&mut out);
+    line(&mut out, "END:VCALENDAR");
+    out
+  }
+
+  fn write_vevent(&self, out: &mut String) {
+    line(out, "BEGIN:VEVENT");
+    line(out, &format!("UID:{}", self.fact_id));
+    line(
+      out,
+      &format!("DTSTAMP:{}", self.recorded_at.format("%Y%m%dT%H%M%SZ")),
+    );
+    line(
+      out,
+      &format!("DTSTART;VALUE=DATE:{}", self.date.format("%Y%m%d")),
+    );
+    let end = self.date + Days::new(1);
+    line(out, &format!("DTEND;VALUE=DATE:{}", end.format("%Y%m%d")));
+    // A 29 February date falls back to the 28th in common years.
+    if self.date.month() == 2 && self.date.day() == 29 {
+      line(out, "RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1");
+    } else {
+      line(out, "RRULE:FREQ=YEARLY");
+    }
+    let label = self.occasion.label();
+    line(
+      out,
+      &format!(
+        "SUMMARY:{}",
+        escape_text(&format!("{}'s {label}", self.name))
+      ),
+    );
+    let since = match self.occasion {
+      Occasion::Birthday => "Born",
+      Occasion::Anniversary => "Since",
+    };
+    line(
+      out,
+      &format!(
+        "DESCRIPTION:{}",
+        escape_text(&format!("{since} {}", self.date.format("%-d %B %Y")))
+      ),
+    );
+    line(out, &format!("CATEGORIES:{}", label.to_ascii_uppercase()));
+    line(out, "TRANSP:TRANSPARENT");
+    line(out, "END:VEVENT");
+  }
+}
+
+/// The events generated from `view`'s active facts, in fact order.
+pub fn events(view: &ContactView) -> Vec<Event> {
+  let name_fact = view.active_facts.iter().find_map(|rf| {
+    let FactValue::Name(n) = &rf.fact.value else {
+      return None;
+    };
+    Some((rf, n.full.as_str()))
+  });
+  let name = name_fact.map_or("Unnamed contact", |(_, name)| name);
+
+  view
+    .active_facts
+    .iter()
+    .filter_map(|rf| {
+      let (occasion, date) = match &rf.fact.value {
+        FactValue::Birthday(d) => (Occasion::Birthday, *d),
+        FactValue::Anniversary(d) => (Occasion::Anniversary, *d),
+        _ => return None,
+      };
+      let mut hasher = Sha256::new();
+      hasher.update(rf.fact.fact_id.as_bytes());
+      hasher.update(rf.fact.recorded_at.timestamp_micros().to_le_bytes());
+      if let Some((name_rf, _)) = name_fact {
+        hasher.update(name_rf.fact.fact_id.as_bytes());
+        hasher
+          .update(name_rf.fact.recorded_at.timestamp_micros().to_le_bytes());
+      }
+      Some(Event {
+        fact_id: rf.fact.fact_id,
+        occasion,
+        date,
+        name: name.to_string(),
+        recorded_at: rf.fact.recorded_at,
+        etag: format!("\"{}\"", hex::encode(hasher.finalize())),
+      })
+    })
+    .collect()
+}
+
+/// A whole calendar holding `events`, for `.ics` subscriptions.
+pub fn to_ics(display_name: &str, events: &[Event]) -> String {
+  let mut out = String::new();
+  begin_calendar(&mut out);
+  line(
+    &mut out,
+    &format!("X-WR-CALNAME:{}", escape_text(display_name)),
+  );
+  for event in events {
+    event.write_vevent(&mut out);
+  }
+  line(&mut out, "END:VCALENDAR");
+  out
+}
+
+/// ETag of a calendar holding `events`; also its `getctag`. Changes whenever
+/// an event is added, removed or changed.
+pub fn calendar_etag(events: &[Event]) -> String {
+  let mut etags: Vec<&str> = events.iter().map(|e| e.etag.as_str()).collect();
+  etags.sort_unstable();
+  let mut hasher = Sha256::new();
+  for etag in etags {
+    hasher.update(etag.as_bytes());
+  }
+  format!("\"{}\"", hex::encode(hasher.finalize()))
+}
+
+fn begin_calendar(out: &mut String) {
+  line(out, "BEGIN:VCALENDAR");
+  line(out, "VERSION:2.0");
+  line(out, &format!("PRODID:{PRODID}"));
+  line(out, "CALSCALE:GREGORIAN");
+}
+
+/// Append a content line, folded at 75 octets (RFC 5545 §3.1).
+fn line(out: &mut String, content: &str) {
+  let mut width = 0;
+  for c in content.chars() {
+    if width + c.len_utf8() > 75 {
+      out.push_str("\r\n ");
+      width = 1;
+    }
+    out.push(c);
+    width += c.len_utf8();
+  }
+  out.push_str("\r\n");
+}
+
+/// Escape a TEXT value (RFC 5545 §3.3.11).
+fn escape_text(text: &str) -> String {
+  let mut out = String::with_capacity(text.len());
+  for c in text.chars() {
+    match c {
+      '\\' => out.push_str("\\\\"),
+      ';' => out.push_str("\\;"),
+      ',' => out.push_str("\\,"),
+      '\n' => out.push_str("\\n"),
+      '\r' => {}
+      c => out.push(c),
+    }
+  }
+  out
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+  use kith_core::{
+    fact::{Confidence, Fact, NameValue, RecordingContext},
+    lifecycle::{FactStatus, ResolvedFact},
+    subject::{Subject, SubjectKind},
+  };
+
+  use super::*;
+
+  fn fact(value: FactValue, ts_secs: i64) -> ResolvedFact {
+    ResolvedFact {
+      fact:   Fact {
+        fact_id: Uuid::new_v4(),
+        subject_id: Uuid::nil(),
+        value,
+        recorded_at: Utc.timestamp_opt(ts_secs, 0).unwrap(),
+        effective_at: None,
+        effective_until: None,
+        source: None,
+        confidence: Confidence::Certain,
+        recording_context: RecordingContext::Manual,
+        tags: vec![],
+      },
+      status: FactStatus::Active,
+    }
+  }
+
+  fn name(full: &str) -> FactValue {
+    FactValue::Name(NameValue {
+      given:      None,
+      family:     None,
+      additional: None,
+      prefix:     None,
+      suffix:     None,
+      full:       full.into(),
+    })
+  }
+
+  fn view(facts: Vec<ResolvedFact>) -> ContactView {
+    let ts = Utc.timestamp_opt(0, 0).unwrap();
+    ContactView {
+      subject:      Subject {
+        subject_id: Uuid::nil(),
+        created_at: ts,
+        kind:       SubjectKind::Person,
+      },
+      as_of:        ts,
+      valid_at:     None,
+      active_facts: facts,
+    }
+  }
+
+  fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(y, m, d).unwrap()
+  }
+
+  #[test]
+  fn birthdays_become_yearly_all_day_events() {
+    let v = view(vec![
+      fact(name("Alice, Smith"), 1000),
+      fact(FactValue::Birthday(date(1990, 3, 15)), 2000),
+      fact(FactValue::Note("met at a conference".into()), 3000),
+    ]);
+    let events = events(&v);
+    assert_eq!(events.len(), 1);
+    let ics = events[0].to_ics();
+    assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+    assert!(ics.contains("DTSTART;VALUE=DATE:19900315\r\n"));
+    assert!(ics.contains("DTEND;VALUE=DATE:19900316\r\n"));
+    assert!(ics.contains("RRULE:FREQ=YEARLY\r\n"));
+    assert!(ics.contains("SUMMARY:Alice\\, Smith's birthday\r\n"));
+    assert!(ics.contains("DTSTAMP:19700101T003320Z\r\n"));
+    assert!(ics.ends_with("END:VCALENDAR\r\n"));
+  }
+
+  #[test]
+  fn leap_day_recurs_on_the_last_day_of_february() {
+    let v = view(vec![fact(FactValue::Anniversary(date(2000, 2, 29)), 0)]);
+    let ics = events(&v)[0].to_ics();
+    assert!(ics.contains("RRULE:FREQ=YEARLY;BYMONTH=2;BYMONTHDAY=-1\r\n"));
+    assert!(ics.contains("SUMMARY:Unnamed contact's anniversary\r\n"));
+  }
+
+  #[test]
+  fn etag_follows_the_date_and_name_facts() {
+    let bday = fact(FactValue::Birthday(date(1990, 3, 15)), 2000);
+    let alice = fact(name("Alice"), 1000);
+    let base = events(&view(vec![alice.clone(), bday.clone()]));
+    let same = events(&view(vec![bday.clone(), alice]));
+    assert_eq!(base[0].etag, same[0].etag);
+
+    let renamed = events(&view(vec![fact(name("Alicia"), 1500), bday]));
+    assert_ne!(base[0].etag, renamed[0].etag);
+    assert_ne!(calendar_etag(&base), calendar_etag(&renamed));
+  }
+
+  #[test]
+  fn long_lines_are_folded() {
+    let mut out = String::new();
+    line(&mut out, &"é".repeat(60));
+    for l in out.split("\r\n") {
+      assert!(l.len() <= 75, "{l:?}");
+    }
+    assert_eq!(out.replace("\r\n ", ""), format!("{}\r\n", "é".repeat(60)));
+  }
+}
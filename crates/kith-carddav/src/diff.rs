@@ -2,24 +2,88 @@
 //!
 //! Computes the set of new facts, supersessions, and retractions needed to
 //! transition the current contact state to match an incoming vCard.
+//!
+//! A vCard only carries values. Metadata curated through the API — tags,
+//! confidence, effective dates and source — has nowhere to live in it, so a
+//! supersession keeps the metadata of the fact it replaces (see
+//! [`inherit_metadata`]). Facts with no counterpart start from the defaults.
+//!
+//! Facts are paired in two stages. First by key: the same email address, the
+//! same phone number, and so on (see [`fact_matches`]). Then the leftovers of
+//! each type are paired by how similar their keys are, so that fixing a typo
+//! in an address supersedes the old fact instead of retracting it and adding
+//! an unrelated one (see [`similar_pairs`]).
+//!
+//! Finally the [`WritePolicy`] decides which of those changes a client may
+//! make; the rest are dropped and reported in [`DiffResult::preserved`] and
+//! [`DiffResult::ignored`].
+
+use std::collections::HashMap;
 
 use kith_core::{
-  fact::{Confidence, FactValue, NewFact, RecordingContext},
+  fact::{Confidence, Fact, FactValue, NewFact, RecordingContext},
   lifecycle::ContactView,
+  similarity::{normalize_phone, similarity},
+  store::Changeset,
 };
+use kith_vcard::ParsedVcard;
 use uuid::Uuid;
 
+use crate::policy::WritePolicy;
+
+/// Default for the `match_threshold` of [`diff_parsed`]: the similarity (see
+/// [`similarity`]) at which a removed and an added fact count as one edit.
+///
+/// High enough that `alice@example.com` and `bob@example.com` stay distinct,
+/// low enough for a transposed pair of letters or digits in an email or phone
+/// number.
+pub const DEFAULT_MATCH_THRESHOLD: f64 = 0.8;
+
 /// The result of diffing an incoming vCard against the current store state.
 pub struct DiffResult {
   pub new_facts:     Vec<NewFact>,
   pub supersessions: Vec<(Uuid /* old_fact_id */, NewFact)>,
   pub retractions:   Vec<Uuid>,
+  /// Active facts the vCard would have superseded or retracted, kept because
+  /// the [`WritePolicy`] protects their type.
+  pub preserved:     Vec<Uuid>,
+  /// Incoming facts the [`WritePolicy`] does not let the vCard record.
+  pub ignored:       Vec<NewFact>,
+}
+
+impl DiffResult {
+  /// `true` if the store already matches the incoming vCard, as far as the
+  /// write policy allows.
+  pub fn is_empty(&self) -> bool {
+    self.new_facts.is_empty()
+      && self.supersessions.is_empty()
+      && self.retractions.is_empty()
+  }
+
+  /// Convert into a [`Changeset`], recording `retraction_reason` on every
+  /// retraction.
+  pub fn into_changeset(self, retraction_reason: &str) -> Changeset {
+    Changeset {
+      new_facts: self.new_facts,
+      supersessions: self.supersessions,
+      retractions: self
+        .retractions
+        .into_iter()
+        .map(|id| (id, Some(retraction_reason.to_owned())))
+        .collect(),
+      ..Default::default()
+    }
+  }
 }
 
 /// Compute the minimal set of store operations that transitions `current_view`
 /// to match `incoming_vcard`.
 ///
 /// When `current_view` is `None` (new contact), all parsed facts are new.
+/// Inline photos are ignored; to keep them, turn them into facts with
+/// [`photo::record`](crate::photo::record) and call [`diff_parsed`].
+/// Similarity matching uses [`DEFAULT_MATCH_THRESHOLD`] and changes are
+/// limited by the default [`WritePolicy`].
 pub fn diff(
   incoming_vcard: &str,
   subject_id: Uuid,
@@ -27,6 +91,30 @@ pub fn diff(
   current_view: Option<&ContactView>,
 ) -> Result<DiffResult, kith_vcard::Error> {
   let parsed = kith_vcard::parse(incoming_vcard, source_name)?;
+  Ok(diff_parsed(
+    parsed,
+    subject_id,
+    source_name,
+    current_view,
+    DEFAULT_MATCH_THRESHOLD,
+    &WritePolicy::default(),
+  ))
+}
+
+/// [`diff`] for an already parsed vCard.
+///
+/// A leftover added fact supersedes a leftover removed one of the same type
+/// if their keys are at least `match_threshold` similar; above `1.0`
+/// only exact key matches count. Changes `policy` does not allow are moved
+/// to [`DiffResult::preserved`] and [`DiffResult::ignored`].
+pub fn diff_parsed(
+  parsed: ParsedVcard,
+  subject_id: Uuid,
+  source_name: &str,
+  current_view: Option<&ContactView>,
+  match_threshold: f64,
+  policy: &WritePolicy,
+) -> DiffResult {
   let uid = parsed.uid.clone();
 
   // Build incoming facts with the real subject_id and correct context.
@@ -46,40 +134,75 @@ pub fn diff(
 
   let Some(view) = current_view else {
     // No existing contact — all incoming facts are new.
-    return Ok(DiffResult {
-      new_facts:     incoming,
-      supersessions: vec![],
-      retractions:   vec![],
-    });
+    return restrict(
+      DiffResult {
+        new_facts:     incoming,
+        supersessions: vec![],
+        retractions:   vec![],
+        preserved:     vec![],
+        ignored:       vec![],
+      },
+      policy,
+      &[],
+    );
   };
 
   let active: Vec<&kith_core::lifecycle::ResolvedFact> =
     view.active_facts.iter().collect();
 
-  let mut new_facts: Vec<NewFact> = vec![];
+  // Unmatched incoming facts, with their position among the vCard's facts of
+  // the same type.
+  let mut unmatched: Vec<(NewFact, usize)> = vec![];
   let mut supersessions: Vec<(Uuid, NewFact)> = vec![];
   // Track which active fact IDs were matched.
   let mut matched_active: std::collections::HashSet<Uuid> =
     std::collections::HashSet::new();
+  let mut positions: HashMap<&'static str, usize> = HashMap::new();
 
   for incoming_fact in incoming {
+    let position = positions
+      .entry(incoming_fact.value.discriminant())
+      .and_modify(|p| *p += 1)
+      .or_insert(0);
+    let position = *position;
+
     // Try to find a matching active fact by type + key fields, skipping any
     // facts already consumed by a previous incoming fact.
-    let match_result = find_match(&incoming_fact.value, &active, &matched_active);
+    let match_result =
+      find_match(&incoming_fact.value, &active, &matched_active);
 
     match match_result {
-      Some((old_id, old_value)) => {
-        matched_active.insert(old_id);
-        if values_identical(&incoming_fact.value, old_value) {
+      Some(old) => {
+        matched_active.insert(old.fact_id);
+        if values_identical(&incoming_fact.value, &old.value) {
           // Unchanged — no-op.
         } else {
           // Value changed — supersession.
-          supersessions.push((old_id, incoming_fact));
+          supersessions
+            .push((old.fact_id, inherit_metadata(incoming_fact, old)));
         }
       }
       None => {
-        new_facts.push(incoming_fact);
+        unmatched.push((incoming_fact, position));
+      }
+    }
+  }
+
+  // Second stage: pair the leftovers by similarity.
+  let mut paired: Vec<Option<&Fact>> = vec![None; unmatched.len()];
+  for (i, old) in
+    similar_pairs(&unmatched, &active, &matched_active, match_threshold)
+  {
+    matched_active.insert(old.fact_id);
+    paired[i] = Some(old);
+  }
+  let mut new_facts: Vec<NewFact> = vec![];
+  for ((incoming_fact, _), old) in unmatched.into_iter().zip(paired) {
+    match old {
+      Some(old) => {
+        supersessions.push((old.fact_id, inherit_metadata(incoming_fact, old)))
       }
+      None => new_facts.push(incoming_fact),
     }
   }
 
@@ -90,11 +213,79 @@ pub fn diff(
     .map(|rf| rf.fact.fact_id)
     .collect();
 
-  Ok(DiffResult {
+  restrict(
+    DiffResult {
+      new_facts,
+      supersessions,
+      retractions,
+      preserved: vec![],
+      ignored: vec![],
+    },
+    policy,
+    &active,
+  )
+}
+
+/// Drop the changes in `result` that `policy` forbids.
+///
+/// A dropped supersession keeps the old fact; under
+/// [`SyncPolicy::AddOnly`](crate::policy::SyncPolicy::AddOnly) its
+/// replacement is still recorded, as a new fact, unless an active fact
+/// already holds the same value (as it does when the same edit is uploaded
+/// again).
+fn restrict(
+  result: DiffResult,
+  policy: &WritePolicy,
+  active: &[&kith_core::lifecycle::ResolvedFact],
+) -> DiffResult {
+  let policy_of = |value: &FactValue| policy.get(value.discriminant());
+  let mut preserved = vec![];
+  let mut ignored = vec![];
+
+  let mut new_facts = vec![];
+  let mut additions = result.new_facts;
+  let mut supersessions = vec![];
+  for (old_id, replacement) in result.supersessions {
+    if policy_of(&replacement.value).allows_change() {
+      supersessions.push((old_id, replacement));
+    } else {
+      preserved.push(old_id);
+      let recorded = active
+        .iter()
+        .any(|rf| values_identical(&replacement.value, &rf.fact.value));
+      if !recorded {
+        additions.push(replacement);
+      }
+    }
+  }
+  for fact in additions {
+    if policy_of(&fact.value).allows_add() {
+      new_facts.push(fact);
+    } else {
+      ignored.push(fact);
+    }
+  }
+
+  let mut retractions = vec![];
+  for id in result.retractions {
+    let protected = active
+      .iter()
+      .find(|rf| rf.fact.fact_id == id)
+      .is_some_and(|rf| !policy_of(&rf.fact.value).allows_change());
+    if protected {
+      preserved.push(id);
+    } else {
+      retractions.push(id);
+    }
+  }
+
+  DiffResult {
     new_facts,
     supersessions,
     retractions,
-  })
+    preserved,
+    ignored,
+  }
 }
 
 /// Find a matching active fact for the given incoming value.
@@ -103,23 +294,43 @@ pub fn diff(
 /// incoming facts with the same key (e.g. two emails at the same address)
 /// each consume a distinct active fact rather than both matching the same one.
 ///
-/// Returns `(fact_id, &FactValue)` if a match is found.
+/// Returns the matching fact, if any.
 fn find_match<'a>(
   incoming: &FactValue,
   active: &[&'a kith_core::lifecycle::ResolvedFact],
   already_matched: &std::collections::HashSet<Uuid>,
-) -> Option<(Uuid, &'a FactValue)> {
+) -> Option<&'a Fact> {
   for rf in active {
     if already_matched.contains(&rf.fact.fact_id) {
       continue;
     }
     if fact_matches(incoming, &rf.fact.value) {
-      return Some((rf.fact.fact_id, &rf.fact.value));
+      return Some(&rf.fact);
     }
   }
   None
 }
 
+/// Carry the metadata of `old` over to `replacement`, the fact superseding
+/// it.
+///
+/// The merge policy:
+///
+/// - `value` comes from the vCard: it is what the client changed.
+/// - `recording_context` stays `Imported`: it records how *this* fact entered
+///   the store.
+/// - `tags`, `confidence`, `source`, `effective_at` and `effective_until` come
+///   from `old`. A vCard cannot express them, so their absence in the upload
+///   says nothing, and the replaced fact holds the best knowledge.
+fn inherit_metadata(mut replacement: NewFact, old: &Fact) -> NewFact {
+  replacement.tags = old.tags.clone();
+  replacement.confidence = old.confidence;
+  replacement.source = old.source.clone();
+  replacement.effective_at = old.effective_at.clone();
+  replacement.effective_until = old.effective_until.clone();
+  replacement
+}
+
 /// Returns true if `incoming` and `existing` are the same logical piece of
 /// information (same type + key fields).
 fn fact_matches(incoming: &FactValue, existing: &FactValue) -> bool {
@@ -197,13 +408,116 @@ fn fact_matches(incoming: &FactValue, existing: &FactValue) -> bool {
     // Key: custom key.
     (Custom { key: ka, .. }, Custom { key: kb, .. }) => ka == kb,
 
-    // Photo: match by path.
-    (Photo(a), Photo(b)) => a.path == b.path,
+    // Key: content hash.
+    (Photo(a), Photo(b)) => a.content_hash == b.content_hash,
 
     _ => false,
   }
 }
 
+// ─── Similarity matching ─────────────────────────────────────────────────────
+
+/// Pair unmatched incoming facts (with their positions, see [`diff_parsed`])
+/// with unmatched active facts of the same type whose keys are at least
+/// `threshold` similar. Returns `(index into unmatched, active fact)` pairs.
+///
+/// Candidates are ranked by similarity, then by whether their labels agree,
+/// then by whether they sit at the same position among facts of their type,
+/// and paired best first; every fact is paired at most once.
+fn similar_pairs<'a>(
+  unmatched: &[(NewFact, usize)],
+  active: &[&'a kith_core::lifecycle::ResolvedFact],
+  already_matched: &std::collections::HashSet<Uuid>,
+  threshold: f64,
+) -> Vec<(usize, &'a Fact)> {
+  // Leftover active facts, with their position among the active facts of
+  // their type.
+  let mut positions: HashMap<&'static str, usize> = HashMap::new();
+  let leftovers: Vec<(&Fact, usize)> = active
+    .iter()
+    .map(|rf| {
+      let position = positions
+        .entry(rf.fact.value.discriminant())
+        .and_modify(|p| *p += 1)
+        .or_insert(0);
+      (&rf.fact, *position)
+    })
+    .filter(|(f, _)| !already_matched.contains(&f.fact_id))
+    .collect();
+
+  let mut candidates = vec![];
+  for (i, (incoming, in_pos)) in unmatched.iter().enumerate() {
+    let Some(in_key) = match_key(&incoming.value) else {
+      continue;
+    };
+    for (j, (old, old_pos)) in leftovers.iter().enumerate() {
+      if incoming.value.discriminant() != old.value.discriminant() {
+        continue;
+      }
+      let Some(old_key) = match_key(&old.value) else {
+        continue;
+      };
+      let score = similarity(&in_key, &old_key);
+      if score >= threshold {
+        let label = same_label(&incoming.value, &old.value);
+        candidates.push((score, label, in_pos == old_pos, i, j));
+      }
+    }
+  }
+  // Stable, so equal candidates keep vCard order.
+  candidates.sort_by(|a, b| {
+    b.0.total_cmp(&a.0).then(b.1.cmp(&a.1)).then(b.2.cmp(&a.2))
+  });
+
+  let mut used_incoming = vec![false; unmatched.len()];
+  let mut used_old = vec![false; leftovers.len()];
+  let mut pairs = vec![];
+  for (_, _, _, i, j) in candidates {
+    if !used_incoming[i] && !used_old[j] {
+      used_incoming[i] = true;
+      used_old[j] = true;
+      pairs.push((i, leftovers[j].0));
+    }
+  }
+  pairs
+}
+
+/// The text compared by similarity matching, normalised as in
+/// [`fact_matches`]. Only key-like values, where a small edit is a
+/// correction of the same thing, are paired this way; for any other type,
+/// free text above all, a similar value may well be a different fact.
+fn match_key(value: &FactValue) -> Option<String> {
+  use FactValue::*;
+  match value {
+    Email(e) => Some(e.address.to_lowercase()),
+    Phone(p) => Some(normalize_phone(&p.number)),
+    Address(a) => Some(
+      [&a.street, &a.locality, &a.postal_code]
+        .map(normalize_opt)
+        .join("\n"),
+    ),
+    Url(u) => Some(u.url.clone()),
+    Im(i) => Some(i.handle.clone()),
+    Social(s) => Some(s.handle.clone()),
+    _ => None,
+  }
+}
+
+/// Whether two values of the same type carry the same label (or, for
+/// handles, the same service).
+fn same_label(a: &FactValue, b: &FactValue) -> bool {
+  use FactValue::*;
+  match (a, b) {
+    (Email(a), Email(b)) => a.label == b.label,
+    (Phone(a), Phone(b)) => a.label == b.label && a.kind == b.kind,
+    (Address(a), Address(b)) => a.label == b.label,
+    (Url(a), Url(b)) => a.context == b.context,
+    (Im(a), Im(b)) => a.service.eq_ignore_ascii_case(&b.service),
+    (Social(a), Social(b)) => a.platform.eq_ignore_ascii_case(&b.platform),
+    _ => false,
+  }
+}
+
 /// Returns true if the two values are structurally identical.
 fn values_identical(a: &FactValue, b: &FactValue) -> bool {
   // Serialize both to JSON and compare; avoids re-implementing equality.
@@ -212,12 +526,6 @@ fn values_identical(a: &FactValue, b: &FactValue) -> bool {
   ja == jb
 }
 
-fn normalize_phone(s: &str) -> String {
-  s.chars()
-    .filter(|c| !c.is_whitespace() && *c != '-')
-    .collect()
-}
-
 fn normalize_opt(s: &Option<String>) -> String {
   s.as_deref().unwrap_or("").to_lowercase()
 }
@@ -226,14 +534,15 @@ fn normalize_opt(s: &Option<String>) -> String {
 
 #[cfg(test)]
 mod tests {
-  use chrono::{TimeZone, Utc};
+  use chrono::{NaiveDate, TimeZone, Utc};
   use kith_core::{
-    fact::{Confidence, Fact, FactValue, RecordingContext},
+    fact::{Confidence, EffectiveDate, Fact, FactValue, RecordingContext},
     lifecycle::{ContactView, FactStatus, ResolvedFact},
     subject::{Subject, SubjectKind},
   };
 
   use super::*;
+  use crate::policy::SyncPolicy;
 
   const SRC: &str = "test";
 
@@ -283,6 +592,7 @@ mod tests {
         kind:       SubjectKind::Person,
       },
       as_of: ts,
+      valid_at: None,
       active_facts,
     };
 
@@ -326,6 +636,7 @@ mod tests {
         kind:       SubjectKind::Person,
       },
       as_of: ts,
+      valid_at: None,
       active_facts,
     }
   }
@@ -357,6 +668,41 @@ mod tests {
     );
   }
 
+  #[test]
+  fn supersession_inherits_metadata() {
+    let id = Uuid::new_v4();
+    let initial = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Alice\r\nEMAIL;TYPE=WORK:\
+                   alice@example.com\r\nEND:VCARD\r\n";
+    let mut view = initial_view(initial, id);
+    let since =
+      EffectiveDate::DateOnly(NaiveDate::from_ymd_opt(2019, 3, 1).unwrap());
+    for rf in &mut view.active_facts {
+      if matches!(rf.fact.value, FactValue::Email(_)) {
+        rf.fact.tags = vec!["work".to_string()];
+        rf.fact.confidence = Confidence::Probable;
+        rf.fact.source = Some("business card".to_string());
+        rf.fact.effective_at = Some(since.clone());
+      }
+    }
+
+    let updated = "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Alice\r\nEMAIL;TYPE=HOME:\
+                   alice@example.com\r\nEND:VCARD\r\n";
+    let result = diff(updated, id, SRC, Some(&view)).unwrap();
+
+    assert_eq!(result.supersessions.len(), 1);
+    let (_, replacement) = &result.supersessions[0];
+    assert!(matches!(replacement.value, FactValue::Email(_)));
+    assert_eq!(replacement.tags, vec!["work".to_string()]);
+    assert_eq!(replacement.confidence, Confidence::Probable);
+    assert_eq!(replacement.source.as_deref(), Some("business card"));
+    assert_eq!(replacement.effective_at, Some(since));
+    assert_eq!(replacement.effective_until, None);
+    assert!(matches!(
+      replacement.recording_context,
+      RecordingContext::Imported { .. }
+    ));
+  }
+
   #[test]
   fn new_phone_is_new_fact() {
     let id = Uuid::new_v4();
@@ -414,6 +760,167 @@ mod tests {
     );
   }
 
+  // ── Similarity matching
+
+  /// The id of the active fact in `view` whose search text is `text`.
+  fn fact_id(view: &ContactView, text: &str) -> Uuid {
+    view
+      .active_facts
+      .iter()
+      .find(|rf| rf.fact.value.search_text() == text)
+      .unwrap()
+      .fact
+      .fact_id
+  }
+
+  /// A vCard for Alice with the given extra content lines.
+  fn card(lines: &str) -> String {
+    format!("BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Alice\r\n{lines}END:VCARD\r\n")
+  }
+
+  /// Diff `card(updated)` against a view of `card(initial)`, returning the
+  /// view, the ids of the superseded facts and the full result.
+  fn edit(
+    initial: &str,
+    updated: &str,
+  ) -> (ContactView, Vec<Uuid>, DiffResult) {
+    let id = Uuid::new_v4();
+    let view = initial_view(&card(initial), id);
+    let result = diff(&card(updated), id, SRC, Some(&view)).unwrap();
+    let superseded = result.supersessions.iter().map(|(old, _)| *old).collect();
+    (view, superseded, result)
+  }
+
+  #[test]
+  fn email_typo_fix_is_supersession() {
+    let (view, superseded, result) =
+      edit("EMAIL:alcie@example.com\r\n", "EMAIL:alice@example.com\r\n");
+    assert_eq!(superseded, vec![fact_id(&view, "alcie@example.com")]);
+    assert!(result.new_facts.is_empty());
+    assert!(result.retractions.is_empty());
+  }
+
+  #[test]
+  fn url_scheme_change_is_supersession() {
+    let (view, superseded, result) =
+      edit("URL:http://alice.dev/\r\n", "URL:https://alice.dev/\r\n");
+    assert_eq!(superseded, vec![fact_id(&view, "http://alice.dev/")]);
+    assert!(result.new_facts.is_empty());
+    assert!(result.retractions.is_empty());
+  }
+
+  #[test]
+  fn phone_digit_swap_is_supersession() {
+    let (_, superseded, result) =
+      edit("TEL:+15555551234\r\n", "TEL:+15555551243\r\n");
+    assert_eq!(superseded.len(), 1);
+    assert!(result.new_facts.is_empty());
+    assert!(result.retractions.is_empty());
+  }
+
+  #[test]
+  fn unrelated_replacement_is_retraction_and_new_fact() {
+    let (_, superseded, result) =
+      edit("EMAIL:alice@example.com\r\n", "EMAIL:bob@example.com\r\n");
+    assert!(superseded.is_empty());
+    assert_eq!(result.new_facts.len(), 1);
+    assert_eq!(result.retractions.len(), 1);
+  }
+
+  #[test]
+  fn similar_notes_are_not_paired() {
+    let (_, superseded, result) = edit(
+      "NOTE:Owes me lunch from March\r\n",
+      "NOTE:Owes me lunch from May\r\n",
+    );
+    assert!(superseded.is_empty());
+    assert_eq!(result.new_facts.len(), 1);
+    assert_eq!(result.retractions.len(), 1);
+  }
+
+  #[test]
+  fn equally_similar_candidates_are_split_by_label() {
+    let (view, superseded, result) = edit(
+      "EMAIL;TYPE=WORK:ann@x.io\r\nEMAIL;TYPE=HOME:ann@y.io\r\n",
+      "EMAIL;TYPE=HOME:ann@z.io\r\n",
+    );
+    assert_eq!(superseded, vec![fact_id(&view, "ann@y.io")]);
+    assert_eq!(result.retractions, vec![fact_id(&view, "ann@x.io")]);
+  }
+
+  #[test]
+  fn threshold_above_one_disables_similarity_matching() {
+    let id = Uuid::new_v4();
+    let view = initial_view(&card("EMAIL:alcie@example.com\r\n"), id);
+    let parsed =
+      kith_vcard::parse(&card("EMAIL:alice@example.com\r\n"), SRC).unwrap();
+    let result =
+      diff_parsed(parsed, id, SRC, Some(&view), 1.1, &WritePolicy::default());
+    assert!(result.supersessions.is_empty());
+    assert_eq!(result.new_facts.len(), 1);
+    assert_eq!(result.retractions.len(), 1);
+  }
+
+  // ── Write policy
+
+  const MEETING: &str = "X-KITH-MEETING:Lunch\r\n";
+
+  #[test]
+  fn dropped_meeting_is_preserved() {
+    let id = Uuid::new_v4();
+    let view = initial_view(&card(MEETING), id);
+    let result = diff(&card(""), id, SRC, Some(&view)).unwrap();
+    assert!(result.is_empty());
+    assert_eq!(result.preserved, vec![fact_id(&view, "Lunch")]);
+  }
+
+  #[test]
+  fn add_only_edit_is_recorded_alongside_original() {
+    let id = Uuid::new_v4();
+    let view =
+      initial_view(&card("X-KITH-MEETING;LOCATION=Cafe:Lunch\r\n"), id);
+    let result = diff(
+      &card("X-KITH-MEETING;LOCATION=Pub:Lunch\r\n"),
+      id,
+      SRC,
+      Some(&view),
+    )
+    .unwrap();
+    assert!(result.supersessions.is_empty());
+    assert_eq!(result.new_facts.len(), 1);
+    assert_eq!(result.preserved, vec![fact_id(&view, "Lunch Cafe")]);
+  }
+
+  #[test]
+  fn read_only_type_ignores_the_vcard() {
+    let id = Uuid::new_v4();
+    let view = initial_view(&card("NOTE:old\r\n"), id);
+    let mut policy = WritePolicy::default();
+    policy.set("note", SyncPolicy::ReadOnly);
+    let parsed = kith_vcard::parse(&card("NOTE:new\r\n"), SRC).unwrap();
+    let result = diff_parsed(parsed, id, SRC, Some(&view), 1.1, &policy);
+    assert!(result.is_empty());
+    assert_eq!(result.preserved, vec![fact_id(&view, "old")]);
+    assert_eq!(result.ignored.len(), 1);
+  }
+
+  #[test]
+  fn full_policy_retracts_dropped_meeting() {
+    let id = Uuid::new_v4();
+    let view = initial_view(&card(MEETING), id);
+    let parsed = kith_vcard::parse(&card(""), SRC).unwrap();
+    let result = diff_parsed(
+      parsed,
+      id,
+      SRC,
+      Some(&view),
+      DEFAULT_MATCH_THRESHOLD,
+      &WritePolicy::full(),
+    );
+    assert_eq!(result.retractions, vec![fact_id(&view, "Lunch")]);
+    assert!(result.preserved.is_empty());
+  }
+
   #[test]
   fn full_contact_round_trip() {
     let id = Uuid::new_v4();
@@ -460,6 +967,7 @@ mod tests {
         kind:       SubjectKind::Person,
       },
       as_of: ts,
+      valid_at: None,
       active_facts,
     };
 
@@ -4,16 +4,39 @@ use axum::{
   http::{HeaderValue, StatusCode, header},
   response::{IntoResponse, Response},
 };
+use quick_xml::escape::escape;
 use thiserror::Error;
 
+use crate::xml::{NS_CARDDAV, NS_DAV};
+
 #[derive(Debug, Error)]
 pub enum Error {
   #[error("unauthorized")]
   Unauthorized,
+  #[error("forbidden: {0}")]
+  Forbidden(String),
   #[error("not found")]
   NotFound,
+  /// Too many failed logins; the client may retry after this long.
+  #[error("too many failed logins; retry in {0:?}")]
+  TooManyRequests(std::time::Duration),
   #[error("precondition failed")]
   PreconditionFailed,
+  /// A `sync-collection` token this server did not issue (RFC 6578 §3.2).
+  #[error("invalid sync token: {0}")]
+  InvalidSyncToken(String),
+  /// A `text-match` collation this server does not implement (RFC 6352
+  /// §8.3).
+  #[error("unsupported collation: {0}")]
+  UnsupportedCollation(String),
+  /// An `address-data` media type or version this server cannot produce
+  /// (RFC 6352 §10.4.1).
+  #[error("unsupported address data: {0}")]
+  UnsupportedAddressData(String),
+  /// A PUT whose vCard `UID` is already used by the resource at this href
+  /// (RFC 6352 §6.3.2.1).
+  #[error("UID already in use by {0}")]
+  UidConflict(String),
   #[error("conflict: {0}")]
   Conflict(String),
   #[error("bad request: {0}")]
@@ -24,6 +47,8 @@ pub enum Error {
   Vcard(#[from] kith_vcard::Error),
   #[error("store error: {0}")]
   Store(#[source] Box<dyn std::error::Error + Send + Sync>),
+  #[error("photo storage error: {0}")]
+  Photo(#[from] std::io::Error),
 }
 
 impl IntoResponse for Error {
@@ -38,11 +63,47 @@ impl IntoResponse for Error {
         );
         res
       }
+      Error::Forbidden(msg) => {
+        tracing::warn!(reason = %msg, "forbidden (403)");
+        (StatusCode::FORBIDDEN, msg).into_response()
+      }
       Error::NotFound => (StatusCode::NOT_FOUND, "Not Found").into_response(),
+      Error::TooManyRequests(wait) => {
+        // Round up, so a client that waits exactly this long is let in.
+        let secs = wait.as_secs() + u64::from(wait.subsec_nanos() > 0);
+        (
+          StatusCode::TOO_MANY_REQUESTS,
+          [(header::RETRY_AFTER, secs.to_string())],
+          "Too Many Requests",
+        )
+          .into_response()
+      }
       Error::PreconditionFailed => {
         tracing::warn!("precondition failed (412)");
         (StatusCode::PRECONDITION_FAILED, "Precondition Failed").into_response()
       }
+      Error::InvalidSyncToken(token) => {
+        tracing::warn!(token = %token, "invalid sync token (403)");
+        forbidden_precondition("D:valid-sync-token")
+      }
+      Error::UnsupportedCollation(collation) => {
+        tracing::warn!(collation = %collation, "unsupported collation (403)");
+        forbidden_precondition("C:supported-collation")
+      }
+      Error::UnsupportedAddressData(requested) => {
+        tracing::warn!(requested = %requested, "unsupported address data (403)");
+        forbidden_precondition("C:supported-address-data")
+      }
+      Error::UidConflict(href) => {
+        tracing::warn!(href = %href, "UID conflict (409)");
+        failed_precondition(
+          StatusCode::CONFLICT,
+          &format!(
+            "<C:no-uid-conflict><D:href>{}</D:href></C:no-uid-conflict>",
+            escape(&href)
+          ),
+        )
+      }
       Error::Conflict(msg) => {
         tracing::warn!(reason = %msg, "conflict (409)");
         (StatusCode::CONFLICT, msg).into_response()
@@ -63,6 +124,31 @@ impl IntoResponse for Error {
         tracing::error!(error = %e, "store error (500)");
         (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
       }
+      Error::Photo(e) => {
+        tracing::error!(error = %e, "photo storage error (500)");
+        (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()
+      }
     }
   }
 }
+
+/// A 403 whose body names the failed precondition (RFC 4918 §16), e.g.
+/// `D:valid-sync-token`.
+fn forbidden_precondition(element: &str) -> Response {
+  failed_precondition(StatusCode::FORBIDDEN, &format!("<{element}/>"))
+}
+
+/// A `status` response whose `D:error` body holds `condition`, the XML of
+/// the failed precondition.
+fn failed_precondition(status: StatusCode, condition: &str) -> Response {
+  let body = format!(
+    "<?xml version=\"1.0\" encoding=\"UTF-8\"?><D:error xmlns:D=\"{NS_DAV}\" \
+     xmlns:C=\"{NS_CARDDAV}\">{condition}</D:error>"
+  );
+  (
+    status,
+    [(header::CONTENT_TYPE, "application/xml; charset=utf-8")],
+    body,
+  )
+    .into_response()
+}
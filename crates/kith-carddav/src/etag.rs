@@ -1,46 +1,50 @@
 //! ETag computation for ContactView resources.
 //!
 //! ETags are SHA-256 hashes over the sorted (fact_id, recorded_at) pairs of
-//! all active facts. Ordering is deterministic regardless of insertion order.
+//! all active facts, plus the content hash of each photo, since the photo
+//! bytes are part of the vCard served. Ordering is deterministic regardless
+//! of insertion order.
+//!
+//! Each vCard version is a separate representation with its own ETag, since
+//! the bytes served differ. The 4.0 ETag is the plain hash, so it is stable
+//! across releases; other versions mix their version string in.
 
 use chrono::{DateTime, Utc};
-use kith_core::lifecycle::ContactView;
+use kith_core::{fact::FactValue, lifecycle::ContactView};
+use kith_vcard::Version;
 use sha2::{Digest, Sha256};
 use uuid::Uuid;
 
-/// Compute an ETag for the given `ContactView`.
+/// Compute an ETag for the given `ContactView` served as a `version` vCard.
 ///
 /// Stable: same subject + same active facts in any order → same ETag.
 /// The subject_id is always included so that two contacts with identical (or
 /// zero) active facts still produce distinct ETags.
-pub fn compute_etag(view: &ContactView) -> String {
-  let mut pairs: Vec<(Uuid, DateTime<Utc>)> = view
+pub fn compute_etag(view: &ContactView, version: Version) -> String {
+  let mut entries: Vec<(Uuid, DateTime<Utc>, Option<&str>)> = view
     .active_facts
     .iter()
-    .map(|rf| (rf.fact.fact_id, rf.fact.recorded_at))
+    .map(|rf| {
+      let photo_hash = match &rf.fact.value {
+        FactValue::Photo(p) => Some(p.content_hash.as_str()),
+        _ => None,
+      };
+      (rf.fact.fact_id, rf.fact.recorded_at, photo_hash)
+    })
     .collect();
-  pairs.sort_by_key(|(id, _)| *id);
+  entries.sort_by_key(|(id, ..)| *id);
 
   let mut hasher = Sha256::new();
   hasher.update(view.subject.subject_id.as_bytes());
-  for (id, ts) in &pairs {
+  for (id, ts, photo_hash) in &entries {
     hasher.update(id.as_bytes());
     hasher.update(ts.timestamp_micros().to_le_bytes());
+    if let Some(hash) = photo_hash {
+      hasher.update(hash.as_bytes());
+    }
   }
-  let hash = hasher.finalize();
-  format!("\"{}\"", hex::encode(hash))
-}
-
-/// Compute an ETag directly from (fact_id, recorded_at) pairs.
-///
-/// The slice is sorted in-place for determinism.
-pub fn compute_etag_from_pairs(pairs: &mut [(Uuid, DateTime<Utc>)]) -> String {
-  pairs.sort_by_key(|(id, _)| *id);
-
-  let mut hasher = Sha256::new();
-  for (id, ts) in pairs.iter() {
-    hasher.update(id.as_bytes());
-    hasher.update(ts.timestamp_micros().to_le_bytes());
+  if version != Version::V4 {
+    hasher.update(version.as_str().as_bytes());
   }
   let hash = hasher.finalize();
   format!("\"{}\"", hex::encode(hash))
@@ -50,7 +54,9 @@ pub fn compute_etag_from_pairs(pairs: &mut [(Uuid, DateTime<Utc>)]) -> String {
 mod tests {
   use chrono::{TimeZone, Utc};
   use kith_core::{
-    fact::{Confidence, Fact, FactValue, NameValue, RecordingContext},
+    fact::{
+      Confidence, Fact, FactValue, NameValue, PhotoValue, RecordingContext,
+    },
     lifecycle::{ContactView, FactStatus, ResolvedFact},
     subject::{Subject, SubjectKind},
   };
@@ -93,6 +99,7 @@ mod tests {
         kind:       SubjectKind::Person,
       },
       as_of:        ts,
+      valid_at:     None,
       active_facts: facts,
     }
   }
@@ -107,7 +114,10 @@ mod tests {
     let view1 = make_view(vec![make_fact(id_a, ts_a), make_fact(id_b, ts_b)]);
     let view2 = make_view(vec![make_fact(id_b, ts_b), make_fact(id_a, ts_a)]);
 
-    assert_eq!(compute_etag(&view1), compute_etag(&view2));
+    assert_eq!(
+      compute_etag(&view1, Version::V4),
+      compute_etag(&view2, Version::V4)
+    );
   }
 
   #[test]
@@ -118,6 +128,35 @@ mod tests {
     let view1 = make_view(vec![make_fact(id_a, 1000)]);
     let view2 = make_view(vec![make_fact(id_a, 1000), make_fact(id_b, 2000)]);
 
-    assert_ne!(compute_etag(&view1), compute_etag(&view2));
+    assert_ne!(
+      compute_etag(&view1, Version::V4),
+      compute_etag(&view2, Version::V4)
+    );
+  }
+
+  #[test]
+  fn each_version_has_its_own_etag() {
+    let view = make_view(vec![make_fact(Uuid::new_v4(), 1000)]);
+    assert_ne!(
+      compute_etag(&view, Version::V3),
+      compute_etag(&view, Version::V4)
+    );
+  }
+
+  #[test]
+  fn photo_content_feeds_etag() {
+    let photo = |hash: &str| {
+      let mut rf = make_fact(Uuid::nil(), 1000);
+      rf.fact.value = FactValue::Photo(PhotoValue {
+        path:         format!("x/{hash}.jpg"),
+        content_hash: hash.to_string(),
+        media_type:   "image/jpeg".to_string(),
+      });
+      make_view(vec![rf])
+    };
+    assert_ne!(
+      compute_etag(&photo("aaaa"), Version::V4),
+      compute_etag(&photo("bbbb"), Version::V4)
+    );
   }
 }
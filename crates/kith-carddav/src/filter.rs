@@ -0,0 +1,458 @@
+//! `addressbook-query` filters (RFC 6352 §10.5).
+//!
+//! A [`Filter`] is evaluated against the vCard a client would receive, so
+//! every property the serializer emits can be filtered on. Where part of a
+//! filter can be expressed as a [`FactQuery`], [`Filter::store_queries`]
+//! narrows the candidates in the store first; the exact match is always
+//! decided by [`Filter::matches`].
+
+use kith_core::store::FactQuery;
+use kith_vcard::ContentLine;
+
+// ─── Filter tree ─────────────────────────────────────────────────────────────
+
+/// How the conditions of a filter or prop-filter combine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Test {
+  #[default]
+  AnyOf,
+  AllOf,
+}
+
+impl Test {
+  fn combine(self, mut results: impl Iterator<Item = bool>) -> bool {
+    match self {
+      Test::AnyOf => results.any(|r| r),
+      Test::AllOf => results.all(|r| r),
+    }
+  }
+}
+
+/// The `<C:filter>` element. No prop-filters matches every contact.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+  pub test:         Test,
+  pub prop_filters: Vec<PropFilter>,
+}
+
+/// A `<C:prop-filter>`: conditions on every instance of one property.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropFilter {
+  /// Upper-cased property name, e.g. `EMAIL`.
+  pub name:           String,
+  pub test:           Test,
+  /// `<C:is-not-defined/>`: matches only if the property is absent.
+  pub is_not_defined: bool,
+  pub text_matches:   Vec<TextMatch>,
+  pub param_filters:  Vec<ParamFilter>,
+}
+
+/// A `<C:param-filter>` on the parameters of a property.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParamFilter {
+  /// Upper-cased parameter name, e.g. `TYPE`.
+  pub name:           String,
+  pub is_not_defined: bool,
+  pub text_match:     Option<TextMatch>,
+}
+
+/// A `<C:text-match>` against a property or parameter value.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TextMatch {
+  pub text:       String,
+  pub collation:  Collation,
+  pub match_type: MatchType,
+  /// `negate-condition="yes"`.
+  pub negate:     bool,
+}
+
+/// Supported `collation` values. Both compare case-insensitively; they
+/// differ in which characters have case.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Collation {
+  /// `i;ascii-casemap`: only `A`–`Z` fold.
+  AsciiCasemap,
+  /// `i;unicode-casemap`, the CardDAV default.
+  #[default]
+  UnicodeCasemap,
+}
+
+impl Collation {
+  /// Parse a `collation` attribute; `None` if it is not supported.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "i;ascii-casemap" => Some(Self::AsciiCasemap),
+      "i;unicode-casemap" => Some(Self::UnicodeCasemap),
+      _ => None,
+    }
+  }
+
+  fn fold(self, s: &str) -> String {
+    match self {
+      Self::AsciiCasemap => s.to_ascii_lowercase(),
+      Self::UnicodeCasemap => s.to_lowercase(),
+    }
+  }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MatchType {
+  Equals,
+  #[default]
+  Contains,
+  StartsWith,
+  EndsWith,
+}
+
+impl MatchType {
+  /// Parse a `match-type` attribute; `None` if it is not recognised.
+  pub fn from_name(name: &str) -> Option<Self> {
+    match name {
+      "equals" => Some(Self::Equals),
+      "contains" => Some(Self::Contains),
+      "starts-with" => Some(Self::StartsWith),
+      "ends-with" => Some(Self::EndsWith),
+      _ => None,
+    }
+  }
+}
+
+// ─── Evaluation ──────────────────────────────────────────────────────────────
+
+impl Filter {
+  /// `true` if the vCard made of `lines` satisfies the filter.
+  pub fn matches(&self, lines: &[ContentLine]) -> bool {
+    if self.prop_filters.is_empty() {
+      return true;
+    }
+    self
+      .test
+      .combine(self.prop_filters.iter().map(|pf| pf.matches(lines)))
+  }
+}
+
+impl PropFilter {
+  fn matches(&self, lines: &[ContentLine]) -> bool {
+    let props: Vec<&ContentLine> = lines
+      .iter()
+      .filter(|l| l.name.eq_ignore_ascii_case(&self.name))
+      .collect();
+    if self.is_not_defined {
+      return props.is_empty();
+    }
+    if props.is_empty() {
+      return false;
+    }
+    if self.text_matches.is_empty() && self.param_filters.is_empty() {
+      return true;
+    }
+
+    // Each condition holds if any instance of the property satisfies it.
+    let texts: Vec<String> = props.iter().map(|p| p.text()).collect();
+    let text_results = self
+      .text_matches
+      .iter()
+      .map(|tm| texts.iter().any(|t| tm.matches(t)));
+    let param_results = self.param_filters.iter().map(|pf| pf.matches(&props));
+    self.test.combine(text_results.chain(param_results))
+  }
+}
+
+impl ParamFilter {
+  fn matches(&self, props: &[&ContentLine]) -> bool {
+    // Multi-valued parameters (`TYPE=work,voice`) match on any one value.
+    let values: Vec<&str> = props
+      .iter()
+      .flat_map(|p| &p.params)
+      .filter(|p| p.name.eq_ignore_ascii_case(&self.name))
+      .flat_map(|p| p.value.split(','))
+      .collect();
+    if self.is_not_defined {
+      return values.is_empty();
+    }
+    match &self.text_match {
+      None => !values.is_empty(),
+      Some(tm) => values.iter().any(|v| tm.matches(v)),
+    }
+  }
+}
+
+impl TextMatch {
+  /// `true` if `value` satisfies the match, after applying `negate`.
+  pub fn matches(&self, value: &str) -> bool {
+    let value = self.collation.fold(value);
+    let text = self.collation.fold(&self.text);
+    let hit = match self.match_type {
+      MatchType::Equals => value == text,
+      MatchType::Contains => value.contains(&text),
+      MatchType::StartsWith => value.starts_with(&text),
+      MatchType::EndsWith => value.ends_with(&text),
+    };
+    hit != self.negate
+  }
+
+  /// A positive match on non-empty text, which no empty or absent value can
+  /// satisfy.
+  fn requires_value(&self) -> bool { !self.negate && !self.text.is_empty() }
+
+  /// The `FactQuery::text` equivalent of this match, if the index can find
+  /// every value it accepts.
+  ///
+  /// The index matches whole words or word prefixes, so only matches
+  /// anchored at the start of the value translate: every word in the text
+  /// but the last is whole, and the last is a prefix (or whole, for
+  /// `equals`). Text containing characters the index may split on
+  /// differently is left to [`Filter::matches`].
+  fn search_text(&self) -> Option<String> {
+    if !self.requires_value() {
+      return None;
+    }
+    let prefix = match self.match_type {
+      MatchType::StartsWith => true,
+      MatchType::Equals => false,
+      MatchType::Contains | MatchType::EndsWith => return None,
+    };
+    let plain = self.text.chars().all(|c| {
+      c.is_alphanumeric()
+        || c.is_whitespace()
+        || (c.is_ascii_punctuation() && c != '"' && c != '*')
+    });
+    if !plain || !self.text.chars().any(char::is_alphanumeric) {
+      return None;
+    }
+    let mut text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if prefix {
+      text.push('*');
+    }
+    Some(text)
+  }
+}
+
+// ─── Store push-down ─────────────────────────────────────────────────────────
+
+/// How a property is backed by the store: the fact types one of which must
+/// be active for the serializer to emit it, and whether its value appears
+/// verbatim (as consecutive words) in those facts' search text.
+fn backing(prop: &str) -> Option<(&'static [&'static str], bool)> {
+  Some(match prop.to_ascii_uppercase().as_str() {
+    "FN" => (&["name"], true),
+    // Components are ordered differently from the search text.
+    "N" => (&["name"], false),
+    "NICKNAME" => (&["alias"], true),
+    "EMAIL" => (&["email"], true),
+    "TEL" => (&["phone"], true),
+    "ADR" => (&["address"], true),
+    "URL" => (&["url"], true),
+    "IMPP" => (&["im"], false),
+    "BDAY" => (&["birthday"], false),
+    "NOTE" => (&["note"], true),
+    "ORG" | "TITLE" | "ROLE" => (&["org_membership"], true),
+    _ => return None,
+  })
+}
+
+impl Filter {
+  /// Store queries whose combined results include every subject the filter
+  /// can match, or `None` if the filter cannot be narrowed this way and
+  /// every member must be checked.
+  pub fn store_queries(&self) -> Option<Vec<FactQuery>> {
+    match self.test {
+      // One narrowing prop-filter bounds the whole conjunction.
+      Test::AllOf => self
+        .prop_filters
+        .iter()
+        .find_map(PropFilter::store_query)
+        .map(|q| vec![q]),
+      // A disjunction needs every branch bounded.
+      Test::AnyOf if !self.prop_filters.is_empty() => self
+        .prop_filters
+        .iter()
+        .map(PropFilter::store_query)
+        .collect(),
+      Test::AnyOf => None,
+    }
+  }
+}
+
+impl PropFilter {
+  /// A query for the subjects this prop-filter can match.
+  ///
+  /// Unless the filter is `is-not-defined`, a match needs at least one
+  /// instance of the property and so an active fact behind it. A word query
+  /// narrows further when its text-match must hold: under `allof`, or as the
+  /// only condition.
+  fn store_query(&self) -> Option<FactQuery> {
+    if self.is_not_defined {
+      return None;
+    }
+    let (fact_types, verbatim) = backing(&self.name)?;
+    let text = match (self.test, &self.text_matches[..]) {
+      _ if !verbatim => None,
+      (Test::AllOf, tms) => tms.iter().find_map(TextMatch::search_text),
+      (Test::AnyOf, [tm]) if self.param_filters.is_empty() => tm.search_text(),
+      (Test::AnyOf, _) => None,
+    };
+    Some(FactQuery {
+      text,
+      fact_types: fact_types.iter().map(|t| t.to_string()).collect(),
+      active_only: true,
+      ..Default::default()
+    })
+  }
+}
+
+// ─── Tests ───────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn card() -> Vec<ContentLine> {
+    kith_vcard::content_lines(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Ada \
+       Lovelace\r\nEMAIL;TYPE=WORK:ada@example.com\r\nEMAIL;TYPE=HOME:ada@\
+       home.org\r\nTEL;TYPE=CELL:+44 20 7946 0000\r\nEND:VCARD\r\n",
+    )
+    .unwrap()
+  }
+
+  fn text(name: &str, text: &str, match_type: MatchType) -> PropFilter {
+    PropFilter {
+      name: name.to_string(),
+      text_matches: vec![TextMatch {
+        text: text.to_string(),
+        match_type,
+        ..Default::default()
+      }],
+      ..Default::default()
+    }
+  }
+
+  fn filter(test: Test, prop_filters: Vec<PropFilter>) -> Filter {
+    Filter { test, prop_filters }
+  }
+
+  #[test]
+  fn text_match_types_and_collations() {
+    let tm = |text: &str, match_type, collation| TextMatch {
+      text: text.to_string(),
+      collation,
+      match_type,
+      negate: false,
+    };
+    use Collation::*;
+    use MatchType::*;
+    assert!(tm("ada lovelace", Equals, UnicodeCasemap).matches("Ada Lovelace"));
+    assert!(tm("LOVE", Contains, UnicodeCasemap).matches("Ada Lovelace"));
+    assert!(tm("ada", StartsWith, AsciiCasemap).matches("Ada Lovelace"));
+    assert!(tm("lace", EndsWith, AsciiCasemap).matches("Ada Lovelace"));
+    assert!(!tm("lace", StartsWith, AsciiCasemap).matches("Ada Lovelace"));
+    assert!(tm("émile", Equals, UnicodeCasemap).matches("ÉMILE"));
+    assert!(!tm("émile", Equals, AsciiCasemap).matches("ÉMILE"));
+
+    let negated = TextMatch {
+      negate: true,
+      ..tm("ada", StartsWith, UnicodeCasemap)
+    };
+    assert!(!negated.matches("Ada Lovelace"));
+    assert!(negated.matches("Charles Babbage"));
+  }
+
+  #[test]
+  fn prop_filters_combine_with_anyof_and_allof() {
+    let card = card();
+    let fn_ada = text("FN", "ada", MatchType::StartsWith);
+    let email_nope = text("EMAIL", "nobody@", MatchType::StartsWith);
+    let email_home = text("EMAIL", "home.org", MatchType::EndsWith);
+
+    assert!(filter(Test::AnyOf, vec![]).matches(&card));
+    assert!(
+      filter(Test::AnyOf, vec![fn_ada.clone(), email_nope.clone()])
+        .matches(&card)
+    );
+    assert!(
+      !filter(Test::AllOf, vec![fn_ada.clone(), email_nope]).matches(&card)
+    );
+    // Any one EMAIL instance may satisfy the match.
+    assert!(filter(Test::AllOf, vec![fn_ada, email_home]).matches(&card));
+  }
+
+  #[test]
+  fn is_not_defined_and_param_filters() {
+    let card = card();
+    let missing = |name: &str| PropFilter {
+      name: name.to_string(),
+      is_not_defined: true,
+      ..Default::default()
+    };
+    assert!(filter(Test::AnyOf, vec![missing("NOTE")]).matches(&card));
+    assert!(!filter(Test::AnyOf, vec![missing("EMAIL")]).matches(&card));
+
+    let tel_type = |value: &str| PropFilter {
+      name: "TEL".to_string(),
+      param_filters: vec![ParamFilter {
+        name:           "TYPE".to_string(),
+        is_not_defined: false,
+        text_match:     Some(TextMatch {
+          text: value.to_string(),
+          match_type: MatchType::Equals,
+          ..Default::default()
+        }),
+      }],
+      ..Default::default()
+    };
+    assert!(filter(Test::AnyOf, vec![tel_type("cell")]).matches(&card));
+    assert!(!filter(Test::AnyOf, vec![tel_type("fax")]).matches(&card));
+  }
+
+  #[test]
+  fn push_down_only_when_it_cannot_lose_matches() {
+    let starts = text("FN", "Ada Lo", MatchType::StartsWith);
+    let queries = filter(Test::AnyOf, vec![starts.clone()])
+      .store_queries()
+      .unwrap();
+    assert_eq!(queries[0].text.as_deref(), Some("Ada Lo*"));
+    assert_eq!(queries[0].fact_types, ["name"]);
+
+    // `contains` narrows by fact type but cannot use the word index.
+    let contains = text("EMAIL", "example", MatchType::Contains);
+    let queries = filter(Test::AnyOf, vec![contains.clone()])
+      .store_queries()
+      .unwrap();
+    assert_eq!(queries[0].text, None);
+    assert_eq!(queries[0].fact_types, ["email"]);
+
+    // An unmapped property in a disjunction could match anything.
+    let uid = text("UID", "x", MatchType::Contains);
+    assert!(
+      filter(Test::AnyOf, vec![starts.clone(), uid.clone()])
+        .store_queries()
+        .is_none()
+    );
+    assert_eq!(
+      filter(Test::AllOf, vec![uid, contains])
+        .store_queries()
+        .unwrap()
+        .len(),
+      1
+    );
+
+    // A negated match still needs the property, but not the words.
+    let negated = PropFilter {
+      text_matches: vec![TextMatch {
+        negate: true,
+        ..starts.text_matches[0].clone()
+      }],
+      ..starts
+    };
+    let queries = filter(Test::AnyOf, vec![negated]).store_queries().unwrap();
+    assert_eq!(queries[0].text, None);
+
+    let missing = PropFilter {
+      name: "FN".to_string(),
+      is_not_defined: true,
+      ..Default::default()
+    };
+    assert!(filter(Test::AnyOf, vec![missing]).store_queries().is_none());
+  }
+}
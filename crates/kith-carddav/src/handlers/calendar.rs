@@ -0,0 +1,308 @@
+//! Handlers for the read-only birthday calendar (see [`crate::calendar`]).
+//!
+//! Each account's calendar home holds one calendar, `birthdays`, served over
+//! CalDAV (PROPFIND, `calendar-multiget` and `calendar-query`, GET of single
+//! events) and as a whole `birthdays.ics` feed for clients that only
+//! subscribe to URLs.
+
+use axum::{
+  body::Body,
+  http::{HeaderMap, Method, StatusCode, header},
+  response::{IntoResponse, Response},
+};
+use chrono::Datelike;
+use kith_core::{store::ContactStore, subject::SubjectKind};
+use percent_encoding::percent_decode_str;
+
+use super::{calendar_home_href, multistatus_response};
+use crate::{
+  AppState,
+  calendar::{self, BIRTHDAYS, CONTENT_TYPE, Event},
+  error::Error,
+  xml::{
+    MultistatusBuilder, PropName, Property, ReportKind, ResourceType,
+    parse_propfind, parse_report,
+  },
+};
+
+const DISPLAY_NAME: &str = "Birthdays";
+
+/// Every event of every person in the store, in calendar order.
+async fn all_events<S>(state: &AppState<S>) -> Result<Vec<Event>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let subjects = state
+    .store
+    .list_subjects(Some(SubjectKind::Person))
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+
+  let mut events = Vec::new();
+  for subject in subjects {
+    if let Some(view) = state
+      .store
+      .materialize(subject.subject_id, None, None)
+      .await
+      .map_err(|e| Error::Store(Box::new(e)))?
+    {
+      events.extend(calendar::events(&view));
+    }
+  }
+  events.sort_by(|a, b| {
+    (a.date.month(), a.date.day(), &a.name).cmp(&(
+      b.date.month(),
+      b.date.day(),
+      &b.name,
+    ))
+  });
+  Ok(events)
+}
+
+/// The event served as resource `name`.
+async fn find_event<S>(state: &AppState<S>, name: &str) -> Result<Event, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  all_events(state)
+    .await?
+    .into_iter()
+    .find(|e| e.resource_name() == name)
+    .ok_or(Error::NotFound)
+}
+
+fn collection_props(events: &[Event]) -> Vec<Property> {
+  vec![
+    Property::ResourceType(vec![
+      ResourceType::Collection,
+      ResourceType::Calendar,
+    ]),
+    Property::DisplayName(DISPLAY_NAME.to_string()),
+    Property::SupportedCalendarComponentSet,
+    Property::GetCTag(calendar::calendar_etag(events)),
+    Property::ReadOnlyPrivilegeSet,
+  ]
+}
+
+fn event_props(event: &Event) -> Vec<Property> {
+  vec![
+    Property::GetContentType(CONTENT_TYPE.to_string()),
+    Property::GetETag(event.etag.clone()),
+  ]
+}
+
+/// PROPFIND /dav/calendars/:user/  — calendar home set
+pub async fn home_set<S>(
+  state: &AppState<S>,
+  depth: u8,
+  body: &[u8],
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let _req = parse_propfind(body)?;
+  let home = calendar_home_href(state);
+
+  let mut ms = MultistatusBuilder::new();
+  ms.response(&format!("{home}/")).propstat_ok(&[
+    Property::ResourceType(vec![ResourceType::Collection]),
+    Property::DisplayName("Calendars".to_string()),
+  ]);
+
+  if depth >= 1 {
+    let events = all_events(state).await?;
+    ms.response(&format!("{home}/{BIRTHDAYS}/"))
+      .propstat_ok(&collection_props(&events));
+  }
+
+  Ok(multistatus_response(ms.finish()))
+}
+
+/// PROPFIND /dav/calendars/:user/birthdays/  — the calendar (Depth 0 or 1)
+pub async fn collection<S>(
+  state: &AppState<S>,
+  depth: u8,
+  body: &[u8],
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  if depth > 1 {
+    return Ok(
+      (StatusCode::FORBIDDEN, "Depth: infinity not supported").into_response(),
+    );
+  }
+
+  let _req = parse_propfind(body)?;
+  let events = all_events(state).await?;
+  let coll_href = format!("{}/{BIRTHDAYS}", calendar_home_href(state));
+
+  let mut ms = MultistatusBuilder::new();
+  ms.response(&format!("{coll_href}/"))
+    .propstat_ok(&collection_props(&events));
+
+  if depth >= 1 {
+    for event in &events {
+      ms.response(&format!("{coll_href}/{}", event.resource_name()))
+        .propstat_ok(&event_props(event));
+    }
+  }
+
+  Ok(multistatus_response(ms.finish()))
+}
+
+/// REPORT /dav/calendars/:user/birthdays/
+///
+/// `calendar-multiget` returns the events asked for; `calendar-query`
+/// returns every event, whatever its filter, since the calendar is small
+/// and clients filter by time range themselves.
+pub async fn report<S>(
+  state: &AppState<S>,
+  body: &[u8],
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let report = parse_report(body)?;
+  let events = all_events(state).await?;
+  let coll_href = format!("{}/{BIRTHDAYS}", calendar_home_href(state));
+  // Without a `prop` element the parser asks for the ETag and the data.
+  let with_data = report
+    .props
+    .iter()
+    .any(|p| matches!(p, PropName::CalendarData | PropName::AddressData));
+  let props = |event: &Event| {
+    let mut props = event_props(event);
+    if with_data {
+      props.push(Property::CalendarData(event.to_ics()));
+    }
+    props
+  };
+
+  let mut ms = MultistatusBuilder::new();
+  match report.kind {
+    ReportKind::Multiget => {
+      for href in &report.hrefs {
+        let name = href.trim_end_matches('/').rsplit('/').next();
+        let name = name.and_then(|n| percent_decode_str(n).decode_utf8().ok());
+        let event = name.and_then(|name| {
+          events.iter().find(|e| e.resource_name() == name.as_ref())
+        });
+        match event {
+          Some(event) => {
+            ms.response(&format!("{coll_href}/{}", event.resource_name()))
+              .propstat_ok(&props(event));
+          }
+          None => {
+            ms.response(href).status_not_found();
+          }
+        }
+      }
+    }
+    ReportKind::Query => {
+      for event in &events {
+        ms.response(&format!("{coll_href}/{}", event.resource_name()))
+          .propstat_ok(&props(event));
+      }
+    }
+    ReportKind::SyncCollection => {
+      return Err(Error::BadRequest(
+        "sync-collection is not supported on calendars".into(),
+      ));
+    }
+  }
+
+  Ok(multistatus_response(ms.finish()))
+}
+
+/// GET/HEAD /dav/calendars/:user/birthdays/:fact_id.ics  — one event
+pub async fn event<S>(
+  state: &AppState<S>,
+  method: &Method,
+  headers: &HeaderMap,
+  name: &str,
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let event = find_event(state, name).await?;
+  Ok(ics_response(method, headers, &event.etag, event.to_ics()))
+}
+
+/// PROPFIND /dav/calendars/:user/birthdays/:fact_id.ics
+pub async fn event_propfind<S>(
+  state: &AppState<S>,
+  name: &str,
+  body: &[u8],
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let _req = parse_propfind(body)?;
+  let event = find_event(state, name).await?;
+  let href = format!(
+    "{}/{BIRTHDAYS}/{}",
+    calendar_home_href(state),
+    event.resource_name()
+  );
+
+  let mut ms = MultistatusBuilder::new();
+  ms.response(&href).propstat_ok(&event_props(&event));
+  Ok(multistatus_response(ms.finish()))
+}
+
+/// GET/HEAD /dav/calendars/:user/birthdays.ics  — the whole calendar, for
+/// subscriptions
+pub async fn feed<S>(
+  state: &AppState<S>,
+  method: &Method,
+  headers: &HeaderMap,
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let events = all_events(state).await?;
+  let etag = calendar::calendar_etag(&events);
+  let ics = calendar::to_ics(DISPLAY_NAME, &events);
+  Ok(ics_response(method, headers, &etag, ics))
+}
+
+/// Serve `ics`, or a 304 if the client already has `etag`.
+fn ics_response(
+  method: &Method,
+  headers: &HeaderMap,
+  etag: &str,
+  ics: String,
+) -> Response {
+  let not_modified = headers
+    .get(header::IF_NONE_MATCH)
+    .and_then(|v| v.to_str().ok())
+    .is_some_and(|v| v.split(',').any(|t| t.trim() == etag));
+  if not_modified {
+    return Response::builder()
+      .status(StatusCode::NOT_MODIFIED)
+      .header(header::ETAG, etag)
+      .body(Body::empty())
+      .unwrap();
+  }
+
+  let builder = Response::builder()
+    .status(StatusCode::OK)
+    .header(header::CONTENT_TYPE, CONTENT_TYPE)
+    .header(header::ETAG, etag)
+    .header(header::CONTENT_LENGTH, ics.len());
+
+  if *method == Method::HEAD {
+    builder.body(Body::empty()).unwrap()
+  } else {
+    builder.body(Body::from(ics)).unwrap()
+  }
+}
@@ -1,24 +1,29 @@
-//! DELETE handler — retract all active facts for a contact.
+//! DELETE handler — remove a contact from an address book.
 //!
-//! The subject row itself is preserved (subjects are permanent envelopes).
+//! If the contact belongs to no other book, all its active facts are
+//! retracted as well. The subject row itself is preserved (subjects are
+//! permanent envelopes).
 
 use axum::{
   http::StatusCode,
   response::{IntoResponse, Response},
 };
-use kith_core::store::ContactStore;
+use kith_core::store::{Changeset, ContactStore};
 
-use crate::{AppState, error::Error, handlers::propfind::parse_uid};
+use super::{addressbook, resolve_resource};
+use crate::{AppState, error::Error};
 
 pub async fn handler<S>(
   state: &AppState<S>,
+  ab: &str,
   uid_vcf: &str,
 ) -> Result<Response, Error>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let uid = parse_uid(uid_vcf)?;
+  let book = addressbook(state, ab).await?;
+  let uid = resolve_resource(state, uid_vcf).await?;
 
   state
     .store
@@ -29,7 +34,7 @@ where
 
   let facts = state
     .store
-    .get_facts(uid, None, false)
+    .get_facts(uid, None, None, false)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?;
 
@@ -39,13 +44,37 @@ where
     return Err(Error::NotFound);
   }
 
-  for rf in facts {
-    state
-      .store
-      .retract(rf.fact.fact_id, Some("Deleted via CardDAV".to_string()))
-      .await
-      .map_err(|e| Error::Store(Box::new(e)))?;
+  let books = state
+    .store
+    .subject_addressbooks(uid)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  if !books
+    .iter()
+    .any(|b| b.addressbook_id == book.addressbook_id)
+  {
+    return Err(Error::NotFound);
+  }
+
+  // Only the last book to let go of a contact deletes it. The retractions
+  // and the membership change go in one transaction, so a failure cannot
+  // leave a half-deleted contact behind.
+  let mut changeset = Changeset {
+    addressbook_removals: vec![(book.addressbook_id, uid)],
+    ..Default::default()
+  };
+  if books.len() == 1 {
+    let reason = Some("Deleted via CardDAV".to_string());
+    changeset.retractions = facts
+      .into_iter()
+      .map(|rf| (rf.fact.fact_id, reason.clone()))
+      .collect();
   }
+  state
+    .store
+    .apply_changeset(changeset)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
 
   Ok(StatusCode::NO_CONTENT.into_response())
 }
@@ -1,42 +1,56 @@
 //! GET and HEAD handlers for vCard resources.
+//!
+//! The vCard version follows the `Accept` header (see
+//! [`accepted_version`]); each version has its own ETag. Photos are embedded
+//! from `photo_dir`.
 
 use axum::{
   body::Body,
-  http::{Method, StatusCode, header},
+  http::{HeaderMap, Method, StatusCode, header},
   response::Response,
 };
 use kith_core::store::ContactStore;
 
-use crate::{
-  AppState, error::Error, etag::compute_etag, handlers::propfind::parse_uid,
+use super::{
+  accepted_version, addressbook, in_addressbook, render_vcard,
+  resolve_resource, vcard_content_type,
 };
+use crate::{AppState, error::Error, etag::compute_etag};
 
 pub async fn handler<S>(
   state: &AppState<S>,
   method: &Method,
+  headers: &HeaderMap,
+  ab: &str,
   uid_vcf: &str,
 ) -> Result<Response, Error>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let uid = parse_uid(uid_vcf)?;
+  let book = addressbook(state, ab).await?;
+  let uid = resolve_resource(state, uid_vcf).await?;
+  if !in_addressbook(state, &book, uid).await? {
+    return Err(Error::NotFound);
+  }
 
   let view = state
     .store
-    .materialize(uid, None)
+    .materialize(uid, None, None)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?
     .filter(|v| !v.active_facts.is_empty())
     .ok_or(Error::NotFound)?;
 
-  let etag = compute_etag(&view);
-  let vcard = kith_vcard::serialize(&view)?;
+  let version = accepted_version(headers);
+  let etag = compute_etag(&view, version);
+  let vcard = render_vcard(state, &view, version).await?;
 
   let builder = Response::builder()
     .status(StatusCode::OK)
-    .header(header::CONTENT_TYPE, "text/vcard; charset=utf-8")
+    .header(header::CONTENT_TYPE, vcard_content_type(version))
     .header(header::ETAG, &etag)
+    .header(header::VARY, "Accept")
     .header(header::CONTENT_LENGTH, vcard.len());
 
   if *method == Method::HEAD {
@@ -0,0 +1,57 @@
+//! MKCOL handler — create an address book (RFC 5689 extended MKCOL).
+
+use axum::{
+  http::StatusCode,
+  response::{IntoResponse, Response},
+};
+use kith_core::{addressbook::NewAddressBook, store::ContactStore};
+
+use crate::{AppState, error::Error, xml::parse_mkcol};
+
+pub async fn handler<S>(
+  state: &AppState<S>,
+  ab: &str,
+  body: &[u8],
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let req = parse_mkcol(body)?;
+  if !req.is_addressbook {
+    return Ok(
+      (
+        StatusCode::FORBIDDEN,
+        "only address book collections can be created here",
+      )
+        .into_response(),
+    );
+  }
+
+  // RFC 4918 §9.3.1: MKCOL on an existing resource is 405.
+  let existing = state
+    .store
+    .get_addressbook(ab)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  if existing.is_some() {
+    return Ok(StatusCode::METHOD_NOT_ALLOWED.into_response());
+  }
+
+  let book = state
+    .store
+    .create_addressbook(NewAddressBook {
+      name:         ab.to_string(),
+      display_name: req.display_name,
+      description:  req.description,
+    })
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  tracing::info!(
+    addressbook = %book.name,
+    id = %book.addressbook_id,
+    "address book created",
+  );
+
+  Ok(StatusCode::CREATED.into_response())
+}
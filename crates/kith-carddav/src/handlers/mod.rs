@@ -1,5 +1,7 @@
+pub mod calendar;
 pub mod delete;
 pub mod get;
+pub mod mkcol;
 pub mod options;
 pub mod propfind;
 pub mod put;
@@ -7,13 +9,244 @@ pub mod report;
 
 use axum::{
   body::Body,
-  http::{StatusCode, header},
+  http::{HeaderMap, StatusCode, header},
   response::Response,
 };
+use kith_core::{
+  addressbook::{AddressBook, SyncToken},
+  lifecycle::ContactView,
+  store::ContactStore,
+};
+use kith_vcard::{SerializeOptions, Version};
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
+use uuid::Uuid;
+
+use crate::{AppState, error::Error, handlers::propfind::parse_uid, photo};
 
 pub(super) const CONTENT_TYPE_MULTISTATUS: &str =
   "application/xml; charset=utf-8";
 
+/// Prefix that turns a [`SyncToken`] into the URI RFC 6578 requires.
+const SYNC_TOKEN_PREFIX: &str = "urn:kith:sync:";
+
+pub(super) fn sync_token_uri(token: &SyncToken) -> String {
+  format!("{SYNC_TOKEN_PREFIX}{token}")
+}
+
+pub(super) fn parse_sync_token_uri(uri: &str) -> Result<SyncToken, Error> {
+  uri
+    .strip_prefix(SYNC_TOKEN_PREFIX)
+    .and_then(|t| t.parse().ok())
+    .ok_or_else(|| Error::InvalidSyncToken(uri.to_string()))
+}
+
+/// `Content-Type` of a vCard resource served as `version`.
+pub(super) fn vcard_content_type(version: Version) -> &'static str {
+  match version {
+    Version::V3 => "text/vcard; charset=utf-8; version=3.0",
+    Version::V4 => "text/vcard; charset=utf-8",
+  }
+}
+
+/// The vCard version a client asks for in its `Accept` header.
+///
+/// The most preferred `text/vcard` range wins; one without a `version`
+/// parameter, or a wildcard, means 4.0. Without any usable range the answer
+/// is 4.0 as well: clients sending odd headers get data, not a 406.
+pub(super) fn accepted_version(headers: &HeaderMap) -> Version {
+  let mut best: Option<(f32, Version)> = None;
+  let ranges = headers
+    .get_all(header::ACCEPT)
+    .iter()
+    .filter_map(|v| v.to_str().ok())
+    .flat_map(|v| v.split(','));
+  for range in ranges {
+    let mut parts = range.split(';').map(str::trim);
+    let media = parts.next().unwrap_or_default().to_ascii_lowercase();
+    let mut q = 1.0;
+    let mut version = Some(Version::V4);
+    for param in parts {
+      let Some((key, value)) = param.split_once('=') else {
+        continue;
+      };
+      let value = value.trim().trim_matches('"');
+      match key.trim().to_ascii_lowercase().as_str() {
+        "q" => q = value.parse().unwrap_or(0.0),
+        "version" => version = Version::parse(value),
+        _ => {}
+      }
+    }
+    let version = match media.as_str() {
+      "text/vcard" | "text/x-vcard" => version,
+      "text/*" | "*/*" => Some(Version::V4),
+      _ => None,
+    };
+    if let Some(version) = version
+      && q > 0.0
+      && best.is_none_or(|(best_q, _)| q > best_q)
+    {
+      best = Some((q, version));
+    }
+  }
+  best.map(|(_, v)| v).unwrap_or_default()
+}
+
+/// Serialize `view` as a `version` vCard, with the client's `UID` and its
+/// photos embedded.
+pub(super) async fn render_vcard<S>(
+  state: &AppState<S>,
+  view: &ContactView,
+  version: Version,
+) -> Result<String, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let options = SerializeOptions {
+    uid:    client_uid(state, view.subject.subject_id).await?,
+    photos: photo::load(&state.user.photo_dir, view).await,
+  };
+  Ok(kith_vcard::serialize_with(view, version, &options)?)
+}
+
+/// The `UID` a client gave `subject_id`, if any.
+pub(super) async fn client_uid<S>(
+  state: &AppState<S>,
+  subject_id: Uuid,
+) -> Result<Option<String>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let resource = state
+    .store
+    .subject_resource(subject_id)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  Ok(resource.and_then(|r| r.uid))
+}
+
+// ─── Resource names ──────────────────────────────────────────────────────────
+
+/// Characters escaped in a resource name when it is put into a href: those
+/// not allowed in a path segment, plus `%` itself.
+const SEGMENT: &AsciiSet = &CONTROLS
+  .add(b' ')
+  .add(b'"')
+  .add(b'#')
+  .add(b'%')
+  .add(b'/')
+  .add(b'<')
+  .add(b'>')
+  .add(b'?')
+  .add(b'[')
+  .add(b'\\')
+  .add(b']')
+  .add(b'^')
+  .add(b'`')
+  .add(b'{')
+  .add(b'|')
+  .add(b'}');
+
+/// The subject a resource name (the decoded `{name}` of
+/// `/dav/addressbooks/{user}/{ab}/{name}`) refers to.
+///
+/// Names clients chose are looked up in the store. Any other name is read as
+/// `{subject_id}.vcf`, falling back to the v5 UUID of [`parse_uid`] for
+/// contacts written before names were recorded.
+pub(super) async fn resolve_resource<S>(
+  state: &AppState<S>,
+  name: &str,
+) -> Result<Uuid, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let resource = state
+    .store
+    .resource_by_name(name)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  match resource {
+    Some(r) => Ok(r.subject_id),
+    None => parse_uid(name),
+  }
+}
+
+/// The resource name `subject_id` is served under: the one its client chose,
+/// else `{subject_id}.vcf`.
+pub(super) async fn resource_name<S>(
+  state: &AppState<S>,
+  subject_id: Uuid,
+) -> Result<String, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let resource = state
+    .store
+    .subject_resource(subject_id)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  Ok(match resource {
+    Some(r) => r.name,
+    None => format!("{subject_id}.vcf"),
+  })
+}
+
+/// The absolute href of the principal of `state`'s account, with a trailing
+/// slash.
+pub(crate) fn principal_href<S: ContactStore>(state: &AppState<S>) -> String {
+  format!(
+    "{}/dav/principals/{}/",
+    state.config.base_url,
+    utf8_percent_encode(&state.user.username, SEGMENT)
+  )
+}
+
+/// The absolute href of the address book home set of `state`'s account,
+/// without a trailing slash.
+pub(crate) fn home_href<S: ContactStore>(state: &AppState<S>) -> String {
+  format!(
+    "{}/dav/addressbooks/{}",
+    state.config.base_url,
+    utf8_percent_encode(&state.user.username, SEGMENT)
+  )
+}
+
+/// The absolute href of the calendar home set of `state`'s account, without
+/// a trailing slash.
+pub(super) fn calendar_home_href<S: ContactStore>(
+  state: &AppState<S>,
+) -> String {
+  format!(
+    "{}/dav/calendars/{}",
+    state.config.base_url,
+    utf8_percent_encode(&state.user.username, SEGMENT)
+  )
+}
+
+/// `segment` percent-encoded for use as one segment of a href path.
+pub(crate) fn encode_segment(segment: &str) -> String {
+  utf8_percent_encode(segment, SEGMENT).to_string()
+}
+
+/// The absolute href of address book `ab` in the home set at `home` (see
+/// [`home_href`]), with a trailing slash.
+pub(super) fn collection_href(home: &str, ab: &str) -> String {
+  format!("{home}/{}/", utf8_percent_encode(ab, SEGMENT))
+}
+
+/// The absolute href of resource `name` in address book `ab` of the home set
+/// at `home` (see [`home_href`]).
+pub(super) fn resource_href(home: &str, ab: &str, name: &str) -> String {
+  format!(
+    "{}{}",
+    collection_href(home, ab),
+    utf8_percent_encode(name, SEGMENT)
+  )
+}
+
 pub(super) fn multistatus_response(body: Vec<u8>) -> Response {
   Response::builder()
     .status(StatusCode::MULTI_STATUS)
@@ -21,3 +254,74 @@ pub(super) fn multistatus_response(body: Vec<u8>) -> Response {
     .body(Body::from(body))
     .unwrap()
 }
+
+/// Look up the address book named by the `{ab}` path segment.
+pub(super) async fn addressbook<S>(
+  state: &AppState<S>,
+  ab: &str,
+) -> Result<AddressBook, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  state
+    .store
+    .get_addressbook(ab)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?
+    .ok_or(Error::NotFound)
+}
+
+/// Whether `uid` is currently a member of `book`.
+pub(super) async fn in_addressbook<S>(
+  state: &AppState<S>,
+  book: &AddressBook,
+  uid: Uuid,
+) -> Result<bool, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let books = state
+    .store
+    .subject_addressbooks(uid)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  Ok(
+    books
+      .iter()
+      .any(|b| b.addressbook_id == book.addressbook_id),
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use axum::http::HeaderValue;
+
+  use super::*;
+
+  fn accept(value: &str) -> Version {
+    let mut headers = HeaderMap::new();
+    headers.insert(header::ACCEPT, HeaderValue::from_str(value).unwrap());
+    accepted_version(&headers)
+  }
+
+  #[test]
+  fn accept_header_selects_vcard_version() {
+    assert_eq!(accepted_version(&HeaderMap::new()), Version::V4);
+    assert_eq!(accept("text/vcard;version=3.0"), Version::V3);
+    assert_eq!(accept("text/vcard; version=\"3.0\""), Version::V3);
+    assert_eq!(accept("text/vcard"), Version::V4);
+    assert_eq!(accept("*/*"), Version::V4);
+    assert_eq!(
+      accept("text/vcard;version=4.0;q=0.5, text/vcard;version=3.0"),
+      Version::V3
+    );
+    assert_eq!(
+      accept("text/vcard;version=3.0;q=0.2, text/vcard;version=4.0"),
+      Version::V4
+    );
+    // Unsupported versions are ignored rather than refused.
+    assert_eq!(accept("text/vcard;version=2.1"), Version::V4);
+  }
+}
@@ -10,12 +10,12 @@ pub fn handler() -> Response {
     (
       header::ALLOW,
       HeaderValue::from_static(
-        "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT",
+        "OPTIONS, GET, HEAD, PUT, DELETE, PROPFIND, REPORT, MKCOL",
       ),
     ),
     (
       axum::http::HeaderName::from_static("dav"),
-      HeaderValue::from_static("1, 3, addressbook"),
+      HeaderValue::from_static("1, 3, addressbook, calendar-access"),
     ),
   ])
     .into_response()
@@ -1,13 +1,18 @@
 //! PROPFIND handlers for principal, home-set, collection, and resource.
 
 use axum::{
-  http::StatusCode,
+  http::{HeaderMap, StatusCode},
   response::{IntoResponse, Response},
 };
-use kith_core::{store::ContactStore, subject::SubjectKind};
+use kith_core::{addressbook::AddressBook, store::ContactStore};
 use uuid::Uuid;
 
-use super::multistatus_response;
+use super::{
+  accepted_version, addressbook, calendar_home_href, collection_href,
+  home_href, in_addressbook, multistatus_response, principal_href,
+  render_vcard, resolve_resource, resource_href, resource_name, sync_token_uri,
+  vcard_content_type,
+};
 use crate::{
   AppState,
   error::Error,
@@ -15,9 +20,13 @@ use crate::{
   xml::{MultistatusBuilder, Property, ResourceType, parse_propfind},
 };
 
-/// PROPFIND /dav/  — principal
+/// PROPFIND /dav/ and /dav/principals/:user/  — principal
+///
+/// `href` is the URL the client asked about; either way the response points
+/// at the account's own principal and home set.
 pub async fn principal<S>(
   state: &AppState<S>,
+  href: &str,
   body: &[u8],
 ) -> Result<Response, Error>
 where
@@ -25,22 +34,21 @@ where
   S::Error: std::error::Error + Send + Sync + 'static,
 {
   let _req = parse_propfind(body)?;
-  let base = &state.config.base_url;
-  let href = format!("{base}/dav/");
-  let home = format!("{base}/dav/addressbooks/");
+  let home = format!("{}/", home_href(state));
 
   let mut ms = MultistatusBuilder::new();
-  ms.response(&href).propstat_ok(&[
+  ms.response(href).propstat_ok(&[
     Property::ResourceType(vec![ResourceType::Principal]),
-    Property::DisplayName(state.config.auth_username.clone()),
-    Property::CurrentUserPrincipal(href.clone()),
+    Property::DisplayName(state.user.username.clone()),
+    Property::CurrentUserPrincipal(principal_href(state)),
     Property::AddressbookHomeSet(home),
+    Property::CalendarHomeSet(format!("{}/", calendar_home_href(state))),
   ]);
 
   Ok(multistatus_response(ms.finish()))
 }
 
-/// PROPFIND /dav/addressbooks/  — home set
+/// PROPFIND /dav/addressbooks/:user/  — home set
 pub async fn home_set<S>(
   state: &AppState<S>,
   depth: u8,
@@ -51,36 +59,38 @@ where
   S::Error: std::error::Error + Send + Sync + 'static,
 {
   let _req = parse_propfind(body)?;
-  let base = &state.config.base_url;
-  let href = format!("{base}/dav/addressbooks/");
+  let home = home_href(state);
 
   let mut ms = MultistatusBuilder::new();
-  ms.response(&href).propstat_ok(&[
+  ms.response(&format!("{home}/")).propstat_ok(&[
     Property::ResourceType(vec![ResourceType::Collection]),
     Property::DisplayName("Address Books".to_string()),
   ]);
 
-  // At Depth:1 list the configured addressbook so clients can discover it.
+  // At Depth:1 list every address book so clients can discover them.
   if depth >= 1 {
-    let ab = &state.config.addressbook;
-    let ab_href = format!("{base}/dav/addressbooks/{ab}/");
-    ms.response(&ab_href).propstat_ok(&[
-      Property::ResourceType(vec![
-        ResourceType::Collection,
-        ResourceType::Addressbook,
-      ]),
-      Property::DisplayName(ab.clone()),
-      Property::SupportedAddressData,
-      Property::AddressbookDescription(format!("{ab} address book")),
-    ]);
+    let books = state
+      .store
+      .list_addressbooks()
+      .await
+      .map_err(|e| Error::Store(Box::new(e)))?;
+    for book in &books {
+      let ab_href = collection_href(&home, &book.name);
+      let props = addressbook_props(state, book).await?;
+      ms.response(&ab_href).propstat_ok(&props);
+    }
   }
 
   Ok(multistatus_response(ms.finish()))
 }
 
-/// PROPFIND /dav/addressbooks/:ab/  — collection (Depth 0 or 1)
+/// PROPFIND /dav/addressbooks/:user/:ab/  — collection (Depth 0 or 1)
+///
+/// Member ETags are those of the representation the `Accept` header selects,
+/// so they compare equal to what the same client gets from GET.
 pub async fn collection<S>(
   state: &AppState<S>,
+  headers: &HeaderMap,
   ab: &str,
   depth: u8,
   body: &[u8],
@@ -96,54 +106,38 @@ where
   }
 
   let _req = parse_propfind(body)?;
-  let base = &state.config.base_url;
-  let coll_href = format!("{base}/dav/addressbooks/{ab}/");
-
-  let ctag = state
-    .store
-    .collection_ctag()
-    .await
-    .map_err(|e| Error::Store(Box::new(e)))?
-    .map(|dt| dt.to_rfc3339())
-    .unwrap_or_else(|| "empty".to_string());
+  let book = addressbook(state, ab).await?;
+  let home = home_href(state);
+  let coll_href = collection_href(&home, ab);
 
   let mut ms = MultistatusBuilder::new();
-  ms.response(&coll_href).propstat_ok(&[
-    Property::ResourceType(vec![
-      ResourceType::Collection,
-      ResourceType::Addressbook,
-    ]),
-    Property::DisplayName(ab.to_string()),
-    Property::SupportedAddressData,
-    Property::AddressbookDescription(format!("{ab} address book")),
-    Property::GetCTag(ctag),
-  ]);
+  let props = addressbook_props(state, &book).await?;
+  ms.response(&coll_href).propstat_ok(&props);
 
   if depth >= 1 {
+    let version = accepted_version(headers);
     let subjects = state
       .store
-      .list_subjects(Some(SubjectKind::Person))
+      .addressbook_members(book.addressbook_id)
       .await
       .map_err(|e| Error::Store(Box::new(e)))?;
 
     for subject in subjects {
       if let Some(view) = state
         .store
-        .materialize(subject.subject_id, None)
+        .materialize(subject.subject_id, None, None)
         .await
         .map_err(|e| Error::Store(Box::new(e)))?
         .filter(|v| !v.active_facts.is_empty())
       {
-        let etag = compute_etag(&view);
-        let vcard = kith_vcard::serialize(&view)?;
-        let content_len = vcard.len() as u64;
-        let resource_href =
-          format!("{base}/dav/addressbooks/{ab}/{}.vcf", subject.subject_id);
-
-        ms.response(&resource_href).propstat_ok(&[
-          Property::GetContentType("text/vcard; charset=utf-8".to_string()),
+        // No getcontentlength: it would mean serialising every vCard just
+        // to measure it. Clients fetch the data they need by ETag.
+        let etag = compute_etag(&view, version);
+        let name = resource_name(state, subject.subject_id).await?;
+
+        ms.response(&resource_href(&home, ab, &name)).propstat_ok(&[
+          Property::GetContentType(vcard_content_type(version).to_string()),
           Property::GetETag(etag),
-          Property::GetContentLength(content_len),
         ]);
       }
     }
@@ -152,9 +146,10 @@ where
   Ok(multistatus_response(ms.finish()))
 }
 
-/// PROPFIND /dav/addressbooks/:ab/:uid.vcf  — single resource
+/// PROPFIND /dav/addressbooks/:user/:ab/:uid.vcf  — single resource
 pub async fn resource<S>(
   state: &AppState<S>,
+  headers: &HeaderMap,
   ab: &str,
   uid_vcf: &str,
   body: &[u8],
@@ -164,21 +159,26 @@ where
   S::Error: std::error::Error + Send + Sync + 'static,
 {
   let _req = parse_propfind(body)?;
-  let uid = parse_uid(uid_vcf)?;
+  let book = addressbook(state, ab).await?;
+  let uid = resolve_resource(state, uid_vcf).await?;
+  if !in_addressbook(state, &book, uid).await? {
+    return Err(Error::NotFound);
+  }
 
   let view = state
     .store
-    .materialize(uid, None)
+    .materialize(uid, None, None)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?
     .filter(|v| !v.active_facts.is_empty())
     .ok_or(Error::NotFound)?;
 
-  let etag = compute_etag(&view);
-  let vcard = kith_vcard::serialize(&view)?;
+  let version = accepted_version(headers);
+  let etag = compute_etag(&view, version);
+  let vcard = render_vcard(state, &view, version).await?;
   let content_len = vcard.len() as u64;
-  let base = &state.config.base_url;
-  let href = format!("{base}/dav/addressbooks/{ab}/{uid_vcf}");
+  let href =
+    resource_href(&home_href(state), ab, &resource_name(state, uid).await?);
 
   let last_modified = view
     .active_facts
@@ -192,7 +192,7 @@ where
 
   let mut ms = MultistatusBuilder::new();
   ms.response(&href).propstat_ok(&[
-    Property::GetContentType("text/vcard; charset=utf-8".to_string()),
+    Property::GetContentType(vcard_content_type(version).to_string()),
     Property::GetETag(etag),
     Property::GetContentLength(content_len),
     Property::GetLastModified(lm_str),
@@ -201,6 +201,53 @@ where
   Ok(multistatus_response(ms.finish()))
 }
 
+/// Collection-level properties of an address book, including its change
+/// tokens.
+async fn addressbook_props<S>(
+  state: &AppState<S>,
+  book: &AddressBook,
+) -> Result<Vec<Property>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let ctag = state
+    .store
+    .collection_ctag(book.addressbook_id)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?
+    .map(|dt| dt.to_rfc3339())
+    .unwrap_or_else(|| "empty".to_string());
+  let sync_token = state
+    .store
+    .sync_token()
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+
+  Ok(vec![
+    Property::ResourceType(vec![
+      ResourceType::Collection,
+      ResourceType::Addressbook,
+    ]),
+    Property::DisplayName(
+      book
+        .display_name
+        .clone()
+        .unwrap_or_else(|| book.name.clone()),
+    ),
+    Property::SupportedAddressData,
+    Property::AddressbookDescription(
+      book
+        .description
+        .clone()
+        .unwrap_or_else(|| format!("{} address book", book.name)),
+    ),
+    Property::GetCTag(ctag),
+    Property::SyncToken(sync_token_uri(&sync_token)),
+    Property::SupportedReportSet,
+  ])
+}
+
 /// Namespace UUID for deriving stable v5 UUIDs from non-UUID contact UIDs.
 ///
 /// Generated once for kith; must never change or existing contacts will
@@ -208,13 +255,13 @@ where
 const UID_NAMESPACE: Uuid =
   Uuid::from_u128(0x6b697468_0000_5000_8000_000000000000);
 
-/// Parse `{uuid}.vcf` → `Uuid`.
+/// Parse `{uuid}.vcf` → `Uuid`, for resource names no client recorded (see
+/// [`resolve_resource`](super::resolve_resource)).
 ///
 /// If the path component is already a valid UUID it is used as-is.
-/// Otherwise a deterministic UUID v5 is derived from the raw string so that
-/// clients using non-UUID UIDs (e.g. Thunderbird's `alice@example.com` or
-/// braced `{…}` identifiers) always land on the same stable address without
-/// requiring any schema changes.
+/// Otherwise a deterministic UUID v5 is derived from the raw string, which is
+/// where contacts with non-UUID names (e.g. Thunderbird's
+/// `alice@example.com`) lived before their names were recorded.
 pub fn parse_uid(uid_vcf: &str) -> Result<Uuid, Error> {
   let s = uid_vcf.strip_suffix(".vcf").unwrap_or(uid_vcf);
   if let Ok(uuid) = Uuid::parse_str(s) {
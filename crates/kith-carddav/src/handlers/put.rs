@@ -4,18 +4,21 @@ use axum::{
   http::{HeaderMap, StatusCode, header},
   response::{IntoResponse, Response},
 };
-use kith_core::{store::ContactStore, subject::SubjectKind};
+use kith_core::{
+  resource::NewResource, store::ContactStore, subject::SubjectKind,
+};
+use kith_vcard::Version;
+use uuid::Uuid;
 
-use crate::{
-  AppState, diff,
-  error::Error,
-  etag::{compute_etag, compute_etag_from_pairs},
-  handlers::propfind::parse_uid,
+use super::{
+  addressbook, home_href, in_addressbook, resolve_resource, resource_href,
 };
+use crate::{AppState, diff, error::Error, etag::compute_etag, photo};
 
 pub async fn handler<S>(
   state: &AppState<S>,
   headers: &HeaderMap,
+  ab: &str,
   uid_vcf: &str,
   body: &str,
 ) -> Result<Response, Error>
@@ -23,7 +26,34 @@ where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let uid = parse_uid(uid_vcf)?;
+  // RFC 4918 §9.7.1: a PUT whose parent collection is missing is a 409.
+  let book = match addressbook(state, ab).await {
+    Err(Error::NotFound) => {
+      return Err(Error::Conflict(format!("address book {ab:?} not found")));
+    }
+    other => other?,
+  };
+  let uid = resolve_resource(state, uid_vcf).await?;
+
+  let mut parsed = kith_vcard::parse(body, "carddav-put").map_err(|e| {
+    // A parse error here means the client sent a malformed vCard — that is
+    // a 400, not a 500.  Log a truncated excerpt so the problem vCard can
+    // be identified in the logs without emitting the full (potentially
+    // large) body.
+    let excerpt: String = body.chars().take(256).collect();
+    tracing::warn!(
+      uid = %uid,
+      error = %e,
+      vcard_excerpt = %excerpt,
+      "PUT rejected: vCard parse error",
+    );
+    Error::BadRequest(format!("vCard parse error: {e}"))
+  })?;
+
+  // RFC 6352 §6.3.2.1: a UID names one resource only.
+  if let Some(client_uid) = &parsed.uid {
+    check_uid(state, ab, uid, client_uid).await?;
+  }
 
   let if_match = headers
     .get(header::IF_MATCH)
@@ -40,24 +70,37 @@ where
     .get_subject(uid)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?;
+  // A merged contact lives on under the subject that absorbed it; its old
+  // href is gone and must not be recreated.
+  if existing_subject
+    .as_ref()
+    .is_some_and(|s| s.subject_id != uid)
+  {
+    return Err(Error::Conflict(
+      "contact has been merged into another".into(),
+    ));
+  }
 
   let is_new = existing_subject.is_none();
+  // The contact may already exist through another address book; this PUT
+  // then adds it here rather than creating it.
+  let is_member = !is_new && in_addressbook(state, &book, uid).await?;
 
   if is_new {
     if if_match.is_some() {
       return Err(Error::PreconditionFailed);
     }
-    state
-      .store
-      .add_subject_with_id(uid, SubjectKind::Person)
-      .await
-      .map_err(|e| Error::Store(Box::new(e)))?;
+  } else if !is_member {
+    // Not visible at this URL, so there is no current ETag to match.
+    if if_match.is_some() {
+      return Err(Error::PreconditionFailed);
+    }
   } else {
     // If-None-Match: * means "fail if the resource exists and is visible".
     if if_none_match.as_deref() == Some("*") {
       let visible = state
         .store
-        .materialize(uid, None)
+        .materialize(uid, None, None)
         .await
         .map_err(|e| Error::Store(Box::new(e)))?
         .map(|v| !v.active_facts.is_empty())
@@ -75,16 +118,21 @@ where
     if let Some(ref etag_header) = if_match {
       let view = state
         .store
-        .materialize(uid, None)
+        .materialize(uid, None, None)
         .await
         .map_err(|e| Error::Store(Box::new(e)))?
         .ok_or(Error::NotFound)?;
-      let current_etag = compute_etag(&view);
-      if strip_etag_quotes(&current_etag) != strip_etag_quotes(etag_header) {
+      // The client may hold the ETag of either representation.
+      let current_etags =
+        [Version::V4, Version::V3].map(|v| compute_etag(&view, v));
+      if !current_etags
+        .iter()
+        .any(|e| strip_etag_quotes(e) == strip_etag_quotes(etag_header))
+      {
         tracing::warn!(
           uid = %uid,
           if_match = %etag_header,
-          current_etag = %current_etag,
+          current_etag = %current_etags[0],
           "PUT rejected: ETag mismatch (If-Match)",
         );
         return Err(Error::PreconditionFailed);
@@ -92,131 +140,141 @@ where
     }
   }
 
+  // Photos become ordinary facts for the diff; their files are written
+  // once those facts are committed.
+  let photos = photo::record(uid, &mut parsed);
+
+  // The first write fixes the href and UID the contact is served under.
+  let resource = state
+    .store
+    .subject_resource(uid)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
   let current_view = state
     .store
-    .materialize(uid, None)
+    .materialize(uid, None, None)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?;
 
-  let result = diff::diff(body, uid, "carddav-put", current_view.as_ref())
-    .map_err(|e| {
-      // A parse error here means the client sent a malformed vCard — that is
-      // a 400, not a 500.  Log a truncated excerpt so the problem vCard can
-      // be identified in the logs without emitting the full (potentially
-      // large) body.
-      let excerpt: String = body.chars().take(256).collect();
-      tracing::warn!(
-        uid = %uid,
-        error = %e,
-        vcard_excerpt = %excerpt,
-        "PUT rejected: vCard parse error",
-      );
-      Error::BadRequest(format!("vCard parse error: {e}"))
-    })?;
+  let result = diff::diff_parsed(
+    parsed.clone(),
+    uid,
+    "carddav-put",
+    current_view.as_ref(),
+    state.config.match_threshold,
+    &state.config.write_policy,
+  );
+
+  // The stored contact differs from the upload, so the client must not be
+  // told its copy is current.
+  let partial = !result.preserved.is_empty() || !result.ignored.is_empty();
+  if partial {
+    tracing::info!(
+      uid = %uid,
+      preserved = ?result.preserved,
+      ignored = result.ignored.len(),
+      "PUT kept facts protected by the write policy",
+    );
+  }
 
-  let mut new_pairs = Vec::new();
-  for new_fact in result.new_facts {
-    let recorded = state
+  // The subject, its resource and its membership are written with the facts,
+  // so a failed PUT leaves nothing behind. Joining the book in the same
+  // write also keeps a new contact from ever being an unassigned member of
+  // the default book.
+  let mut changeset = result.into_changeset("Superseded by CardDAV PUT");
+  if is_new {
+    changeset.new_subjects.push((uid, SubjectKind::Person));
+  }
+  if resource.is_none() {
+    changeset.resources.push(NewResource {
+      subject_id: uid,
+      name:       uid_vcf.to_string(),
+      uid:        parsed.uid.clone(),
+    });
+  }
+  changeset.addressbook_adds.push((book.addressbook_id, uid));
+
+  if let Err(e) = state.store.apply_changeset(changeset).await {
+    // The changeset is all-or-nothing, so a failure leaves the store as it
+    // was. If this contact moved since it was read, the usual cause is a
+    // concurrent write to it; the client should fetch it again and retry.
+    let fresh_view = state
       .store
-      .record_fact(new_fact)
+      .materialize(uid, None, None)
       .await
       .map_err(|e| Error::Store(Box::new(e)))?;
-    new_pairs.push((recorded.fact_id, recorded.recorded_at));
-  }
-
-  // Apply supersessions and retractions with idempotency recovery.
-  //
-  // If any operation fails (e.g. due to a concurrent PUT for the same
-  // contact), re-materialize and re-diff to check whether the store already
-  // matches the incoming vCard.  If so, the concurrent writer already did the
-  // work and this PUT is a no-op — skip the remaining operations and proceed
-  // to ETag computation.  If the store still diverges, the error is genuine
-  // and we propagate it.
-  let mut idempotent_success = false;
-
-  'supersessions: for (old_id, replacement) in result.supersessions {
-    match state.store.supersede(old_id, replacement).await {
-      Ok((_, new_fact)) => {
-        new_pairs.push((new_fact.fact_id, new_fact.recorded_at));
-      }
-      Err(e) => {
-        let fresh_view = state
-          .store
-          .materialize(uid, None)
-          .await
-          .map_err(|me| Error::Store(Box::new(me)))?;
-        let re_diff =
-          diff::diff(body, uid, "carddav-put", fresh_view.as_ref())
-            .map_err(|de| {
-              Error::BadRequest(format!("vCard parse error: {de}"))
-            })?;
-        if re_diff.new_facts.is_empty()
-          && re_diff.supersessions.is_empty()
-          && re_diff.retractions.is_empty()
-        {
-          tracing::debug!(
-            uid = %uid,
-            "PUT idempotent: concurrent write already applied supersessions",
-          );
-          idempotent_success = true;
-          break 'supersessions;
-        }
-        return Err(Error::Store(Box::new(e)));
-      }
+    let etag = |v: &_| compute_etag(v, Version::V4);
+    if fresh_view.as_ref().map(etag) != current_view.as_ref().map(etag) {
+      tracing::warn!(
+        uid = %uid,
+        error = %e,
+        "PUT rejected: contact changed concurrently",
+      );
+      return Err(Error::Conflict("contact changed concurrently".into()));
     }
+    return Err(Error::Store(Box::new(e)));
   }
+  // Should this fail, the contact is served without the photo until a later
+  // PUT carrying it writes the file.
+  photo::save(&state.user.photo_dir, uid, &photos).await?;
 
-  if !idempotent_success {
-    for fact_id in result.retractions {
-      match state
-        .store
-        .retract(fact_id, Some("Superseded by CardDAV PUT".to_string()))
-        .await
-      {
-        Ok(_) => {}
-        Err(e) => {
-          let fresh_view = state
-            .store
-            .materialize(uid, None)
-            .await
-            .map_err(|me| Error::Store(Box::new(me)))?;
-          let re_diff =
-            diff::diff(body, uid, "carddav-put", fresh_view.as_ref())
-              .map_err(|de| {
-                Error::BadRequest(format!("vCard parse error: {de}"))
-              })?;
-          if re_diff.new_facts.is_empty()
-            && re_diff.supersessions.is_empty()
-            && re_diff.retractions.is_empty()
-          {
-            tracing::debug!(
-              uid = %uid,
-              "PUT idempotent: concurrent write already applied retractions",
-            );
-            break;
-          }
-          return Err(Error::Store(Box::new(e)));
-        }
-      }
-    }
+  let status = if !is_member {
+    StatusCode::CREATED
+  } else {
+    StatusCode::NO_CONTENT
+  };
+  if partial {
+    return Ok(status.into_response());
   }
 
-  let new_etag = match state
+  let view = state
     .store
-    .materialize(uid, None)
+    .materialize(uid, None, None)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?
-  {
-    Some(view) => compute_etag(&view),
-    None => compute_etag_from_pairs(&mut new_pairs),
-  };
+    .ok_or(Error::NotFound)?;
+  // Answer with the ETag of the representation the client uploaded.
+  let version = parsed.version.unwrap_or_default();
+  let new_etag = compute_etag(&view, version);
+  Ok((status, [(header::ETAG, new_etag)]).into_response())
+}
 
-  let status = if is_new {
-    StatusCode::CREATED
-  } else {
-    StatusCode::NO_CONTENT
+/// Fail with [`Error::UidConflict`] if `client_uid` already belongs to a
+/// subject other than `uid`.
+async fn check_uid<S>(
+  state: &AppState<S>,
+  ab: &str,
+  uid: Uuid,
+  client_uid: &str,
+) -> Result<(), Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let Some(owner) = state
+    .store
+    .resource_by_uid(client_uid)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?
+  else {
+    return Ok(());
   };
-  Ok((status, [(header::ETAG, new_etag)]).into_response())
+  if owner.subject_id == uid {
+    return Ok(());
+  }
+
+  // Point at a book the other contact is actually in, if it is in any.
+  let books = state
+    .store
+    .subject_addressbooks(owner.subject_id)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+  let book = books.first().map_or(ab, |b| b.name.as_str());
+  Err(Error::UidConflict(resource_href(
+    &home_href(state),
+    book,
+    &owner.name,
+  )))
 }
 
 /// Strip surrounding double-quotes from an ETag value.
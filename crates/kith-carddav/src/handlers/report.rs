@@ -1,10 +1,21 @@
-//! REPORT handlers for `addressbook-multiget` and `addressbook-query`.
+//! REPORT handlers for `addressbook-multiget`, `addressbook-query` and
+//! `sync-collection`.
+
+use std::collections::HashSet;
 
 use axum::response::Response;
-use kith_core::{store::ContactStore, subject::SubjectKind};
+use kith_core::{
+  addressbook::AddressBook, lifecycle::ContactView, store::ContactStore,
+};
+use kith_vcard::{SerializeOptions, Version};
+use percent_encoding::percent_decode_str;
 use uuid::Uuid;
 
-use super::multistatus_response;
+use super::{
+  addressbook, client_uid, collection_href, home_href, in_addressbook,
+  multistatus_response, parse_sync_token_uri, render_vcard, resolve_resource,
+  resource_href, resource_name, sync_token_uri,
+};
 use crate::{
   AppState,
   error::Error,
@@ -25,42 +36,48 @@ where
   S::Error: std::error::Error + Send + Sync + 'static,
 {
   let report = parse_report(body)?;
+  let book = addressbook(state, ab).await?;
   match report.kind {
-    ReportKind::Multiget => multiget(state, ab, &report).await,
-    ReportKind::Query => query(state, ab, &report).await,
+    ReportKind::Multiget => multiget(state, &book, &report).await,
+    ReportKind::Query => query(state, &book, &report).await,
+    ReportKind::SyncCollection => sync_collection(state, &book, &report).await,
   }
 }
 
 /// `addressbook-multiget`: fetch the requested hrefs and return their data.
 async fn multiget<S>(
   state: &AppState<S>,
-  ab: &str,
+  book: &AddressBook,
   report: &ReportRequest,
 ) -> Result<Response, Error>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let base = &state.config.base_url;
-  let want_address_data = report.props.contains(&PropName::AddressData);
-  let want_etag = report.props.contains(&PropName::GetETag);
+  let home = home_href(state);
+  let ab = &book.name;
 
   let mut ms = MultistatusBuilder::new();
 
   for href in &report.hrefs {
-    let canonical_href = canonicalize_href(base, ab, href);
+    let canonical_href = canonicalize_href(&home, ab, href);
 
-    let uid = match uid_from_href(href) {
-      Some(uid) => uid,
+    // Contacts outside this book are as absent as unknown ones.
+    let uid = match name_from_href(href) {
+      Some(name) => resolve_resource(state, &name).await?,
       None => {
         ms.response(&canonical_href).status_not_found();
         continue;
       }
     };
+    if !in_addressbook(state, book, uid).await? {
+      ms.response(&canonical_href).status_not_found();
+      continue;
+    }
 
     let view = state
       .store
-      .materialize(uid, None)
+      .materialize(uid, None, None)
       .await
       .map_err(|e| Error::Store(Box::new(e)))?
       .filter(|v| !v.active_facts.is_empty());
@@ -70,14 +87,7 @@ where
         ms.response(&canonical_href).status_not_found();
       }
       Some(view) => {
-        let mut props: Vec<Property> = Vec::new();
-        if want_etag {
-          props.push(Property::GetETag(compute_etag(&view)));
-        }
-        if want_address_data {
-          let vcard = kith_vcard::serialize(&view)?;
-          props.push(Property::AddressData(vcard));
-        }
+        let props = resource_props(state, &view, report).await?;
         ms.response(&canonical_href).propstat_ok(&props);
       }
     }
@@ -86,69 +96,174 @@ where
   Ok(multistatus_response(ms.finish()))
 }
 
-/// `addressbook-query`: return all contacts in the addressbook.
+/// `addressbook-query`: return the contacts in the addressbook that match
+/// the report's filter.
 ///
-/// We ignore filter conditions for now and return every active contact,
-/// which is correct when the client sends an empty or always-true filter.
+/// The store narrows the candidates where the filter allows it; each
+/// candidate is then matched against the vCard the client would receive.
 async fn query<S>(
   state: &AppState<S>,
-  ab: &str,
+  book: &AddressBook,
   report: &ReportRequest,
 ) -> Result<Response, Error>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let base = &state.config.base_url;
-  let want_address_data = report.props.contains(&PropName::AddressData);
-  let want_etag = report.props.contains(&PropName::GetETag);
+  let home = home_href(state);
+  let ab = &book.name;
 
-  let subjects = state
+  let members = state
     .store
-    .list_subjects(Some(SubjectKind::Person))
+    .addressbook_members(book.addressbook_id)
     .await
     .map_err(|e| Error::Store(Box::new(e)))?;
 
+  let mut candidates: Vec<Uuid> =
+    members.iter().map(|s| s.subject_id).collect();
+  if let Some(queries) = report.filter.store_queries() {
+    let mut hits = HashSet::new();
+    for q in &queries {
+      let found = state
+        .store
+        .search(q)
+        .await
+        .map_err(|e| Error::Store(Box::new(e)))?;
+      hits.extend(found.into_iter().map(|s| s.subject_id));
+    }
+    candidates.retain(|id| hits.contains(id));
+  }
+
   let mut ms = MultistatusBuilder::new();
 
-  for subject in subjects {
+  for uid in candidates {
     let view = state
       .store
-      .materialize(subject.subject_id, None)
+      .materialize(uid, None, None)
       .await
       .map_err(|e| Error::Store(Box::new(e)))?
       .filter(|v| !v.active_facts.is_empty());
+    let Some(view) = view else { continue };
+
+    // Photos are left out: filters never look inside them.
+    let options = SerializeOptions {
+      uid: client_uid(state, uid).await?,
+      ..Default::default()
+    };
+    let vcard = kith_vcard::serialize_with(&view, Version::V4, &options)?;
+    if !report.filter.matches(&kith_vcard::content_lines(&vcard)?) {
+      continue;
+    }
+
+    let href = resource_href(&home, ab, &resource_name(state, uid).await?);
+    let props = resource_props(state, &view, report).await?;
+    ms.response(&href).propstat_ok(&props);
+  }
+
+  Ok(multistatus_response(ms.finish()))
+}
+
+/// `sync-collection` (RFC 6578): return the members changed or removed since
+/// the client's token, then the token to sync from next time. Without a token
+/// this is an initial sync and lists every member.
+async fn sync_collection<S>(
+  state: &AppState<S>,
+  book: &AddressBook,
+  report: &ReportRequest,
+) -> Result<Response, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let home = home_href(state);
+  let ab = &book.name;
+
+  let since = report
+    .sync_token
+    .as_deref()
+    .map(parse_sync_token_uri)
+    .transpose()?;
+  if let Some(since) = since {
+    // Tokens only grow, so one from the future was never issued here.
+    let current = state
+      .store
+      .sync_token()
+      .await
+      .map_err(|e| Error::Store(Box::new(e)))?;
+    if !since.is_at_or_before(&current) {
+      return Err(Error::InvalidSyncToken(sync_token_uri(&since)));
+    }
+  }
 
-    if let Some(view) = view {
-      let href =
-        format!("{base}/dav/addressbooks/{ab}/{}.vcf", subject.subject_id);
-      let mut props: Vec<Property> = Vec::new();
-      if want_etag {
-        props.push(Property::GetETag(compute_etag(&view)));
+  let changes = state
+    .store
+    .addressbook_changes(book.addressbook_id, since)
+    .await
+    .map_err(|e| Error::Store(Box::new(e)))?;
+
+  let mut ms = MultistatusBuilder::new();
+
+  for uid in changes.changed {
+    let href = resource_href(&home, ab, &resource_name(state, uid).await?);
+    let view = state
+      .store
+      .materialize(uid, None, None)
+      .await
+      .map_err(|e| Error::Store(Box::new(e)))?
+      .filter(|v| !v.active_facts.is_empty());
+    match view {
+      Some(view) => {
+        let props = resource_props(state, &view, report).await?;
+        ms.response(&href).propstat_ok(&props);
       }
-      if want_address_data {
-        let vcard = kith_vcard::serialize(&view)?;
-        props.push(Property::AddressData(vcard));
+      None => {
+        ms.response(&href).status_not_found();
       }
-      ms.response(&href).propstat_ok(&props);
     }
   }
+  for uid in changes.removed {
+    let href = resource_href(&home, ab, &resource_name(state, uid).await?);
+    ms.response(&href).status_not_found();
+  }
 
+  ms.sync_token(&sync_token_uri(&changes.token));
   Ok(multistatus_response(ms.finish()))
 }
 
 // ─── Helpers ─────────────────────────────────────────────────────────────────
 
-/// Parse a UUID from a href like `.../uuid.vcf` or `.../uuid`.
-fn uid_from_href(href: &str) -> Option<Uuid> {
+/// The requested `getetag` / `address-data` properties of a resource, in the
+/// vCard version the report asks for.
+async fn resource_props<S>(
+  state: &AppState<S>,
+  view: &ContactView,
+  report: &ReportRequest,
+) -> Result<Vec<Property>, Error>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let mut props: Vec<Property> = Vec::new();
+  if report.props.contains(&PropName::GetETag) {
+    props.push(Property::GetETag(compute_etag(view, report.version)));
+  }
+  if report.props.contains(&PropName::AddressData) {
+    let vcard = render_vcard(state, view, report.version).await?;
+    props.push(Property::AddressData(vcard));
+  }
+  Ok(props)
+}
+
+/// The decoded resource name at the end of a href like `.../name.vcf`.
+fn name_from_href(href: &str) -> Option<String> {
   let last = href.trim_end_matches('/').rsplit('/').next()?;
-  let s = last.strip_suffix(".vcf").unwrap_or(last);
-  Uuid::parse_str(s).ok()
+  let name = percent_decode_str(last).decode_utf8().ok()?;
+  (!name.is_empty()).then(|| name.into_owned())
 }
 
 /// Produce a canonical absolute href for a resource given the href the client
 /// supplied (which may be absolute or relative).
-fn canonicalize_href(base: &str, ab: &str, href: &str) -> String {
+fn canonicalize_href(home: &str, ab: &str, href: &str) -> String {
   if href.starts_with("http://") || href.starts_with("https://") {
     href.to_string()
   } else {
@@ -158,7 +273,7 @@ fn canonicalize_href(base: &str, ab: &str, href: &str) -> String {
       .rsplit('/')
       .next()
       .unwrap_or(href);
-    format!("{base}/dav/addressbooks/{ab}/{last}")
+    format!("{}{last}", collection_href(home, ab))
   }
 }
 
@@ -169,23 +284,31 @@ mod tests {
   use super::*;
 
   #[test]
-  fn uid_from_absolute_href() {
+  fn name_from_absolute_href() {
     let uid = Uuid::new_v4();
-    let href =
-      format!("https://contacts.jlewis.sh/dav/addressbooks/personal/{uid}.vcf");
-    assert_eq!(uid_from_href(&href), Some(uid));
+    let href = format!(
+      "https://contacts.jlewis.sh/dav/addressbooks/user/personal/{uid}.vcf"
+    );
+    assert_eq!(name_from_href(&href), Some(format!("{uid}.vcf")));
   }
 
   #[test]
-  fn uid_from_relative_href() {
+  fn name_from_relative_href() {
     let uid = Uuid::new_v4();
-    let href = format!("/dav/addressbooks/personal/{uid}.vcf");
-    assert_eq!(uid_from_href(&href), Some(uid));
+    let href = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+    assert_eq!(name_from_href(&href), Some(format!("{uid}.vcf")));
   }
 
   #[test]
-  fn uid_from_garbage_returns_none() {
-    assert_eq!(uid_from_href("/not-a-uuid"), None);
+  fn name_from_href_is_percent_decoded() {
+    assert_eq!(
+      name_from_href("/dav/addressbooks/user/personal/%7Bab%20c%7D.vcf"),
+      Some("{ab c}.vcf".to_string())
+    );
+    assert_eq!(
+      name_from_href("/dav/addressbooks/user/personal/%FF.vcf"),
+      None
+    );
   }
 }
 
@@ -201,7 +324,7 @@ mod integration {
   use uuid::Uuid;
 
   use crate::{
-    router,
+    Users, router,
     test_helpers::{auth_header, make_state},
   };
 
@@ -214,19 +337,19 @@ mod integration {
       r#"<?xml version="1.0"?>
 <card:addressbook-multiget xmlns:D="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">
   <D:prop><D:getetag/><card:address-data/></D:prop>
-  <D:href>/dav/addressbooks/personal/{uid}.vcf</D:href>
+  <D:href>/dav/addressbooks/user/personal/{uid}.vcf</D:href>
 </card:addressbook-multiget>"#
     );
 
     let req = Request::builder()
       .method("REPORT")
-      .uri("/dav/addressbooks/personal")
+      .uri("/dav/addressbooks/user/personal")
       .header(header::AUTHORIZATION, auth)
       .header(header::CONTENT_TYPE, "application/xml")
       .body(Body::from(body))
       .unwrap();
 
-    let resp = router(state).oneshot(req).await.unwrap();
+    let resp = router(Users::new([state])).oneshot(req).await.unwrap();
     assert_eq!(resp.status().as_u16(), 207);
     let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
       .await
@@ -247,30 +370,33 @@ mod integration {
     // PUT the contact first.
     let put_req = Request::builder()
       .method("PUT")
-      .uri(format!("/dav/addressbooks/personal/{uid}.vcf"))
+      .uri(format!("/dav/addressbooks/user/personal/{uid}.vcf"))
       .header(header::AUTHORIZATION, auth.clone())
       .header(header::CONTENT_TYPE, "text/vcard")
       .body(Body::from(vcard.clone()))
       .unwrap();
-    router(state.clone()).oneshot(put_req).await.unwrap();
+    router(Users::new([state.clone()]))
+      .oneshot(put_req)
+      .await
+      .unwrap();
 
     let body = format!(
       r#"<?xml version="1.0"?>
 <card:addressbook-multiget xmlns:D="DAV:" xmlns:card="urn:ietf:params:xml:ns:carddav">
   <D:prop><D:getetag/><card:address-data/></D:prop>
-  <D:href>/dav/addressbooks/personal/{uid}.vcf</D:href>
+  <D:href>/dav/addressbooks/user/personal/{uid}.vcf</D:href>
 </card:addressbook-multiget>"#
     );
 
     let req = Request::builder()
       .method("REPORT")
-      .uri("/dav/addressbooks/personal")
+      .uri("/dav/addressbooks/user/personal")
       .header(header::AUTHORIZATION, auth)
       .header(header::CONTENT_TYPE, "application/xml")
       .body(Body::from(body))
       .unwrap();
 
-    let resp = router(state).oneshot(req).await.unwrap();
+    let resp = router(Users::new([state])).oneshot(req).await.unwrap();
     assert_eq!(resp.status().as_u16(), 207);
     let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
       .await
@@ -279,4 +405,235 @@ mod integration {
     assert!(xml.contains("BEGIN:VCARD"), "missing vcard data: {xml}");
     assert!(xml.contains("200 OK"), "missing 200 propstat: {xml}");
   }
+
+  async fn send(
+    state: &crate::AppState<kith_store_sqlite::SqliteStore>,
+    method: &str,
+    uri: &str,
+    body: String,
+  ) -> (u16, String) {
+    let req = Request::builder()
+      .method(method)
+      .uri(uri)
+      .header(header::AUTHORIZATION, auth_header("user", "secret"))
+      .body(Body::from(body))
+      .unwrap();
+    let resp = router(Users::new([state.clone()]))
+      .oneshot(req)
+      .await
+      .unwrap();
+    let status = resp.status().as_u16();
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    (status, String::from_utf8(bytes.to_vec()).unwrap())
+  }
+
+  fn sync_body(token: &str) -> String {
+    format!(
+      r#"<?xml version="1.0"?>
+<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{token}</D:sync-token>
+  <D:sync-level>1</D:sync-level>
+  <D:prop><D:getetag/></D:prop>
+</D:sync-collection>"#
+    )
+  }
+
+  fn vcard(uid: Uuid, name: &str) -> String {
+    format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:{name}\r\nEND:VCARD\r\n"
+    )
+  }
+
+  /// The text of the first `<D:sync-token>` element in `xml`.
+  fn sync_token(xml: &str) -> String {
+    let start = xml.find("<D:sync-token>").expect("no sync-token") + 14;
+    let end = xml[start..].find('<').unwrap();
+    xml[start..start + end].to_string()
+  }
+
+  #[tokio::test]
+  async fn sync_collection_reports_only_changes_since_token() {
+    let state = make_state("secret").await;
+    let (edited, deleted, untouched) =
+      (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    for uid in [edited, deleted, untouched] {
+      let uri = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+      send(&state, "PUT", &uri, vcard(uid, "Before")).await;
+    }
+
+    // Initial sync: every member, plus a token.
+    let (status, xml) = send(
+      &state,
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      sync_body(""),
+    )
+    .await;
+    assert_eq!(status, 207);
+    for uid in [edited, deleted, untouched] {
+      assert!(xml.contains(&uid.to_string()), "{xml}");
+    }
+    let token = sync_token(&xml);
+
+    // The collection advertises the same token.
+    let (_, props) = send(
+      &state,
+      "PROPFIND",
+      "/dav/addressbooks/user/personal",
+      String::new(),
+    )
+    .await;
+    assert_eq!(sync_token(&props), token);
+    assert!(props.contains("sync-collection"), "{props}");
+
+    let uri = format!("/dav/addressbooks/user/personal/{edited}.vcf");
+    send(&state, "PUT", &uri, vcard(edited, "After")).await;
+    let uri = format!("/dav/addressbooks/user/personal/{deleted}.vcf");
+    send(&state, "DELETE", &uri, String::new()).await;
+
+    let (status, xml) = send(
+      &state,
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      sync_body(&token),
+    )
+    .await;
+    assert_eq!(status, 207);
+    assert!(xml.contains(&edited.to_string()), "{xml}");
+    assert!(xml.contains(&deleted.to_string()), "{xml}");
+    assert!(!xml.contains(&untouched.to_string()), "{xml}");
+    assert_eq!(xml.matches("200 OK").count(), 1, "{xml}");
+    assert_eq!(xml.matches("404 Not Found").count(), 1, "{xml}");
+    let next = sync_token(&xml);
+    assert_ne!(next, token);
+
+    // Nothing changed since the new token.
+    let (_, xml) = send(
+      &state,
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      sync_body(&next),
+    )
+    .await;
+    assert!(!xml.contains("<D:response>"), "{xml}");
+    assert_eq!(sync_token(&xml), next);
+  }
+
+  #[tokio::test]
+  async fn sync_collection_rejects_unknown_token() {
+    let state = make_state("secret").await;
+    for token in ["bogus", "urn:kith:sync:99.0.0.0"] {
+      let (status, xml) = send(
+        &state,
+        "REPORT",
+        "/dav/addressbooks/user/personal",
+        sync_body(token),
+      )
+      .await;
+      assert_eq!(status, 403);
+      assert!(xml.contains("valid-sync-token"), "{xml}");
+    }
+  }
+
+  fn query_body(filter: &str) -> String {
+    format!(
+      r#"<?xml version="1.0"?>
+<C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+  <D:prop><D:getetag/></D:prop>
+  <C:filter{filter}</C:filter>
+</C:addressbook-query>"#
+    )
+  }
+
+  #[tokio::test]
+  async fn query_applies_filters() {
+    let state = make_state("secret").await;
+    let (ada, alan, grace) = (Uuid::new_v4(), Uuid::new_v4(), Uuid::new_v4());
+    for (uid, extra) in [
+      (
+        ada,
+        "FN:Ada Lovelace\r\nEMAIL;TYPE=work:ada@example.com\r\n",
+      ),
+      (alan, "FN:Alan Turing\r\nTEL;TYPE=cell:+44 20 7946 0000\r\n"),
+      (grace, "FN:Grace Hopper\r\nEMAIL:grace@navy.mil\r\n"),
+    ] {
+      let card = format!(
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\n{extra}END:VCARD\r\n"
+      );
+      let uri = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+      send(&state, "PUT", &uri, card).await;
+    }
+
+    let cases = [
+      (
+        r#"><C:prop-filter name="FN">
+          <C:text-match match-type="starts-with">a</C:text-match>
+        </C:prop-filter>"#,
+        vec![ada, alan],
+      ),
+      (
+        r#" test="allof">
+          <C:prop-filter name="FN">
+            <C:text-match match-type="starts-with">a</C:text-match>
+          </C:prop-filter>
+          <C:prop-filter name="EMAIL">
+            <C:text-match>EXAMPLE</C:text-match>
+          </C:prop-filter>"#,
+        vec![ada],
+      ),
+      (
+        r#"><C:prop-filter name="EMAIL"><C:is-not-defined/></C:prop-filter>"#,
+        vec![alan],
+      ),
+      (
+        r#"><C:prop-filter name="FN">
+          <C:text-match match-type="ends-with" negate-condition="yes"
+            >hopper</C:text-match>
+        </C:prop-filter>"#,
+        vec![ada, alan],
+      ),
+      (
+        r#"><C:prop-filter name="TEL">
+          <C:param-filter name="TYPE">
+            <C:text-match match-type="equals">CELL</C:text-match>
+          </C:param-filter>
+        </C:prop-filter>"#,
+        vec![alan],
+      ),
+      (">", vec![ada, alan, grace]),
+    ];
+    for (filter, expected) in cases {
+      let (status, xml) = send(
+        &state,
+        "REPORT",
+        "/dav/addressbooks/user/personal",
+        query_body(filter),
+      )
+      .await;
+      assert_eq!(status, 207);
+      for uid in [ada, alan, grace] {
+        assert_eq!(
+          xml.contains(&uid.to_string()),
+          expected.contains(&uid),
+          "filter {filter} for {uid}: {xml}"
+        );
+      }
+    }
+
+    let (status, xml) = send(
+      &state,
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      query_body(
+        r#"><C:prop-filter name="FN">
+          <C:text-match collation="i;octet">Ada</C:text-match>
+        </C:prop-filter>"#,
+      ),
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert!(xml.contains("supported-collation"), "{xml}");
+  }
 }
@@ -4,72 +4,273 @@
 //! backed by any [`ContactStore`].
 
 pub mod auth;
+pub mod calendar;
 pub mod diff;
 pub mod error;
 pub mod etag;
+pub mod filter;
 pub mod handlers;
+pub mod photo;
+pub mod policy;
+pub mod throttle;
 pub mod xml;
 
-use std::{path::PathBuf, sync::Arc};
+use std::{collections::BTreeMap, net::IpAddr, path::PathBuf, sync::Arc};
 
-use auth::{AuthConfig, verify_auth};
+use auth::{ClientIp, verify_auth};
 use axum::{
   Router,
-  extract::{DefaultBodyLimit, Path, State},
+  extract::{DefaultBodyLimit, Path, Request, State},
   http::{HeaderMap, Method, StatusCode},
   response::{IntoResponse, Redirect, Response},
   routing::any,
 };
 use bytes::Bytes;
 pub use error::Error;
-use handlers::{delete, get, options, propfind, put, report};
+use handlers::{delete, get, mkcol, options, propfind, put, report};
 use kith_api::api_router;
-use kith_core::store::ContactStore;
+use kith_core::{
+  addressbook::{AddressBook, NewAddressBook},
+  store::ContactStore,
+};
 use serde::Deserialize;
+use tower::ServiceExt as _;
 use tower_http::trace::{DefaultMakeSpan, TraceLayer};
 
 // ─── Configuration
 // ────────────────────────────────────────────────────────────
 
 /// Runtime server configuration, deserialised from `config.toml`.
+///
+/// The top-level `store_path`, `photo_dir`, `auth_username` and
+/// `auth_password_hash` describe the first account; `users` adds more.
 #[derive(Deserialize, Clone)]
 pub struct ServerConfig {
   pub host:               String,
   pub port:               u16,
   pub base_url:           String,
+  /// Name of each account's default address book, created on startup if
+  /// missing. It also lists every person not assigned to any other book.
   pub addressbook:        String,
   pub store_path:         PathBuf,
+  /// Directory holding contact photos, one subdirectory per subject.
+  pub photo_dir:          PathBuf,
   pub auth_username:      String,
   pub auth_password_hash: String,
+  /// Further accounts, each with a store of its own.
+  #[serde(default)]
+  pub users:              Vec<UserConfig>,
+  /// How similar an edited email, phone number, URL, … must be to the one
+  /// it replaces for a PUT to record a supersession rather than a retraction
+  /// and a new fact. See [`diff::DEFAULT_MATCH_THRESHOLD`].
+  #[serde(default = "default_match_threshold")]
+  pub match_threshold:    f64,
+  /// Which fact types a PUT may add, change or remove. See
+  /// [`policy::WritePolicy`].
+  #[serde(default)]
+  pub write_policy:       policy::WritePolicy,
+  /// Failed-login lockouts and credential caching. See
+  /// [`throttle::AuthLimits`].
+  #[serde(default)]
+  pub auth_limits:        throttle::AuthLimits,
+}
+
+fn default_match_threshold() -> f64 { diff::DEFAULT_MATCH_THRESHOLD }
+
+impl ServerConfig {
+  /// Every account: the one configured at the top level, then `users`.
+  pub fn accounts(&self) -> Vec<UserConfig> {
+    let primary = UserConfig {
+      username:      self.auth_username.clone(),
+      password_hash: self.auth_password_hash.clone(),
+      store_path:    self.store_path.clone(),
+      photo_dir:     self.photo_dir.clone(),
+    };
+    std::iter::once(primary)
+      .chain(self.users.iter().cloned())
+      .collect()
+  }
+}
+
+/// One account: its credentials and where its contacts live.
+#[derive(Deserialize, Clone)]
+pub struct UserConfig {
+  /// Login name, also the `{user}` segment of the account's URLs.
+  pub username:      String,
+  /// PHC string produced by argon2, e.g. `$argon2id$v=19$…`
+  pub password_hash: String,
+  pub store_path:    PathBuf,
+  /// Directory holding contact photos, one subdirectory per subject.
+  pub photo_dir:     PathBuf,
 }
 
 // ─── Application state
 // ────────────────────────────────────────────────────────
 
-/// Shared state threaded through all axum handlers.
+/// One account's state, threaded through all CardDAV handlers.
+///
+/// Each account has a store of its own, so nothing a handler does with
+/// `store` can reach another account's contacts.
 #[derive(Clone)]
 pub struct AppState<S: ContactStore> {
   pub store:  Arc<S>,
   pub config: Arc<ServerConfig>,
-  pub auth:   Arc<AuthConfig>,
+  pub user:   Arc<UserConfig>,
+}
+
+/// Every account the server hosts, keyed by username, and the
+/// [`AuthGuard`](throttle::AuthGuard) protecting them.
+#[derive(Clone)]
+pub struct Users<S: ContactStore> {
+  accounts: Arc<BTreeMap<String, Account<S>>>,
+  guard:    Arc<throttle::AuthGuard>,
+}
+
+/// An account's CardDAV state and its JSON API.
+struct Account<S: ContactStore> {
+  state: AppState<S>,
+  api:   Router,
+}
+
+impl<S> Users<S>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  /// Collect the accounts of `states`. A username given twice keeps the
+  /// last state. Auth limits come from the first state's config.
+  pub fn new(states: impl IntoIterator<Item = AppState<S>>) -> Self {
+    let accounts: BTreeMap<_, _> = states
+      .into_iter()
+      .map(|state| {
+        let api = api_router(state.store.clone());
+        (state.user.username.clone(), Account { state, api })
+      })
+      .collect();
+    let limits = accounts
+      .values()
+      .next()
+      .map(|a| a.state.config.auth_limits.clone())
+      .unwrap_or_default();
+    Users {
+      accounts: Arc::new(accounts),
+      guard:    Arc::new(throttle::AuthGuard::new(limits)),
+    }
+  }
+
+  /// The state of the account called `username`.
+  pub fn get(&self, username: &str) -> Option<&AppState<S>> {
+    self.accounts.get(username).map(|a| &a.state)
+  }
+
+  /// The state of every account, in username order.
+  pub fn states(&self) -> impl Iterator<Item = &AppState<S>> {
+    self.accounts.values().map(|a| &a.state)
+  }
+
+  /// Failed-login tracking and the credential cache.
+  pub fn guard(&self) -> &throttle::AuthGuard { &self.guard }
+}
+
+// ─── Startup
+// ──────────────────────────────────────────────────────────────────
+
+/// Make the address book called `name` the default, creating it if needed.
+pub async fn ensure_default_addressbook<S: ContactStore>(
+  store: &S,
+  name: &str,
+) -> Result<AddressBook, S::Error> {
+  let book = match store.get_addressbook(name).await? {
+    Some(book) => book,
+    None => {
+      store
+        .create_addressbook(NewAddressBook {
+          name:         name.to_string(),
+          display_name: None,
+          description:  None,
+        })
+        .await?
+    }
+  };
+  if !book.is_default {
+    store.set_default_addressbook(book.addressbook_id).await?;
+  }
+  Ok(AddressBook {
+    is_default: true,
+    ..book
+  })
 }
 
 // ─── Helpers
 // ──────────────────────────────────────────────────────────────────
 
-/// Return `Err(401 response)` for any method other than OPTIONS.
-fn require_auth<S>(
-  method: &Method,
+/// Authenticate the request as the account `user` its path names.
+///
+/// Valid credentials for any other account get a 404, exactly as if `user`
+/// did not exist, so one account cannot even probe for another.
+async fn require_user<'a, S>(
   headers: &HeaderMap,
-  state: &AppState<S>,
-) -> Result<(), Box<Response>>
+  ip: Option<IpAddr>,
+  users: &'a Users<S>,
+  user: &str,
+) -> Result<&'a AppState<S>, Box<Response>>
 where
   S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
 {
-  if method == Method::OPTIONS {
-    return Ok(());
+  let state = verify_auth(headers, ip, users)
+    .await
+    .map_err(|e| Box::new(e.into_response()))?;
+  if state.user.username != user {
+    return Err(Box::new(Error::NotFound.into_response()));
+  }
+  Ok(state)
+}
+
+/// [`require_user`] for the address book routes, which also accept the URLs
+/// from before there were accounts: `/dav/addressbooks/{ab}` and
+/// `/dav/addressbooks/{ab}/{name}`. When `user` is not the authenticated
+/// account but names one of its address books, the request is permanently
+/// redirected to the same book (and resource `name`) under the account's
+/// home set, so clients set up against the old layout keep syncing. A
+/// segment that is some account's username is never taken for a book name.
+async fn require_user_or_redirect<'a, S>(
+  headers: &HeaderMap,
+  ip: Option<IpAddr>,
+  users: &'a Users<S>,
+  user: &str,
+  name: Option<&str>,
+) -> Result<&'a AppState<S>, Box<Response>>
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let state = verify_auth(headers, ip, users)
+    .await
+    .map_err(|e| Box::new(e.into_response()))?;
+  if state.user.username == user {
+    return Ok(state);
+  }
+  if users.accounts.contains_key(user) {
+    return Err(Box::new(Error::NotFound.into_response()));
+  }
+  let book = state
+    .store
+    .get_addressbook(user)
+    .await
+    .map_err(|e| Box::new(Error::Store(Box::new(e)).into_response()))?;
+  if book.is_none() {
+    return Err(Box::new(Error::NotFound.into_response()));
   }
-  verify_auth(headers, &state.auth).map_err(|e| Box::new(e.into_response()))
+  let mut location = format!(
+    "{}/{}/",
+    handlers::home_href(state),
+    handlers::encode_segment(user)
+  );
+  if let Some(name) = name {
+    location.push_str(&handlers::encode_segment(name));
+  }
+  Err(Box::new(Redirect::permanent(&location).into_response()))
 }
 
 /// Parse the `Depth` header as a `u8`, defaulting to `0`.
@@ -85,27 +286,49 @@ fn depth(headers: &HeaderMap) -> u8 {
 // ───────────────────────────────────────────────────────────────────
 
 /// Build an axum [`Router`] for the CardDAV server.
-pub fn router<S>(state: AppState<S>) -> Router
+///
+/// Every account's collections live under its own principal and home set:
+///
+/// ```text
+/// /dav/principals/{user}/
+/// /dav/addressbooks/{user}/{ab}/{name}
+/// /dav/calendars/{user}/birthdays/{fact_id}.ics
+/// /dav/calendars/{user}/birthdays.ics
+/// ```
+///
+/// `/api` serves the JSON API of whichever account the request
+/// authenticates as. The single-account URLs `/dav/addressbooks/{ab}/…`
+/// still work: they redirect to the authenticated account's book.
+pub fn router<S>(users: Users<S>) -> Router
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  let store = state.store.clone();
+  let api = any(api_handler::<S>).with_state(users.clone());
   Router::new()
     .route("/.well-known/carddav", any(well_known_dav_handler))
+    .route("/.well-known/caldav", any(well_known_dav_handler))
     .route("/.well-known/dav", any(well_known_dav_handler))
     .route("/dav", any(dav_root_handler::<S>))
-    .route("/dav/addressbooks", any(dav_home_handler::<S>))
-    .route("/dav/addressbooks/{ab}", any(dav_collection_handler::<S>))
+    .route("/dav/principals/{user}", any(dav_principal_handler::<S>))
+    .route("/dav/addressbooks/{user}", any(dav_home_handler::<S>))
+    .route(
+      "/dav/addressbooks/{user}/{ab}",
+      any(dav_collection_handler::<S>),
+    )
     .route(
-      "/dav/addressbooks/{ab}/{uid_vcf}",
+      "/dav/addressbooks/{user}/{ab}/{uid_vcf}",
       any(dav_resource_handler::<S>),
     )
+    .route("/dav/calendars/{user}", any(cal_home_handler::<S>))
+    .route("/dav/calendars/{user}/{cal}", any(cal_collection_handler::<S>))
+    .route(
+      "/dav/calendars/{user}/{cal}/{event}",
+      any(cal_event_handler::<S>),
+    )
     .route("/dav/{*path}", any(dav_wildcard_handler))
-    .with_state(state)
-    // Nest the JSON API after applying CardDAV state; both routers are
-    // Router<()> at this point so the state types match.
-    .nest("/api", api_router(store))
+    .with_state(users)
+    .nest_service("/api", api)
     .layer(DefaultBodyLimit::max(8 * 1024 * 1024))
     .layer(
       TraceLayer::new_for_http()
@@ -115,44 +338,88 @@ where
 
 // ─── Route handlers ──────────────────────────────────────────────────────────
 
+/// `/dav`: points an authenticated client at its own principal.
 async fn dav_root_handler<S>(
-  State(state): State<AppState<S>>,
+  State(users): State<Users<S>>,
   method: Method,
   headers: HeaderMap,
+  ClientIp(ip): ClientIp,
   body: Bytes,
 ) -> Response
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  if let Err(r) = require_auth(&method, &headers, &state) {
-    return *r;
+  if method == Method::OPTIONS {
+    return options::handler();
   }
+  let state = match verify_auth(&headers, ip, &users).await {
+    Ok(state) => state,
+    Err(e) => return e.into_response(),
+  };
   match method.as_str() {
-    "OPTIONS" => options::handler(),
-    "PROPFIND" => propfind::principal(&state, &body)
-      .await
-      .into_response_or_err(),
+    "PROPFIND" => {
+      let href = format!("{}/dav/", state.config.base_url);
+      propfind::principal(state, &href, &body)
+        .await
+        .into_response_or_err()
+    }
+    _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+  }
+}
+
+async fn dav_principal_handler<S>(
+  State(users): State<Users<S>>,
+  Path(user): Path<String>,
+  method: Method,
+  headers: HeaderMap,
+  ClientIp(ip): ClientIp,
+  body: Bytes,
+) -> Response
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  if method == Method::OPTIONS {
+    return options::handler();
+  }
+  let state = match require_user(&headers, ip, &users, &user).await {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
+  match method.as_str() {
+    "PROPFIND" => {
+      let href = handlers::principal_href(state);
+      propfind::principal(state, &href, &body)
+        .await
+        .into_response_or_err()
+    }
     _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
   }
 }
 
 async fn dav_home_handler<S>(
-  State(state): State<AppState<S>>,
+  State(users): State<Users<S>>,
+  Path(user): Path<String>,
   method: Method,
   headers: HeaderMap,
+  ClientIp(ip): ClientIp,
   body: Bytes,
 ) -> Response
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  if let Err(r) = require_auth(&method, &headers, &state) {
-    return *r;
+  if method == Method::OPTIONS {
+    return options::handler();
   }
+  let state =
+    match require_user_or_redirect(&headers, ip, &users, &user, None).await {
+      Ok(state) => state,
+      Err(r) => return *r,
+    };
   match method.as_str() {
-    "OPTIONS" => options::handler(),
-    "PROPFIND" => propfind::home_set(&state, depth(&headers), &body)
+    "PROPFIND" => propfind::home_set(state, depth(&headers), &body)
       .await
       .into_response_or_err(),
     _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
@@ -160,25 +427,42 @@ where
 }
 
 async fn dav_collection_handler<S>(
-  State(state): State<AppState<S>>,
-  Path(ab): Path<String>,
+  State(users): State<Users<S>>,
+  Path((user, ab)): Path<(String, String)>,
   method: Method,
   headers: HeaderMap,
+  ClientIp(ip): ClientIp,
   body: Bytes,
 ) -> Response
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  if let Err(r) = require_auth(&method, &headers, &state) {
-    return *r;
+  if method == Method::OPTIONS {
+    return options::handler();
   }
+  let state = match require_user_or_redirect(
+    &headers,
+    ip,
+    &users,
+    &user,
+    Some(&ab),
+  )
+  .await
+  {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
   match method.as_str() {
-    "OPTIONS" => options::handler(),
-    "PROPFIND" => propfind::collection(&state, &ab, depth(&headers), &body)
+    "PROPFIND" => {
+      propfind::collection(state, &headers, &ab, depth(&headers), &body)
+        .await
+        .into_response_or_err()
+    }
+    "REPORT" => report::handler(state, &ab, &body)
       .await
       .into_response_or_err(),
-    "REPORT" => report::handler(&state, &ab, &body)
+    "MKCOL" => mkcol::handler(state, &ab, &body)
       .await
       .into_response_or_err(),
     _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
@@ -186,23 +470,29 @@ where
 }
 
 async fn dav_resource_handler<S>(
-  State(state): State<AppState<S>>,
-  Path((ab, uid_vcf)): Path<(String, String)>,
+  State(users): State<Users<S>>,
+  Path((user, ab, uid_vcf)): Path<(String, String, String)>,
   method: Method,
   headers: HeaderMap,
+  ClientIp(ip): ClientIp,
   body: Bytes,
 ) -> Response
 where
   S: ContactStore + Clone + Send + Sync + 'static,
   S::Error: std::error::Error + Send + Sync + 'static,
 {
-  if let Err(r) = require_auth(&method, &headers, &state) {
-    return *r;
+  if method == Method::OPTIONS {
+    return options::handler();
   }
-  // Attach the addressbook name and raw resource identifier to every
-  // tracing event emitted while handling this request.
+  let state = match require_user(&headers, ip, &users, &user).await {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
+  // Attach the account, addressbook name and raw resource identifier to
+  // every tracing event emitted while handling this request.
   let span = tracing::info_span!(
     "carddav.resource",
+    user = %user,
     ab = %ab,
     uid = %uid_vcf,
     method = %method,
@@ -210,8 +500,7 @@ where
   let _guard = span.enter();
 
   match method.as_str() {
-    "OPTIONS" => options::handler(),
-    "GET" | "HEAD" => get::handler(&state, &method, &uid_vcf)
+    "GET" | "HEAD" => get::handler(state, &method, &headers, &ab, &uid_vcf)
       .await
       .into_response_or_err(),
     "PUT" => {
@@ -228,20 +517,171 @@ where
             .into_response();
         }
       };
-      put::handler(&state, &headers, &uid_vcf, body_str)
+      put::handler(state, &headers, &ab, &uid_vcf, body_str)
+        .await
+        .into_response_or_err()
+    }
+    "DELETE" => delete::handler(state, &ab, &uid_vcf)
+      .await
+      .into_response_or_err(),
+    "PROPFIND" => propfind::resource(state, &headers, &ab, &uid_vcf, &body)
+      .await
+      .into_response_or_err(),
+    _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+  }
+}
+
+async fn cal_home_handler<S>(
+  State(users): State<Users<S>>,
+  Path(user): Path<String>,
+  method: Method,
+  headers: HeaderMap,
+  ClientIp(ip): ClientIp,
+  body: Bytes,
+) -> Response
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  if method == Method::OPTIONS {
+    return options::handler();
+  }
+  let state = match require_user(&headers, ip, &users, &user).await {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
+  match method.as_str() {
+    "PROPFIND" => {
+      handlers::calendar::home_set(state, depth(&headers), &body)
+        .await
+        .into_response_or_err()
+    }
+    _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+  }
+}
+
+/// `/dav/calendars/{user}/birthdays` over CalDAV, or the
+/// `/dav/calendars/{user}/birthdays.ics` feed.
+async fn cal_collection_handler<S>(
+  State(users): State<Users<S>>,
+  Path((user, cal)): Path<(String, String)>,
+  method: Method,
+  headers: HeaderMap,
+  ClientIp(ip): ClientIp,
+  body: Bytes,
+) -> Response
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  if method == Method::OPTIONS {
+    return options::handler();
+  }
+  let state = match require_user(&headers, ip, &users, &user).await {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
+  let is_feed = cal.strip_suffix(".ics") == Some(calendar::BIRTHDAYS);
+  if cal != calendar::BIRTHDAYS && !is_feed {
+    return Error::NotFound.into_response();
+  }
+  match method.as_str() {
+    "GET" | "HEAD" if is_feed => {
+      handlers::calendar::feed(state, &method, &headers)
+        .await
+        .into_response_or_err()
+    }
+    "PROPFIND" if !is_feed => {
+      handlers::calendar::collection(state, depth(&headers), &body)
         .await
         .into_response_or_err()
     }
-    "DELETE" => delete::handler(&state, &uid_vcf)
+    "REPORT" if !is_feed => handlers::calendar::report(state, &body)
       .await
       .into_response_or_err(),
-    "PROPFIND" => propfind::resource(&state, &ab, &uid_vcf, &body)
+    "PUT" | "DELETE" | "MKCOL" | "MKCALENDAR" | "PROPPATCH" => {
+      read_only_calendar()
+    }
+    _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
+  }
+}
+
+async fn cal_event_handler<S>(
+  State(users): State<Users<S>>,
+  Path((user, cal, event)): Path<(String, String, String)>,
+  method: Method,
+  headers: HeaderMap,
+  ClientIp(ip): ClientIp,
+  body: Bytes,
+) -> Response
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  if method == Method::OPTIONS {
+    return options::handler();
+  }
+  let state = match require_user(&headers, ip, &users, &user).await {
+    Ok(state) => state,
+    Err(r) => return *r,
+  };
+  if cal != calendar::BIRTHDAYS {
+    return Error::NotFound.into_response();
+  }
+  match method.as_str() {
+    "GET" | "HEAD" => {
+      handlers::calendar::event(state, &method, &headers, &event)
+        .await
+        .into_response_or_err()
+    }
+    "PROPFIND" => handlers::calendar::event_propfind(state, &event, &body)
       .await
       .into_response_or_err(),
+    "PUT" | "DELETE" | "PROPPATCH" => read_only_calendar(),
     _ => StatusCode::METHOD_NOT_ALLOWED.into_response(),
   }
 }
 
+/// Birthdays change by editing the contact, never the calendar.
+fn read_only_calendar() -> Response {
+  Error::Forbidden("the birthday calendar is read-only".into()).into_response()
+}
+
+/// `/api/*`: forward to the JSON API of the account the request
+/// authenticates as.
+async fn api_handler<S>(
+  State(users): State<Users<S>>,
+  request: Request,
+) -> Response
+where
+  S: ContactStore + Clone + Send + Sync + 'static,
+  S::Error: std::error::Error + Send + Sync + 'static,
+{
+  let ip = auth::client_ip(
+    request.headers(),
+    request.extensions(),
+    users.guard().limits(),
+  );
+  let login = match auth::login(request.headers(), ip, &users).await {
+    Ok(login) => login,
+    Err(e) => return e.into_response(),
+  };
+  // A device's app password must not be able to mint or revoke others.
+  if login.app_password.is_some()
+    && request.uri().path().starts_with("/app-passwords")
+  {
+    return Error::Forbidden(
+      "app passwords are managed with the account password".into(),
+    )
+    .into_response();
+  }
+  let api = users.accounts[&login.state.user.username].api.clone();
+  match api.oneshot(request).await {
+    Ok(response) => response,
+    Err(infallible) => match infallible {},
+  }
+}
+
 async fn dav_wildcard_handler(method: Method) -> Response {
   if method == Method::OPTIONS {
     options::handler()
@@ -272,16 +712,20 @@ impl IntoResponseOrErr for Result<Response, Error> {
 
 #[cfg(test)]
 mod tests {
+  use std::net::SocketAddr;
+
   use axum::{
     body::Body,
+    extract::ConnectInfo,
     http::{Request, StatusCode, header},
   };
+  use kith_core::app_password::{NewAppPassword, generate_secret, hash_secret};
   use kith_store_sqlite::SqliteStore;
   use tower::ServiceExt as _;
   use uuid::Uuid;
 
   use super::{
-    test_helpers::{auth_header, make_state},
+    test_helpers::{auth_header, make_state, make_user_state},
     *,
   };
 
@@ -297,7 +741,7 @@ mod tests {
       builder = builder.header(k, v);
     }
     let req = builder.body(Body::from(body.to_string())).unwrap();
-    router(state).oneshot(req).await.unwrap()
+    router(Users::new([state])).oneshot(req).await.unwrap()
   }
 
   // ── OPTIONS
@@ -306,9 +750,14 @@ mod tests {
   #[tokio::test]
   async fn options_returns_204_with_dav_header() {
     let state = make_state("secret").await;
-    let resp =
-      oneshot_raw(state, "OPTIONS", "/dav/addressbooks/personal", vec![], "")
-        .await;
+    let resp = oneshot_raw(
+      state,
+      "OPTIONS",
+      "/dav/addressbooks/user/personal",
+      vec![],
+      "",
+    )
+    .await;
     assert_eq!(resp.status(), StatusCode::NO_CONTENT);
     let dav_val = resp.headers().get("dav").unwrap().to_str().unwrap();
     assert!(dav_val.contains("addressbook"), "DAV header: {dav_val}");
@@ -325,7 +774,7 @@ mod tests {
     let resp = oneshot_raw(
       state,
       "PROPFIND",
-      "/dav/addressbooks/personal",
+      "/dav/addressbooks/user/personal",
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::HeaderName::from_static("depth"), "1"),
@@ -348,7 +797,7 @@ mod tests {
     oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::CONTENT_TYPE, "text/vcard"),
@@ -361,7 +810,7 @@ mod tests {
     let resp = oneshot_raw(
       state,
       "PROPFIND",
-      "/dav/addressbooks/personal",
+      "/dav/addressbooks/user/personal",
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::HeaderName::from_static("depth"), "1"),
@@ -391,7 +840,7 @@ mod tests {
     let resp = oneshot_raw(
       state,
       "GET",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
@@ -415,7 +864,7 @@ mod tests {
     let put_resp = oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::CONTENT_TYPE, "text/vcard"),
@@ -429,7 +878,7 @@ mod tests {
     let get_resp = oneshot_raw(
       state,
       "GET",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
@@ -464,7 +913,7 @@ mod tests {
     let resp1 = oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       &vcard,
     )
@@ -484,7 +933,7 @@ mod tests {
     let resp2 = oneshot_raw(
       state,
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::IF_MATCH, etag.as_str()),
@@ -509,7 +958,7 @@ mod tests {
     let resp1 = oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       &vcard,
     )
@@ -531,7 +980,7 @@ mod tests {
     let resp2 = oneshot_raw(
       state,
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::IF_MATCH, etag_bare.as_str()),
@@ -554,7 +1003,7 @@ mod tests {
     oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       &vcard,
     )
@@ -566,7 +1015,7 @@ mod tests {
     let resp2 = oneshot_raw(
       state,
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![
         (header::AUTHORIZATION, auth.as_str()),
         (header::IF_MATCH, "\"stale-etag\""),
@@ -577,6 +1026,40 @@ mod tests {
     assert_eq!(resp2.status(), StatusCode::PRECONDITION_FAILED);
   }
 
+  #[tokio::test]
+  async fn repeated_add_only_edit_is_recorded_once() {
+    let mut state = make_state("secret").await;
+    let mut config = (*state.config).clone();
+    config.write_policy.set("name", policy::SyncPolicy::AddOnly);
+    state.config = Arc::new(config);
+    let auth = auth_header("user", "secret");
+    let uid = Uuid::new_v4();
+    let uri = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+    let vcard = |name: &str| {
+      format!(
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:{name}\r\nEND:VCARD\r\n"
+      )
+    };
+    let headers = || vec![(header::AUTHORIZATION, auth.as_str())];
+
+    let resp =
+      oneshot_raw(state.clone(), "PUT", &uri, headers(), &vcard("First")).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    assert!(resp.headers().contains_key(header::ETAG));
+
+    // The client keeps sending its edit, since the stored contact never
+    // matches it; only the first upload records the new name.
+    for _ in 0..2 {
+      let resp =
+        oneshot_raw(state.clone(), "PUT", &uri, headers(), &vcard("Second"))
+          .await;
+      assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+      assert!(!resp.headers().contains_key(header::ETAG));
+    }
+    let facts = state.store.get_facts(uid, None, None, false).await.unwrap();
+    assert_eq!(facts.len(), 2);
+  }
+
   // ── DELETE ───────────────────────────────────────────────────────────────────
 
   #[tokio::test]
@@ -592,7 +1075,7 @@ mod tests {
     oneshot_raw(
       state.clone(),
       "PUT",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       &vcard,
     )
@@ -601,7 +1084,7 @@ mod tests {
     let del_resp = oneshot_raw(
       state.clone(),
       "DELETE",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
@@ -611,7 +1094,7 @@ mod tests {
     let get_resp = oneshot_raw(
       state,
       "GET",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
@@ -627,7 +1110,7 @@ mod tests {
     let resp = oneshot_raw(
       state,
       "DELETE",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
       vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
@@ -635,47 +1118,1405 @@ mod tests {
     assert_eq!(resp.status(), StatusCode::NOT_FOUND);
   }
 
-  // ── Auth ─────────────────────────────────────────────────────────────────────
+  #[tokio::test]
+  async fn deleted_contact_is_restored_through_the_api() {
+    let app = two_users().await;
+    let uid = Uuid::new_v4();
+    let card = format!("/dav/addressbooks/bob/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\n\
+       EMAIL:carol@example.com\r\nEND:VCARD\r\n"
+    );
+    let resp = send(&app, "PUT", &card, "bob", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = send(&app, "DELETE", &card, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let restore = format!("/api/subjects/{uid}/reinstate");
+    let resp = send(&app, "POST", &restore, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let restored: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(restored.as_array().unwrap().len(), 2);
+
+    // The card is back, in the default book, with every property.
+    let resp = send(&app, "GET", &card, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let body = body_text(resp).await;
+    assert!(body.contains("FN:Carol"), "{body}");
+    assert!(body.contains("carol@example.com"), "{body}");
+
+    // Nothing is left to restore.
+    let resp = send(&app, "POST", &restore, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  }
 
   #[tokio::test]
-  async fn unauthenticated_requests_return_401() {
-    let state = make_state("secret").await;
+  async fn edited_property_lineage_is_served_through_the_api() {
+    let app = two_users().await;
+    let uid = Uuid::new_v4();
+    let card = format!("/dav/addressbooks/bob/personal/{uid}.vcf");
+    for email in ["carol@example.com", "carol@example.org"] {
+      let vcard = format!(
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\n\
+         EMAIL:{email}\r\nEND:VCARD\r\n"
+      );
+      let resp = send(&app, "PUT", &card, "bob", &vcard).await;
+      assert!(resp.status().is_success(), "PUT: {}", resp.status());
+    }
+
+    let facts = format!("/api/facts?subject_id={uid}&fact_type=email");
+    let resp = send(&app, "GET", &facts, "bob", "").await;
+    let facts: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    let fact_id = facts[0]["fact"]["fact_id"].as_str().unwrap();
+
+    let lineage = format!("/api/facts/{fact_id}/lineage");
+    let resp = send(&app, "GET", &lineage, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let lineage: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    let addresses: Vec<_> = lineage
+      .as_array()
+      .unwrap()
+      .iter()
+      .map(|rf| rf["fact"]["value"]["data"]["address"].as_str().unwrap())
+      .collect();
+    assert_eq!(addresses, ["carol@example.com", "carol@example.org"]);
+
+    let unknown = format!("/api/facts/{}/lineage", Uuid::new_v4());
+    let resp = send(&app, "GET", &unknown, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn import_is_undone_through_the_api() {
+    let app = two_users().await;
     let uid = Uuid::new_v4();
+    let card = format!("/dav/addressbooks/bob/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\n\
+       EMAIL:carol@example.com\r\nEND:VCARD\r\n"
+    );
+    let resp = send(&app, "PUT", &card, "bob", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let resp = send(&app, "GET", "/api/import-sources", "bob", "").await;
+    let sources: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(
+      sources,
+      serde_json::json!([
+        { "source_name": "carddav-put", "facts": 2, "active_facts": 2 }
+      ])
+    );
+
+    let preview = "/api/import-sources/facts?source_name=carddav-put";
+    let resp = send(&app, "GET", preview, "bob", "").await;
+    let facts: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(facts.as_array().unwrap().len(), 2);
+
+    let auth = auth_header("bob", "bob-secret");
+    let retract = "/api/import-sources/retract";
+    let undo = r#"{"source_name":"carddav-put","reason":"bad sync"}"#;
+    let resp = send_with(&app, "POST", retract, &auth, undo).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let retracted: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(retracted.as_array().unwrap().len(), 2);
+    assert_eq!(retracted[0]["reason"], "bad sync");
+
+    // The card went with its facts, and nothing is left to undo.
+    let resp = send(&app, "GET", &card, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send_with(&app, "POST", retract, &auth, undo).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_text(resp).await, "[]");
+  }
+
+  // ── Address books
+  // ────────────────────────────────────────────────────────────
+
+  async fn body_text(resp: axum::response::Response) -> String {
+    let bytes = axum::body::to_bytes(resp.into_body(), usize::MAX)
+      .await
+      .unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+  }
 
+  async fn put_contact(state: &AppState<SqliteStore>, ab: &str, uid: Uuid) {
+    let auth = auth_header("user", "secret");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\nEND:VCARD\r\n"
+    );
     let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      &format!("/dav/addressbooks/user/{ab}/{uid}.vcf"),
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &vcard,
+    )
+    .await;
+    assert!(resp.status().is_success(), "PUT {ab}: {}", resp.status());
+  }
+
+  async fn get_status(
+    state: &AppState<SqliteStore>,
+    ab: &str,
+    uid: Uuid,
+  ) -> StatusCode {
+    let auth = auth_header("user", "secret");
+    oneshot_raw(
       state.clone(),
       "GET",
-      &format!("/dav/addressbooks/personal/{uid}.vcf"),
-      vec![],
+      &format!("/dav/addressbooks/user/{ab}/{uid}.vcf"),
+      vec![(header::AUTHORIZATION, auth.as_str())],
       "",
     )
-    .await;
-    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
-    assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+    .await
+    .status()
   }
-}
 
-// ─── Shared test helpers
-// ──────────────────────────────────────────────────────
+  async fn ctag(state: &AppState<SqliteStore>, ab: &str) -> String {
+    let auth = auth_header("user", "secret");
+    let resp = oneshot_raw(
+      state.clone(),
+      "PROPFIND",
+      &format!("/dav/addressbooks/user/{ab}"),
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+    let xml = body_text(resp).await;
+    let start = xml.find("<CS:getctag>").unwrap() + "<CS:getctag>".len();
+    let end = xml[start..].find('<').unwrap();
+    xml[start..start + end].to_string()
+  }
 
-#[cfg(test)]
-pub(crate) mod test_helpers {
-  use std::{path::PathBuf, sync::Arc};
+  #[tokio::test]
+  async fn extended_mkcol_creates_addressbook_listed_in_home_set() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let body = r#"<?xml version="1.0"?>
+<D:mkcol xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+  <D:set><D:prop>
+    <D:resourcetype><D:collection/><C:addressbook/></D:resourcetype>
+    <D:displayname>Work Contacts</D:displayname>
+  </D:prop></D:set>
+</D:mkcol>"#;
+    let resp = oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/work",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      body,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
 
-  use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
-  use base64::{Engine as _, engine::general_purpose::STANDARD as B64};
+    let again = oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/work",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+    assert_eq!(again.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+    let resp = oneshot_raw(
+      state,
+      "PROPFIND",
+      "/dav/addressbooks/user",
+      vec![
+        (header::AUTHORIZATION, auth.as_str()),
+        (header::HeaderName::from_static("depth"), "1"),
+      ],
+      "",
+    )
+    .await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains("/dav/addressbooks/user/personal/"), "{xml}");
+    assert!(xml.contains("/dav/addressbooks/user/work/"), "{xml}");
+    assert!(xml.contains("Work Contacts"), "{xml}");
+  }
+
+  #[tokio::test]
+  async fn addressbook_names_are_encoded_in_hrefs() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let resp = oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/my%20book",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let uid = Uuid::new_v4();
+    put_contact(&state, "my%20book", uid).await;
+
+    let mut xml = String::new();
+    for uri in ["/dav/addressbooks/user", "/dav/addressbooks/user/my%20book"] {
+      let resp = oneshot_raw(
+        state.clone(),
+        "PROPFIND",
+        uri,
+        vec![
+          (header::AUTHORIZATION, auth.as_str()),
+          (header::HeaderName::from_static("depth"), "1"),
+        ],
+        "",
+      )
+      .await;
+      xml = body_text(resp).await;
+      assert!(xml.contains("/user/my%20book/</D:href>"), "{xml}");
+      assert!(!xml.contains("/my book/"), "{xml}");
+    }
+    assert!(xml.contains(&format!("/user/my%20book/{uid}.vcf")), "{xml}");
+  }
+
+  #[tokio::test]
+  async fn mkcol_for_plain_collection_is_forbidden() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let body = r#"<?xml version="1.0"?>
+<D:mkcol xmlns:D="DAV:"><D:set><D:prop>
+  <D:resourcetype><D:collection/></D:resourcetype>
+</D:prop></D:set></D:mkcol>"#;
+    let resp = oneshot_raw(
+      state,
+      "MKCOL",
+      "/dav/addressbooks/user/misc",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      body,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+  }
+
+  #[tokio::test]
+  async fn unknown_addressbook_returns_404_and_put_409() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let resp = oneshot_raw(
+      state.clone(),
+      "PROPFIND",
+      "/dav/addressbooks/user/nope",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let uid = Uuid::new_v4();
+    let resp = oneshot_raw(
+      state,
+      "PUT",
+      &format!("/dav/addressbooks/user/nope/{uid}.vcf"),
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:X\r\nEND:VCARD\r\n",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+  }
+
+  #[tokio::test]
+  async fn contacts_are_scoped_to_their_addressbooks() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/work",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+
+    let colleague = Uuid::new_v4();
+    let friend = Uuid::new_v4();
+    put_contact(&state, "work", colleague).await;
+    put_contact(&state, "personal", friend).await;
+
+    assert_eq!(get_status(&state, "work", colleague).await, StatusCode::OK);
+    assert_eq!(
+      get_status(&state, "personal", colleague).await,
+      StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+      get_status(&state, "work", friend).await,
+      StatusCode::NOT_FOUND
+    );
+
+    let resp = oneshot_raw(
+      state,
+      "PROPFIND",
+      "/dav/addressbooks/user/work",
+      vec![
+        (header::AUTHORIZATION, auth.as_str()),
+        (header::HeaderName::from_static("depth"), "1"),
+      ],
+      "",
+    )
+    .await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains(&colleague.to_string()), "{xml}");
+    assert!(!xml.contains(&friend.to_string()), "{xml}");
+  }
+
+  #[tokio::test]
+  async fn delete_only_retracts_when_leaving_last_addressbook() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/family",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+
+    let uid = Uuid::new_v4();
+    put_contact(&state, "personal", uid).await;
+    put_contact(&state, "family", uid).await;
+
+    let delete = async |ab: &str| {
+      oneshot_raw(
+        state.clone(),
+        "DELETE",
+        &format!("/dav/addressbooks/user/{ab}/{uid}.vcf"),
+        vec![(header::AUTHORIZATION, auth.as_str())],
+        "",
+      )
+      .await
+      .status()
+    };
+
+    assert_eq!(delete("personal").await, StatusCode::NO_CONTENT);
+    assert_eq!(
+      get_status(&state, "personal", uid).await,
+      StatusCode::NOT_FOUND
+    );
+    assert_eq!(get_status(&state, "family", uid).await, StatusCode::OK);
+
+    assert_eq!(delete("family").await, StatusCode::NO_CONTENT);
+    assert_eq!(
+      get_status(&state, "family", uid).await,
+      StatusCode::NOT_FOUND
+    );
+    let view = state.store.materialize(uid, None, None).await.unwrap();
+    assert!(view.unwrap().active_facts.is_empty());
+  }
+
+  #[tokio::test]
+  async fn ctag_changes_only_for_the_touched_addressbook() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    oneshot_raw(
+      state.clone(),
+      "MKCOL",
+      "/dav/addressbooks/user/work",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "",
+    )
+    .await;
+    put_contact(&state, "personal", Uuid::new_v4()).await;
+
+    let personal_before = ctag(&state, "personal").await;
+    let work_before = ctag(&state, "work").await;
+    put_contact(&state, "work", Uuid::new_v4()).await;
+
+    assert_eq!(ctag(&state, "personal").await, personal_before);
+    assert_ne!(ctag(&state, "work").await, work_before);
+  }
+
+  // ── vCard versions
+  // ───────────────────────────────────────────────────────────
+
+  /// GET `uri` with `accept`, returning the ETag, Content-Type and body.
+  async fn get_vcard(
+    state: &AppState<SqliteStore>,
+    uri: &str,
+    accept: &str,
+  ) -> (String, String, String) {
+    let auth = auth_header("user", "secret");
+    let resp = oneshot_raw(
+      state.clone(),
+      "GET",
+      uri,
+      vec![
+        (header::AUTHORIZATION, auth.as_str()),
+        (header::ACCEPT, accept),
+      ],
+      "",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let value = |name| resp.headers()[name].to_str().unwrap().to_string();
+    let (etag, content_type) =
+      (value(header::ETAG), value(header::CONTENT_TYPE));
+    (etag, content_type, body_text(resp).await)
+  }
+
+  #[tokio::test]
+  async fn vcard_3_is_negotiated_with_its_own_etag() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let uid = Uuid::new_v4();
+    let uri = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:{uid}\r\nFN:Dora\r\nEND:VCARD\r\n"
+    );
+    let put = oneshot_raw(
+      state.clone(),
+      "PUT",
+      &uri,
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &vcard,
+    )
+    .await;
+    assert_eq!(put.status(), StatusCode::CREATED);
+    let put_etag = put.headers()[header::ETAG].to_str().unwrap().to_string();
+
+    let (v3_etag, v3_type, v3_body) =
+      get_vcard(&state, &uri, "text/vcard;version=3.0").await;
+    assert!(v3_body.contains("VERSION:3.0"), "{v3_body}");
+    assert!(v3_type.contains("version=3.0"), "{v3_type}");
+    assert_eq!(v3_etag, put_etag, "PUT answers with the uploaded version");
+
+    let (v4_etag, _, v4_body) = get_vcard(&state, &uri, "text/vcard").await;
+    assert!(v4_body.contains("VERSION:4.0"), "{v4_body}");
+    assert_ne!(v3_etag, v4_etag);
+
+    // REPORT honours the address-data version, ETag included.
+    let report = format!(
+      r#"<C:addressbook-multiget xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+  <D:prop><D:getetag/><C:address-data content-type="text/vcard" version="3.0"/></D:prop>
+  <D:href>{uri}</D:href>
+</C:addressbook-multiget>"#
+    );
+    let resp = oneshot_raw(
+      state.clone(),
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &report,
+    )
+    .await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains("VERSION:3.0"), "{xml}");
+    assert!(xml.contains(v3_etag.trim_matches('"')), "{xml}");
+
+    // Either representation's ETag satisfies If-Match.
+    let updated = vcard.replace("Dora", "Dorothy");
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      &uri,
+      vec![
+        (header::AUTHORIZATION, auth.as_str()),
+        (header::IF_MATCH, v3_etag.as_str()),
+      ],
+      &updated,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  }
+
+  #[tokio::test]
+  async fn unsupported_address_data_version_is_forbidden() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let report = r#"<C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+  <D:prop><C:address-data content-type="text/vcard" version="2.1"/></D:prop>
+</C:addressbook-query>"#;
+    let resp = oneshot_raw(
+      state,
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      report,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    assert!(body_text(resp).await.contains("supported-address-data"));
+  }
+
+  // ── Photos ───────────────────────────────────────────────────────────────────
+
+  #[tokio::test]
+  async fn inline_photo_survives_a_sync_round_trip() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let uid = Uuid::new_v4();
+    let uri = format!("/dav/addressbooks/user/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:3.0\r\nUID:{uid}\r\nFN:Edna\r\nPHOTO;ENCODING=b;\
+       TYPE=JPEG:/9j/4A==\r\nEND:VCARD\r\n"
+    );
+    let put = |if_match: Option<String>| {
+      let (state, uri, auth, vcard) =
+        (state.clone(), uri.clone(), auth.clone(), vcard.clone());
+      async move {
+        let mut headers = vec![(header::AUTHORIZATION, auth.as_str())];
+        if let Some(etag) = &if_match {
+          headers.push((header::IF_MATCH, etag.as_str()));
+        }
+        let resp = oneshot_raw(state, "PUT", &uri, headers, &vcard).await;
+        let etag = resp.headers()[header::ETAG].to_str().unwrap().to_string();
+        (resp.status(), etag)
+      }
+    };
+
+    let (status, etag) = put(None).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let files = std::fs::read_dir(state.user.photo_dir.join(uid.to_string()))
+      .unwrap()
+      .count();
+    assert_eq!(files, 1);
+
+    let (_, _, v4_body) = get_vcard(&state, &uri, "text/vcard").await;
+    assert!(
+      v4_body.contains("PHOTO:data:image/jpeg;base64,/9j/4A==\r\n"),
+      "{v4_body}"
+    );
+    let (_, _, v3_body) =
+      get_vcard(&state, &uri, "text/vcard;version=3.0").await;
+    assert!(
+      v3_body.contains("PHOTO;ENCODING=b;TYPE=JPEG:/9j/4A==\r\n"),
+      "{v3_body}"
+    );
+
+    // The client's next sync sends the same picture back: nothing changes.
+    let (status, again) = put(Some(etag.clone())).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    assert_eq!(again, etag);
+
+    std::fs::remove_dir_all(&state.user.photo_dir).unwrap();
+  }
+
+  // ── Client hrefs and UIDs
+  // ────────────────────────────────────────────────────
+
+  #[tokio::test]
+  async fn client_href_and_uid_are_echoed_back() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let uri = "/dav/addressbooks/user/personal/alice@example.com.vcf";
+    let vcard = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{d8a1c2e4-alice}\r\nFN:\
+                 Alice\r\nEND:VCARD\r\n";
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      uri,
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      vcard,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      "/dav/addressbooks/user/personal/Bob%20Jones.vcf",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nFN:Bob\r\nEND:VCARD\r\n",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let (_, _, body) = get_vcard(&state, uri, "text/vcard").await;
+    assert!(body.contains("UID:{d8a1c2e4-alice}\r\n"), "{body}");
+
+    let resp = oneshot_raw(
+      state.clone(),
+      "PROPFIND",
+      "/dav/addressbooks/user/personal",
+      vec![
+        (header::AUTHORIZATION, auth.as_str()),
+        (header::HeaderName::from_static("depth"), "1"),
+      ],
+      "",
+    )
+    .await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains(&format!("{uri}</D:href>")), "{xml}");
+    assert!(xml.contains("/personal/Bob%20Jones.vcf</D:href>"), "{xml}");
+
+    let report = r#"<D:sync-collection xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+  <D:sync-token/>
+  <D:prop><C:address-data/></D:prop>
+</D:sync-collection>"#;
+    let resp = oneshot_raw(
+      state.clone(),
+      "REPORT",
+      "/dav/addressbooks/user/personal",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      report,
+    )
+    .await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains(&format!("{uri}</D:href>")), "{xml}");
+    assert!(xml.contains("UID:{d8a1c2e4-alice}"), "{xml}");
+  }
+
+  #[tokio::test]
+  async fn uid_used_by_another_resource_is_a_conflict() {
+    let state = make_state("secret").await;
+    let auth = auth_header("user", "secret");
+    let vcard = |name: &str| {
+      format!(
+        "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:shared-uid\r\nFN:{name}\r\nEND:\
+         VCARD\r\n"
+      )
+    };
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      "/dav/addressbooks/user/personal/first.vcf",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &vcard("First"),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      "/dav/addressbooks/user/personal/second.vcf",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &vcard("Second"),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+    let xml = body_text(resp).await;
+    assert!(xml.contains("<C:no-uid-conflict>"), "{xml}");
+    assert!(xml.contains("/personal/first.vcf</D:href>"), "{xml}");
+
+    // Updating the resource that owns the UID is fine.
+    let resp = oneshot_raw(
+      state.clone(),
+      "PUT",
+      "/dav/addressbooks/user/personal/first.vcf",
+      vec![(header::AUTHORIZATION, auth.as_str())],
+      &vcard("Firstly"),
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+  }
+
+  // ── Auth ─────────────────────────────────────────────────────────────────────
+
+  #[tokio::test]
+  async fn unauthenticated_requests_return_401() {
+    let state = make_state("secret").await;
+    let uid = Uuid::new_v4();
+
+    let resp = oneshot_raw(
+      state.clone(),
+      "GET",
+      &format!("/dav/addressbooks/user/personal/{uid}.vcf"),
+      vec![],
+      "",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    assert!(resp.headers().contains_key(header::WWW_AUTHENTICATE));
+  }
+
+  #[tokio::test]
+  async fn api_requires_auth() {
+    let state = make_state("secret").await;
+    let resp = oneshot_raw(state, "GET", "/api/subjects", vec![], "").await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+  }
+
+  // ── Multiple users
+  // ───────────────────────────────────────────────────────────
+
+  /// A router serving `alice` and `bob`, each with an empty store and the
+  /// password `{user}-secret`.
+  async fn two_users() -> Router {
+    router(Users::new([
+      make_user_state("alice", "alice-secret").await,
+      make_user_state("bob", "bob-secret").await,
+    ]))
+  }
+
+  async fn send(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    user: &str,
+    body: &str,
+  ) -> axum::response::Response {
+    let auth = auth_header(user, &format!("{user}-secret"));
+    let req = Request::builder()
+      .method(method)
+      .uri(uri)
+      .header(header::AUTHORIZATION, auth)
+      .header(header::HeaderName::from_static("depth"), "1")
+      .body(Body::from(body.to_string()))
+      .unwrap();
+    app.clone().oneshot(req).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn single_account_urls_redirect_to_the_users_book() {
+    let app = two_users().await;
+    let resp =
+      send(&app, "PROPFIND", "/dav/addressbooks/personal", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+      resp.headers()[header::LOCATION],
+      "http://localhost:5232/dav/addressbooks/bob/personal/"
+    );
+
+    let uid = Uuid::new_v4();
+    let old = format!("/dav/addressbooks/personal/{uid}.vcf");
+    let resp = send(&app, "PUT", &old, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::PERMANENT_REDIRECT);
+    assert_eq!(
+      resp.headers()[header::LOCATION],
+      format!("http://localhost:5232/dav/addressbooks/bob/personal/{uid}.vcf")
+    );
+
+    // Neither another account nor an unknown book is redirected, even when
+    // the account's name is also one of the user's books.
+    let resp =
+      send(&app, "MKCOL", "/dav/addressbooks/bob/alice", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp =
+      send(&app, "PROPFIND", "/dav/addressbooks/alice", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp =
+      send(&app, "PROPFIND", "/dav/addressbooks/work", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn root_points_at_the_users_principal() {
+    let app = two_users().await;
+    let resp = send(&app, "PROPFIND", "/dav", "bob", "").await;
+    assert_eq!(resp.status().as_u16(), 207);
+    let xml = body_text(resp).await;
+    assert!(xml.contains("/dav/principals/bob/</D:href>"), "{xml}");
+    assert!(xml.contains("/dav/addressbooks/bob/</D:href>"), "{xml}");
+
+    let resp = send(&app, "PROPFIND", "/dav/principals/bob", "bob", "").await;
+    assert_eq!(resp.status().as_u16(), 207);
+    let resp = send(&app, "PROPFIND", "/dav/principals/alice", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn users_cannot_see_each_others_contacts() {
+    let app = two_users().await;
+    let uid = Uuid::new_v4();
+    let uri = format!("/dav/addressbooks/alice/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\nEND:VCARD\r\n"
+    );
+    let resp = send(&app, "PUT", &uri, "alice", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Not through alice's URLs with bob's credentials…
+    let resp = send(&app, "GET", &uri, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send(&app, "PUT", &uri, "bob", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    // …nor in bob's own address book…
+    let bobs = format!("/dav/addressbooks/bob/personal/{uid}.vcf");
+    let resp = send(&app, "GET", &bobs, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send(
+      &app,
+      "PROPFIND",
+      "/dav/addressbooks/bob/personal",
+      "bob",
+      "",
+    )
+    .await;
+    assert!(!body_text(resp).await.contains(&uid.to_string()));
+
+    // …nor through the API.
+    let resp = send(&app, "GET", "/api/subjects", "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(body_text(resp).await, "[]");
+    let resp =
+      send(&app, "GET", &format!("/api/subjects/{uid}"), "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+
+    let resp = send(&app, "GET", "/api/subjects", "alice", "").await;
+    assert!(body_text(resp).await.contains(&uid.to_string()));
+  }
+
+  // ── App passwords
+  // ────────────────────────────────────────────────────────────
+
+  async fn send_with(
+    app: &Router,
+    method: &str,
+    uri: &str,
+    auth: &str,
+    json: &str,
+  ) -> axum::response::Response {
+    let req = Request::builder()
+      .method(method)
+      .uri(uri)
+      .header(header::AUTHORIZATION, auth)
+      .header(header::CONTENT_TYPE, "application/json")
+      .header(header::HeaderName::from_static("depth"), "1")
+      .body(Body::from(json.to_string()))
+      .unwrap();
+    app.clone().oneshot(req).await.unwrap()
+  }
+
+  /// Create an app password for `user`; returns its id and secret.
+  async fn create_app_password(app: &Router, user: &str) -> (String, String) {
+    let auth = auth_header(user, &format!("{user}-secret"));
+    let resp = send_with(
+      app,
+      "POST",
+      "/api/app-passwords",
+      &auth,
+      r#"{"label":"phone"}"#,
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let created: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    (
+      created["app_password_id"].as_str().unwrap().to_string(),
+      created["secret"].as_str().unwrap().to_string(),
+    )
+  }
+
+  #[tokio::test]
+  async fn app_passwords_work_as_basic_and_bearer_credentials() {
+    let app = two_users().await;
+    let (_, secret) = create_app_password(&app, "bob").await;
+
+    let basic = auth_header("bob", &secret);
+    let resp =
+      send_with(&app, "PROPFIND", "/dav/principals/bob", &basic, "").await;
+    assert_eq!(resp.status().as_u16(), 207);
+
+    // A bearer token finds its own account.
+    let bearer = format!("Bearer {secret}");
+    let resp = send_with(&app, "PROPFIND", "/dav", &bearer, "").await;
+    assert!(body_text(resp).await.contains("/dav/principals/bob/"));
+    let resp = send_with(&app, "GET", "/api/subjects", &bearer, "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // It is bob's password, not alice's.
+    let resp = send_with(
+      &app,
+      "PROPFIND",
+      "/dav/principals/alice",
+      &auth_header("alice", &secret),
+      "",
+    )
+    .await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let resp = send(&app, "GET", "/api/app-passwords", "bob", "").await;
+    let listed = body_text(resp).await;
+    assert!(listed.contains(r#""last_used_at":"20"#), "{listed}");
+    assert!(!listed.contains(&secret));
+  }
+
+  #[tokio::test]
+  async fn revoked_app_passwords_are_refused() {
+    let app = two_users().await;
+    let (id, secret) = create_app_password(&app, "alice").await;
+    let bearer = format!("Bearer {secret}");
+
+    // An app password cannot manage app passwords…
+    let revoke = format!("/api/app-passwords/{id}/revoke");
+    let resp = send_with(&app, "POST", &revoke, &bearer, "").await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+    // …but the account password can.
+    let resp = send(&app, "POST", &revoke, "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = send_with(&app, "GET", "/api/subjects", &bearer, "").await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    let basic = auth_header("alice", &secret);
+    let resp = send_with(&app, "GET", "/api/subjects", &basic, "").await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    let missing = format!("/api/app-passwords/{}/revoke", Uuid::new_v4());
+    let resp = send(&app, "POST", &missing, "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  }
+
+  // ── Login throttling
+  // ─────────────────────────────────────────────────────────
+
+  /// A request from `ip`, as told by the `x-client-ip` header.
+  async fn send_from(
+    app: &Router,
+    ip: &str,
+    auth: &str,
+  ) -> axum::response::Response {
+    let req = Request::builder()
+      .method("PROPFIND")
+      .uri("/dav/principals/bob")
+      .header(header::AUTHORIZATION, auth)
+      .header("x-client-ip", ip)
+      .body(Body::empty())
+      .unwrap();
+    app.clone().oneshot(req).await.unwrap()
+  }
+
+  #[tokio::test]
+  async fn repeated_failures_lock_out_ip_and_username() {
+    let mut bob = make_user_state("bob", "bob-secret").await;
+    Arc::make_mut(&mut bob.config).auth_limits = throttle::AuthLimits {
+      free_attempts: 1,
+      base_lockout_secs: 60,
+      client_ip_header: Some("x-client-ip".into()),
+      ..Default::default()
+    };
+    let secret = generate_secret();
+    bob
+      .store
+      .create_app_password(NewAppPassword {
+        label:       "phone".into(),
+        secret_hash: hash_secret(&secret),
+      })
+      .await
+      .unwrap();
+    let app = router(Users::new([bob]));
+    let wrong = auth_header("bob", "guess");
+    let right = auth_header("bob", "bob-secret");
+
+    for _ in 0..2 {
+      let resp = send_from(&app, "192.0.2.1", &wrong).await;
+      assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    // Locked out: even the right password is not checked.
+    let resp = send_from(&app, "192.0.2.1", &right).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry: u64 = resp.headers()[header::RETRY_AFTER]
+      .to_str()
+      .unwrap()
+      .parse()
+      .unwrap();
+    assert!((1..=60).contains(&retry), "{retry}");
+
+    // The username is locked from everywhere…
+    let resp = send_from(&app, "192.0.2.2", &right).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    // …except to the account's app passwords.
+    let resp =
+      send_from(&app, "192.0.2.2", &auth_header("bob", &secret)).await;
+    assert_eq!(resp.status().as_u16(), 207);
+  }
+
+  #[tokio::test]
+  async fn client_ip_is_the_entry_the_proxy_added() {
+    let mut bob = make_user_state("bob", "bob-secret").await;
+    Arc::make_mut(&mut bob.config).auth_limits = throttle::AuthLimits {
+      free_attempts: 1,
+      base_lockout_secs: 60,
+      client_ip_header: Some("x-client-ip".into()),
+      ..Default::default()
+    };
+    let app = router(Users::new([bob]));
+    let wrong = auth_header("bob", "guess");
+    let nobody = auth_header("nobody", "guess");
+
+    // A fresh leading entry on every request does not dodge the lockout.
+    for spoof in ["203.0.113.1", "203.0.113.2"] {
+      let ip = format!("{spoof}, 192.0.2.1");
+      let resp = send_from(&app, &ip, &nobody).await;
+      assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = send_from(&app, "203.0.113.3, 192.0.2.1", &wrong).await;
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+    let resp = send_from(&app, "192.0.2.1, 192.0.2.2", &wrong).await;
+    assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+    // Without the header, the connection's address is throttled instead.
+    let peer = SocketAddr::from(([198, 51, 100, 7], 4242));
+    let propfind = || {
+      let mut req = Request::builder()
+        .method("PROPFIND")
+        .uri("/dav/principals/bob")
+        .header(header::AUTHORIZATION, &nobody)
+        .body(Body::empty())
+        .unwrap();
+      req.extensions_mut().insert(ConnectInfo(peer));
+      req
+    };
+    for _ in 0..2 {
+      let resp = app.clone().oneshot(propfind()).await.unwrap();
+      assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+    }
+    let resp = app.clone().oneshot(propfind()).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::TOO_MANY_REQUESTS);
+  }
+
+  #[tokio::test]
+  async fn verified_passwords_are_cached() {
+    let state = make_user_state("bob", "bob-secret").await;
+    let users = Users::new([state]);
+    let app = router(users.clone());
+    assert!(!users.guard().is_verified("bob", "bob-secret"));
+
+    let resp = send(&app, "PROPFIND", "/dav/principals/bob", "bob", "").await;
+    assert_eq!(resp.status().as_u16(), 207);
+    assert!(users.guard().is_verified("bob", "bob-secret"));
+    assert!(!users.guard().is_verified("bob", "bob-guess"));
+  }
+
+  // ── Birthday calendar
+  // ────────────────────────────────────────────────────────
+
+  #[tokio::test]
+  async fn birthdays_are_served_as_a_calendar() {
+    let app = two_users().await;
+    let vcard = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:carol\r\nFN:Carol\r\n\
+                 BDAY:19900315\r\nEND:VCARD\r\n";
+    let card = "/dav/addressbooks/bob/personal/carol.vcf";
+    let resp = send(&app, "PUT", card, "bob", vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    let resp = send(&app, "PROPFIND", "/dav/principals/bob", "bob", "").await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains("<cal:calendar-home-set>"), "{xml}");
+    assert!(xml.contains("/dav/calendars/bob/</D:href>"), "{xml}");
+
+    let resp = send(&app, "PROPFIND", "/dav/calendars/bob", "bob", "").await;
+    let xml = body_text(resp).await;
+    assert!(xml.contains("/dav/calendars/bob/birthdays/</D:href>"), "{xml}");
+    assert!(xml.contains("<cal:calendar/>"), "{xml}");
+
+    let calendar = "/dav/calendars/bob/birthdays";
+    let resp = send(&app, "PROPFIND", calendar, "bob", "").await;
+    assert_eq!(resp.status().as_u16(), 207);
+    let xml = body_text(resp).await;
+    let event = xml
+      .split("<D:href>")
+      .filter_map(|r| r.split_once("</D:href>"))
+      .map(|(href, _)| href)
+      .find(|href| href.ends_with(".ics"))
+      .expect("an event")
+      .strip_prefix("http://localhost:5232")
+      .unwrap()
+      .to_string();
+
+    let resp = send(&app, "GET", &event, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let etag = resp.headers()[header::ETAG].to_str().unwrap().to_string();
+    let ics = body_text(resp).await;
+    assert!(ics.contains("DTSTART;VALUE=DATE:19900315\r\n"), "{ics}");
+    assert!(ics.contains("RRULE:FREQ=YEARLY\r\n"), "{ics}");
+    assert!(ics.contains("SUMMARY:Carol's birthday\r\n"), "{ics}");
+
+    let req = Request::builder()
+      .uri(&event)
+      .header(header::AUTHORIZATION, auth_header("bob", "bob-secret"))
+      .header(header::IF_NONE_MATCH, &etag)
+      .body(Body::empty())
+      .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::NOT_MODIFIED);
+
+    let body = r#"<?xml version="1.0"?>
+<C:calendar-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:caldav">
+  <D:prop><D:getetag/><C:calendar-data/></D:prop>
+  <C:filter><C:comp-filter name="VCALENDAR"/></C:filter>
+</C:calendar-query>"#;
+    let resp = send(&app, "REPORT", calendar, "bob", body).await;
+    assert_eq!(resp.status().as_u16(), 207);
+    let xml = body_text(resp).await;
+    assert!(xml.contains(&etag.replace('"', "&quot;")), "{xml}");
+    assert!(xml.contains("DTSTART;VALUE=DATE:19900315"), "{xml}");
+
+    let feed = "/dav/calendars/bob/birthdays.ics";
+    let resp = send(&app, "GET", feed, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let ics = body_text(resp).await;
+    assert!(ics.contains("X-WR-CALNAME:Birthdays\r\n"), "{ics}");
+    assert!(ics.contains("DTSTART;VALUE=DATE:19900315\r\n"), "{ics}");
+
+    // The calendar is read-only and private to its account.
+    let resp = send(&app, "PUT", &event, "bob", &ics).await;
+    assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+    let resp = send(&app, "GET", feed, "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let other = feed.replace("bob", "alice");
+    let resp = send(&app, "GET", &other, "alice", "").await;
+    assert!(!body_text(resp).await.contains("BEGIN:VEVENT"));
+  }
+
+  #[tokio::test]
+  async fn removed_birthdays_leave_the_calendar() {
+    let app = two_users().await;
+    let card = "/dav/addressbooks/bob/personal/carol.vcf";
+    let with_bday = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:carol\r\nFN:Carol\r\n\
+                     BDAY:19900315\r\nEND:VCARD\r\n";
+    send(&app, "PUT", card, "bob", with_bday).await;
+    let feed = "/dav/calendars/bob/birthdays.ics";
+    let resp = send(&app, "GET", feed, "bob", "").await;
+    let before = resp.headers()[header::ETAG].clone();
+
+    let without = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:carol\r\nFN:Carol\r\n\
+                   END:VCARD\r\n";
+    send(&app, "PUT", card, "bob", without).await;
+    let resp = send(&app, "GET", feed, "bob", "").await;
+    assert_ne!(resp.headers()[header::ETAG], before);
+    assert!(!body_text(resp).await.contains("BEGIN:VEVENT"));
+  }
+
+  // ── Change events
+  // ────────────────────────────────────────────────────────────
+
+  /// The next `(event, id, data)` of a server-sent event stream.
+  #[tokio::test]
+  async fn history_moves_between_accounts_through_the_api() {
+    let app = two_users().await;
+    let uid = Uuid::new_v4();
+    let card = format!("/dav/addressbooks/alice/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{uid}\r\nFN:Carol\r\nEND:VCARD\r\n"
+    );
+    let resp = send(&app, "PUT", &card, "alice", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+    let resp = send(&app, "DELETE", &card, "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+
+    let export = format!("/api/subjects/{uid}/export");
+    let resp = send(&app, "GET", &export, "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], "application/jsonl");
+    let doc = body_text(resp).await;
+    let resp = send(&app, "GET", "/api/export", "alice", "").await;
+    assert_eq!(body_text(resp).await.lines().count(), doc.lines().count());
+
+    // Bob gets carol's whole history, retraction included.
+    let resp = send(&app, "POST", "/api/import", "bob", &doc).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let report: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(report["subjects"], 1);
+    assert_eq!(report["retractions"], 1);
+    let resp = send(&app, "GET", &export, "bob", "").await;
+    let bobs = body_text(resp).await;
+    // Only the header's `exported_at` differs, and the `personal` book: bob's
+    // own stands in for alice's.
+    let book = |doc: &str| {
+      let line = doc.lines().find(|l| l.contains("address_book")).unwrap();
+      let record: serde_json::Value = serde_json::from_str(line).unwrap();
+      record["addressbook_id"].as_str().unwrap().to_string()
+    };
+    let records = |doc: &str| {
+      doc
+        .lines()
+        .skip(1)
+        .filter(|l| !l.contains("address_book"))
+        .map(|l| l.replace(&book(doc), "personal"))
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(records(&bobs), records(&doc));
+
+    let resp = send(&app, "POST", "/api/import", "bob", &doc).await;
+    let report: serde_json::Value =
+      serde_json::from_str(&body_text(resp).await).unwrap();
+    assert_eq!(report["facts"], 0);
+    assert_eq!(report["skipped"], doc.lines().count() - 1);
+
+    let resp = send(&app, "POST", "/api/import", "bob", "not json").await;
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    let missing = format!("/api/subjects/{}/export", Uuid::new_v4());
+    let resp = send(&app, "GET", &missing, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn merged_contact_is_removed_from_its_addressbook() {
+    let app = two_users().await;
+    let auth = auth_header("alice", "alice-secret");
+    let (survivor, absorbed) = (Uuid::new_v4(), Uuid::new_v4());
+    let card =
+      |uid: Uuid| format!("/dav/addressbooks/alice/personal/{uid}.vcf");
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{survivor}\r\nFN:Carol\r\n\
+       END:VCARD\r\n"
+    );
+    send(&app, "PUT", &card(survivor), "alice", &vcard).await;
+    let vcard = format!(
+      "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:{absorbed}\r\nFN:Carol\r\n\
+       EMAIL:carol@example.com\r\nEND:VCARD\r\n"
+    );
+    send(&app, "PUT", &card(absorbed), "alice", &vcard).await;
+
+    let sync = |token: &str| {
+      format!(
+        r#"<D:sync-collection xmlns:D="DAV:">
+  <D:sync-token>{token}</D:sync-token>
+  <D:prop><D:getetag/></D:prop>
+</D:sync-collection>"#
+      )
+    };
+    let book = "/dav/addressbooks/alice/personal";
+    let xml =
+      body_text(send(&app, "REPORT", book, "alice", &sync("")).await).await;
+    let start = xml.find("<D:sync-token>").unwrap() + "<D:sync-token>".len();
+    let token = &xml[start..start + xml[start..].find('<').unwrap()];
+
+    let merge = format!("/api/subjects/{survivor}/merge");
+    let body = format!(r#"{{"absorbed_id":"{absorbed}"}}"#);
+    let resp = send_with(&app, "POST", &merge, &auth, &body).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+
+    // The absorbed href is gone; the survivor carries its facts.
+    let xml =
+      body_text(send(&app, "REPORT", book, "alice", &sync(token)).await).await;
+    assert!(xml.contains(&card(survivor)), "{xml}");
+    assert!(xml.contains(&card(absorbed)), "{xml}");
+    assert_eq!(xml.matches("404 Not Found").count(), 1, "{xml}");
+    let resp = send(&app, "GET", &card(absorbed), "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+    let resp = send(&app, "GET", &card(survivor), "alice", "").await;
+    assert!(body_text(resp).await.contains("carol@example.com"));
+    let resp = send(&app, "PUT", &card(absorbed), "alice", &vcard).await;
+    assert_eq!(resp.status(), StatusCode::CONFLICT);
+
+    let unmerge = format!("/api/subjects/{survivor}/unmerge");
+    let resp = send_with(&app, "POST", &unmerge, &auth, &body).await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = send(&app, "GET", &card(absorbed), "alice", "").await;
+    assert_eq!(resp.status(), StatusCode::OK);
+    let resp = send(&app, "GET", &card(survivor), "alice", "").await;
+    assert!(!body_text(resp).await.contains("carol@example.com"));
+  }
+
+  async fn next_event(
+    body: &mut axum::body::BodyDataStream,
+    buf: &mut String,
+  ) -> (String, String, serde_json::Value) {
+    use futures_util::StreamExt as _;
+    loop {
+      if let Some(end) = buf.find("\n\n") {
+        let frame: String = buf.drain(..end + 2).collect();
+        let field = |name: &str| {
+          frame
+            .lines()
+            .find_map(|l| l.strip_prefix(name))
+            .map(|v| v.trim().to_string())
+        };
+        if let Some(event) = field("event:") {
+          let data = serde_json::from_str(&field("data:").unwrap()).unwrap();
+          return (event, field("id:").unwrap(), data);
+        }
+        continue;
+      }
+      let chunk = tokio::time::timeout(
+        std::time::Duration::from_secs(5),
+        body.next(),
+      )
+      .await
+      .expect("no event within 5s")
+      .expect("stream ended")
+      .unwrap();
+      buf.push_str(std::str::from_utf8(&chunk).unwrap());
+    }
+  }
+
+  #[tokio::test]
+  async fn store_changes_are_streamed_as_server_sent_events() {
+    let app = two_users().await;
+    let card = "/dav/addressbooks/bob/personal/carol.vcf";
+    let vcard = "BEGIN:VCARD\r\nVERSION:4.0\r\nUID:carol\r\nFN:Carol\r\n\
+                 END:VCARD\r\n";
+    let resp = send(&app, "PUT", card, "bob", vcard).await;
+    assert_eq!(resp.status(), StatusCode::CREATED);
+
+    // Resuming from the start replays the PUT…
+    let req = Request::builder()
+      .uri("/api/events")
+      .header(header::AUTHORIZATION, auth_header("bob", "bob-secret"))
+      .header("last-event-id", "0.0.0.0")
+      .body(Body::empty())
+      .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::OK);
+    assert_eq!(resp.headers()[header::CONTENT_TYPE], "text/event-stream");
+    let mut body = resp.into_body().into_data_stream();
+    let mut buf = String::new();
+    let (event, id, data) = next_event(&mut body, &mut buf).await;
+    assert_eq!(event, "fact-recorded");
+    assert_eq!(id, "1.0.0.0");
+    assert_eq!(data["fact"]["value"]["data"]["full"], "Carol");
+    let subject_id = data["subject_id"].clone();
+
+    // …then streams writes as they happen.
+    let resp = send(&app, "DELETE", card, "bob", "").await;
+    assert_eq!(resp.status(), StatusCode::NO_CONTENT);
+    let (event, id, data) = next_event(&mut body, &mut buf).await;
+    assert_eq!(event, "fact-retracted");
+    assert_eq!(id, "1.0.1.0");
+    assert_eq!(data["subject_id"], subject_id);
+
+    let req = Request::builder()
+      .uri("/api/events")
+      .header(header::AUTHORIZATION, auth_header("bob", "bob-secret"))
+      .header("last-event-id", "latest")
+      .body(Body::empty())
+      .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+  }
+}
+
+// ─── Shared test helpers
+// ──────────────────────────────────────────────────────
+
+#[cfg(test)]
+pub(crate) mod test_helpers {
+  use std::{path::PathBuf, sync::Arc};
+
+  use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+  use base64::{Engine as _, engine::general_purpose::STANDARD as B64};
   use kith_store_sqlite::SqliteStore;
   use rand_core::OsRng;
 
-  use crate::{AppState, ServerConfig, auth::AuthConfig};
+  use crate::{AppState, ServerConfig, UserConfig};
 
+  /// State of an account called `user` with an empty in-memory store.
   pub(crate) async fn make_state(password: &str) -> AppState<SqliteStore> {
+    make_user_state("user", password).await
+  }
+
+  /// State of an account called `username` with an empty in-memory store.
+  pub(crate) async fn make_user_state(
+    username: &str,
+    password: &str,
+  ) -> AppState<SqliteStore> {
     let store = SqliteStore::open_in_memory().await.unwrap();
+    crate::ensure_default_addressbook(&store, "personal")
+      .await
+      .unwrap();
     let salt = SaltString::generate(&mut OsRng);
     let hash = Argon2::default()
       .hash_password(password.as_bytes(), &salt)
       .unwrap()
       .to_string();
+    let user = UserConfig {
+      username:      username.to_string(),
+      password_hash: hash,
+      store_path:    PathBuf::from(":memory:"),
+      photo_dir:     std::env::temp_dir()
+        .join(format!("kith-photos-{}", uuid::Uuid::new_v4())),
+    };
     AppState {
       store:  Arc::new(store),
       config: Arc::new(ServerConfig {
@@ -683,14 +2524,16 @@ pub(crate) mod test_helpers {
         port:               5232,
         base_url:           "http://localhost:5232".to_string(),
         addressbook:        "personal".to_string(),
-        store_path:         PathBuf::from(":memory:"),
-        auth_username:      "user".to_string(),
-        auth_password_hash: hash.clone(),
-      }),
-      auth:   Arc::new(AuthConfig {
-        username:      "user".to_string(),
-        password_hash: hash,
+        store_path:         user.store_path.clone(),
+        photo_dir:          user.photo_dir.clone(),
+        auth_username:      user.username.clone(),
+        auth_password_hash: user.password_hash.clone(),
+        users:              vec![],
+        match_threshold:    crate::diff::DEFAULT_MATCH_THRESHOLD,
+        write_policy:       Default::default(),
+        auth_limits:        Default::default(),
       }),
+      user:   Arc::new(user),
     }
   }
 
@@ -1,7 +1,7 @@
 //! kith-carddav server binary.
 //!
 //! Reads `config.toml` (or the path specified with `--config`), opens an
-//! in-process SQLite store, and serves CardDAV over HTTP.
+//! in-process SQLite store per account, and serves CardDAV over HTTP.
 //!
 //! # Password hash generation
 //!
@@ -12,18 +12,19 @@
 //! ```
 
 use std::{
+  net::SocketAddr,
   path::{Path, PathBuf},
   sync::Arc,
 };
 
 use anyhow::Context as _;
 use argon2::{Argon2, PasswordHasher, password_hash::SaltString};
+use axum::{ServiceExt, extract::Request};
 use clap::Parser;
-use kith_carddav::{AppState, ServerConfig, auth::AuthConfig};
+use kith_carddav::{AppState, ServerConfig, Users};
 use kith_store_sqlite::SqliteStore;
 use rand_core::OsRng;
 use tokio::net::TcpListener;
-use tower::make::Shared;
 use tower_http::normalize_path::NormalizePath;
 use tracing::level_filters::LevelFilter;
 use tracing_subscriber::EnvFilter;
@@ -65,10 +66,15 @@ async fn main() -> anyhow::Result<()> {
     return Ok(());
   }
 
-  // Load configuration.
+  // Load configuration. Nested keys come from `KITH_TABLE__KEY` variables,
+  // e.g. `KITH_AUTH_LIMITS__CLIENT_IP_HEADER`.
   let settings = config::Config::builder()
     .add_source(config::File::from(cli.config).required(false))
-    .add_source(config::Environment::with_prefix("KITH"))
+    .add_source(
+      config::Environment::with_prefix("KITH")
+        .prefix_separator("_")
+        .separator("__"),
+    )
     .build()
     .context("failed to read config file")?;
 
@@ -76,25 +82,50 @@ async fn main() -> anyhow::Result<()> {
     .try_deserialize()
     .context("failed to deserialise ServerConfig")?;
 
-  // Expand `~` in store path.
-  let store_path = expand_tilde(&server_cfg.store_path);
+  let config = Arc::new(server_cfg.clone());
+
+  // Open every account's store, each with its default address book.
+  let mut states: Vec<AppState<SqliteStore>> = vec![];
+  for mut user in server_cfg.accounts() {
+    // Expand `~` in store path and photo directory.
+    let store_path = expand_tilde(&user.store_path);
+    user.photo_dir = expand_tilde(&user.photo_dir);
+
+    // Accounts must not share contacts, so each needs a store of its own.
+    for other in &states {
+      if other.user.username == user.username {
+        anyhow::bail!("user {:?} is configured twice", user.username);
+      }
+      if expand_tilde(&other.user.store_path) == store_path
+        || other.user.photo_dir == user.photo_dir
+      {
+        anyhow::bail!(
+          "users {:?} and {:?} share a store path or photo directory",
+          other.user.username,
+          user.username,
+        );
+      }
+    }
+
+    let store = SqliteStore::open(&store_path)
+      .await
+      .with_context(|| format!("failed to open store at {store_path:?}"))?;
+
+    kith_carddav::ensure_default_addressbook(&store, &server_cfg.addressbook)
+      .await
+      .context("failed to prepare the default address book")?;
+
+    tracing::info!(user = %user.username, store = ?store_path, "Opened store");
+    states.push(AppState {
+      store:  Arc::new(store),
+      config: config.clone(),
+      user:   Arc::new(user),
+    });
+  }
 
-  // Open SQLite store.
-  let store = SqliteStore::open(&store_path)
-    .await
-    .with_context(|| format!("failed to open store at {store_path:?}"))?;
-
-  // Build application state.
-  let state = AppState {
-    store:  Arc::new(store),
-    auth:   Arc::new(AuthConfig {
-      username:      server_cfg.auth_username.clone(),
-      password_hash: server_cfg.auth_password_hash.clone(),
-    }),
-    config: Arc::new(server_cfg.clone()),
-  };
-
-  let app = NormalizePath::trim_trailing_slash(kith_carddav::router(state));
+  let app = NormalizePath::trim_trailing_slash(kith_carddav::router(
+    Users::new(states),
+  ));
   let address = format!("{}:{}", server_cfg.host, server_cfg.port);
 
   tracing::info!("Listening on http://{address}");
@@ -102,7 +133,11 @@ async fn main() -> anyhow::Result<()> {
     .await
     .with_context(|| format!("failed to bind {address}"))?;
 
-  axum::serve(listener, Shared::new(app)).await.context("server error")?;
+  // The peer address feeds the per-IP login throttle.
+  let app = ServiceExt::<Request>::into_make_service_with_connect_info::<
+    SocketAddr,
+  >(app);
+  axum::serve(listener, app).await.context("server error")?;
 
   Ok(())
 }
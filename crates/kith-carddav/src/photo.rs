@@ -0,0 +1,229 @@
+//! On-disk storage for contact photos.
+//!
+//! Inline vCard photos are decoded on the way in and written to
+//! `{photo_dir}/{subject_id}/{content_hash}.{ext}`; the store only records a
+//! [`PhotoValue`] pointing at the file. Files are content-addressed, so a
+//! client re-uploading the same picture on every sync writes nothing new, and
+//! the unchanged `PhotoValue` makes the diff a no-op. They are written only
+//! once the facts referring to them are committed, so a failed PUT leaves no
+//! file behind.
+//!
+//! Files are never deleted: superseded and retracted `Photo` facts still
+//! refer to them.
+
+use std::{
+  io,
+  path::{Component, Path, PathBuf},
+};
+
+use kith_core::{
+  fact::{FactValue, NewFact, PhotoValue},
+  lifecycle::ContactView,
+};
+use kith_vcard::{InlinePhoto, ParsedVcard, PhotoData};
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+/// The value of the `Photo` fact describing `photo`, without writing it.
+pub fn describe(subject_id: Uuid, photo: &InlinePhoto) -> PhotoValue {
+  let content_hash = hex::encode(Sha256::digest(&photo.data));
+  PhotoValue {
+    path: format!(
+      "{subject_id}/{content_hash}.{}",
+      extension(&photo.media_type)
+    ),
+    content_hash,
+    media_type: photo.media_type.clone(),
+  }
+}
+
+/// Write `photo` under `photo_dir` unless an identical file is already there,
+/// and return the value of the `Photo` fact describing it.
+pub async fn store(
+  photo_dir: &Path,
+  subject_id: Uuid,
+  photo: &InlinePhoto,
+) -> io::Result<PhotoValue> {
+  let value = describe(subject_id, photo);
+  let full = photo_dir.join(&value.path);
+
+  if !tokio::fs::try_exists(&full).await? {
+    let dir = photo_dir.join(subject_id.to_string());
+    tokio::fs::create_dir_all(&dir).await?;
+    // Write under a unique name and rename into place, so a concurrent
+    // reader never sees a partial file.
+    let tmp = dir.join(format!(".{}.tmp", Uuid::new_v4()));
+    tokio::fs::write(&tmp, &photo.data).await?;
+    if let Err(e) = tokio::fs::rename(&tmp, &full).await {
+      let _ = tokio::fs::remove_file(&tmp).await;
+      return Err(e);
+    }
+  }
+
+  Ok(value)
+}
+
+/// Turn every inline photo of `parsed` into a `Photo` fact, so the vCard can
+/// go through the normal diff. Returns the photos, to [`save`] once the facts
+/// are committed.
+pub fn record(subject_id: Uuid, parsed: &mut ParsedVcard) -> Vec<InlinePhoto> {
+  let photos = std::mem::take(&mut parsed.photos);
+  for photo in &photos {
+    parsed.facts.push(NewFact::new(
+      subject_id,
+      FactValue::Photo(describe(subject_id, photo)),
+    ));
+  }
+  photos
+}
+
+/// Write `photos` under `photo_dir`, skipping those already there.
+pub async fn save(
+  photo_dir: &Path,
+  subject_id: Uuid,
+  photos: &[InlinePhoto],
+) -> io::Result<()> {
+  for photo in photos {
+    store(photo_dir, subject_id, photo).await?;
+  }
+  Ok(())
+}
+
+/// Read the bytes of `view`'s active photos, for
+/// [`kith_vcard::serialize_with`].
+///
+/// A missing or unreadable file is logged and left out rather than failing
+/// the request: the rest of the contact is still worth serving.
+pub async fn load(photo_dir: &Path, view: &ContactView) -> PhotoData {
+  let mut photos = PhotoData::new();
+  for rf in &view.active_facts {
+    let FactValue::Photo(p) = &rf.fact.value else {
+      continue;
+    };
+    let Some(full) = resolve(photo_dir, &p.path) else {
+      tracing::warn!(path = %p.path, "photo path escapes photo_dir");
+      continue;
+    };
+    match tokio::fs::read(&full).await {
+      Ok(data) => {
+        photos.insert(p.content_hash.clone(), data);
+      }
+      Err(e) => {
+        tracing::warn!(path = ?full, error = %e, "photo unreadable");
+      }
+    }
+  }
+  photos
+}
+
+/// Join a fact's relative path onto `photo_dir`, refusing anything that
+/// could point outside it.
+fn resolve(photo_dir: &Path, path: &str) -> Option<PathBuf> {
+  let relative = Path::new(path);
+  relative
+    .components()
+    .all(|c| matches!(c, Component::Normal(_)))
+    .then(|| photo_dir.join(relative))
+}
+
+/// File extension for a media type: its subtype, with `jpeg` shortened to
+/// the customary `jpg` and any `+suffix` dropped.
+fn extension(media_type: &str) -> &str {
+  let subtype = media_type
+    .split_once('/')
+    .map_or("", |(_, sub)| sub)
+    .split('+')
+    .next()
+    .unwrap_or_default();
+  match subtype {
+    "jpeg" => "jpg",
+    "" => "bin",
+    sub if sub.chars().all(|c| c.is_ascii_alphanumeric()) => sub,
+    _ => "bin",
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn temp_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("kith-photos-{}", Uuid::new_v4()))
+  }
+
+  #[tokio::test]
+  async fn identical_photos_share_one_file() {
+    let dir = temp_dir();
+    let subject_id = Uuid::new_v4();
+    let photo = InlinePhoto {
+      media_type: "image/jpeg".to_string(),
+      data:       vec![0xff, 0xd8, 0xff, 0xe0],
+    };
+
+    let first = store(&dir, subject_id, &photo).await.unwrap();
+    let second = store(&dir, subject_id, &photo).await.unwrap();
+    assert_eq!(first.path, second.path);
+    assert_eq!(
+      first.path,
+      format!("{subject_id}/{}.jpg", first.content_hash)
+    );
+    assert_eq!(
+      std::fs::read_dir(dir.join(subject_id.to_string()))
+        .unwrap()
+        .count(),
+      1
+    );
+    assert_eq!(std::fs::read(dir.join(&first.path)).unwrap(), photo.data);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn recorded_photos_are_written_only_when_saved() {
+    let dir = temp_dir();
+    let subject_id = Uuid::new_v4();
+    let mut parsed = ParsedVcard {
+      uid:     None,
+      version: None,
+      facts:   Vec::new(),
+      photos:  vec![InlinePhoto {
+        media_type: "image/png".to_string(),
+        data:       vec![0x89, 0x50, 0x4e, 0x47],
+      }],
+    };
+
+    let photos = record(subject_id, &mut parsed);
+    assert!(parsed.photos.is_empty());
+    let [fact] = &parsed.facts[..] else {
+      panic!("expected one fact, got {:?}", parsed.facts);
+    };
+    let FactValue::Photo(value) = &fact.value else {
+      panic!("expected a photo, got {:?}", fact.value);
+    };
+    assert!(!dir.exists());
+
+    save(&dir, subject_id, &photos).await.unwrap();
+    assert_eq!(std::fs::read(dir.join(&value.path)).unwrap(), photos[0].data);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn paths_outside_photo_dir_are_refused() {
+    let dir = Path::new("/photos");
+    assert_eq!(
+      resolve(dir, "a/b.jpg"),
+      Some(PathBuf::from("/photos/a/b.jpg"))
+    );
+    assert_eq!(resolve(dir, "../etc/passwd"), None);
+    assert_eq!(resolve(dir, "/etc/passwd"), None);
+  }
+
+  #[test]
+  fn extension_follows_media_type() {
+    assert_eq!(extension("image/jpeg"), "jpg");
+    assert_eq!(extension("image/png"), "png");
+    assert_eq!(extension("image/svg+xml"), "svg");
+    assert_eq!(extension("image/x.odd"), "bin");
+  }
+}
@@ -0,0 +1,109 @@
+//! Write protection for facts that CardDAV clients do not understand.
+//!
+//! A PUT replaces the whole vCard, so a client that drops lines it cannot
+//! display would retract the facts behind them. A [`WritePolicy`] assigns
+//! each fact type a [`SyncPolicy`] limiting what a PUT may do to it; the diff
+//! (see [`diff_parsed`](crate::diff::diff_parsed)) leaves anything else
+//! untouched.
+
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
+/// What a CardDAV PUT may do to facts of one type.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyncPolicy {
+  /// Facts are added, superseded and retracted to match the vCard.
+  #[default]
+  Full,
+  /// Facts may be added but never superseded or retracted. An edited value
+  /// is recorded alongside the original.
+  AddOnly,
+  /// The vCard is ignored for this type: nothing is added, superseded or
+  /// retracted.
+  ReadOnly,
+}
+
+impl SyncPolicy {
+  /// Whether a PUT may record new facts of this type.
+  pub fn allows_add(self) -> bool { self != SyncPolicy::ReadOnly }
+
+  /// Whether a PUT may supersede or retract existing facts of this type.
+  pub fn allows_change(self) -> bool { self == SyncPolicy::Full }
+}
+
+/// A [`SyncPolicy`] per fact type, keyed by
+/// [`FactValue::discriminant`](kith_core::fact::FactValue::discriminant).
+///
+/// Types not listed sync fully. Deserialising starts from
+/// [`WritePolicy::default`], so a config only lists the types it changes:
+///
+/// ```toml
+/// [write_policy]
+/// note = "add-only"
+/// meeting = "full"
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "HashMap<String, SyncPolicy>")]
+pub struct WritePolicy(HashMap<String, SyncPolicy>);
+
+impl WritePolicy {
+  /// A policy under which every type syncs fully.
+  pub fn full() -> Self { WritePolicy(HashMap::new()) }
+
+  /// The policy for facts whose discriminant is `fact_type`.
+  pub fn get(&self, fact_type: &str) -> SyncPolicy {
+    self.0.get(fact_type).copied().unwrap_or_default()
+  }
+
+  /// Set the policy for `fact_type`.
+  pub fn set(&mut self, fact_type: impl Into<String>, policy: SyncPolicy) {
+    self.0.insert(fact_type.into(), policy);
+  }
+}
+
+/// Meetings, relationships and introductions are add-only: they are written
+/// as `X-KITH-*` lines that most clients drop when they save a contact.
+impl Default for WritePolicy {
+  fn default() -> Self {
+    WritePolicy(
+      ["meeting", "relationship", "introduction"]
+        .into_iter()
+        .map(|t| (t.to_string(), SyncPolicy::AddOnly))
+        .collect(),
+    )
+  }
+}
+
+impl From<HashMap<String, SyncPolicy>> for WritePolicy {
+  fn from(overrides: HashMap<String, SyncPolicy>) -> Self {
+    let mut policy = WritePolicy::default();
+    policy.0.extend(overrides);
+    policy
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn kith_only_types_are_add_only_by_default() {
+    let policy = WritePolicy::default();
+    assert_eq!(policy.get("meeting"), SyncPolicy::AddOnly);
+    assert_eq!(policy.get("relationship"), SyncPolicy::AddOnly);
+    assert_eq!(policy.get("introduction"), SyncPolicy::AddOnly);
+    assert_eq!(policy.get("email"), SyncPolicy::Full);
+  }
+
+  #[test]
+  fn config_overrides_merge_with_defaults() {
+    let policy: WritePolicy =
+      serde_json::from_str(r#"{ "note": "read-only", "meeting": "full" }"#)
+        .unwrap();
+    assert_eq!(policy.get("note"), SyncPolicy::ReadOnly);
+    assert_eq!(policy.get("meeting"), SyncPolicy::Full);
+    assert_eq!(policy.get("introduction"), SyncPolicy::AddOnly);
+  }
+}
@@ -0,0 +1,382 @@
+//! Brute-force protection and caching for credential checks.
+//!
+//! Every failed login counts against the client's IP address and, for a
+//! known account, against its username. Past [`AuthLimits::free_attempts`]
+//! each further failure locks the key out for twice as long as the last,
+//! up to [`AuthLimits::max_lockout_secs`]; a locked-out request gets a 429
+//! without its password being checked. Failures are forgotten once a key
+//! has been quiet for [`AuthLimits::failure_window_secs`].
+//!
+//! Account passwords are checked with argon2, which is deliberately slow.
+//! A check counts as a failure from the moment it starts (see
+//! [`AuthGuard::begin_attempt`]), so guesses sent side by side cannot all
+//! be checked before the first of them fails. A client syncing a book sends
+//! dozens of requests with the same credentials, so successful checks are
+//! remembered for [`AuthLimits::cache_ttl_secs`].
+
+use std::{
+  collections::HashMap,
+  net::IpAddr,
+  sync::Mutex,
+  time::{Duration, Instant},
+};
+
+use hmac::{Hmac, Mac};
+use rand_core::{OsRng, RngCore};
+use serde::Deserialize;
+use sha2::Sha256;
+
+/// Tuning for [`AuthGuard`], the `[auth_limits]` table of `config.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AuthLimits {
+  /// Failures a key may rack up before it is locked out.
+  pub free_attempts:       u32,
+  /// The first lockout; each further failure doubles it.
+  pub base_lockout_secs:   u64,
+  /// The longest lockout.
+  pub max_lockout_secs:    u64,
+  /// How long a key must go without failing for its failures to be
+  /// forgotten.
+  pub failure_window_secs: u64,
+  /// How long a successful account-password check is remembered. `0`
+  /// disables the cache.
+  pub cache_ttl_secs:      u64,
+  /// Header carrying the client's address when the server sits behind a
+  /// proxy, e.g. `Fly-Client-IP`. Without it every request would appear to
+  /// come from the proxy. Only set this if the proxy sets or appends to the
+  /// header: of a comma-separated list the last entry is used.
+  pub client_ip_header:    Option<String>,
+}
+
+impl Default for AuthLimits {
+  fn default() -> Self {
+    AuthLimits {
+      free_attempts:       5,
+      base_lockout_secs:   1,
+      max_lockout_secs:    15 * 60,
+      failure_window_secs: 15 * 60,
+      cache_ttl_secs:      60,
+      client_ip_header:    None,
+    }
+  }
+}
+
+/// What failures are counted against.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Key {
+  Ip(IpAddr),
+  User(String),
+}
+
+#[derive(Debug)]
+struct Failures {
+  count:        u32,
+  last_failure: Instant,
+  locked_until: Option<Instant>,
+}
+
+/// Failed-login tracking and the cache of successful checks, shared by
+/// every request.
+#[derive(Debug)]
+pub struct AuthGuard {
+  limits:   AuthLimits,
+  failures: Mutex<HashMap<Key, Failures>>,
+  /// Key of the [`credential_digest`]s in `verified`, drawn afresh by every
+  /// process.
+  secret:   [u8; 32],
+  /// Digest of `username:password` → when the entry expires.
+  verified: Mutex<HashMap<[u8; 32], Instant>>,
+}
+
+impl Default for AuthGuard {
+  fn default() -> Self { AuthGuard::new(AuthLimits::default()) }
+}
+
+impl AuthGuard {
+  pub fn new(limits: AuthLimits) -> Self {
+    let mut secret = [0; 32];
+    OsRng.fill_bytes(&mut secret);
+    AuthGuard {
+      limits,
+      failures: Mutex::default(),
+      secret,
+      verified: Mutex::default(),
+    }
+  }
+
+  pub fn limits(&self) -> &AuthLimits { &self.limits }
+
+  /// How long requests from `ip` must wait, if it is locked out.
+  pub fn ip_lockout(&self, ip: Option<IpAddr>) -> Option<Duration> {
+    let key = Key::Ip(ip?);
+    self.lockout_at(&key, Instant::now())
+  }
+
+  /// How long logins as `username` must wait, if it is locked out.
+  pub fn user_lockout(&self, username: &str) -> Option<Duration> {
+    self.lockout_at(&Key::User(username.to_string()), Instant::now())
+  }
+
+  /// Count a failed login from `ip`, and against `username` if the account
+  /// exists.
+  pub fn record_failure(&self, ip: Option<IpAddr>, username: Option<&str>) {
+    let mut failures = self.failures.lock().unwrap();
+    self.count_failure(&mut failures, keys(ip, username), Instant::now());
+  }
+
+  /// Start checking `username`'s password for a client at `ip`.
+  ///
+  /// Fails with the wait if either is locked out. Otherwise the attempt
+  /// counts as a failure until [`record_success`](Self::record_success)
+  /// clears it; both happen under one lock, so no attempt can start between
+  /// another's lockout check and its failure.
+  pub fn begin_attempt(
+    &self,
+    ip: Option<IpAddr>,
+    username: &str,
+  ) -> Result<(), Duration> {
+    self.begin_attempt_at(keys(ip, Some(username)), Instant::now())
+  }
+
+  /// Forget the failures of `ip` and `username` after a successful login.
+  pub fn record_success(&self, ip: Option<IpAddr>, username: &str) {
+    let mut failures = self.failures.lock().unwrap();
+    for key in keys(ip, Some(username)) {
+      failures.remove(&key);
+    }
+  }
+
+  /// Whether `password` was recently verified as `username`'s password.
+  pub fn is_verified(&self, username: &str, password: &str) -> bool {
+    self.is_verified_at(username, password, Instant::now())
+  }
+
+  /// Remember that `password` is `username`'s password.
+  pub fn remember(&self, username: &str, password: &str) {
+    self.remember_at(username, password, Instant::now());
+  }
+
+  fn lockout_at(&self, key: &Key, now: Instant) -> Option<Duration> {
+    lockout(&self.failures.lock().unwrap(), key, now)
+  }
+
+  fn begin_attempt_at(
+    &self,
+    keys: Vec<Key>,
+    now: Instant,
+  ) -> Result<(), Duration> {
+    let mut failures = self.failures.lock().unwrap();
+    let wait = keys.iter().filter_map(|k| lockout(&failures, k, now)).max();
+    if let Some(wait) = wait {
+      return Err(wait);
+    }
+    self.count_failure(&mut failures, keys, now);
+    Ok(())
+  }
+
+  fn count_failure(
+    &self,
+    failures: &mut HashMap<Key, Failures>,
+    keys: Vec<Key>,
+    now: Instant,
+  ) {
+    let window = Duration::from_secs(self.limits.failure_window_secs);
+    // Attackers choose the keys, so drop stale ones rather than letting the
+    // map grow without bound.
+    failures.retain(|_, f| {
+      now.duration_since(f.last_failure) < window
+        || f.locked_until.is_some_and(|until| until > now)
+    });
+    for key in keys {
+      let entry = failures.entry(key.clone()).or_insert(Failures {
+        count:        0,
+        last_failure: now,
+        locked_until: None,
+      });
+      entry.count += 1;
+      entry.last_failure = now;
+      if let Some(lockout) = self.lockout_for(entry.count) {
+        tracing::warn!(?key, failures = entry.count, ?lockout, "auth lockout");
+        entry.locked_until = Some(now + lockout);
+      }
+    }
+  }
+
+  /// The lockout after the `count`th consecutive failure.
+  fn lockout_for(&self, count: u32) -> Option<Duration> {
+    let over = count
+      .checked_sub(self.limits.free_attempts)?
+      .checked_sub(1)?;
+    let secs = self
+      .limits
+      .base_lockout_secs
+      .saturating_mul(1u64.checked_shl(over).unwrap_or(u64::MAX))
+      .min(self.limits.max_lockout_secs);
+    Some(Duration::from_secs(secs))
+  }
+
+  fn is_verified_at(
+    &self,
+    username: &str,
+    password: &str,
+    now: Instant,
+  ) -> bool {
+    let key = self.credential_digest(username, password);
+    let mut verified = self.verified.lock().unwrap();
+    match verified.get(&key) {
+      Some(&expires) if expires > now => true,
+      Some(_) => {
+        verified.remove(&key);
+        false
+      }
+      None => false,
+    }
+  }
+
+  fn remember_at(&self, username: &str, password: &str, now: Instant) {
+    if self.limits.cache_ttl_secs == 0 {
+      return;
+    }
+    let mut verified = self.verified.lock().unwrap();
+    verified.retain(|_, &mut expires| expires > now);
+    verified.insert(
+      self.credential_digest(username, password),
+      now + Duration::from_secs(self.limits.cache_ttl_secs),
+    );
+  }
+
+  /// HMAC-SHA256 of `username` and `password` under this process's secret.
+  ///
+  /// Without the secret, a digest found in memory could be checked against
+  /// guessed passwords at the speed of SHA-256 instead of argon2. Each part
+  /// is prefixed with its length, so no other pair hashes the same input.
+  fn credential_digest(&self, username: &str, password: &str) -> [u8; 32] {
+    let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret)
+      .expect("HMAC takes keys of any length");
+    for part in [username, password] {
+      mac.update(&(part.len() as u64).to_be_bytes());
+      mac.update(part.as_bytes());
+    }
+    mac.finalize().into_bytes().into()
+  }
+}
+
+/// How long `key` is still locked out for, if it is.
+fn lockout(
+  failures: &HashMap<Key, Failures>,
+  key: &Key,
+  now: Instant,
+) -> Option<Duration> {
+  let until = failures.get(key)?.locked_until?;
+  (until > now).then(|| until - now)
+}
+
+fn keys(ip: Option<IpAddr>, username: Option<&str>) -> Vec<Key> {
+  ip.map(Key::Ip)
+    .into_iter()
+    .chain(username.map(|u| Key::User(u.to_string())))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const IP: IpAddr = IpAddr::V4(std::net::Ipv4Addr::LOCALHOST);
+
+  fn guard() -> AuthGuard {
+    AuthGuard::new(AuthLimits {
+      free_attempts: 2,
+      base_lockout_secs: 10,
+      max_lockout_secs: 60,
+      ..Default::default()
+    })
+  }
+
+  fn fail(guard: &AuthGuard, now: Instant) {
+    let mut failures = guard.failures.lock().unwrap();
+    guard.count_failure(&mut failures, keys(Some(IP), Some("alice")), now);
+  }
+
+  #[test]
+  fn lockout_doubles_up_to_the_maximum() {
+    let guard = guard();
+    let now = Instant::now();
+    let ip = Key::Ip(IP);
+    let user = Key::User("alice".into());
+
+    fail(&guard, now);
+    fail(&guard, now);
+    assert_eq!(guard.lockout_at(&ip, now), None);
+
+    let lockouts: Vec<_> = (0..4)
+      .map(|_| {
+        fail(&guard, now);
+        guard.lockout_at(&user, now).unwrap().as_secs()
+      })
+      .collect();
+    assert_eq!(lockouts, [10, 20, 40, 60]);
+    assert_eq!(guard.lockout_at(&ip, now).unwrap().as_secs(), 60);
+    assert_eq!(guard.lockout_at(&ip, now + Duration::from_secs(60)), None);
+  }
+
+  #[test]
+  fn success_and_quiet_periods_reset_failures() {
+    let guard = guard();
+    let now = Instant::now();
+    let user = Key::User("alice".into());
+
+    for _ in 0..3 {
+      fail(&guard, now);
+    }
+    guard.record_success(Some(IP), "alice");
+    fail(&guard, now);
+    assert_eq!(guard.lockout_at(&user, now), None);
+
+    let later = now + Duration::from_secs(guard.limits.failure_window_secs);
+    fail(&guard, later);
+    fail(&guard, later);
+    assert_eq!(guard.lockout_at(&user, later), None);
+  }
+
+  #[test]
+  fn attempts_count_before_they_finish() {
+    let guard = guard();
+    let now = Instant::now();
+    let attempt = || guard.begin_attempt_at(keys(Some(IP), Some("alice")), now);
+
+    // Checks in flight at once: the third locks the key out as it starts.
+    assert_eq!(attempt(), Ok(()));
+    assert_eq!(attempt(), Ok(()));
+    assert_eq!(attempt(), Ok(()));
+    assert_eq!(attempt(), Err(Duration::from_secs(10)));
+
+    guard.record_success(Some(IP), "alice");
+    assert_eq!(attempt(), Ok(()));
+  }
+
+  #[test]
+  fn verified_credentials_expire() {
+    let guard = guard();
+    let now = Instant::now();
+    guard.remember_at("alice", "secret", now);
+    assert!(guard.is_verified_at("alice", "secret", now));
+    assert!(!guard.is_verified_at("alice", "other", now));
+    assert!(!guard.is_verified_at("bob", "secret", now));
+
+    let ttl = Duration::from_secs(guard.limits.cache_ttl_secs);
+    assert!(!guard.is_verified_at("alice", "secret", now + ttl));
+
+    // Digests are keyed per guard.
+    assert_ne!(
+      guard.credential_digest("alice", "secret"),
+      AuthGuard::default().credential_digest("alice", "secret"),
+    );
+    // A NUL cannot move the split between username and password.
+    assert_ne!(
+      guard.credential_digest("alice\0x", "secret"),
+      guard.credential_digest("alice", "x\0secret"),
+    );
+  }
+}
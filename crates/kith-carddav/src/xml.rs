@@ -1,22 +1,29 @@
-//! WebDAV / CardDAV XML parsing and generation.
+//! WebDAV / CardDAV / CalDAV XML parsing and generation.
 //!
 //! Uses `quick-xml`'s writer API for generation and a hand-written parser
 //! for reading PROPFIND request bodies.
 
 use std::io::Cursor;
 
+use kith_vcard::Version;
 use quick_xml::{
   Writer,
   events::{BytesEnd, BytesStart, BytesText, Event},
 };
 
-use crate::error::Error;
+use crate::{
+  error::Error,
+  filter::{
+    Collation, Filter, MatchType, ParamFilter, PropFilter, Test, TextMatch,
+  },
+};
 
 // ─── Namespaces
 // ───────────────────────────────────────────────────────────────
 
 pub const NS_DAV: &str = "DAV:";
 pub const NS_CARDDAV: &str = "urn:ietf:params:xml:ns:carddav";
+pub const NS_CALDAV: &str = "urn:ietf:params:xml:ns:caldav";
 pub const NS_CALSERVER: &str = "http://calendarserver.org/ns/";
 
 // ─── PROPFIND request ────────────────────────────────────────────────────────
@@ -41,6 +48,10 @@ pub enum PropName {
   AddressbookDescription,
   SupportedAddressData,
   AddressData,
+  SyncToken,
+  SupportedReportSet,
+  CalendarHomeSet,
+  CalendarData,
   Unknown(String),
 }
 
@@ -116,6 +127,10 @@ fn parse_prop_name(local: &[u8]) -> PropName {
     b"addressbook-description" => PropName::AddressbookDescription,
     b"supported-address-data" => PropName::SupportedAddressData,
     b"address-data" => PropName::AddressData,
+    b"sync-token" => PropName::SyncToken,
+    b"supported-report-set" => PropName::SupportedReportSet,
+    b"calendar-home-set" => PropName::CalendarHomeSet,
+    b"calendar-data" => PropName::CalendarData,
     other => PropName::Unknown(String::from_utf8_lossy(other).into_owned()),
   }
 }
@@ -127,6 +142,7 @@ pub enum ResourceType {
   Principal,
   Collection,
   Addressbook,
+  Calendar,
 }
 
 #[derive(Debug, Clone)]
@@ -144,6 +160,17 @@ pub enum Property {
   AddressData(String),
   /// Apple CalendarServer `getctag` — opaque change token for the collection.
   GetCTag(String),
+  /// RFC 6578 `sync-token` — the token a `sync-collection` REPORT starts from.
+  SyncToken(String),
+  /// The REPORTs a collection accepts.
+  SupportedReportSet,
+  CalendarHomeSet(String),
+  /// The component types a calendar holds; always just `VEVENT`.
+  SupportedCalendarComponentSet,
+  CalendarData(String),
+  /// `D:current-user-privilege-set` (RFC 3744) granting only `D:read`, so
+  /// clients show a collection as read-only.
+  ReadOnlyPrivilegeSet,
 }
 
 pub struct MultistatusBuilder {
@@ -172,6 +199,7 @@ impl MultistatusBuilder {
     let mut ms = BytesStart::new("D:multistatus");
     ms.push_attribute(("xmlns:D", NS_DAV));
     ms.push_attribute(("xmlns:card", NS_CARDDAV));
+    ms.push_attribute(("xmlns:cal", NS_CALDAV));
     ms.push_attribute(("xmlns:CS", NS_CALSERVER));
     writer.write_event(Event::Start(ms)).unwrap();
 
@@ -185,6 +213,13 @@ impl MultistatusBuilder {
     }
   }
 
+  /// Emit the `<D:sync-token>` that closes a `sync-collection` response.
+  /// Call after all responses.
+  pub fn sync_token(&mut self, token: &str) -> &mut Self {
+    write_text_elem(&mut self.writer, "D:sync-token", token);
+    self
+  }
+
   pub fn finish(mut self) -> Vec<u8> {
     self
       .writer
@@ -306,6 +341,7 @@ fn write_property(w: &mut Writer<Cursor<Vec<u8>>>, prop: &Property) {
         match rt {
           ResourceType::Collection => write_empty(w, "D:collection"),
           ResourceType::Addressbook => write_empty(w, "card:addressbook"),
+          ResourceType::Calendar => write_empty(w, "cal:calendar"),
           ResourceType::Principal => write_empty(w, "D:principal"),
         }
       }
@@ -343,6 +379,38 @@ fn write_property(w: &mut Writer<Cursor<Vec<u8>>>, prop: &Property) {
     }
     Property::AddressData(data) => write_text_elem(w, "card:address-data", data),
     Property::GetCTag(ctag) => write_text_elem(w, "CS:getctag", ctag),
+    Property::SyncToken(token) => write_text_elem(w, "D:sync-token", token),
+    Property::SupportedReportSet => {
+      write_start(w, "D:supported-report-set");
+      for report in [
+        "card:addressbook-multiget",
+        "card:addressbook-query",
+        "D:sync-collection",
+      ] {
+        write_start(w, "D:supported-report");
+        write_start(w, "D:report");
+        write_empty(w, report);
+        write_end(w, "D:report");
+        write_end(w, "D:supported-report");
+      }
+      write_end(w, "D:supported-report-set");
+    }
+    Property::CalendarHomeSet(href) => {
+      write_href_element(w, "cal:calendar-home-set", href)
+    }
+    Property::SupportedCalendarComponentSet => {
+      write_start(w, "cal:supported-calendar-component-set");
+      write_empty_with_attr(w, "cal:comp", &[("name", "VEVENT")]);
+      write_end(w, "cal:supported-calendar-component-set");
+    }
+    Property::CalendarData(data) => write_text_elem(w, "cal:calendar-data", data),
+    Property::ReadOnlyPrivilegeSet => {
+      write_start(w, "D:current-user-privilege-set");
+      write_start(w, "D:privilege");
+      write_empty(w, "D:read");
+      write_end(w, "D:privilege");
+      write_end(w, "D:current-user-privilege-set");
+    }
   }
 }
 
@@ -359,6 +427,10 @@ fn write_prop_name_elem(w: &mut Writer<Cursor<Vec<u8>>>, name: &PropName) {
     PropName::AddressbookDescription => "card:addressbook-description",
     PropName::SupportedAddressData => "card:supported-address-data",
     PropName::AddressData => "card:address-data",
+    PropName::SyncToken => "D:sync-token",
+    PropName::SupportedReportSet => "D:supported-report-set",
+    PropName::CalendarHomeSet => "cal:calendar-home-set",
+    PropName::CalendarData => "cal:calendar-data",
     PropName::Unknown(s) => s.as_str(),
   };
   write_empty(w, tag);
@@ -368,22 +440,36 @@ fn write_prop_name_elem(w: &mut Writer<Cursor<Vec<u8>>>, name: &PropName) {
 
 #[derive(Debug, PartialEq)]
 pub enum ReportKind {
-  /// `card:addressbook-multiget` — fetch specific resources by href.
+  /// `card:addressbook-multiget` or `cal:calendar-multiget` — fetch specific
+  /// resources by href.
   Multiget,
-  /// `card:addressbook-query` — fetch resources matching a filter.
+  /// `card:addressbook-query` or `cal:calendar-query` — fetch resources
+  /// matching a filter.
   Query,
+  /// `D:sync-collection` (RFC 6578) — fetch what changed since a token.
+  SyncCollection,
 }
 
 #[derive(Debug)]
 pub struct ReportRequest {
-  pub kind:  ReportKind,
+  pub kind:       ReportKind,
   /// The `<D:prop>` names requested by the client.
-  pub props: Vec<PropName>,
+  pub props:      Vec<PropName>,
   /// Hrefs listed by the client (only populated for `Multiget`).
-  pub hrefs: Vec<String>,
+  pub hrefs:      Vec<String>,
+  /// The token to sync from (only for `SyncCollection`); `None` if empty,
+  /// which asks for an initial sync.
+  pub sync_token: Option<String>,
+  /// The `<C:filter>` (only for `Query`); matches everything if absent.
+  pub filter:     Filter,
+  /// The vCard version asked for by `<C:address-data version="…">`; also
+  /// selects which representation's ETag is reported.
+  pub version:    Version,
 }
 
-/// Parse an `addressbook-multiget` or `addressbook-query` request body.
+/// Parse an `addressbook-multiget`, `addressbook-query` or `sync-collection`
+/// request body, or a `calendar-multiget` or `calendar-query` one. Calendar
+/// filters are not parsed.
 pub fn parse_report(xml: &[u8]) -> Result<ReportRequest, Error> {
   if xml.is_empty() {
     return Err(Error::BadRequest("empty REPORT body".into()));
@@ -395,39 +481,104 @@ pub fn parse_report(xml: &[u8]) -> Result<ReportRequest, Error> {
   let mut kind: Option<ReportKind> = None;
   let mut props: Vec<PropName> = Vec::new();
   let mut hrefs: Vec<String> = Vec::new();
+  let mut sync_token: Option<String> = None;
+  let mut filter = Filter::default();
+  let mut version = Version::default();
   let mut in_prop = false;
   let mut in_href = false;
+  let mut in_sync_token = false;
+  let mut in_filter = false;
+  // The filter elements currently open, innermost last.
+  let mut prop_filter: Option<PropFilter> = None;
+  let mut param_filter: Option<ParamFilter> = None;
+  let mut text_match: Option<TextMatch> = None;
   let mut buf = Vec::new();
 
   loop {
-    match reader.read_event_into(&mut buf) {
+    let event = reader.read_event_into(&mut buf);
+    let is_empty = matches!(event, Ok(Event::Empty(_)));
+    match event {
       Ok(Event::Start(ref e) | Event::Empty(ref e)) => {
         let name_buf = e.name();
         let local = local_name(name_buf.as_ref());
         match local {
-          b"addressbook-multiget" => {
+          b"addressbook-multiget" | b"calendar-multiget" => {
             kind = Some(ReportKind::Multiget);
           }
-          b"addressbook-query" => {
+          b"addressbook-query" | b"calendar-query" => {
             kind = Some(ReportKind::Query);
           }
+          b"sync-collection" => {
+            kind = Some(ReportKind::SyncCollection);
+          }
+          b"sync-token" if !in_prop => {
+            in_sync_token = true;
+          }
           b"prop" => {
             in_prop = true;
           }
           b"href" if !in_prop => {
             in_href = true;
           }
+          b"address-data" if in_prop => {
+            version = parse_address_data(e)?;
+            props.push(PropName::AddressData);
+          }
           _ if in_prop => {
             props.push(parse_prop_name(local));
           }
+          b"filter" => {
+            filter.test = parse_test(e)?;
+            in_filter = !is_empty;
+          }
+          b"prop-filter" if in_filter => {
+            prop_filter = Some(PropFilter {
+              name: required_attr(e, b"name")?.to_ascii_uppercase(),
+              test: parse_test(e)?,
+              ..Default::default()
+            });
+          }
+          b"param-filter" if prop_filter.is_some() => {
+            param_filter = Some(ParamFilter {
+              name: required_attr(e, b"name")?.to_ascii_uppercase(),
+              ..Default::default()
+            });
+          }
+          b"is-not-defined" => {
+            if let Some(pf) = param_filter.as_mut() {
+              pf.is_not_defined = true;
+            } else if let Some(pf) = prop_filter.as_mut() {
+              pf.is_not_defined = true;
+            }
+          }
+          b"text-match" if prop_filter.is_some() => {
+            text_match = Some(parse_text_match(e)?);
+          }
           _ => {}
         }
+        // An empty element is also its own end.
+        if is_empty {
+          close_filter_element(
+            local,
+            &mut filter,
+            &mut prop_filter,
+            &mut param_filter,
+            &mut text_match,
+          );
+        }
       }
       Ok(Event::Text(ref e)) => {
         if in_href {
           hrefs.push(e.unescape().unwrap_or_default().into_owned());
           in_href = false;
         }
+        if in_sync_token {
+          sync_token = Some(e.unescape().unwrap_or_default().into_owned());
+          in_sync_token = false;
+        }
+        if let Some(tm) = text_match.as_mut() {
+          tm.text = e.unescape().unwrap_or_default().into_owned();
+        }
       }
       Ok(Event::End(ref e)) => {
         let name_buf = e.name();
@@ -438,6 +589,19 @@ pub fn parse_report(xml: &[u8]) -> Result<ReportRequest, Error> {
         if local == b"href" {
           in_href = false;
         }
+        if local == b"sync-token" {
+          in_sync_token = false;
+        }
+        if local == b"filter" {
+          in_filter = false;
+        }
+        close_filter_element(
+          local,
+          &mut filter,
+          &mut prop_filter,
+          &mut param_filter,
+          &mut text_match,
+        );
       }
       Ok(Event::Eof) => break,
       Err(e) => return Err(Error::Xml(e.to_string())),
@@ -455,7 +619,210 @@ pub fn parse_report(xml: &[u8]) -> Result<ReportRequest, Error> {
     props = vec![PropName::GetETag, PropName::AddressData];
   }
 
-  Ok(ReportRequest { kind, props, hrefs })
+  Ok(ReportRequest {
+    kind,
+    props,
+    hrefs,
+    sync_token: sync_token.filter(|t| !t.is_empty()),
+    filter,
+    version,
+  })
+}
+
+/// The vCard version an `address-data` element asks for. Both attributes
+/// are optional and default to `text/vcard` 4.0.
+fn parse_address_data(e: &BytesStart<'_>) -> Result<Version, Error> {
+  let content_type = attr(e, b"content-type")?;
+  if let Some(ct) = content_type.as_deref()
+    && !ct.eq_ignore_ascii_case("text/vcard")
+  {
+    return Err(Error::UnsupportedAddressData(ct.to_string()));
+  }
+  match attr(e, b"version")? {
+    None => Ok(Version::default()),
+    Some(v) => Version::parse(&v).ok_or_else(|| {
+      Error::UnsupportedAddressData(format!("text/vcard version {v}"))
+    }),
+  }
+}
+
+/// Move a finished `text-match`, `param-filter` or `prop-filter` into its
+/// parent.
+fn close_filter_element(
+  local: &[u8],
+  filter: &mut Filter,
+  prop_filter: &mut Option<PropFilter>,
+  param_filter: &mut Option<ParamFilter>,
+  text_match: &mut Option<TextMatch>,
+) {
+  match local {
+    b"text-match" => {
+      let Some(tm) = text_match.take() else { return };
+      if let Some(pf) = param_filter.as_mut() {
+        pf.text_match = Some(tm);
+      } else if let Some(pf) = prop_filter.as_mut() {
+        pf.text_matches.push(tm);
+      }
+    }
+    b"param-filter" => {
+      if let (Some(pf), Some(parent)) = (param_filter.take(), prop_filter) {
+        parent.param_filters.push(pf);
+      }
+    }
+    b"prop-filter" => {
+      if let Some(pf) = prop_filter.take() {
+        filter.prop_filters.push(pf);
+      }
+    }
+    _ => {}
+  }
+}
+
+/// The value of attribute `key` on `e`, if present.
+fn attr(e: &BytesStart<'_>, key: &[u8]) -> Result<Option<String>, Error> {
+  for a in e.attributes() {
+    let a = a.map_err(|e| Error::Xml(e.to_string()))?;
+    if local_name(a.key.as_ref()) == key {
+      let value = a.unescape_value().map_err(|e| Error::Xml(e.to_string()))?;
+      return Ok(Some(value.into_owned()));
+    }
+  }
+  Ok(None)
+}
+
+fn required_attr(e: &BytesStart<'_>, key: &[u8]) -> Result<String, Error> {
+  attr(e, key)?.ok_or_else(|| {
+    Error::BadRequest(format!(
+      "<{}> is missing its {:?} attribute",
+      String::from_utf8_lossy(local_name(e.name().as_ref())),
+      String::from_utf8_lossy(key),
+    ))
+  })
+}
+
+/// The `test` attribute of a `filter` or `prop-filter`.
+fn parse_test(e: &BytesStart<'_>) -> Result<Test, Error> {
+  match attr(e, b"test")?.as_deref() {
+    None | Some("anyof") => Ok(Test::AnyOf),
+    Some("allof") => Ok(Test::AllOf),
+    Some(other) => {
+      Err(Error::BadRequest(format!("unknown filter test {other:?}")))
+    }
+  }
+}
+
+/// A `text-match` element's attributes; its text is filled in later.
+fn parse_text_match(e: &BytesStart<'_>) -> Result<TextMatch, Error> {
+  let collation = match attr(e, b"collation")? {
+    None => Collation::default(),
+    Some(name) => {
+      Collation::from_name(&name).ok_or(Error::UnsupportedCollation(name))?
+    }
+  };
+  let match_type = match attr(e, b"match-type")? {
+    None => MatchType::default(),
+    Some(name) => MatchType::from_name(&name).ok_or_else(|| {
+      Error::BadRequest(format!("unknown match-type {name:?}"))
+    })?,
+  };
+  let negate = match attr(e, b"negate-condition")?.as_deref() {
+    None | Some("no") => false,
+    Some("yes") => true,
+    Some(other) => {
+      return Err(Error::BadRequest(format!(
+        "invalid negate-condition {other:?}"
+      )));
+    }
+  };
+  Ok(TextMatch {
+    text: String::new(),
+    collation,
+    match_type,
+    negate,
+  })
+}
+
+// ─── MKCOL request parsing ───────────────────────────────────────────────────
+
+/// The properties an extended MKCOL (RFC 5689) body asks to set.
+#[derive(Debug, Default, PartialEq)]
+pub struct MkcolRequest {
+  /// `false` only if the body sets a `resourcetype` without
+  /// `card:addressbook`.
+  pub is_addressbook: bool,
+  pub display_name:   Option<String>,
+  pub description:    Option<String>,
+}
+
+/// Parse an extended MKCOL body. An empty body (plain MKCOL) asks for an
+/// address book with no properties, since that is all the home set can hold.
+pub fn parse_mkcol(xml: &[u8]) -> Result<MkcolRequest, Error> {
+  let mut req = MkcolRequest {
+    is_addressbook: true,
+    ..Default::default()
+  };
+  if xml.is_empty() {
+    return Ok(req);
+  }
+
+  let mut reader = quick_xml::Reader::from_reader(xml);
+  reader.config_mut().trim_text(true);
+
+  let mut in_resourcetype = false;
+  let mut saw_addressbook = false;
+  let mut saw_resourcetype = false;
+  // The text-valued property currently open, if any.
+  let mut text_prop: Option<Vec<u8>> = None;
+  let mut buf = Vec::new();
+
+  loop {
+    match reader.read_event_into(&mut buf) {
+      Ok(Event::Start(ref e))
+        if local_name(e.name().as_ref()) == b"resourcetype" =>
+      {
+        saw_resourcetype = true;
+        in_resourcetype = true;
+      }
+      Ok(Event::Start(ref e) | Event::Empty(ref e)) => {
+        let name_buf = e.name();
+        let local = local_name(name_buf.as_ref());
+        text_prop = None;
+        match local {
+          // An empty `<D:resourcetype/>` asks for a plain collection.
+          b"resourcetype" => saw_resourcetype = true,
+          b"addressbook" if in_resourcetype => saw_addressbook = true,
+          b"displayname" | b"addressbook-description" => {
+            text_prop = Some(local.to_vec());
+          }
+          _ => {}
+        }
+      }
+      Ok(Event::Text(ref e)) => {
+        if let Some(prop) = text_prop.take() {
+          let text = e.unescape().unwrap_or_default().into_owned();
+          match prop.as_slice() {
+            b"displayname" => req.display_name = Some(text),
+            _ => req.description = Some(text),
+          }
+        }
+      }
+      Ok(Event::End(ref e)) => {
+        let name_buf = e.name();
+        let local = local_name(name_buf.as_ref());
+        if local == b"resourcetype" {
+          in_resourcetype = false;
+        }
+        text_prop = None;
+      }
+      Ok(Event::Eof) => break,
+      Err(e) => return Err(Error::Xml(e.to_string())),
+      _ => {}
+    }
+    buf.clear();
+  }
+
+  req.is_addressbook = !saw_resourcetype || saw_addressbook;
+  Ok(req)
 }
 
 // ─── Tests ────────────────────────────────────────────────────────────────────
@@ -521,4 +888,120 @@ mod tests {
     assert!(xml_str.contains("abc123"), "missing etag");
     assert!(xml_str.contains("Personal"), "missing display name");
   }
+
+  #[test]
+  fn parse_extended_mkcol() {
+    let xml = br#"<?xml version="1.0"?>
+    <D:mkcol xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+      <D:set>
+        <D:prop>
+          <D:resourcetype><D:collection/><C:addressbook/></D:resourcetype>
+          <D:displayname>Work &amp; Co</D:displayname>
+          <C:addressbook-description>Colleagues</C:addressbook-description>
+        </D:prop>
+      </D:set>
+    </D:mkcol>"#;
+    assert_eq!(parse_mkcol(xml).unwrap(), MkcolRequest {
+      is_addressbook: true,
+      display_name:   Some("Work & Co".to_string()),
+      description:    Some("Colleagues".to_string()),
+    });
+  }
+
+  #[test]
+  fn mkcol_plain_collection_is_not_addressbook() {
+    let xml = br#"<?xml version="1.0"?>
+    <D:mkcol xmlns:D="DAV:">
+      <D:set><D:prop>
+        <D:resourcetype><D:collection/></D:resourcetype>
+      </D:prop></D:set>
+    </D:mkcol>"#;
+    assert!(!parse_mkcol(xml).unwrap().is_addressbook);
+    assert!(parse_mkcol(b"").unwrap().is_addressbook);
+  }
+
+  #[test]
+  fn parse_sync_collection() {
+    let xml = br#"<?xml version="1.0"?>
+    <D:sync-collection xmlns:D="DAV:">
+      <D:sync-token>urn:kith:sync:1.0.0.0</D:sync-token>
+      <D:sync-level>1</D:sync-level>
+      <D:prop><D:getetag/></D:prop>
+    </D:sync-collection>"#;
+    let report = parse_report(xml).unwrap();
+    assert_eq!(report.kind, ReportKind::SyncCollection);
+    assert_eq!(report.sync_token.as_deref(), Some("urn:kith:sync:1.0.0.0"));
+    assert_eq!(report.props, [PropName::GetETag]);
+
+    let initial = br#"<D:sync-collection xmlns:D="DAV:">
+      <D:sync-token/><D:prop><D:getetag/></D:prop>
+    </D:sync-collection>"#;
+    assert_eq!(parse_report(initial).unwrap().sync_token, None);
+  }
+
+  #[test]
+  fn parse_query_filter() {
+    let xml = br#"<?xml version="1.0"?>
+    <C:addressbook-query xmlns:D="DAV:" xmlns:C="urn:ietf:params:xml:ns:carddav">
+      <D:prop><D:getetag/></D:prop>
+      <C:filter test="allof">
+        <C:prop-filter name="email" test="anyof">
+          <C:text-match collation="i;ascii-casemap" match-type="starts-with"
+            >ada</C:text-match>
+          <C:text-match negate-condition="yes">example</C:text-match>
+        </C:prop-filter>
+        <C:prop-filter name="TEL">
+          <C:param-filter name="type">
+            <C:text-match match-type="equals">cell</C:text-match>
+          </C:param-filter>
+        </C:prop-filter>
+        <C:prop-filter name="NOTE"><C:is-not-defined/></C:prop-filter>
+      </C:filter>
+    </C:addressbook-query>"#;
+    let filter = parse_report(xml).unwrap().filter;
+    assert_eq!(filter.test, Test::AllOf);
+    let [email, tel, note] = &filter.prop_filters[..] else {
+      panic!("expected three prop-filters: {filter:?}")
+    };
+
+    assert_eq!(email.name, "EMAIL");
+    assert_eq!(email.text_matches, [
+      TextMatch {
+        text:       "ada".to_string(),
+        collation:  Collation::AsciiCasemap,
+        match_type: MatchType::StartsWith,
+        negate:     false,
+      },
+      TextMatch {
+        text:       "example".to_string(),
+        collation:  Collation::UnicodeCasemap,
+        match_type: MatchType::Contains,
+        negate:     true,
+      },
+    ]);
+
+    assert_eq!(tel.param_filters[0].name, "TYPE");
+    let tm = tel.param_filters[0].text_match.as_ref().unwrap();
+    assert_eq!(
+      (tm.text.as_str(), tm.match_type),
+      ("cell", MatchType::Equals)
+    );
+    assert!(tel.text_matches.is_empty());
+
+    assert!(note.is_not_defined);
+  }
+
+  #[test]
+  fn unsupported_collation_is_rejected() {
+    let xml =
+      br#"<C:addressbook-query xmlns:C="urn:ietf:params:xml:ns:carddav">
+      <C:filter><C:prop-filter name="FN">
+        <C:text-match collation="i;octet">Ada</C:text-match>
+      </C:prop-filter></C:filter>
+    </C:addressbook-query>"#;
+    assert!(matches!(
+      parse_report(xml),
+      Err(Error::UnsupportedCollation(c)) if c == "i;octet"
+    ));
+  }
 }
@@ -4,7 +4,10 @@ use std::{collections::HashMap, sync::Arc};
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use fuzzy_matcher::{FuzzyMatcher, skim::SkimMatcherV2};
-use kith_core::{lifecycle::ResolvedFact, subject::Subject};
+use kith_core::{
+  duplicates::DuplicateCandidate, lifecycle::ResolvedFact, store::ImportSource,
+  subject::Subject,
+};
 use uuid::Uuid;
 
 use crate::client::ApiClient;
@@ -17,6 +20,12 @@ pub enum Screen {
   ContactList,
   /// Focus on the contact detail pane.
   ContactDetail,
+  /// Reviewing pairs of contacts that may be duplicates.
+  Duplicates,
+  /// Browsing the earlier versions of the selected contact's facts.
+  History,
+  /// Reviewing import sources, and undoing an import.
+  Imports,
 }
 
 // ─── App ──────────────────────────────────────────────────────────────────────
@@ -50,6 +59,33 @@ pub struct App {
   /// Active facts for the currently-selected subject.
   pub facts: Vec<ResolvedFact>,
 
+  /// Candidate duplicate pairs, highest score first. Loaded when the
+  /// duplicates screen is opened.
+  pub duplicates: Vec<DuplicateCandidate>,
+
+  /// Cursor position within `duplicates`.
+  pub duplicate_cursor: usize,
+
+  /// Cursor position within `facts` on the history screen.
+  pub history_cursor: usize,
+
+  /// Every version of the fact under `history_cursor`, oldest first.
+  pub lineage: Vec<ResolvedFact>,
+
+  /// Sources imported facts came from. Loaded when the imports screen is
+  /// opened.
+  pub import_sources: Vec<ImportSource>,
+
+  /// Cursor position within `import_sources`.
+  pub import_cursor: usize,
+
+  /// The active facts from the source under `import_cursor`: what undoing
+  /// the import would retract.
+  pub import_facts: Vec<ResolvedFact>,
+
+  /// Whether undoing the import under the cursor awaits a second `x`.
+  pub undo_pending: bool,
+
   /// One-line status message shown in the status bar.
   pub status_msg: String,
 
@@ -70,6 +106,14 @@ impl App {
       detail_scroll: 0,
       selected_subject_id: None,
       facts: Vec::new(),
+      duplicates: Vec::new(),
+      duplicate_cursor: 0,
+      history_cursor: 0,
+      lineage: Vec::new(),
+      import_sources: Vec::new(),
+      import_cursor: 0,
+      import_facts: Vec::new(),
+      undo_pending: false,
       status_msg: String::new(),
       client: Arc::new(client),
     }
@@ -133,6 +177,109 @@ impl App {
     }
   }
 
+  /// Fetch candidate duplicates and the names of the first pairs.
+  async fn load_duplicates(&mut self) -> anyhow::Result<()> {
+    self.status_msg = "Looking for duplicates…".into();
+    match self.client.list_duplicates().await {
+      Ok(duplicates) => {
+        self.duplicates = duplicates;
+        self.duplicate_cursor = 0;
+        let ids: Vec<_> = self
+          .duplicates
+          .iter()
+          .take(50)
+          .flat_map(|c| c.subject_ids)
+          .collect();
+        for id in ids {
+          self.ensure_name(id).await;
+        }
+        self.status_msg = if self.duplicates.is_empty() {
+          "No duplicates found.".into()
+        } else {
+          String::new()
+        };
+        Ok(())
+      }
+      Err(e) => {
+        self.status_msg = format!("Error: {e}");
+        Err(e)
+      }
+    }
+  }
+
+  /// Load the versions of the fact under the history cursor.
+  async fn load_lineage(&mut self) -> anyhow::Result<()> {
+    let Some(fact_id) = self
+      .facts
+      .get(self.history_cursor)
+      .map(|rf| rf.fact.fact_id)
+    else {
+      self.lineage.clear();
+      return Ok(());
+    };
+    match self.client.fact_lineage(fact_id).await {
+      Ok(lineage) => {
+        self.lineage = lineage;
+        self.status_msg = String::new();
+        Ok(())
+      }
+      Err(e) => {
+        self.status_msg = format!("Error: {e}");
+        Err(e)
+      }
+    }
+  }
+
+  /// Fetch the import sources, then the facts of the first one.
+  async fn load_import_sources(&mut self) -> anyhow::Result<()> {
+    self.status_msg = "Loading imports…".into();
+    match self.client.list_import_sources().await {
+      Ok(sources) => {
+        self.import_sources = sources;
+        self.import_cursor = 0;
+        self.status_msg = if self.import_sources.is_empty() {
+          "No imported facts.".into()
+        } else {
+          String::new()
+        };
+        self.load_import_facts().await
+      }
+      Err(e) => {
+        self.status_msg = format!("Error: {e}");
+        Err(e)
+      }
+    }
+  }
+
+  /// Load the active facts from the source under the imports cursor, and
+  /// the names of their subjects.
+  async fn load_import_facts(&mut self) -> anyhow::Result<()> {
+    let Some(source) = self.import_sources.get(self.import_cursor) else {
+      self.import_facts.clear();
+      return Ok(());
+    };
+    match self.client.import_facts(&source.source_name).await {
+      Ok(facts) => {
+        self.import_facts = facts;
+        let mut ids: Vec<_> = self
+          .import_facts
+          .iter()
+          .take(50)
+          .map(|rf| rf.fact.subject_id)
+          .collect();
+        ids.dedup();
+        for id in ids {
+          self.ensure_name(id).await;
+        }
+        Ok(())
+      }
+      Err(e) => {
+        self.status_msg = format!("Error: {e}");
+        Err(e)
+      }
+    }
+  }
+
   // ── Filtered list ─────────────────────────────────────────────────────────
 
   /// Returns subjects that match the current filter query.
@@ -181,6 +328,9 @@ impl App {
     match self.screen {
       Screen::ContactList => self.handle_list_key(key).await,
       Screen::ContactDetail => self.handle_detail_key(key).await,
+      Screen::Duplicates => self.handle_duplicates_key(key).await,
+      Screen::History => self.handle_history_key(key).await,
+      Screen::Imports => self.handle_imports_key(key).await,
     }
   }
 
@@ -254,6 +404,12 @@ impl App {
         self.list_cursor = 0;
       }
 
+      // Duplicates review
+      KeyCode::Char('d') => self.open_duplicates().await,
+
+      // Import sources
+      KeyCode::Char('i') => self.open_imports().await,
+
       _ => {}
     }
     Ok(true)
@@ -302,11 +458,245 @@ impl App {
         }
       }
 
+      // Restore a deleted contact
+      KeyCode::Char('u') => self.restore_contact().await?,
+
+      // Version history of the contact's facts
+      KeyCode::Char('v') => self.open_history().await,
+
+      _ => {}
+    }
+    Ok(true)
+  }
+
+  async fn handle_duplicates_key(
+    &mut self,
+    key: KeyEvent,
+  ) -> anyhow::Result<bool> {
+    match key.code {
+      // Quit
+      KeyCode::Char('q') => return Ok(false),
+
+      // Back to list
+      KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => {
+        self.screen = Screen::ContactList;
+        self.duplicates.clear();
+      }
+
+      // Navigation
+      KeyCode::Down | KeyCode::Char('j') => {
+        if self.duplicate_cursor + 1 < self.duplicates.len() {
+          self.duplicate_cursor += 1;
+          self.ensure_duplicate_names().await;
+        }
+      }
+      KeyCode::Up | KeyCode::Char('k') => {
+        if self.duplicate_cursor > 0 {
+          self.duplicate_cursor -= 1;
+          self.ensure_duplicate_names().await;
+        }
+      }
+
+      // Merge the second contact into the first
+      KeyCode::Char('m') => self.merge_duplicate().await?,
+
+      // Skip: drop the pair from this review
+      KeyCode::Char('s') if self.duplicate_cursor < self.duplicates.len() => {
+        self.duplicates.remove(self.duplicate_cursor);
+        self.duplicate_cursor = self
+          .duplicate_cursor
+          .min(self.duplicates.len().saturating_sub(1));
+        self.ensure_duplicate_names().await;
+      }
+
+      _ => {}
+    }
+    Ok(true)
+  }
+
+  async fn handle_history_key(
+    &mut self,
+    key: KeyEvent,
+  ) -> anyhow::Result<bool> {
+    match key.code {
+      // Quit
+      KeyCode::Char('q') => return Ok(false),
+
+      // Back to detail
+      KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => {
+        self.screen = Screen::ContactDetail;
+        self.lineage.clear();
+      }
+
+      // Navigation
+      KeyCode::Down | KeyCode::Char('j')
+        if self.history_cursor + 1 < self.facts.len() =>
+      {
+        self.history_cursor += 1;
+        let _ = self.load_lineage().await;
+      }
+      KeyCode::Up | KeyCode::Char('k') if self.history_cursor > 0 => {
+        self.history_cursor -= 1;
+        let _ = self.load_lineage().await;
+      }
+
+      _ => {}
+    }
+    Ok(true)
+  }
+
+  async fn handle_imports_key(
+    &mut self,
+    key: KeyEvent,
+  ) -> anyhow::Result<bool> {
+    // Any key but a second `x` cancels a pending undo.
+    let confirmed = self.undo_pending && key.code == KeyCode::Char('x');
+    if self.undo_pending && !confirmed {
+      self.status_msg = String::new();
+    }
+    self.undo_pending = false;
+
+    match key.code {
+      // Quit
+      KeyCode::Char('q') => return Ok(false),
+
+      // Back to list
+      KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') => {
+        self.screen = Screen::ContactList;
+        self.import_sources.clear();
+        self.import_facts.clear();
+      }
+
+      // Navigation
+      KeyCode::Down | KeyCode::Char('j')
+        if self.import_cursor + 1 < self.import_sources.len() =>
+      {
+        self.import_cursor += 1;
+        let _ = self.load_import_facts().await;
+      }
+      KeyCode::Up | KeyCode::Char('k') if self.import_cursor > 0 => {
+        self.import_cursor -= 1;
+        let _ = self.load_import_facts().await;
+      }
+
+      // Undo the import: `x` twice
+      KeyCode::Char('x') if confirmed => self.undo_import().await?,
+      KeyCode::Char('x') if !self.import_facts.is_empty() => {
+        self.undo_pending = true;
+        self.status_msg = format!(
+          "Press x again to retract {} facts.",
+          self.import_facts.len()
+        );
+      }
+
       _ => {}
     }
     Ok(true)
   }
 
+  /// Transition to `Imports`, loading the sources. Stays put if they cannot
+  /// be loaded; the error is in the status bar.
+  async fn open_imports(&mut self) {
+    if self.load_import_sources().await.is_ok() {
+      self.screen = Screen::Imports;
+    }
+  }
+
+  /// Retract every active fact from the source under the cursor, then
+  /// reload the contacts and the sources.
+  async fn undo_import(&mut self) -> anyhow::Result<()> {
+    let Some(source) = self.import_sources.get(self.import_cursor) else {
+      return Ok(());
+    };
+    let source_name = source.source_name.clone();
+    let reason = format!("Undo import of {source_name}");
+    let retracted =
+      match self.client.retract_import(&source_name, &reason).await {
+        Ok(retracted) => retracted,
+        Err(e) => {
+          self.status_msg = format!("Error: {e}");
+          return Ok(());
+        }
+      };
+    let cursor = self.import_cursor;
+    self.load_subjects().await?;
+    self.load_import_sources().await?;
+    self.import_cursor =
+      cursor.min(self.import_sources.len().saturating_sub(1));
+    self.load_import_facts().await?;
+    self.status_msg =
+      format!("Retracted {} facts from {source_name}.", retracted.len());
+    Ok(())
+  }
+
+  /// Transition to `History`, starting at the fact at the top of the detail
+  /// pane. Stays put if the contact has no facts or the versions cannot be
+  /// loaded; the error is in the status bar.
+  async fn open_history(&mut self) {
+    if self.facts.is_empty() {
+      return;
+    }
+    self.history_cursor = self.detail_scroll.min(self.facts.len() - 1);
+    if self.load_lineage().await.is_ok() {
+      self.screen = Screen::History;
+    }
+  }
+
+  /// Transition to `Duplicates`, loading the candidates. Stays put if they
+  /// cannot be loaded; the error is in the status bar.
+  async fn open_duplicates(&mut self) {
+    if self.load_duplicates().await.is_ok() {
+      self.screen = Screen::Duplicates;
+    }
+  }
+
+  /// Load the names of the pair under the duplicates cursor.
+  async fn ensure_duplicate_names(&mut self) {
+    let Some(candidate) = self.duplicates.get(self.duplicate_cursor) else {
+      return;
+    };
+    for id in candidate.subject_ids {
+      self.ensure_name(id).await;
+    }
+  }
+
+  /// Merge the second contact of the pair under the cursor into the first,
+  /// then reload the contacts and the remaining candidates.
+  async fn merge_duplicate(&mut self) -> anyhow::Result<()> {
+    let Some(candidate) = self.duplicates.get(self.duplicate_cursor) else {
+      return Ok(());
+    };
+    let [survivor, absorbed] = candidate.subject_ids;
+    if let Err(e) = self.client.merge_subjects(survivor, absorbed).await {
+      self.status_msg = format!("Error: {e}");
+      return Ok(());
+    }
+    let cursor = self.duplicate_cursor;
+    self.load_subjects().await?;
+    self.load_duplicates().await?;
+    self.duplicate_cursor = cursor.min(self.duplicates.len().saturating_sub(1));
+    self.ensure_duplicate_names().await;
+    self.status_msg = "Merged.".into();
+    Ok(())
+  }
+
+  /// Reinstate the facts of the deleted contact shown in the detail pane.
+  async fn restore_contact(&mut self) -> anyhow::Result<()> {
+    let Some(subject_id) = self.selected_subject_id else {
+      return Ok(());
+    };
+    match self.client.reinstate_subject(subject_id).await {
+      Ok(restored) => {
+        self.names.remove(&subject_id);
+        self.ensure_name(subject_id).await;
+        self.load_facts(subject_id).await?;
+        self.status_msg = format!("Restored {} facts.", restored.len());
+      }
+      Err(e) => self.status_msg = format!("Error: {e}"),
+    }
+    Ok(())
+  }
+
   /// Transition to `ContactDetail` for `subject_id`, loading facts.
   async fn open_detail(&mut self, subject_id: Uuid) -> anyhow::Result<()> {
     self.ensure_name(subject_id).await;
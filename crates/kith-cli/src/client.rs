@@ -3,7 +3,12 @@
 use std::time::Duration;
 
 use anyhow::{Context, Result, anyhow};
-use kith_core::{lifecycle::ResolvedFact, subject::Subject};
+use kith_core::{
+  duplicates::DuplicateCandidate,
+  lifecycle::{Reinstatement, ResolvedFact, Retraction, SubjectMerge},
+  store::ImportSource,
+  subject::Subject,
+};
 use reqwest::Client;
 use uuid::Uuid;
 
@@ -61,6 +66,44 @@ impl ApiClient {
     resp.json().await.context("deserialising subjects")
   }
 
+  /// `POST /api/subjects/<survivor>/merge`
+  pub async fn merge_subjects(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+  ) -> Result<SubjectMerge> {
+    let url = self.url(&format!("/subjects/{survivor}/merge"));
+    let resp = self
+      .auth(self.client.post(url))
+      .json(&serde_json::json!({ "absorbed_id": absorbed }))
+      .send()
+      .await
+      .context("POST /subjects/:id/merge failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("POST /subjects/:id/merge → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising merge")
+  }
+
+  /// `POST /api/subjects/<id>/reinstate`
+  pub async fn reinstate_subject(
+    &self,
+    subject_id: Uuid,
+  ) -> Result<Vec<Reinstatement>> {
+    let url = self.url(&format!("/subjects/{subject_id}/reinstate"));
+    let resp = self
+      .auth(self.client.post(url))
+      .send()
+      .await
+      .context("POST /subjects/:id/reinstate failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("POST /subjects/:id/reinstate → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising reinstatements")
+  }
+
   // ── Facts ─────────────────────────────────────────────────────────────────
 
   /// `GET /api/facts?subject_id=<id>[&fact_type=<t>]`
@@ -105,4 +148,91 @@ impl ApiClient {
     }
     resp.json().await.context("deserialising name facts")
   }
+
+  /// `GET /api/facts/<id>/lineage`
+  pub async fn fact_lineage(&self, fact_id: Uuid) -> Result<Vec<ResolvedFact>> {
+    let url = self.url(&format!("/facts/{fact_id}/lineage"));
+    let resp = self
+      .auth(self.client.get(url))
+      .send()
+      .await
+      .context("GET /facts/:id/lineage failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("GET /facts/:id/lineage → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising lineage")
+  }
+
+  // ── Duplicates ────────────────────────────────────────────────────────────
+
+  /// `GET /api/duplicates`
+  pub async fn list_duplicates(&self) -> Result<Vec<DuplicateCandidate>> {
+    let resp = self
+      .auth(self.client.get(self.url("/duplicates")))
+      .send()
+      .await
+      .context("GET /duplicates failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("GET /duplicates → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising duplicates")
+  }
+
+  // ── Import sources ────────────────────────────────────────────────────────
+
+  /// `GET /api/import-sources`
+  pub async fn list_import_sources(&self) -> Result<Vec<ImportSource>> {
+    let resp = self
+      .auth(self.client.get(self.url("/import-sources")))
+      .send()
+      .await
+      .context("GET /import-sources failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("GET /import-sources → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising import sources")
+  }
+
+  /// `GET /api/import-sources/facts?source_name=<name>`
+  pub async fn import_facts(
+    &self,
+    source_name: &str,
+  ) -> Result<Vec<ResolvedFact>> {
+    let resp = self
+      .auth(self.client.get(self.url("/import-sources/facts")))
+      .query(&[("source_name", source_name)])
+      .send()
+      .await
+      .context("GET /import-sources/facts failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("GET /import-sources/facts → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising import facts")
+  }
+
+  /// `POST /api/import-sources/retract`
+  pub async fn retract_import(
+    &self,
+    source_name: &str,
+    reason: &str,
+  ) -> Result<Vec<Retraction>> {
+    let resp = self
+      .auth(self.client.post(self.url("/import-sources/retract")))
+      .json(&serde_json::json!({
+        "source_name": source_name,
+        "reason": reason,
+      }))
+      .send()
+      .await
+      .context("POST /import-sources/retract failed")?;
+
+    if !resp.status().is_success() {
+      return Err(anyhow!("POST /import-sources/retract → {}", resp.status()));
+    }
+    resp.json().await.context("deserialising retractions")
+  }
 }
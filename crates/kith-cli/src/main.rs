@@ -5,6 +5,8 @@
 //! ```
 //! kith --url http://localhost:5232 --user alice --password secret
 //! kith --config ~/.config/kith/config.toml
+//! kith imports list
+//! kith imports undo vCard --reason "duplicated contacts"
 //! ```
 
 mod app;
@@ -16,7 +18,7 @@ use std::{io, time::Duration};
 
 use anyhow::{Context, Result};
 use app::App;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use client::{ApiClient, ApiConfig};
 use crossterm::{
   event::{self, Event},
@@ -46,6 +48,32 @@ struct Args {
   /// API password (plaintext).
   #[arg(long, env = "KITH_PASSWORD")]
   password: Option<String>,
+
+  /// Run a single command instead of the terminal UI.
+  #[command(subcommand)]
+  command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+  /// Inspect import sources and undo an import.
+  #[command(subcommand)]
+  Imports(ImportsCommand),
+}
+
+#[derive(Subcommand, Debug)]
+enum ImportsCommand {
+  /// List every import source with its active and total fact counts.
+  List,
+  /// Show the active facts an undo of SOURCE would retract.
+  Preview { source: String },
+  /// Retract every active fact from SOURCE.
+  Undo {
+    source: String,
+    /// Recorded on every retraction (default: "Undo import of SOURCE").
+    #[arg(long)]
+    reason: Option<String>,
+  },
 }
 
 // ─── Config file ──────────────────────────────────────────────────────────────
@@ -93,6 +121,9 @@ async fn main() -> Result<()> {
   };
 
   let client = ApiClient::new(api_config)?;
+  if let Some(Command::Imports(command)) = args.command {
+    return run_imports(&client, command).await;
+  }
   let mut app = App::new(client);
 
   // Set up the terminal.
@@ -120,6 +151,38 @@ async fn main() -> Result<()> {
   run_result
 }
 
+// ─── Commands ─────────────────────────────────────────────────────────────────
+
+async fn run_imports(
+  client: &ApiClient,
+  command: ImportsCommand,
+) -> Result<()> {
+  match command {
+    ImportsCommand::List => {
+      for source in client.list_import_sources().await? {
+        println!(
+          "{:>5}/{:<5} {}",
+          source.active_facts, source.facts, source.source_name
+        );
+      }
+    }
+    ImportsCommand::Preview { source } => {
+      for rf in client.import_facts(&source).await? {
+        let (label, primary, _) =
+          ui::contact_detail::format_fact(&rf.fact.value);
+        println!("{}  {label}: {primary}", rf.fact.subject_id);
+      }
+    }
+    ImportsCommand::Undo { source, reason } => {
+      let reason =
+        reason.unwrap_or_else(|| format!("Undo import of {source}"));
+      let retracted = client.retract_import(&source, &reason).await?;
+      println!("Retracted {} facts from {source}.", retracted.len());
+    }
+  }
+  Ok(())
+}
+
 // ─── Event loop ───────────────────────────────────────────────────────────────
 
 async fn run_event_loop(
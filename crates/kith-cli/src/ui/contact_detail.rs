@@ -47,7 +47,7 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
   if app.facts.is_empty() {
     f.render_widget(
       Paragraph::new(Span::styled(
-        "No active facts for this contact.",
+        "No active facts for this contact. Press u to restore it.",
         colors::style_muted(),
       )),
       inner,
@@ -108,10 +108,10 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
     lines.push(Line::from(spans));
   }
 
-  // Phase-B/C hint footer.
+  // Phase-C hint footer.
   lines.push(Line::from(""));
   lines.push(Line::from(vec![Span::styled(
-    "Phase B: history  Phase C: edit / retract / add",
+    "Phase C: edit / retract / add",
     colors::style_subtle(),
   )]));
 
@@ -125,7 +125,7 @@ pub fn draw(f: &mut Frame, area: Rect, app: &App) {
 // ─── Fact formatting ──────────────────────────────────────────────────────────
 
 /// Returns `(type_label, value_string, extra_string)` for a fact value.
-fn format_fact(value: &FactValue) -> (&'static str, String, String) {
+pub fn format_fact(value: &FactValue) -> (&'static str, String, String) {
   match value {
     FactValue::Name(n) => ("name", n.full.clone(), String::new()),
     FactValue::Alias(a) => (
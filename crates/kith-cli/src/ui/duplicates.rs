@@ -0,0 +1,141 @@
+//! Duplicates review — candidate pairs on the left, their evidence on the
+//! right.
+
+use kith_core::duplicates::{DuplicateCandidate, Evidence};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Direction, Layout, Rect},
+  style::Style,
+  text::{Line, Span},
+  widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+use uuid::Uuid;
+
+use crate::{app::App, colors};
+
+// ─── Public entry ─────────────────────────────────────────────────────────────
+
+/// Render the duplicates review into `area`.
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+  let cols = Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+    .split(area);
+
+  draw_pairs(f, cols[0], app);
+  draw_evidence(f, cols[1], app);
+}
+
+// ─── Panes ────────────────────────────────────────────────────────────────────
+
+fn draw_pairs(f: &mut Frame, area: Rect, app: &App) {
+  let block = Block::default()
+    .title(Span::styled(
+      format!(" Possible duplicates ({}) ", app.duplicates.len()),
+      colors::style_muted(),
+    ))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border_focus())
+    .style(Style::default().bg(colors::panel_bg()));
+
+  let items: Vec<ListItem> = app
+    .duplicates
+    .iter()
+    .map(|c| {
+      let [a, b] = c.subject_ids;
+      ListItem::new(Line::from(vec![
+        Span::styled(
+          format!("{:>3}%  ", (c.score * 100.0).round()),
+          colors::style_accent_text(),
+        ),
+        Span::styled(name(app, a), colors::style_text()),
+        Span::styled("  ⇄  ", colors::style_subtle()),
+        Span::styled(name(app, b), colors::style_text()),
+      ]))
+    })
+    .collect();
+
+  let mut state = ListState::default();
+  state.select(if app.duplicates.is_empty() {
+    None
+  } else {
+    Some(app.duplicate_cursor)
+  });
+
+  f.render_stateful_widget(
+    List::new(items)
+      .block(block)
+      .highlight_style(colors::style_selected())
+      .highlight_symbol("▶ "),
+    area,
+    &mut state,
+  );
+}
+
+fn draw_evidence(f: &mut Frame, area: Rect, app: &App) {
+  let block = Block::default()
+    .title(Span::styled(" Evidence ", colors::style_muted()))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border())
+    .style(Style::default().bg(colors::panel_bg()));
+  let inner = block.inner(area);
+  f.render_widget(block, area);
+
+  let Some(candidate) = app.duplicates.get(app.duplicate_cursor) else {
+    f.render_widget(
+      Paragraph::new(Span::styled(
+        "No possible duplicates.",
+        colors::style_subtle(),
+      )),
+      inner,
+    );
+    return;
+  };
+
+  let [a, b] = candidate.subject_ids;
+  let mut lines = vec![
+    Line::from(vec![
+      Span::styled(format!("{:<14}", "keep"), colors::style_accent_text()),
+      Span::styled(name(app, a), colors::style_text()),
+    ]),
+    Line::from(vec![
+      Span::styled(format!("{:<14}", "merge"), colors::style_accent_text()),
+      Span::styled(name(app, b), colors::style_text()),
+    ]),
+    Line::from(""),
+  ];
+  lines.extend(evidence_lines(candidate));
+
+  f.render_widget(Paragraph::new(lines), inner);
+}
+
+// ─── Formatting ───────────────────────────────────────────────────────────────
+
+fn evidence_lines(candidate: &DuplicateCandidate) -> Vec<Line<'static>> {
+  candidate
+    .evidence
+    .iter()
+    .map(|e| {
+      let (label, value) = match e {
+        Evidence::Email { address } => ("email", address.clone()),
+        Evidence::Phone { number } => ("phone", number.clone()),
+        Evidence::Name { similarity } => {
+          ("name", format!("{:.0}% similar", similarity * 100.0))
+        }
+        Evidence::OriginalUid { uid } => ("import uid", uid.clone()),
+      };
+      Line::from(vec![
+        Span::styled(format!("{label:<14}"), colors::style_accent_text()),
+        Span::styled(value, colors::style_text()),
+      ])
+    })
+    .collect()
+}
+
+fn name(app: &App, id: Uuid) -> String {
+  app
+    .names
+    .get(&id)
+    .cloned()
+    .unwrap_or_else(|| id.to_string())
+}
@@ -0,0 +1,124 @@
+//! Fact history — the contact's facts on the left, every version of the
+//! selected one on the right.
+
+use kith_core::lifecycle::{FactStatus, ResolvedFact};
+use ratatui::{
+  Frame,
+  layout::{Constraint, Direction, Layout, Rect},
+  style::Style,
+  text::{Line, Span},
+  widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::{app::App, colors, ui::contact_detail::format_fact};
+
+// ─── Public entry ─────────────────────────────────────────────────────────────
+
+/// Render the history screen into `area`.
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+  let cols = Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+    .split(area);
+
+  draw_facts(f, cols[0], app);
+  draw_versions(f, cols[1], app);
+}
+
+// ─── Panes ────────────────────────────────────────────────────────────────────
+
+fn draw_facts(f: &mut Frame, area: Rect, app: &App) {
+  let subject_name = app
+    .selected_subject_id
+    .and_then(|id| app.names.get(&id))
+    .map(String::as_str)
+    .unwrap_or("(unknown)");
+
+  let block = Block::default()
+    .title(Span::styled(
+      format!(" {subject_name} "),
+      colors::style_accent_hi(),
+    ))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border_focus())
+    .style(Style::default().bg(colors::panel_bg()));
+
+  let items: Vec<ListItem> = app
+    .facts
+    .iter()
+    .map(|rf| {
+      let (type_label, value, _) = format_fact(&rf.fact.value);
+      ListItem::new(Line::from(vec![
+        Span::styled(format!("{type_label:<14}"), colors::style_accent_text()),
+        Span::styled(value, colors::style_text()),
+      ]))
+    })
+    .collect();
+
+  let mut state = ListState::default();
+  state.select(if app.facts.is_empty() {
+    None
+  } else {
+    Some(app.history_cursor)
+  });
+
+  f.render_stateful_widget(
+    List::new(items)
+      .block(block)
+      .highlight_style(colors::style_selected())
+      .highlight_symbol("▶ "),
+    area,
+    &mut state,
+  );
+}
+
+fn draw_versions(f: &mut Frame, area: Rect, app: &App) {
+  let block = Block::default()
+    .title(Span::styled(
+      format!(" Versions ({}) ", app.lineage.len()),
+      colors::style_muted(),
+    ))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border())
+    .style(Style::default().bg(colors::panel_bg()));
+  let inner = block.inner(area);
+  f.render_widget(block, area);
+
+  if app.lineage.is_empty() {
+    f.render_widget(
+      Paragraph::new(Span::styled(
+        "No history for this fact.",
+        colors::style_subtle(),
+      )),
+      inner,
+    );
+    return;
+  }
+
+  let lines: Vec<Line> = app.lineage.iter().map(version_line).collect();
+  f.render_widget(Paragraph::new(lines), inner);
+}
+
+// ─── Formatting ───────────────────────────────────────────────────────────────
+
+fn version_line(rf: &ResolvedFact) -> Line<'static> {
+  let (_, value, extra) = format_fact(&rf.fact.value);
+  let (status, style) = match &rf.status {
+    FactStatus::Active => ("current", colors::style_accent_text()),
+    FactStatus::Superseded { .. } => ("replaced", colors::style_muted()),
+    FactStatus::Retracted { .. } => ("retracted", colors::style_subtle()),
+  };
+
+  let mut spans = vec![
+    Span::styled(
+      format!("{}  ", rf.fact.recorded_at.format("%Y-%m-%d")),
+      colors::style_subtle(),
+    ),
+    Span::styled(format!("{status:<11}"), style),
+    Span::styled(value, colors::style_text()),
+  ];
+  if !extra.is_empty() {
+    spans.push(Span::styled(format!("  {extra}"), colors::style_muted()));
+  }
+  Line::from(spans)
+}
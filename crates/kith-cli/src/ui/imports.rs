@@ -0,0 +1,111 @@
+//! Import sources — the sources on the left, the facts an undo would retract
+//! on the right.
+
+use ratatui::{
+  Frame,
+  layout::{Constraint, Direction, Layout, Rect},
+  style::Style,
+  text::{Line, Span},
+  widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::{app::App, colors, ui::contact_detail::format_fact};
+
+// ─── Public entry ─────────────────────────────────────────────────────────────
+
+/// Render the imports screen into `area`.
+pub fn draw(f: &mut Frame, area: Rect, app: &App) {
+  let cols = Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([Constraint::Percentage(40), Constraint::Percentage(60)])
+    .split(area);
+
+  draw_sources(f, cols[0], app);
+  draw_facts(f, cols[1], app);
+}
+
+// ─── Panes ────────────────────────────────────────────────────────────────────
+
+fn draw_sources(f: &mut Frame, area: Rect, app: &App) {
+  let block = Block::default()
+    .title(Span::styled(
+      format!(" Import sources ({}) ", app.import_sources.len()),
+      colors::style_muted(),
+    ))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border_focus())
+    .style(Style::default().bg(colors::panel_bg()));
+
+  let items: Vec<ListItem> = app
+    .import_sources
+    .iter()
+    .map(|s| {
+      ListItem::new(Line::from(vec![
+        Span::styled(
+          format!("{:>5}/{:<5} ", s.active_facts, s.facts),
+          colors::style_accent_text(),
+        ),
+        Span::styled(s.source_name.clone(), colors::style_text()),
+      ]))
+    })
+    .collect();
+
+  let mut state = ListState::default();
+  state.select(if app.import_sources.is_empty() {
+    None
+  } else {
+    Some(app.import_cursor)
+  });
+
+  f.render_stateful_widget(
+    List::new(items)
+      .block(block)
+      .highlight_style(colors::style_selected())
+      .highlight_symbol("▶ "),
+    area,
+    &mut state,
+  );
+}
+
+fn draw_facts(f: &mut Frame, area: Rect, app: &App) {
+  let block = Block::default()
+    .title(Span::styled(
+      format!(" Active facts ({}) ", app.import_facts.len()),
+      colors::style_muted(),
+    ))
+    .borders(Borders::ALL)
+    .border_style(colors::style_border())
+    .style(Style::default().bg(colors::panel_bg()));
+  let inner = block.inner(area);
+  f.render_widget(block, area);
+
+  if app.import_facts.is_empty() {
+    f.render_widget(
+      Paragraph::new(Span::styled(
+        "Nothing from this source is active.",
+        colors::style_subtle(),
+      )),
+      inner,
+    );
+    return;
+  }
+
+  let lines: Vec<Line> = app
+    .import_facts
+    .iter()
+    .map(|rf| {
+      let (type_label, value, _) = format_fact(&rf.fact.value);
+      let name = app
+        .names
+        .get(&rf.fact.subject_id)
+        .cloned()
+        .unwrap_or_else(|| rf.fact.subject_id.to_string());
+      Line::from(vec![
+        Span::styled(format!("{type_label:<14}"), colors::style_accent_text()),
+        Span::styled(value, colors::style_text()),
+        Span::styled(format!("  {name}"), colors::style_muted()),
+      ])
+    })
+    .collect();
+  f.render_widget(Paragraph::new(lines), inner);
+}
@@ -2,6 +2,9 @@
 
 pub mod contact_detail;
 pub mod contact_list;
+pub mod duplicates;
+pub mod history;
+pub mod imports;
 
 use chrono::Local;
 use ratatui::{
@@ -56,13 +59,13 @@ fn draw_header(f: &mut Frame, area: Rect, _app: &App) {
       .add_modifier(ratatui::style::Modifier::BOLD),
   );
   let hints = Span::styled(
-    "  [/] search  [q] quit",
+    "  [/] search  [d] duplicates  [i] imports  [q] quit",
     colors::style_muted(),
   );
   let date_span = Span::styled(format!("{date} "), colors::style_subtle());
 
   let title_w = 5u16;
-  let hints_w = 22u16;
+  let hints_w = 51u16;
   let date_w = date_span.content.len() as u16;
   let pad = area.width.saturating_sub(title_w + hints_w + date_w);
 
@@ -83,6 +86,19 @@ fn draw_header(f: &mut Frame, area: Rect, _app: &App) {
 // ─── Body ─────────────────────────────────────────────────────────────────────
 
 fn draw_body(f: &mut Frame, area: Rect, app: &App) {
+  if app.screen == Screen::Duplicates {
+    duplicates::draw(f, area, app);
+    return;
+  }
+  if app.screen == Screen::History {
+    history::draw(f, area, app);
+    return;
+  }
+  if app.screen == Screen::Imports {
+    imports::draw(f, area, app);
+    return;
+  }
+
   let cols = Layout::default()
     .direction(Direction::Horizontal)
     .constraints([Constraint::Percentage(30), Constraint::Percentage(70)])
@@ -124,11 +140,20 @@ fn draw_status(f: &mut Frame, area: Rect, app: &App) {
     ),
     Screen::ContactList => (
       "NORMAL",
-      "↑↓/jk navigate  / search  Enter detail  q quit",
+      "↑↓/jk navigate  / search  Enter detail  d dupes  i imports  q quit",
     ),
     Screen::ContactDetail => (
       "DETAIL",
-      "↑↓/jk scroll  Esc back  [/] prev/next contact  q quit",
+      "↑↓/jk scroll  v history  u restore  Esc back  [/] prev/next  q quit",
+    ),
+    Screen::Duplicates => (
+      "DUPES",
+      "↑↓/jk navigate  m merge second into first  s skip  Esc back",
+    ),
+    Screen::History => ("HISTORY", "↑↓/jk select fact  Esc back  q quit"),
+    Screen::Imports => (
+      "IMPORTS",
+      "↑↓/jk navigate  x undo import (twice to confirm)  Esc back  q quit",
     ),
   };
 
@@ -0,0 +1,149 @@
+//! Address books — named collections of subjects.
+//!
+//! A subject can belong to any number of address books. Membership is a
+//! property of the collection, not of the contact, so it is tracked outside
+//! the fact log: adding a contact to a "work" book says nothing about the
+//! person themselves.
+
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A named collection of subjects, exposed over CardDAV as
+/// `/dav/addressbooks/{user}/{name}/`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AddressBook {
+  pub addressbook_id: Uuid,
+  /// URL path segment; unique across the store.
+  pub name:           String,
+  pub display_name:   Option<String>,
+  pub description:    Option<String>,
+  /// The default book also contains every person subject that has never been
+  /// assigned to any book. At most one book is the default.
+  pub is_default:     bool,
+  pub created_at:     DateTime<Utc>,
+}
+
+/// Input for [`ContactStore::create_addressbook`].
+///
+/// [`ContactStore::create_addressbook`]: crate::store::ContactStore::create_addressbook
+#[derive(Debug, Clone)]
+pub struct NewAddressBook {
+  pub name:         String,
+  pub display_name: Option<String>,
+  pub description:  Option<String>,
+}
+
+/// What a [`MembershipEvent`] does.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MembershipAction {
+  Add,
+  Remove,
+}
+
+/// Records that a subject was added to or removed from an address book. A
+/// subject is in a book while its latest event there is an
+/// [`MembershipAction::Add`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct MembershipEvent {
+  pub addressbook_id: Uuid,
+  pub subject_id:     Uuid,
+  pub action:         MembershipAction,
+  pub recorded_at:    DateTime<Utc>,
+}
+
+// ─── Sync ────────────────────────────────────────────────────────────────────
+
+/// A position in the store's change history: the high-water mark of each
+/// append-only log (facts, supersessions, retractions, membership events,
+/// subject merges and reinstatements).
+///
+/// Every write advances at least one component and none ever decreases, so a
+/// token taken later is never behind one taken earlier. Rendered as
+/// `facts.supersessions.retractions.memberships.merges.reinstatements`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncToken {
+  pub facts:          i64,
+  pub supersessions:  i64,
+  pub retractions:    i64,
+  pub memberships:    i64,
+  pub merges:         i64,
+  pub reinstatements: i64,
+}
+
+impl SyncToken {
+  /// `true` if no component of `self` is ahead of `other`, i.e. `self` was
+  /// issued no later than `other`.
+  pub fn is_at_or_before(&self, other: &SyncToken) -> bool {
+    self.facts <= other.facts
+      && self.supersessions <= other.supersessions
+      && self.retractions <= other.retractions
+      && self.memberships <= other.memberships
+      && self.merges <= other.merges
+      && self.reinstatements <= other.reinstatements
+  }
+}
+
+impl fmt::Display for SyncToken {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}.{}.{}.{}.{}.{}",
+      self.facts,
+      self.supersessions,
+      self.retractions,
+      self.memberships,
+      self.merges,
+      self.reinstatements
+    )
+  }
+}
+
+impl FromStr for SyncToken {
+  type Err = crate::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || crate::Error::InvalidSyncToken(s.to_string());
+    let parts = s
+      .split('.')
+      .map(|p| p.parse::<i64>().ok().filter(|n| *n >= 0))
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(invalid)?;
+    match parts[..] {
+      [
+        facts,
+        supersessions,
+        retractions,
+        memberships,
+        merges,
+        reinstatements,
+      ] => Ok(Self {
+        facts,
+        supersessions,
+        retractions,
+        memberships,
+        merges,
+        reinstatements,
+      }),
+      _ => Err(invalid()),
+    }
+  }
+}
+
+/// What changed in an address book between two [`SyncToken`]s.
+#[derive(Debug, Clone)]
+pub struct AddressBookChanges {
+  /// The position these changes run up to; pass it back as `since` next time.
+  pub token:   SyncToken,
+  /// Members that were added or whose facts changed. Only visible contacts
+  /// (those with at least one active fact) are listed.
+  pub changed: Vec<Uuid>,
+  /// Subjects that left the book, lost their last active fact or were merged
+  /// into another subject. Always empty for an initial sync.
+  pub removed: Vec<Uuid>,
+}
@@ -0,0 +1,401 @@
+//! Duplicate-contact detection.
+//!
+//! Importing the same people from several sources leaves each of them
+//! recorded as several subjects. [`candidates`] compares every pair of people
+//! on the evidence their active facts share — a normalised email address or
+//! phone number, a similar name, or the same `original_uid` from an import —
+//! and ranks the pairs most likely to be one person first.
+//!
+//! Nothing is merged here; a reviewed pair is merged with
+//! [`ContactStore::merge_subjects`].
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+  fact::{FactValue, RecordingContext},
+  lifecycle::ContactView,
+  similarity::{normalize_phone, similarity},
+  store::ContactStore,
+  subject::SubjectKind,
+};
+
+/// Default for the `min_score` of [`candidates`]: a matching name alone is
+/// reported, a shared but otherwise unremarkable detail is not.
+pub const DEFAULT_MIN_SCORE: f64 = 0.5;
+
+/// The similarity (see [`similarity`]) at which two normalised names count
+/// as evidence.
+pub const NAME_THRESHOLD: f64 = 0.85;
+
+/// One piece of evidence that two subjects are the same person, tagged by
+/// its `signal` field.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "signal", rename_all = "snake_case")]
+pub enum Evidence {
+  /// Both have this email address, compared case-insensitively.
+  Email { address: String },
+  /// Both have this phone number, ignoring spacing.
+  Phone { number: String },
+  /// Their names are at least [`NAME_THRESHOLD`] similar, ignoring case,
+  /// punctuation and word order.
+  Name { similarity: f64 },
+  /// Both have facts imported from a vCard with this `UID`.
+  OriginalUid { uid: String },
+}
+
+impl Evidence {
+  /// How strongly this evidence alone suggests a duplicate, in `0.0..=1.0`.
+  pub fn weight(&self) -> f64 {
+    match self {
+      Evidence::Email { .. } => 0.9,
+      Evidence::Phone { .. } => 0.8,
+      Evidence::Name { similarity } => 0.6 * similarity,
+      Evidence::OriginalUid { .. } => 0.95,
+    }
+  }
+}
+
+/// A pair of people who may be the same person.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+  /// The two subjects, in ascending order.
+  pub subject_ids: [Uuid; 2],
+  /// The likelihood that the pair is one person, in `0.0..1.0`: each piece
+  /// of evidence independently rules out a share of the doubt.
+  pub score:       f64,
+  pub evidence:    Vec<Evidence>,
+}
+
+/// Score every pair of people among `views` and return those scoring at
+/// least `min_score`, highest first. Views of anything but people are
+/// ignored.
+pub fn candidates(
+  views: &[ContactView],
+  min_score: f64,
+) -> Vec<DuplicateCandidate> {
+  let profiles: Vec<Profile> = views
+    .iter()
+    .filter(|v| v.subject.kind == SubjectKind::Person)
+    .map(Profile::new)
+    .collect();
+
+  // Exact signals: every pair sharing a key.
+  let mut keys: BTreeMap<&Key, Vec<usize>> = BTreeMap::new();
+  for (i, profile) in profiles.iter().enumerate() {
+    for key in &profile.keys {
+      keys.entry(key).or_default().push(i);
+    }
+  }
+  let mut pairs: BTreeMap<(usize, usize), Vec<Evidence>> = BTreeMap::new();
+  for (key, holders) in keys {
+    for (n, &i) in holders.iter().enumerate() {
+      for &j in &holders[n + 1..] {
+        pairs.entry((i, j)).or_default().push(key.evidence());
+      }
+    }
+  }
+
+  // Names: compared pairwise, skipping pairs whose lengths alone rule out a
+  // match.
+  for (i, a) in profiles.iter().enumerate() {
+    for (j, b) in profiles.iter().enumerate().skip(i + 1) {
+      let best = a
+        .names
+        .iter()
+        .flat_map(|x| b.names.iter().map(move |y| (x, y)))
+        .filter(|(x, y)| lengths_allow_match(x, y))
+        .map(|(x, y)| similarity(x, y))
+        .fold(0.0, f64::max);
+      if best >= NAME_THRESHOLD {
+        pairs
+          .entry((i, j))
+          .or_default()
+          .push(Evidence::Name { similarity: best });
+      }
+    }
+  }
+
+  let mut out: Vec<DuplicateCandidate> = pairs
+    .into_iter()
+    .map(|((i, j), evidence)| {
+      let doubt: f64 = evidence.iter().map(|e| 1.0 - e.weight()).product();
+      let mut subject_ids = [profiles[i].subject_id, profiles[j].subject_id];
+      subject_ids.sort();
+      DuplicateCandidate {
+        subject_ids,
+        score: 1.0 - doubt,
+        evidence,
+      }
+    })
+    .filter(|c| c.score >= min_score)
+    .collect();
+  out.sort_by(|a, b| {
+    b.score
+      .total_cmp(&a.score)
+      .then_with(|| a.subject_ids.cmp(&b.subject_ids))
+  });
+  out
+}
+
+/// [`candidates`] among every person in `store`, as of now.
+pub async fn find<S: ContactStore>(
+  store: &S,
+  min_score: f64,
+) -> Result<Vec<DuplicateCandidate>, S::Error> {
+  let now = Utc::now();
+  let mut views = Vec::new();
+  for subject in store.list_subjects(Some(SubjectKind::Person)).await? {
+    if let Some(view) = store
+      .materialize(subject.subject_id, Some(now), None)
+      .await?
+    {
+      views.push(view);
+    }
+  }
+  Ok(candidates(&views, min_score))
+}
+
+/// The signals one subject offers for comparison.
+struct Profile {
+  subject_id: Uuid,
+  /// Exact-match signals.
+  keys:       BTreeSet<Key>,
+  /// Normalised names.
+  names:      BTreeSet<String>,
+}
+
+impl Profile {
+  fn new(view: &ContactView) -> Self {
+    let mut keys = BTreeSet::new();
+    let mut names = BTreeSet::new();
+    for rf in &view.active_facts {
+      match &rf.fact.value {
+        FactValue::Email(e) => {
+          let address = e.address.trim().to_lowercase();
+          if !address.is_empty() {
+            keys.insert(Key::Email(address));
+          }
+        }
+        FactValue::Phone(p) => {
+          let number = normalize_phone(&p.number);
+          if !number.is_empty() {
+            keys.insert(Key::Phone(number));
+          }
+        }
+        FactValue::Name(n) => {
+          let name = normalize_name(&n.full);
+          if !name.is_empty() {
+            names.insert(name);
+          }
+        }
+        _ => {}
+      }
+      if let RecordingContext::Imported {
+        original_uid: Some(uid),
+        ..
+      } = &rf.fact.recording_context
+      {
+        keys.insert(Key::OriginalUid(uid.clone()));
+      }
+    }
+    Self {
+      subject_id: view.subject.subject_id,
+      keys,
+      names,
+    }
+  }
+}
+
+/// An exact-match signal, normalised; ordered as its evidence is listed.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+  OriginalUid(String),
+  Email(String),
+  Phone(String),
+}
+
+impl Key {
+  fn evidence(&self) -> Evidence {
+    match self.clone() {
+      Key::OriginalUid(uid) => Evidence::OriginalUid { uid },
+      Key::Email(address) => Evidence::Email { address },
+      Key::Phone(number) => Evidence::Phone { number },
+    }
+  }
+}
+
+/// A name lowercased, with punctuation dropped and its words sorted, so that
+/// "Smith, Alice" and "alice smith" compare equal.
+fn normalize_name(name: &str) -> String {
+  let lower: String = name
+    .chars()
+    .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+    .collect::<String>()
+    .to_lowercase();
+  let mut words: Vec<&str> = lower.split_whitespace().collect();
+  words.sort_unstable();
+  words.join(" ")
+}
+
+/// Whether two strings are close enough in length to reach
+/// [`NAME_THRESHOLD`] similarity; the length difference is a lower bound on
+/// their edit distance.
+fn lengths_allow_match(a: &str, b: &str) -> bool {
+  let (la, lb) = (a.chars().count(), b.chars().count());
+  let longest = la.max(lb);
+  longest == 0
+    || 1.0 - la.abs_diff(lb) as f64 / longest as f64 >= NAME_THRESHOLD
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::{TimeZone, Utc};
+
+  use super::*;
+  use crate::{
+    fact::{
+      Confidence, ContactLabel, EmailValue, Fact, NameValue, PhoneKind,
+      PhoneValue,
+    },
+    lifecycle::{FactStatus, ResolvedFact},
+    subject::Subject,
+  };
+
+  fn fact(value: FactValue, context: RecordingContext) -> ResolvedFact {
+    ResolvedFact {
+      fact:   Fact {
+        fact_id: Uuid::new_v4(),
+        subject_id: Uuid::nil(),
+        value,
+        recorded_at: Utc.timestamp_opt(0, 0).unwrap(),
+        effective_at: None,
+        effective_until: None,
+        source: None,
+        confidence: Confidence::Certain,
+        recording_context: context,
+        tags: vec![],
+      },
+      status: FactStatus::Active,
+    }
+  }
+
+  fn name(full: &str) -> ResolvedFact {
+    fact(
+      FactValue::Name(NameValue {
+        given:      None,
+        family:     None,
+        additional: None,
+        prefix:     None,
+        suffix:     None,
+        full:       full.into(),
+      }),
+      RecordingContext::Manual,
+    )
+  }
+
+  fn email(address: &str) -> ResolvedFact {
+    fact(
+      FactValue::Email(EmailValue {
+        address:    address.into(),
+        label:      ContactLabel::Home,
+        preference: 1,
+      }),
+      RecordingContext::Manual,
+    )
+  }
+
+  fn phone(number: &str) -> ResolvedFact {
+    fact(
+      FactValue::Phone(PhoneValue {
+        number:     number.into(),
+        label:      ContactLabel::Home,
+        kind:       PhoneKind::Voice,
+        preference: 1,
+      }),
+      RecordingContext::Manual,
+    )
+  }
+
+  fn person(facts: Vec<ResolvedFact>) -> ContactView {
+    let ts = Utc.timestamp_opt(0, 0).unwrap();
+    ContactView {
+      subject:      Subject {
+        subject_id: Uuid::new_v4(),
+        created_at: ts,
+        kind:       SubjectKind::Person,
+      },
+      as_of:        ts,
+      valid_at:     None,
+      active_facts: facts,
+    }
+  }
+
+  #[test]
+  fn shared_details_are_ranked_by_strength() {
+    let alice = person(vec![
+      name("Alice Smith"),
+      email("alice@example.com"),
+      phone("+1 555 0100"),
+    ]);
+    let alice2 =
+      person(vec![name("Smith, Alice"), email("Alice@Example.com ")]);
+    let bob = person(vec![name("Bob Jones"), phone("+1-555-0100")]);
+    let carol = person(vec![name("Carol White")]);
+    let views = [alice.clone(), alice2.clone(), bob.clone(), carol];
+
+    let found = candidates(&views, DEFAULT_MIN_SCORE);
+    assert_eq!(found.len(), 2);
+
+    let mut ids = [alice.subject.subject_id, alice2.subject.subject_id];
+    ids.sort();
+    assert_eq!(found[0].subject_ids, ids);
+    assert_eq!(found[0].evidence, vec![
+      Evidence::Email {
+        address: "alice@example.com".into(),
+      },
+      Evidence::Name { similarity: 1.0 },
+    ]);
+    assert!((found[0].score - 0.96).abs() < 1e-9);
+
+    assert!(found[1].subject_ids.contains(&bob.subject.subject_id));
+    assert_eq!(found[1].evidence, vec![Evidence::Phone {
+      number: "+15550100".into(),
+    }]);
+  }
+
+  #[test]
+  fn similar_names_and_import_uids_count() {
+    let imported = |uid: &str| {
+      fact(
+        FactValue::Note("imported".into()),
+        RecordingContext::Imported {
+          source_name:  "Google".into(),
+          original_uid: Some(uid.into()),
+        },
+      )
+    };
+    let a = person(vec![name("Jonathan Doe"), imported("uid-1")]);
+    let b = person(vec![name("Johnathan Doe"), imported("uid-1")]);
+    let c = person(vec![name("Jon Do")]);
+
+    let found = candidates(&[a, b, c], 0.0);
+    assert_eq!(found.len(), 1);
+    assert!(matches!(
+      found[0].evidence[..],
+      [Evidence::OriginalUid { .. }, Evidence::Name { similarity }]
+        if (NAME_THRESHOLD..1.0).contains(&similarity)
+    ));
+  }
+
+  #[test]
+  fn only_people_are_compared() {
+    let a = person(vec![email("team@example.com")]);
+    let mut b = person(vec![email("team@example.com")]);
+    b.subject.kind = SubjectKind::Organization;
+    assert!(candidates(&[a, b], 0.0).is_empty());
+  }
+}
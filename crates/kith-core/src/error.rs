@@ -23,6 +23,12 @@ pub enum Error {
   #[error("unknown fact type discriminant: {0:?}")]
   UnknownFactType(String),
 
+  #[error("invalid sync token: {0:?}")]
+  InvalidSyncToken(String),
+
+  #[error("invalid event id: {0:?}")]
+  InvalidEventId(String),
+
   #[error("serialization error: {0}")]
   Serialization(#[from] serde_json::Error),
 }
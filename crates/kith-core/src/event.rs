@@ -0,0 +1,142 @@
+//! Change events — what was written to the fact log, and where.
+//!
+//! Every fact recorded, superseded, retracted or reinstated is published as a
+//! [`StoreEvent`] (see [`ContactStore::subscribe`]). Each event carries an
+//! [`EventId`]: the position in the store's append-only logs just after it.
+//! Passing the last id seen back to [`ContactStore::events_since`] replays
+//! whatever was missed, so a listener can resume after a disconnect.
+//!
+//! [`ContactStore::subscribe`]: crate::store::ContactStore::subscribe
+//! [`ContactStore::events_since`]: crate::store::ContactStore::events_since
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use uuid::Uuid;
+
+use crate::{
+  addressbook::SyncToken,
+  fact::Fact,
+  lifecycle::{Reinstatement, Retraction, Supersession},
+};
+
+/// A position in the fact logs: the high-water mark of the facts,
+/// supersessions, retractions and reinstatements logs. Rendered as
+/// `facts.supersessions.retractions.reinstatements`. The default is the start
+/// of the logs.
+///
+/// Unlike a [`SyncToken`] it ignores address
+/// book membership and subject merges, neither of which is a change to any
+/// fact.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct EventId {
+  pub facts:          i64,
+  pub supersessions:  i64,
+  pub retractions:    i64,
+  pub reinstatements: i64,
+}
+
+impl EventId {
+  /// `true` if no component of `self` is ahead of `other`, i.e. the event at
+  /// `self` was already delivered to a listener that has seen `other`.
+  pub fn is_at_or_before(&self, other: &EventId) -> bool {
+    self.facts <= other.facts
+      && self.supersessions <= other.supersessions
+      && self.retractions <= other.retractions
+      && self.reinstatements <= other.reinstatements
+  }
+}
+
+impl From<SyncToken> for EventId {
+  fn from(token: SyncToken) -> Self {
+    EventId {
+      facts:          token.facts,
+      supersessions:  token.supersessions,
+      retractions:    token.retractions,
+      reinstatements: token.reinstatements,
+    }
+  }
+}
+
+impl fmt::Display for EventId {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "{}.{}.{}.{}",
+      self.facts, self.supersessions, self.retractions, self.reinstatements
+    )
+  }
+}
+
+impl FromStr for EventId {
+  type Err = crate::Error;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let invalid = || crate::Error::InvalidEventId(s.to_string());
+    let parts = s
+      .split('.')
+      .map(|p| p.parse::<i64>().ok().filter(|n| *n >= 0))
+      .collect::<Option<Vec<_>>>()
+      .ok_or_else(invalid)?;
+    match parts[..] {
+      [facts, supersessions, retractions, reinstatements] => Ok(Self {
+        facts,
+        supersessions,
+        retractions,
+        reinstatements,
+      }),
+      _ => Err(invalid()),
+    }
+  }
+}
+
+impl Serialize for EventId {
+  fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(self)
+  }
+}
+
+impl<'de> Deserialize<'de> for EventId {
+  fn deserialize<D: Deserializer<'de>>(
+    deserializer: D,
+  ) -> Result<Self, D::Error> {
+    let s = String::deserialize(deserializer)?;
+    s.parse().map_err(serde::de::Error::custom)
+  }
+}
+
+/// What an event records.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum Change {
+  FactRecorded { fact: Box<Fact> },
+  FactSuperseded { supersession: Supersession },
+  FactRetracted { retraction: Retraction },
+  FactReinstated { reinstatement: Reinstatement },
+}
+
+impl Change {
+  /// The event's name: `fact-recorded`, `fact-superseded`, `fact-retracted`
+  /// or `fact-reinstated`.
+  pub fn name(&self) -> &'static str {
+    match self {
+      Change::FactRecorded { .. } => "fact-recorded",
+      Change::FactSuperseded { .. } => "fact-superseded",
+      Change::FactRetracted { .. } => "fact-retracted",
+      Change::FactReinstated { .. } => "fact-reinstated",
+    }
+  }
+}
+
+/// One write to the fact log.
+///
+/// A supersession is published as two events: `fact-recorded` for the
+/// replacement, then `fact-superseded`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoreEvent {
+  pub id:         EventId,
+  /// The subject whose facts changed.
+  pub subject_id: Uuid,
+  #[serde(flatten)]
+  pub change:     Change,
+}
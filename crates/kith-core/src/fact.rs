@@ -4,7 +4,7 @@
 //! never updated; lifecycle events (supersession, retraction) are recorded in
 //! separate append-only tables.
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
@@ -26,6 +26,36 @@ pub enum EffectiveDate {
   Unknown,
 }
 
+impl EffectiveDate {
+  /// The earliest instant covered when used as an `effective_at` bound.
+  ///
+  /// A [`DateOnly`](Self::DateOnly) starts at midnight UTC. Returns `None`
+  /// for [`Unknown`](Self::Unknown), which leaves the bound open.
+  pub fn start_instant(&self) -> Option<DateTime<Utc>> {
+    match self {
+      Self::Instant(t) => Some(*t),
+      Self::DateOnly(d) => Some(d.and_time(NaiveTime::MIN).and_utc()),
+      Self::Unknown => None,
+    }
+  }
+
+  /// The first instant *no longer* covered when used as an
+  /// `effective_until` bound (i.e. an exclusive upper bound).
+  ///
+  /// A [`DateOnly`](Self::DateOnly) includes the whole day, so the bound is
+  /// midnight UTC of the following day. Returns `None` for
+  /// [`Unknown`](Self::Unknown), which leaves the bound open.
+  pub fn end_instant(&self) -> Option<DateTime<Utc>> {
+    match self {
+      Self::Instant(t) => Some(*t),
+      Self::DateOnly(d) => d
+        .succ_opt()
+        .map(|next| next.and_time(NaiveTime::MIN).and_utc()),
+      Self::Unknown => None,
+    }
+  }
+}
+
 // ─── Provenance ──────────────────────────────────────────────────────────────
 
 /// How certain the author is about this fact.
@@ -280,6 +310,90 @@ impl FactValue {
     Ok(full.get("data").cloned().unwrap_or(serde_json::Value::Null))
   }
 
+  /// A plain-text rendering of the human-meaningful parts of the value, for
+  /// full-text indexing.
+  ///
+  /// Structural noise (JSON keys, labels, preference ranks, photo paths) is
+  /// omitted so that searching for e.g. "label" or "address" only matches
+  /// facts that actually contain those words. Phone numbers are rendered
+  /// both as written and as bare digits.
+  pub fn search_text(&self) -> String {
+    fn join<'a>(parts: impl IntoIterator<Item = Option<&'a str>>) -> String {
+      parts
+        .into_iter()
+        .flatten()
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+    }
+
+    fn json_text(value: &serde_json::Value, out: &mut Vec<String>) {
+      match value {
+        serde_json::Value::Null => {}
+        serde_json::Value::String(s) => out.push(s.clone()),
+        serde_json::Value::Bool(_) | serde_json::Value::Number(_) => {
+          out.push(value.to_string())
+        }
+        serde_json::Value::Array(items) => {
+          items.iter().for_each(|v| json_text(v, out))
+        }
+        serde_json::Value::Object(map) => {
+          map.values().for_each(|v| json_text(v, out))
+        }
+      }
+    }
+
+    match self {
+      Self::Name(n) => join([
+        n.prefix.as_deref(),
+        n.given.as_deref(),
+        n.additional.as_deref(),
+        n.family.as_deref(),
+        n.suffix.as_deref(),
+        Some(n.full.as_str()),
+      ]),
+      Self::Alias(a) => join([Some(a.name.as_str()), a.context.as_deref()]),
+      Self::Photo(_) => String::new(),
+      Self::Birthday(d) | Self::Anniversary(d) => d.to_string(),
+      Self::Gender(s) | Self::Note(s) | Self::Introduction(s) => s.clone(),
+      Self::Email(e) => e.address.clone(),
+      Self::Phone(p) => {
+        let digits: String =
+          p.number.chars().filter(char::is_ascii_digit).collect();
+        join([Some(p.number.as_str()), Some(digits.as_str())])
+      }
+      Self::Address(a) => join([
+        a.street.as_deref(),
+        a.locality.as_deref(),
+        a.region.as_deref(),
+        a.postal_code.as_deref(),
+        a.country.as_deref(),
+      ]),
+      Self::Url(u) => u.url.clone(),
+      Self::Im(i) => join([Some(i.handle.as_str()), Some(i.service.as_str())]),
+      Self::Social(s) => {
+        join([Some(s.handle.as_str()), Some(s.platform.as_str())])
+      }
+      Self::Relationship(r) => {
+        join([Some(r.relation.as_str()), r.other_name.as_deref()])
+      }
+      Self::OrgMembership(o) => join([
+        Some(o.org_name.as_str()),
+        o.title.as_deref(),
+        o.role.as_deref(),
+      ]),
+      Self::GroupMembership(g) => g.group_name.clone(),
+      Self::Meeting(m) => {
+        join([Some(m.summary.as_str()), m.location.as_deref()])
+      }
+      Self::Custom { key, value } => {
+        let mut parts = vec![key.clone()];
+        json_text(value, &mut parts);
+        parts.join(" ")
+      }
+    }
+  }
+
   /// Deserialise from the discriminant string and JSON payload stored in the
   /// database.
   pub fn from_parts(
@@ -310,6 +424,26 @@ pub struct Fact {
   pub tags:              Vec<String>,
 }
 
+impl Fact {
+  /// Whether the fact holds in the real world at `t`, according to its
+  /// `effective_at` / `effective_until` bounds.
+  ///
+  /// Missing or [`EffectiveDate::Unknown`] bounds are treated as open.
+  pub fn is_valid_at(&self, t: DateTime<Utc>) -> bool {
+    let started = self
+      .effective_at
+      .as_ref()
+      .and_then(EffectiveDate::start_instant)
+      .is_none_or(|start| start <= t);
+    let not_ended = self
+      .effective_until
+      .as_ref()
+      .and_then(EffectiveDate::end_instant)
+      .is_none_or(|end| t < end);
+    started && not_ended
+  }
+}
+
 // ─── NewFact ─────────────────────────────────────────────────────────────────
 
 /// Input to [`crate::store::ContactStore::record_fact`].
@@ -0,0 +1,709 @@
+//! Fact history export and import, as JSON Lines.
+//!
+//! A vCard carries only what is true now. A history document carries
+//! everything the store ever recorded — subjects, facts, supersessions,
+//! retractions and reinstatements, address book membership and subject
+//! merges, with their original IDs and timestamps — so one subject or a whole
+//! store can move between instances without losing its bitemporal record.
+//!
+//! A document holds one JSON object per line. The first is a
+//! [`Record::Header`] naming the format version; every other line is one
+//! record. Records come in dependency order — subjects, address books and
+//! facts, each in log order, then lifecycle events in the order they took
+//! effect, then membership events and merges in log order — so every
+//! reference points at an earlier line or at something the target store
+//! already holds.
+//!
+//! [`import`] replays a document into any [`ContactStore`]: records the
+//! store already holds are skipped, so importing the same document twice
+//! writes nothing the second time.
+
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+  addressbook::{AddressBook, MembershipEvent},
+  fact::Fact,
+  lifecycle::{
+    LifecycleEvent, MergeAction, Reinstatement, ResolvedFact, Retraction,
+    SubjectMerge, Supersession,
+  },
+  store::ContactStore,
+  subject::Subject,
+};
+
+/// The version of the document format written by [`History::to_jsonl`], and
+/// the only one [`import`] reads.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// `Content-Type` of a history document.
+pub const CONTENT_TYPE: &str = "application/jsonl";
+
+/// Everything recorded about a set of subjects, each list in log order.
+///
+/// Returned by [`ContactStore::history`] and written verbatim by
+/// [`ContactStore::restore`].
+#[derive(Debug, Clone, Default)]
+pub struct History {
+  pub subjects:       Vec<Subject>,
+  /// The address books `memberships` refer to.
+  pub addressbooks:   Vec<AddressBook>,
+  pub facts:          Vec<Fact>,
+  pub supersessions:  Vec<Supersession>,
+  pub retractions:    Vec<Retraction>,
+  pub reinstatements: Vec<Reinstatement>,
+  pub memberships:    Vec<MembershipEvent>,
+  pub merges:         Vec<SubjectMerge>,
+}
+
+impl History {
+  /// `true` if the history holds no records at all.
+  pub fn is_empty(&self) -> bool {
+    self.subjects.is_empty()
+      && self.addressbooks.is_empty()
+      && self.facts.is_empty()
+      && self.supersessions.is_empty()
+      && self.retractions.is_empty()
+      && self.reinstatements.is_empty()
+      && self.memberships.is_empty()
+      && self.merges.is_empty()
+  }
+
+  /// Every supersession, retraction and reinstatement, in the order they
+  /// took effect (see [`LifecycleEvent::sort_key`]). Events recorded at the
+  /// same instant keep their log order, supersessions first.
+  pub fn lifecycle_events(&self) -> Vec<LifecycleEvent> {
+    let mut events: Vec<LifecycleEvent> = self
+      .supersessions
+      .iter()
+      .cloned()
+      .map(LifecycleEvent::Supersession)
+      .chain(
+        self
+          .retractions
+          .iter()
+          .cloned()
+          .map(LifecycleEvent::Retraction),
+      )
+      .chain(
+        self
+          .reinstatements
+          .iter()
+          .cloned()
+          .map(LifecycleEvent::Reinstatement),
+      )
+      .collect();
+    events.sort_by_key(LifecycleEvent::sort_key);
+    events
+  }
+
+  /// Render the history as a document, stamped with `exported_at`.
+  pub fn to_jsonl(&self, exported_at: DateTime<Utc>) -> crate::Result<String> {
+    let mut out = String::new();
+    let mut write = |record: &Record| -> crate::Result<()> {
+      out.push_str(&serde_json::to_string(record)?);
+      out.push('\n');
+      Ok(())
+    };
+
+    write(&Record::Header {
+      version: FORMAT_VERSION,
+      exported_at,
+    })?;
+    for subject in &self.subjects {
+      write(&Record::Subject(subject.clone()))?;
+    }
+    for book in &self.addressbooks {
+      write(&Record::AddressBook(book.clone()))?;
+    }
+    for fact in &self.facts {
+      write(&Record::Fact(Box::new(fact.clone())))?;
+    }
+    for event in self.lifecycle_events() {
+      write(&match event {
+        LifecycleEvent::Supersession(sup) => Record::Supersession(sup),
+        LifecycleEvent::Retraction(ret) => Record::Retraction(ret),
+        LifecycleEvent::Reinstatement(rein) => Record::Reinstatement(rein),
+      })?;
+    }
+    for event in &self.memberships {
+      write(&Record::Membership(event.clone()))?;
+    }
+    for merge in &self.merges {
+      write(&Record::Merge(merge.clone()))?;
+    }
+    Ok(out)
+  }
+}
+
+/// One line of a history document, tagged by its `record` field.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "record", rename_all = "snake_case")]
+pub enum Record {
+  Header {
+    version:     u32,
+    exported_at: DateTime<Utc>,
+  },
+  Subject(Subject),
+  AddressBook(AddressBook),
+  Fact(Box<Fact>),
+  Supersession(Supersession),
+  Retraction(Retraction),
+  Reinstatement(Reinstatement),
+  Membership(MembershipEvent),
+  Merge(SubjectMerge),
+}
+
+/// What an [`import`] wrote, and how many records it skipped because the
+/// store already held them.
+#[derive(
+  Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize,
+)]
+pub struct ImportReport {
+  pub subjects:       usize,
+  pub addressbooks:   usize,
+  pub facts:          usize,
+  pub supersessions:  usize,
+  pub retractions:    usize,
+  pub reinstatements: usize,
+  pub memberships:    usize,
+  pub merges:         usize,
+  pub skipped:        usize,
+}
+
+/// Why an [`import`] wrote nothing.
+#[derive(Debug, Error)]
+pub enum ImportError<E> {
+  #[error("the first line of a history document must be its header")]
+  MissingHeader,
+
+  #[error("unsupported history format version {0} (expected {FORMAT_VERSION})")]
+  UnsupportedVersion(u32),
+
+  #[error("line {line}: {source}")]
+  Parse {
+    line:   usize,
+    source: serde_json::Error,
+  },
+
+  /// A record refers to something neither the document nor the store
+  /// holds, or contradicts the store.
+  #[error("line {line}: {reason}")]
+  Invalid { line: usize, reason: String },
+
+  #[error(transparent)]
+  Store(E),
+}
+
+/// Replay the history document `input` into `store`, keeping every record's
+/// original ID and timestamps.
+///
+/// The whole document is validated before anything is written, and then
+/// written all-or-nothing with [`ContactStore::restore`]. Records the store
+/// already holds, or that appear earlier in the document, are counted as
+/// skipped; a record whose ID is taken by a different one is invalid.
+///
+/// Every other lifecycle event must follow on from the fact's status after
+/// the events before it, in the store and then in the document: a
+/// supersession or retraction of a fact that is not active is invalid, as is
+/// a reinstatement of one that is. Merges follow on in the same way: only
+/// two subjects that are not merged into another can be merged, and only
+/// a current merge undone.
+///
+/// An address book the store holds under the same name stands in for the
+/// document's, so that membership lands in it; each instance has its own
+/// default book. Other books are written, but never as the default.
+pub async fn import<S: ContactStore>(
+  store: &S,
+  input: &str,
+) -> Result<ImportReport, ImportError<S::Error>> {
+  let mut lines = input
+    .lines()
+    .enumerate()
+    .map(|(i, text)| (i + 1, text))
+    .filter(|(_, text)| !text.trim().is_empty());
+
+  let (line, text) = lines.next().ok_or(ImportError::MissingHeader)?;
+  match parse(line, text)? {
+    Record::Header { version, .. } if version == FORMAT_VERSION => {}
+    Record::Header { version, .. } => {
+      return Err(ImportError::UnsupportedVersion(version));
+    }
+    _ => return Err(ImportError::MissingHeader),
+  }
+
+  let mut importer = Importer {
+    store,
+    history: History::default(),
+    report: ImportReport::default(),
+    subjects: HashMap::new(),
+    facts: HashMap::new(),
+    events: HashMap::new(),
+    books: HashMap::new(),
+    memberships: HashSet::new(),
+    merges: HashSet::new(),
+    active: HashMap::new(),
+    merged: HashMap::new(),
+    stored: HashMap::new(),
+    stored_books: None,
+  };
+  for (line, text) in lines {
+    importer
+      .add(parse(line, text)?)
+      .await
+      .map_err(|e| e.at(line))?;
+  }
+
+  let Importer {
+    history, report, ..
+  } = importer;
+  if !history.is_empty() {
+    store.restore(history).await.map_err(ImportError::Store)?;
+  }
+  Ok(report)
+}
+
+fn parse<E>(line: usize, text: &str) -> Result<Record, ImportError<E>> {
+  serde_json::from_str(text)
+    .map_err(|source| ImportError::Parse { line, source })
+}
+
+fn already_ended<E>(fact_id: Uuid) -> Rejection<E> {
+  Rejection::Invalid(format!(
+    "fact {fact_id} is already superseded or retracted"
+  ))
+}
+
+fn not_ended<E>(fact_id: Uuid) -> Rejection<E> {
+  Rejection::Invalid(format!("fact {fact_id} is not superseded or retracted"))
+}
+
+/// Reject `record` unless it is `held`, the record already known under its
+/// ID `id`.
+fn unchanged<T: PartialEq, E>(
+  what: &str,
+  id: Uuid,
+  held: &T,
+  record: &T,
+) -> Result<(), Rejection<E>> {
+  if held == record {
+    Ok(())
+  } else {
+    Err(Rejection::Invalid(format!(
+      "{what} {id} differs from the one already recorded"
+    )))
+  }
+}
+
+/// `book` as compared by [`import`], which never writes the default flag.
+fn without_default(book: &AddressBook) -> AddressBook {
+  AddressBook {
+    is_default: false,
+    ..book.clone()
+  }
+}
+
+/// A record that cannot be imported, before its line number is known.
+enum Rejection<E> {
+  Invalid(String),
+  Store(E),
+}
+
+impl<E> Rejection<E> {
+  fn at(self, line: usize) -> ImportError<E> {
+    match self {
+      Rejection::Invalid(reason) => ImportError::Invalid { line, reason },
+      Rejection::Store(e) => ImportError::Store(e),
+    }
+  }
+}
+
+/// The state of an [`import`] part-way through its document.
+struct Importer<'a, S: ContactStore> {
+  store:        &'a S,
+  /// The records still to be written.
+  history:      History,
+  report:       ImportReport,
+  /// Subjects from earlier in the document, by index into
+  /// `history.subjects`.
+  subjects:     HashMap<Uuid, usize>,
+  /// Facts from earlier in the document, by index into `history.facts`.
+  facts:        HashMap<Uuid, usize>,
+  /// Lifecycle events from earlier in the document.
+  events:       HashMap<Uuid, LifecycleEvent>,
+  /// Address books from earlier in the document, with the ID of the book
+  /// each stands for in the store.
+  books:        HashMap<Uuid, (AddressBook, Uuid)>,
+  /// Membership events from earlier in the document.
+  memberships:  HashSet<MembershipEvent>,
+  /// Merges from earlier in the document.
+  merges:       HashSet<SubjectMerge>,
+  /// Whether each fact with lifecycle events earlier in the document is
+  /// active after them.
+  active:       HashMap<Uuid, bool>,
+  /// The subject each subject with merges earlier in the document is merged
+  /// into after them, if any.
+  merged:       HashMap<Uuid, Option<Uuid>>,
+  /// The store's history of each subject, read on first use.
+  stored:       HashMap<Uuid, History>,
+  /// The store's address books, read on first use.
+  stored_books: Option<Vec<AddressBook>>,
+}
+
+impl<S: ContactStore> Importer<'_, S> {
+  async fn add(&mut self, record: Record) -> Result<(), Rejection<S::Error>> {
+    match record {
+      Record::Header { .. } => {
+        return Err(Rejection::Invalid(
+          "a document has only one header".into(),
+        ));
+      }
+      Record::Subject(subject) => {
+        let id = subject.subject_id;
+        if let Some(&i) = self.subjects.get(&id) {
+          unchanged("subject", id, &self.history.subjects[i], &subject)?;
+          self.report.skipped += 1;
+        } else if let Some(held) = self
+          .stored_history(id)
+          .await?
+          .subjects
+          .iter()
+          .find(|s| s.subject_id == id)
+        {
+          unchanged("subject", id, held, &subject)?;
+          self.report.skipped += 1;
+        } else {
+          self.subjects.insert(id, self.history.subjects.len());
+          self.history.subjects.push(subject);
+          self.report.subjects += 1;
+        }
+      }
+      Record::AddressBook(book) => self.add_addressbook(book).await?,
+      Record::Fact(fact) => {
+        let id = fact.fact_id;
+        self.check_subject(fact.subject_id, || format!("fact {id}")).await?;
+        // Fact values cannot be compared directly; their JSON can.
+        let json = |fact: &Fact| serde_json::to_value(fact).ok();
+        if let Some(&i) = self.facts.get(&id) {
+          unchanged("fact", id, &json(&self.history.facts[i]), &json(&fact))?;
+          self.report.skipped += 1;
+        } else if let Some(held) = self.stored_fact(id).await? {
+          unchanged("fact", id, &json(&held.fact), &json(&fact))?;
+          self.report.skipped += 1;
+        } else {
+          self.facts.insert(id, self.history.facts.len());
+          self.history.facts.push(*fact);
+          self.report.facts += 1;
+        }
+      }
+      Record::Supersession(sup) => {
+        if sup.old_fact_id == sup.new_fact_id {
+          return Err(Rejection::Invalid(format!(
+            "supersession {} replaces a fact with itself",
+            sup.supersession_id
+          )));
+        }
+        if !self.facts.contains_key(&sup.new_fact_id)
+          && self.stored_fact(sup.new_fact_id).await?.is_none()
+        {
+          return Err(Rejection::Invalid(format!(
+            "supersession {} refers to unknown fact {}",
+            sup.supersession_id, sup.new_fact_id
+          )));
+        }
+        let event = LifecycleEvent::Supersession(sup.clone());
+        if self.transition(event).await? {
+          self.history.supersessions.push(sup);
+          self.report.supersessions += 1;
+        }
+      }
+      Record::Retraction(ret) => {
+        let event = LifecycleEvent::Retraction(ret.clone());
+        if self.transition(event).await? {
+          self.history.retractions.push(ret);
+          self.report.retractions += 1;
+        }
+      }
+      Record::Reinstatement(rein) => {
+        let event = LifecycleEvent::Reinstatement(rein.clone());
+        if self.transition(event).await? {
+          self.history.reinstatements.push(rein);
+          self.report.reinstatements += 1;
+        }
+      }
+      Record::Membership(event) => self.add_membership(event).await?,
+      Record::Merge(merge) => self.add_merge(merge).await?,
+    }
+    Ok(())
+  }
+
+  /// Check that lifecycle `event` can be written: `Ok(true)` if it should
+  /// be, `Ok(false)` if it was already imported or the store already holds
+  /// it.
+  async fn transition(
+    &mut self,
+    event: LifecycleEvent,
+  ) -> Result<bool, Rejection<S::Error>> {
+    let (fact_id, record_id) = (event.fact_id(), event.id());
+    if let Some(held) = self.events.get(&record_id) {
+      unchanged("lifecycle event", record_id, held, &event)?;
+      self.report.skipped += 1;
+      return Ok(false);
+    }
+    let stored = if self.facts.contains_key(&fact_id) {
+      None
+    } else {
+      let Some(rf) = self.stored_fact(fact_id).await? else {
+        return Err(Rejection::Invalid(format!("unknown fact {fact_id}")));
+      };
+      if let Some(held) = self
+        .stored_history(rf.fact.subject_id)
+        .await?
+        .lifecycle_events()
+        .into_iter()
+        .find(|held| held.id() == record_id)
+      {
+        unchanged("lifecycle event", record_id, &held, &event)?;
+        self.report.skipped += 1;
+        return Ok(false);
+      }
+      Some(rf)
+    };
+
+    let reinstates = matches!(event, LifecycleEvent::Reinstatement(_));
+    let active = match self.active.get(&fact_id) {
+      Some(active) => *active,
+      None => stored.is_none_or(|rf| rf.status.is_active()),
+    };
+    match (active, reinstates) {
+      (true, true) => return Err(not_ended(fact_id)),
+      (false, false) => return Err(already_ended(fact_id)),
+      _ => {}
+    }
+    self.active.insert(fact_id, reinstates);
+    self.events.insert(record_id, event);
+    Ok(true)
+  }
+
+  async fn add_addressbook(
+    &mut self,
+    book: AddressBook,
+  ) -> Result<(), Rejection<S::Error>> {
+    let id = book.addressbook_id;
+    if let Some((held, _)) = self.books.get(&id) {
+      unchanged(
+        "address book",
+        id,
+        &without_default(held),
+        &without_default(&book),
+      )?;
+      self.report.skipped += 1;
+      return Ok(());
+    }
+
+    let stored = self.stored_books().await?;
+    let target = match stored.iter().find(|b| b.addressbook_id == id) {
+      Some(held) => {
+        unchanged(
+          "address book",
+          id,
+          &without_default(held),
+          &without_default(&book),
+        )?;
+        Some(id)
+      }
+      None => stored
+        .iter()
+        .find(|b| b.name == book.name)
+        .map(|b| b.addressbook_id),
+    };
+    match target {
+      Some(_) => self.report.skipped += 1,
+      None if self.history.addressbooks.iter().any(|b| b.name == book.name) => {
+        return Err(Rejection::Invalid(format!(
+          "address book name {:?} is used twice",
+          book.name
+        )));
+      }
+      None => {
+        self.history.addressbooks.push(without_default(&book));
+        self.report.addressbooks += 1;
+      }
+    }
+    self.books.insert(id, (book, target.unwrap_or(id)));
+    Ok(())
+  }
+
+  async fn add_membership(
+    &mut self,
+    mut event: MembershipEvent,
+  ) -> Result<(), Rejection<S::Error>> {
+    let book = event.addressbook_id;
+    let target = self.books.get(&book).map(|(_, target)| *target);
+    event.addressbook_id = match target {
+      Some(target) => target,
+      None if self
+        .stored_books()
+        .await?
+        .iter()
+        .any(|b| b.addressbook_id == book) =>
+      {
+        book
+      }
+      None => {
+        return Err(Rejection::Invalid(format!(
+          "membership event refers to unknown address book {book}"
+        )));
+      }
+    };
+    self
+      .check_subject(event.subject_id, || "membership event".into())
+      .await?;
+
+    if self.memberships.contains(&event)
+      || self
+        .stored_history(event.subject_id)
+        .await?
+        .memberships
+        .contains(&event)
+    {
+      self.report.skipped += 1;
+    } else {
+      self.memberships.insert(event.clone());
+      self.history.memberships.push(event);
+      self.report.memberships += 1;
+    }
+    Ok(())
+  }
+
+  async fn add_merge(
+    &mut self,
+    merge: SubjectMerge,
+  ) -> Result<(), Rejection<S::Error>> {
+    let (survivor, absorbed) = (merge.survivor_id, merge.absorbed_id);
+    for id in [survivor, absorbed] {
+      self.check_subject(id, || "merge".into()).await?;
+    }
+    if self.merges.contains(&merge)
+      || self.stored_history(absorbed).await?.merges.contains(&merge)
+    {
+      self.report.skipped += 1;
+      return Ok(());
+    }
+
+    match merge.action {
+      MergeAction::Merge => {
+        if survivor == absorbed {
+          return Err(Rejection::Invalid(format!(
+            "subject {survivor} cannot be merged into itself"
+          )));
+        }
+        for id in [survivor, absorbed] {
+          if let Some(into) = self.merged_into(id).await? {
+            return Err(Rejection::Invalid(format!(
+              "subject {id} is already merged into {into}"
+            )));
+          }
+        }
+      }
+      MergeAction::Unmerge => {
+        if self.merged_into(absorbed).await? != Some(survivor) {
+          return Err(Rejection::Invalid(format!(
+            "subject {absorbed} is not merged into {survivor}"
+          )));
+        }
+      }
+    }
+    let into = (merge.action == MergeAction::Merge).then_some(survivor);
+    self.merged.insert(absorbed, into);
+    self.merges.insert(merge.clone());
+    self.history.merges.push(merge);
+    self.report.merges += 1;
+    Ok(())
+  }
+
+  /// Fail unless subject `id`, referred to by what `referrer` names, is in
+  /// the store or earlier in the document.
+  async fn check_subject(
+    &self,
+    id: Uuid,
+    referrer: impl FnOnce() -> String,
+  ) -> Result<(), Rejection<S::Error>> {
+    if self.subjects.contains_key(&id) || self.stored_subject(id).await? {
+      return Ok(());
+    }
+    Err(Rejection::Invalid(format!(
+      "{} belongs to unknown subject {id}",
+      referrer()
+    )))
+  }
+
+  /// The subject `id` is merged into after the merges in the store and
+  /// earlier in the document, if any.
+  async fn merged_into(
+    &mut self,
+    id: Uuid,
+  ) -> Result<Option<Uuid>, Rejection<S::Error>> {
+    if let Some(into) = self.merged.get(&id) {
+      return Ok(*into);
+    }
+    let latest = self
+      .stored_history(id)
+      .await?
+      .merges
+      .iter()
+      .rev()
+      .find(|merge| merge.absorbed_id == id);
+    Ok(
+      latest
+        .filter(|merge| merge.action == MergeAction::Merge)
+        .map(|merge| merge.survivor_id),
+    )
+  }
+
+  async fn stored_subject(
+    &self,
+    id: Uuid,
+  ) -> Result<bool, Rejection<S::Error>> {
+    let subject = self.store.get_subject(id).await.map_err(Rejection::Store)?;
+    Ok(subject.is_some())
+  }
+
+  /// Everything the store holds about `subject_id`.
+  async fn stored_history(
+    &mut self,
+    subject_id: Uuid,
+  ) -> Result<&History, Rejection<S::Error>> {
+    if !self.stored.contains_key(&subject_id) {
+      let history = self
+        .store
+        .history(Some(subject_id))
+        .await
+        .map_err(Rejection::Store)?;
+      self.stored.insert(subject_id, history);
+    }
+    Ok(&self.stored[&subject_id])
+  }
+
+  async fn stored_books(
+    &mut self,
+  ) -> Result<&[AddressBook], Rejection<S::Error>> {
+    if self.stored_books.is_none() {
+      let books =
+        self.store.list_addressbooks().await.map_err(Rejection::Store)?;
+      self.stored_books = Some(books);
+    }
+    Ok(self.stored_books.as_deref().unwrap_or_default())
+  }
+
+  async fn stored_fact(
+    &self,
+    id: Uuid,
+  ) -> Result<Option<ResolvedFact>, Rejection<S::Error>> {
+    self.store.get_fact(id).await.map_err(Rejection::Store)
+  }
+}
@@ -3,9 +3,16 @@
 //! This crate is deliberately free of HTTP and database dependencies.
 //! All other crates depend on it; it depends on nothing proprietary.
 
+pub mod addressbook;
+pub mod app_password;
+pub mod duplicates;
 pub mod error;
+pub mod event;
 pub mod fact;
+pub mod history;
 pub mod lifecycle;
+pub mod resource;
+pub mod similarity;
 pub mod store;
 pub mod subject;
 
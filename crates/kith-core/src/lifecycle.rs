@@ -1,8 +1,14 @@
 //! Lifecycle events and resolved fact types.
 //!
-//! Facts are immutable. Their lifecycle (supersession and retraction) is
-//! tracked in two separate append-only tables. A fact's current status is
-//! computed at query time by joining against those tables.
+//! Facts are immutable. Their lifecycle (supersession, retraction and
+//! reinstatement) is tracked in separate append-only tables. A fact's current
+//! status is computed at query time: the latest event to be written wins,
+//! whatever its timestamp, so a reinstated fact is active again and may later
+//! be superseded or retracted anew.
+//!
+//! Subjects have a lifecycle of their own: one can be merged into another
+//! (and later unmerged), recorded as [`SubjectMerge`] events. Merging never
+//! rewrites a fact's `subject_id`; reads follow the merges instead.
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -13,8 +19,8 @@ use crate::{fact::Fact, subject::Subject};
 // ─── Lifecycle event records ─────────────────────────────────────────────────
 
 /// Records that an old fact has been replaced by a newer, corrected version.
-/// A fact can be superseded at most once (enforced by a UNIQUE constraint).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Only an active fact can be superseded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Supersession {
   pub supersession_id: Uuid,
   pub old_fact_id:     Uuid,
@@ -23,8 +29,8 @@ pub struct Supersession {
 }
 
 /// Records that a fact has been withdrawn entirely, with no replacement.
-/// A fact can be retracted at most once (enforced by a UNIQUE constraint).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Only an active fact can be retracted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Retraction {
   pub retraction_id: Uuid,
   pub fact_id:       Uuid,
@@ -32,9 +38,85 @@ pub struct Retraction {
   pub recorded_at:   DateTime<Utc>,
 }
 
+/// Records that a superseded or retracted fact is active again. The
+/// replacement of a superseded fact is left as it is.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Reinstatement {
+  pub reinstatement_id: Uuid,
+  pub fact_id:          Uuid,
+  pub recorded_at:      DateTime<Utc>,
+}
+
+/// Any one lifecycle event of a fact.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LifecycleEvent {
+  Supersession(Supersession),
+  Retraction(Retraction),
+  Reinstatement(Reinstatement),
+}
+
+impl LifecycleEvent {
+  /// The ID of the event record.
+  pub fn id(&self) -> Uuid {
+    match self {
+      Self::Supersession(sup) => sup.supersession_id,
+      Self::Retraction(ret) => ret.retraction_id,
+      Self::Reinstatement(rein) => rein.reinstatement_id,
+    }
+  }
+
+  /// The fact the event ends or reinstates.
+  pub fn fact_id(&self) -> Uuid {
+    match self {
+      Self::Supersession(sup) => sup.old_fact_id,
+      Self::Retraction(ret) => ret.fact_id,
+      Self::Reinstatement(rein) => rein.fact_id,
+    }
+  }
+
+  pub fn recorded_at(&self) -> DateTime<Utc> {
+    match self {
+      Self::Supersession(sup) => sup.recorded_at,
+      Self::Retraction(ret) => ret.recorded_at,
+      Self::Reinstatement(rein) => rein.recorded_at,
+    }
+  }
+
+  /// The order in which events take effect: by time, with a reinstatement
+  /// after any event recorded at the same instant (it can only follow one).
+  pub fn sort_key(&self) -> (DateTime<Utc>, bool) {
+    (self.recorded_at(), matches!(self, Self::Reinstatement(_)))
+  }
+}
+
+/// What a [`SubjectMerge`] event does.
+#[derive(
+  Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum MergeAction {
+  Merge,
+  Unmerge,
+}
+
+/// Records that `absorbed_id` was merged into `survivor_id`, or split back
+/// out of it.
+///
+/// While merged, the absorbed subject's facts are read as the survivor's and
+/// lookups of the absorbed ID resolve to the survivor. A subject is merged
+/// while its latest event as `absorbed_id` is a [`MergeAction::Merge`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct SubjectMerge {
+  pub survivor_id: Uuid,
+  pub absorbed_id: Uuid,
+  pub action:      MergeAction,
+  pub recorded_at: DateTime<Utc>,
+}
+
 // ─── Computed status ─────────────────────────────────────────────────────────
 
-/// The lifecycle status of a fact, computed at query time.
+/// The lifecycle status of a fact, computed at query time from its latest
+/// lifecycle event.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "status", rename_all = "snake_case")]
 pub enum FactStatus {
@@ -67,8 +149,13 @@ pub struct ResolvedFact {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContactView {
   pub subject:      Subject,
-  /// The point in time at which this view was materialised.
+  /// The point in time at which this view was materialised (transaction
+  /// time: what the store knew).
   pub as_of:        DateTime<Utc>,
-  /// All facts with [`FactStatus::Active`] status as of `as_of`.
+  /// The real-world instant the view describes (valid time), if the view was
+  /// restricted to facts effective at that instant.
+  pub valid_at:     Option<DateTime<Utc>>,
+  /// All facts with [`FactStatus::Active`] status as of `as_of` (and, if set,
+  /// effective at `valid_at`).
   pub active_facts: Vec<ResolvedFact>,
 }
@@ -0,0 +1,33 @@
+//! CardDAV resources — how clients name subjects.
+//!
+//! A CardDAV client picks both the href of a contact it creates and the
+//! vCard `UID` inside it, and expects to see them again unchanged. Neither
+//! need be a UUID, so the store remembers them per subject rather than
+//! deriving them from the `subject_id`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The client-facing identity of a subject. Recorded once, when a client
+/// first writes the subject, and never changed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Resource {
+  pub subject_id:  Uuid,
+  /// Last href segment, e.g. `alice@example.com.vcf`; unique across the
+  /// store.
+  pub name:        String,
+  /// The vCard `UID` property, verbatim; unique across the store.
+  pub uid:         Option<String>,
+  pub recorded_at: DateTime<Utc>,
+}
+
+/// Input for [`ContactStore::record_resource`].
+///
+/// [`ContactStore::record_resource`]: crate::store::ContactStore::record_resource
+#[derive(Debug, Clone)]
+pub struct NewResource {
+  pub subject_id: Uuid,
+  pub name:       String,
+  pub uid:        Option<String>,
+}
@@ -0,0 +1,57 @@
+//! String normalisation and fuzzy comparison, shared by vCard diffing and
+//! duplicate detection.
+
+/// Normalised similarity of two strings: `1.0` for equal strings, falling
+/// towards `0.0` as the Levenshtein distance approaches the longer length.
+pub fn similarity(a: &str, b: &str) -> f64 {
+  let len = a.chars().count().max(b.chars().count());
+  if len == 0 {
+    return 1.0;
+  }
+  1.0 - levenshtein(a, b) as f64 / len as f64
+}
+
+/// Levenshtein edit distance over `char`s.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut prev: Vec<usize> = (0..=b.len()).collect();
+  let mut cur = vec![0; b.len() + 1];
+  for (i, ca) in a.chars().enumerate() {
+    cur[0] = i + 1;
+    for (j, cb) in b.iter().enumerate() {
+      let substitution = prev[j] + usize::from(ca != *cb);
+      cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+    }
+    std::mem::swap(&mut prev, &mut cur);
+  }
+  prev[b.len()]
+}
+
+/// A phone number with whitespace and dashes removed.
+pub fn normalize_phone(s: &str) -> String {
+  s.chars()
+    .filter(|c| !c.is_whitespace() && *c != '-')
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn levenshtein_counts_edits() {
+    assert_eq!(levenshtein("", ""), 0);
+    assert_eq!(levenshtein("abc", ""), 3);
+    assert_eq!(levenshtein("kitten", "sitting"), 3);
+    assert_eq!(levenshtein("alcie", "alice"), 2);
+    assert_eq!(
+      similarity("http://a.dev", "https://a.dev"),
+      1.0 - 1.0 / 13.0
+    );
+  }
+
+  #[test]
+  fn phone_numbers_ignore_spacing() {
+    assert_eq!(normalize_phone("+1 555-123 4567"), "+15551234567");
+  }
+}
@@ -7,11 +7,21 @@
 use std::future::Future;
 
 use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::{
-  fact::{Confidence, NewFact},
-  lifecycle::{ContactView, ResolvedFact, Retraction, Supersession},
+  addressbook::{AddressBook, AddressBookChanges, NewAddressBook, SyncToken},
+  app_password::{AppPassword, NewAppPassword},
+  event::{EventId, StoreEvent},
+  fact::{Confidence, Fact, NewFact},
+  history::History,
+  lifecycle::{
+    ContactView, Reinstatement, ResolvedFact, Retraction, SubjectMerge,
+    Supersession,
+  },
+  resource::{NewResource, Resource},
   subject::{Subject, SubjectKind},
 };
 
@@ -20,7 +30,11 @@ use crate::{
 /// Parameters for [`ContactStore::search`].
 #[derive(Debug, Clone, Default)]
 pub struct FactQuery {
-  /// Free-text filter applied over serialised fact values.
+  /// Full-text filter over [`FactValue::search_text`] renderings. Bare terms
+  /// must match whole words, `term*` matches a prefix and `"a phrase"`
+  /// matches consecutive words. Results are ranked by relevance.
+  ///
+  /// [`FactValue::search_text`]: crate::fact::FactValue::search_text
   pub text:            Option<String>,
   /// Restrict to subjects of a specific kind.
   pub kind:            Option<SubjectKind>,
@@ -28,20 +42,95 @@ pub struct FactQuery {
   pub fact_types:      Vec<String>,
   /// All returned subjects must have facts with all of these tags.
   pub tags:            Vec<String>,
+  /// Restrict to facts recorded with this confidence level.
   pub confidence:      Option<Confidence>,
+  /// Restrict to facts recorded strictly after this instant.
   pub recorded_after:  Option<DateTime<Utc>>,
+  /// Restrict to facts recorded strictly before this instant.
   pub recorded_before: Option<DateTime<Utc>>,
+  /// If `true`, superseded and retracted facts are ignored.
+  pub active_only:     bool,
   pub limit:           Option<usize>,
   pub offset:          Option<usize>,
 }
 
+// ─── Changeset ───────────────────────────────────────────────────────────────
+
+/// A batch of writes for [`ContactStore::apply_changeset`], applied
+/// all-or-nothing.
+///
+/// Besides facts and their lifecycle, a changeset can carry the envelope a
+/// write needs — a new subject, its CardDAV resource, address book
+/// membership — so that a failed write leaves none of it behind. Writes are
+/// applied in field order.
+#[derive(Debug, Clone, Default)]
+pub struct Changeset {
+  /// `(subject_id, kind)` pairs to create.
+  pub new_subjects:         Vec<(Uuid, SubjectKind)>,
+  /// CardDAV resources to record, as with [`ContactStore::record_resource`].
+  pub resources:            Vec<NewResource>,
+  /// `(addressbook_id, subject_id)` pairs to add to an address book.
+  pub addressbook_adds:     Vec<(Uuid, Uuid)>,
+  /// Facts to record.
+  pub new_facts:            Vec<NewFact>,
+  /// `(old_fact_id, replacement)` pairs to supersede.
+  pub supersessions:        Vec<(Uuid, NewFact)>,
+  /// `(fact_id, reason)` pairs to retract.
+  pub retractions:          Vec<(Uuid, Option<String>)>,
+  /// Superseded or retracted facts to make active again.
+  pub reinstatements:       Vec<Uuid>,
+  /// `(addressbook_id, subject_id)` pairs to remove from an address book.
+  pub addressbook_removals: Vec<(Uuid, Uuid)>,
+}
+
+impl Changeset {
+  /// `true` if applying the changeset would write nothing.
+  pub fn is_empty(&self) -> bool {
+    self.new_subjects.is_empty()
+      && self.resources.is_empty()
+      && self.addressbook_adds.is_empty()
+      && self.new_facts.is_empty()
+      && self.supersessions.is_empty()
+      && self.retractions.is_empty()
+      && self.reinstatements.is_empty()
+      && self.addressbook_removals.is_empty()
+  }
+}
+
+/// Everything written by a successful [`ContactStore::apply_changeset`].
+///
+/// All records share a single `recorded_at` timestamp.
+#[derive(Debug, Clone)]
+pub struct AppliedChangeset {
+  /// Recorded facts: `new_facts` first, then supersession replacements, each
+  /// in changeset order.
+  pub facts:          Vec<Fact>,
+  pub supersessions:  Vec<Supersession>,
+  pub retractions:    Vec<Retraction>,
+  pub reinstatements: Vec<Reinstatement>,
+}
+
+// ─── Import sources ──────────────────────────────────────────────────────────
+
+/// The facts recorded from one import source: every fact whose
+/// [`RecordingContext`](crate::fact::RecordingContext) is `Imported` with
+/// this `source_name`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ImportSource {
+  pub source_name:  String,
+  /// Facts recorded from the source, whatever their status.
+  pub facts:        usize,
+  /// Those of them that are still active.
+  pub active_facts: usize,
+}
+
 // ─── Trait ───────────────────────────────────────────────────────────────────
 
 /// Abstraction over a Kith contact store backend.
 ///
 /// All write operations on facts are append-only. Mutations are expressed as
-/// lifecycle events (supersession, retraction), which are themselves
-/// append-only.
+/// lifecycle events (supersession, retraction, reinstatement), which are
+/// themselves append-only.
 ///
 /// All methods return `Send` futures so the trait can be used in multi-threaded
 /// async runtimes (e.g. tokio with `axum`).
@@ -67,12 +156,16 @@ pub trait ContactStore: Send + Sync {
   ) -> impl Future<Output = Result<Subject, Self::Error>> + Send + '_;
 
   /// Retrieve a subject by UUID. Returns `None` if not found.
+  ///
+  /// The ID of a subject merged into another resolves to the subject it was
+  /// merged into (see [`merge_subjects`](Self::merge_subjects)).
   fn get_subject(
     &self,
     id: Uuid,
   ) -> impl Future<Output = Result<Option<Subject>, Self::Error>> + Send + '_;
 
-  /// List all subjects, optionally filtered by kind.
+  /// List all subjects, optionally filtered by kind. Subjects merged into
+  /// another are left out.
   fn list_subjects(
     &self,
     kind: Option<SubjectKind>,
@@ -91,7 +184,7 @@ pub trait ContactStore: Send + Sync {
 
   /// Record that an existing fact is superseded by a new (replacement) fact.
   ///
-  /// Returns an error if `old_id` is already superseded or retracted, or if
+  /// Returns an error if `old_id` is currently superseded or retracted, or if
   /// `old_id == replacement.fact_id` (self-supersession).
   fn supersede(
     &self,
@@ -103,13 +196,85 @@ pub trait ContactStore: Send + Sync {
 
   /// Retract a fact entirely (no replacement).
   ///
-  /// Returns an error if the fact is already superseded or retracted.
+  /// Returns an error if the fact is currently superseded or retracted.
   fn retract(
     &self,
     fact_id: Uuid,
     reason: Option<String>,
   ) -> impl Future<Output = Result<Retraction, Self::Error>> + Send + '_;
 
+  /// Make a superseded or retracted fact active again, undoing its latest
+  /// lifecycle event. A superseded fact's replacement stays active too.
+  ///
+  /// Returns an error if the fact is already active.
+  fn reinstate(
+    &self,
+    fact_id: Uuid,
+  ) -> impl Future<Output = Result<Reinstatement, Self::Error>> + Send + '_;
+
+  /// Apply a [`Changeset`] atomically: either every subject, resource,
+  /// membership change, fact, supersession, retraction and reinstatement is
+  /// recorded, or none is. They are written in field order, so a changeset
+  /// may reinstate a fact it retracts, or record facts about a subject it
+  /// creates.
+  ///
+  /// Fails with the same errors as the individual operations would (e.g. a
+  /// fact that is already superseded or retracted, including by an earlier
+  /// entry in the same changeset), in which case nothing is written.
+  /// Membership changes that would change nothing are skipped, as with
+  /// [`add_to_addressbook`](Self::add_to_addressbook).
+  fn apply_changeset(
+    &self,
+    changeset: Changeset,
+  ) -> impl Future<Output = Result<AppliedChangeset, Self::Error>> + Send + '_;
+
+  // ── Import sources ────────────────────────────────────────────────────
+
+  /// List every import source that facts were recorded from, ordered by
+  /// name.
+  fn import_sources(
+    &self,
+  ) -> impl Future<Output = Result<Vec<ImportSource>, Self::Error>> + Send + '_;
+
+  /// The still-active facts recorded from `source_name`, in log order:
+  /// what [`retract_import`](Self::retract_import) would retract.
+  fn import_facts<'a>(
+    &'a self,
+    source_name: &'a str,
+  ) -> impl Future<Output = Result<Vec<ResolvedFact>, Self::Error>> + Send + 'a;
+
+  /// Retract every still-active fact recorded from `source_name` in a
+  /// single transaction, all with the same `reason` and timestamp. Returns
+  /// the retractions in log order; an unknown source retracts nothing.
+  fn retract_import(
+    &self,
+    source_name: String,
+    reason: Option<String>,
+  ) -> impl Future<Output = Result<Vec<Retraction>, Self::Error>> + Send + '_;
+
+  // ── Subject merges ────────────────────────────────────────────────────
+
+  /// Merge `absorbed` into `survivor`.
+  ///
+  /// No fact is rewritten: from now on the absorbed subject's facts are read
+  /// as the survivor's, and its ID resolves to the survivor. Both subjects
+  /// must exist and neither may currently be merged into another; the
+  /// survivor may itself have absorbed others.
+  fn merge_subjects(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+  ) -> impl Future<Output = Result<SubjectMerge, Self::Error>> + Send + '_;
+
+  /// Undo the current merge of `absorbed` into `survivor`, so both read as
+  /// they would had it never happened. Returns an error if `absorbed` is not
+  /// currently merged into `survivor`.
+  fn unmerge_subjects(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+  ) -> impl Future<Output = Result<SubjectMerge, Self::Error>> + Send + '_;
+
   // ── Reads ─────────────────────────────────────────────────────────────
 
   /// Retrieve a single fact by its UUID, with lifecycle status resolved.
@@ -120,38 +285,260 @@ pub trait ContactStore: Send + Sync {
     id: Uuid,
   ) -> impl Future<Output = Result<Option<ResolvedFact>, Self::Error>> + Send + '_;
 
+  /// The version chain through fact `id`, oldest first: the facts it
+  /// replaced, back to the original, then each fact that currently
+  /// supersedes the one before, up to the head — the version that is not
+  /// superseded. Statuses are resolved as in [`get_fact`](Self::get_fact).
+  ///
+  /// A fact reinstated after being superseded is a head again; the
+  /// replacement it had is not followed.
+  ///
+  /// Returns an empty list if no fact with that ID exists.
+  fn fact_lineage(
+    &self,
+    id: Uuid,
+  ) -> impl Future<Output = Result<Vec<ResolvedFact>, Self::Error>> + Send + '_;
+
   /// Return all facts for a subject, with their lifecycle status resolved.
   ///
-  /// - `as_of`: point-in-time filter on `recorded_at`; defaults to now.
+  /// The two time axes are independent:
+  ///
+  /// - `as_of` (transaction time): only facts and lifecycle events recorded at
+  ///   or before this instant are considered, so statuses reflect what the
+  ///   store believed then. Defaults to now.
+  /// - `valid_at` (valid time): if set, only facts whose `effective_at` /
+  ///   `effective_until` range covers this instant are returned (see
+  ///   [`Fact::is_valid_at`](crate::fact::Fact::is_valid_at)).
   /// - `include_inactive`: if `false`, only `Active` facts are returned.
+  ///
+  /// "What was true in 2019, as known today" is `valid_at = 2019`, `as_of =
+  /// None`; "what did I believe in 2021 about 2019" is `valid_at = 2019`,
+  /// `as_of = 2021`.
+  ///
+  /// Merges are followed as they stood at `as_of`: the facts of every
+  /// subject merged into `subject_id` are included, and an absorbed
+  /// `subject_id` gives its survivor's facts.
   fn get_facts(
     &self,
     subject_id: Uuid,
     as_of: Option<DateTime<Utc>>,
+    valid_at: Option<DateTime<Utc>>,
     include_inactive: bool,
   ) -> impl Future<Output = Result<Vec<ResolvedFact>, Self::Error>> + Send + '_;
 
   /// Materialise a [`ContactView`] — the computed, current-state read model
   /// for a subject. Returns `None` if the subject does not exist.
+  ///
+  /// `as_of` and `valid_at` have the same meaning as in
+  /// [`get_facts`](Self::get_facts). The view of an absorbed subject is that
+  /// of the subject it had been merged into at `as_of`.
   fn materialize(
     &self,
     subject_id: Uuid,
     as_of: Option<DateTime<Utc>>,
+    valid_at: Option<DateTime<Utc>>,
   ) -> impl Future<Output = Result<Option<ContactView>, Self::Error>> + Send + '_;
 
-  /// Search for subjects matching `query`. Phase-1 implementation uses SQL
-  /// LIKE; phase 2 will use FTS5.
+  /// Search for subjects matching `query`.
+  ///
+  /// Fact-level filters (`text`, `fact_types`, `tags`, `confidence`, the
+  /// `recorded_*` bounds and `active_only`) must all be satisfied by the
+  /// *same* fact. When none are set, every subject (of `kind`, if given)
+  /// matches, including subjects with no facts.
+  ///
+  /// A subject merged into another is never returned; its facts match on
+  /// behalf of the subject that absorbed it.
   fn search<'a>(
     &'a self,
     query: &'a FactQuery,
   ) -> impl Future<Output = Result<Vec<Subject>, Self::Error>> + Send + 'a;
 
-  /// Return the most recent mutation timestamp across all Person-kind facts and
-  /// their retractions, or `None` if the addressbook is empty.
+  // ── Address books ─────────────────────────────────────────────────────
+
+  /// Create and persist a new, non-default address book.
+  ///
+  /// Returns an error if the name is already taken.
+  fn create_addressbook(
+    &self,
+    input: NewAddressBook,
+  ) -> impl Future<Output = Result<AddressBook, Self::Error>> + Send + '_;
+
+  /// Retrieve an address book by name. Returns `None` if not found.
+  fn get_addressbook<'a>(
+    &'a self,
+    name: &'a str,
+  ) -> impl Future<Output = Result<Option<AddressBook>, Self::Error>> + Send + 'a;
+
+  /// List all address books, ordered by name.
+  fn list_addressbooks(
+    &self,
+  ) -> impl Future<Output = Result<Vec<AddressBook>, Self::Error>> + Send + '_;
+
+  /// Make `addressbook_id` the default book, clearing the flag on any other.
+  fn set_default_addressbook(
+    &self,
+    addressbook_id: Uuid,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send + '_;
+
+  /// Add a subject to an address book. A no-op if it is already a member.
+  fn add_to_addressbook(
+    &self,
+    addressbook_id: Uuid,
+    subject_id: Uuid,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send + '_;
+
+  /// Remove a subject from an address book. A no-op if it is not a member.
+  ///
+  /// Removing a subject from the default book also takes it out of the
+  /// "never assigned" set, so it does not reappear there implicitly.
+  fn remove_from_addressbook(
+    &self,
+    addressbook_id: Uuid,
+    subject_id: Uuid,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send + '_;
+
+  /// List the subjects in an address book, including (for the default book)
+  /// person subjects never assigned to any book.
+  fn addressbook_members(
+    &self,
+    addressbook_id: Uuid,
+  ) -> impl Future<Output = Result<Vec<Subject>, Self::Error>> + Send + '_;
+
+  /// List the address books a subject currently belongs to, by the same
+  /// rules as [`addressbook_members`](Self::addressbook_members).
+  fn subject_addressbooks(
+    &self,
+    subject_id: Uuid,
+  ) -> impl Future<Output = Result<Vec<AddressBook>, Self::Error>> + Send + '_;
+
+  /// Return the store's current position in its change history.
+  fn sync_token(
+    &self,
+  ) -> impl Future<Output = Result<SyncToken, Self::Error>> + Send + '_;
+
+  /// List the members of an address book that changed after `since`, or all
+  /// visible members if `since` is `None` (an initial sync).
+  ///
+  /// The returned token covers exactly the changes reported, so passing it
+  /// back as `since` never skips or repeats a write. A `since` that is ahead
+  /// of the store's current token is an error.
+  fn addressbook_changes(
+    &self,
+    addressbook_id: Uuid,
+    since: Option<SyncToken>,
+  ) -> impl Future<Output = Result<AddressBookChanges, Self::Error>> + Send + '_;
+
+  /// Return the most recent mutation timestamp across the facts, retractions
+  /// and membership changes of an address book's members (and any subject
+  /// merge), or `None` if nothing has ever happened in it.
   ///
   /// Used to derive a `getctag` value for collection-level change detection.
   /// Clients compare the opaque token; when it changes, they re-sync.
   fn collection_ctag(
     &self,
+    addressbook_id: Uuid,
   ) -> impl Future<Output = Result<Option<DateTime<Utc>>, Self::Error>> + Send + '_;
+
+  // ── Change events ─────────────────────────────────────────────────────
+
+  /// Subscribe to the events of every write to the fact log from now on, in
+  /// the order they were committed. A receiver that falls behind gets
+  /// [`broadcast::error::RecvError::Lagged`] and should catch up with
+  /// [`events_since`](Self::events_since).
+  fn subscribe(&self) -> broadcast::Receiver<StoreEvent>;
+
+  /// Replay up to `limit` events recorded after position `after`, oldest
+  /// first. Each log is replayed in order, so resuming from the id of any
+  /// returned event never skips or repeats one.
+  fn events_since(
+    &self,
+    after: EventId,
+    limit: usize,
+  ) -> impl Future<Output = Result<Vec<StoreEvent>, Self::Error>> + Send + '_;
+
+  // ── History ───────────────────────────────────────────────────────────
+
+  /// Everything recorded about one subject, or about every subject if
+  /// `subject_id` is `None`: the subjects, their facts, the supersessions,
+  /// retractions and reinstatements of those facts, their membership events
+  /// and the address books those refer to, and the merges they took part
+  /// in. One subject's history also holds the subjects it was merged with,
+  /// but not their facts. A subject that does not exist gives an empty
+  /// history.
+  fn history(
+    &self,
+    subject_id: Option<Uuid>,
+  ) -> impl Future<Output = Result<History, Self::Error>> + Send + '_;
+
+  /// Write `history` verbatim, keeping its IDs and timestamps, all-or-nothing.
+  ///
+  /// Every record must be new. Whatever a record refers to (a fact's
+  /// subject, a lifecycle event's facts, a membership event's address book)
+  /// must exist in the store or in `history`, and lifecycle events fail as in
+  /// [`apply_changeset`](Self::apply_changeset). Facts are written before
+  /// lifecycle events, which are written in the order of
+  /// [`History::lifecycle_events`], and published like any other write.
+  /// Membership events and merges follow, in their order in `history`.
+  fn restore(
+    &self,
+    history: History,
+  ) -> impl Future<Output = Result<(), Self::Error>> + Send + '_;
+
+  // ── CardDAV resources ─────────────────────────────────────────────────
+
+  /// Record the href name and vCard `UID` a client gave a subject.
+  ///
+  /// Write-once: recording exactly the existing resource again is a no-op,
+  /// anything else for a subject that already has one is an error, as is a
+  /// name or UID that belongs to another subject.
+  fn record_resource(
+    &self,
+    input: NewResource,
+  ) -> impl Future<Output = Result<Resource, Self::Error>> + Send + '_;
+
+  /// Look up a resource by its href name. Returns `None` if not found.
+  fn resource_by_name<'a>(
+    &'a self,
+    name: &'a str,
+  ) -> impl Future<Output = Result<Option<Resource>, Self::Error>> + Send + 'a;
+
+  /// Look up a resource by its vCard `UID`. Returns `None` if not found.
+  fn resource_by_uid<'a>(
+    &'a self,
+    uid: &'a str,
+  ) -> impl Future<Output = Result<Option<Resource>, Self::Error>> + Send + 'a;
+
+  /// The resource recorded for a subject, if a client ever wrote it.
+  fn subject_resource(
+    &self,
+    subject_id: Uuid,
+  ) -> impl Future<Output = Result<Option<Resource>, Self::Error>> + Send + '_;
+
+  // ── App passwords ─────────────────────────────────────────────────────
+
+  /// Store a new, active app password.
+  fn create_app_password(
+    &self,
+    input: NewAppPassword,
+  ) -> impl Future<Output = Result<AppPassword, Self::Error>> + Send + '_;
+
+  /// All app passwords, revoked ones included, oldest first.
+  fn list_app_passwords(
+    &self,
+  ) -> impl Future<Output = Result<Vec<AppPassword>, Self::Error>> + Send + '_;
+
+  /// Revoke an app password. Revoking it again keeps the original
+  /// `revoked_at`. Returns `None` if no such password exists.
+  fn revoke_app_password(
+    &self,
+    app_password_id: Uuid,
+  ) -> impl Future<Output = Result<Option<AppPassword>, Self::Error>> + Send + '_;
+
+  /// Authenticate with an app password: find the active password whose
+  /// secret hashes to `secret_hash` and stamp its `last_used_at`. Returns
+  /// `None` if there is none.
+  fn use_app_password<'a>(
+    &'a self,
+    secret_hash: &'a str,
+  ) -> impl Future<Output = Result<Option<AppPassword>, Self::Error>> + Send + 'a;
 }
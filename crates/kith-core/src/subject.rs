@@ -22,7 +22,7 @@ pub enum SubjectKind {
 
 /// A thin envelope that owns a UUID and a creation timestamp.
 /// All meaningful information about the entity lives in its facts.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Subject {
   pub subject_id: Uuid,
   pub created_at: DateTime<Utc>,
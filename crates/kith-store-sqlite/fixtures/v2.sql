@@ -0,0 +1,72 @@
+-- A database as written by schema version 2: version 1 plus the full-text
+-- index, but no effective_start / effective_end columns.
+
+CREATE TABLE subjects (
+    subject_id  TEXT PRIMARY KEY,
+    created_at  TEXT NOT NULL,
+    kind        TEXT NOT NULL
+);
+
+CREATE TABLE facts (
+    fact_id           TEXT PRIMARY KEY,
+    subject_id        TEXT NOT NULL REFERENCES subjects(subject_id),
+    fact_type         TEXT NOT NULL,
+    value_json        TEXT NOT NULL,
+    recorded_at       TEXT NOT NULL,
+    effective_at      TEXT,
+    effective_until   TEXT,
+    source            TEXT,
+    confidence        TEXT NOT NULL DEFAULT 'certain',
+    recording_context TEXT NOT NULL DEFAULT '{"kind":"manual"}',
+    tags              TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE TABLE supersessions (
+    supersession_id TEXT PRIMARY KEY,
+    old_fact_id     TEXT NOT NULL REFERENCES facts(fact_id),
+    new_fact_id     TEXT NOT NULL REFERENCES facts(fact_id),
+    recorded_at     TEXT NOT NULL,
+    UNIQUE (old_fact_id),
+    CHECK  (old_fact_id != new_fact_id)
+);
+
+CREATE TABLE retractions (
+    retraction_id TEXT PRIMARY KEY,
+    fact_id       TEXT NOT NULL REFERENCES facts(fact_id),
+    reason        TEXT,
+    recorded_at   TEXT NOT NULL,
+    UNIQUE (fact_id)
+);
+
+CREATE INDEX facts_subject_idx  ON facts(subject_id);
+CREATE INDEX facts_type_idx     ON facts(fact_type);
+CREATE INDEX facts_recorded_idx ON facts(recorded_at);
+
+CREATE VIRTUAL TABLE facts_fts USING fts5(
+    fact_id    UNINDEXED,
+    subject_id UNINDEXED,
+    text,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO subjects VALUES
+  ('6f1c1c36-8d0e-4d8a-9a53-1d2b0c7f0a01', '2024-01-01T00:00:00+00:00', 'person');
+
+INSERT INTO facts
+  (fact_id, subject_id, fact_type, value_json, recorded_at, effective_until)
+VALUES (
+  '0b6a2f4e-3f5d-4a7c-8d8e-000000000002',
+  '6f1c1c36-8d0e-4d8a-9a53-1d2b0c7f0a01',
+  'org_membership',
+  '{"org_name":"Analytical Engines Ltd","org_id":null,"title":null,"role":null}',
+  '2024-01-01T00:00:00+00:00',
+  '{"kind":"date_only","value":"2020-06-30"}'
+);
+
+INSERT INTO facts_fts VALUES (
+  '0b6a2f4e-3f5d-4a7c-8d8e-000000000002',
+  '6f1c1c36-8d0e-4d8a-9a53-1d2b0c7f0a01',
+  'Analytical Engines Ltd'
+);
+
+PRAGMA user_version = 2;
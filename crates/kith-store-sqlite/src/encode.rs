@@ -7,8 +7,11 @@
 
 use chrono::{DateTime, Utc};
 use kith_core::{
+  addressbook::{AddressBook, MembershipEvent},
+  app_password::AppPassword,
   fact::{Confidence, EffectiveDate, Fact, FactValue, RecordingContext},
-  lifecycle::{FactStatus, ResolvedFact},
+  lifecycle::{FactStatus, ResolvedFact, SubjectMerge},
+  resource::Resource,
   subject::{Subject, SubjectKind},
 };
 use uuid::Uuid;
@@ -168,3 +171,151 @@ impl RawSubject {
     })
   }
 }
+
+/// Raw values read directly from an `addressbooks` row.
+pub struct RawAddressBook {
+  pub addressbook_id: String,
+  pub name:           String,
+  pub display_name:   Option<String>,
+  pub description:    Option<String>,
+  pub is_default:     bool,
+  pub created_at:     String,
+}
+
+impl RawAddressBook {
+  /// Columns in the order [`RawAddressBook::from_row`] expects.
+  pub const COLUMNS: &str =
+    "addressbook_id, name, display_name, description, is_default, created_at";
+
+  pub fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+    Ok(Self {
+      addressbook_id: row.get(0)?,
+      name:           row.get(1)?,
+      display_name:   row.get(2)?,
+      description:    row.get(3)?,
+      is_default:     row.get(4)?,
+      created_at:     row.get(5)?,
+    })
+  }
+
+  pub fn into_addressbook(self) -> Result<AddressBook> {
+    Ok(AddressBook {
+      addressbook_id: decode_uuid(&self.addressbook_id)?,
+      name:           self.name,
+      display_name:   self.display_name,
+      description:    self.description,
+      is_default:     self.is_default,
+      created_at:     decode_dt(&self.created_at)?,
+    })
+  }
+}
+
+/// Raw strings read directly from an `addressbook_membership` row.
+pub struct RawMembership {
+  pub addressbook_id: String,
+  pub subject_id:     String,
+  pub action:         String,
+  pub recorded_at:    String,
+}
+
+impl RawMembership {
+  pub fn into_event(self) -> Result<MembershipEvent> {
+    Ok(MembershipEvent {
+      addressbook_id: decode_uuid(&self.addressbook_id)?,
+      subject_id:     decode_uuid(&self.subject_id)?,
+      // The column holds the action's serde name.
+      action:         serde_json::from_value(self.action.into())?,
+      recorded_at:    decode_dt(&self.recorded_at)?,
+    })
+  }
+}
+
+/// Raw strings read directly from a `subject_merges` row.
+pub struct RawMerge {
+  pub survivor_id: String,
+  pub absorbed_id: String,
+  pub action:      String,
+  pub recorded_at: String,
+}
+
+impl RawMerge {
+  pub fn into_merge(self) -> Result<SubjectMerge> {
+    Ok(SubjectMerge {
+      survivor_id: decode_uuid(&self.survivor_id)?,
+      absorbed_id: decode_uuid(&self.absorbed_id)?,
+      // The column holds the action's serde name.
+      action:      serde_json::from_value(self.action.into())?,
+      recorded_at: decode_dt(&self.recorded_at)?,
+    })
+  }
+}
+
+/// Raw values read directly from a `carddav_resources` row.
+pub struct RawResource {
+  pub subject_id:  String,
+  pub name:        String,
+  pub uid:         Option<String>,
+  pub recorded_at: String,
+}
+
+impl RawResource {
+  /// Columns in the order [`RawResource::from_row`] expects.
+  pub const COLUMNS: &str = "subject_id, resource_name, vcard_uid, recorded_at";
+
+  pub fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+    Ok(Self {
+      subject_id:  row.get(0)?,
+      name:        row.get(1)?,
+      uid:         row.get(2)?,
+      recorded_at: row.get(3)?,
+    })
+  }
+
+  pub fn into_resource(self) -> Result<Resource> {
+    Ok(Resource {
+      subject_id:  decode_uuid(&self.subject_id)?,
+      name:        self.name,
+      uid:         self.uid,
+      recorded_at: decode_dt(&self.recorded_at)?,
+    })
+  }
+}
+
+/// Raw values read directly from an `app_passwords` row.
+pub struct RawAppPassword {
+  pub app_password_id: String,
+  pub label:           String,
+  pub created_at:      String,
+  pub last_used_at:    Option<String>,
+  pub revoked_at:      Option<String>,
+}
+
+impl RawAppPassword {
+  /// Columns in the order [`RawAppPassword::from_row`] expects.
+  pub const COLUMNS: &str =
+    "app_password_id, label, created_at, last_used_at, revoked_at";
+
+  pub fn from_row(row: &rusqlite::Row<'_>) -> rusqlite::Result<Self> {
+    Ok(Self {
+      app_password_id: row.get(0)?,
+      label:           row.get(1)?,
+      created_at:      row.get(2)?,
+      last_used_at:    row.get(3)?,
+      revoked_at:      row.get(4)?,
+    })
+  }
+
+  pub fn into_app_password(self) -> Result<AppPassword> {
+    Ok(AppPassword {
+      app_password_id: decode_uuid(&self.app_password_id)?,
+      label:           self.label,
+      created_at:      decode_dt(&self.created_at)?,
+      last_used_at:    self
+        .last_used_at
+        .as_deref()
+        .map(decode_dt)
+        .transpose()?,
+      revoked_at:      self.revoked_at.as_deref().map(decode_dt).transpose()?,
+    })
+  }
+}
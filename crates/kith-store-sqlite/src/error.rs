@@ -19,7 +19,7 @@ pub enum Error {
   #[error("date/time parse error: {0}")]
   DateParse(String),
 
-  /// Attempted to supersede or retract a fact that was not found.
+  /// Attempted to supersede, retract or reinstate a fact that was not found.
   #[error("fact not found: {0}")]
   FactNotFound(uuid::Uuid),
 
@@ -29,11 +29,62 @@ pub enum Error {
   #[error("fact {0} is already retracted")]
   AlreadyRetracted(uuid::Uuid),
 
+  /// Attempted to reinstate a fact that is neither superseded nor retracted.
+  #[error("fact {0} is already active")]
+  AlreadyActive(uuid::Uuid),
+
   #[error("subject not found: {0}")]
   SubjectNotFound(uuid::Uuid),
 
+  #[error("address book not found: {0}")]
+  AddressBookNotFound(uuid::Uuid),
+
+  #[error("address book {0:?} already exists")]
+  AddressBookExists(String),
+
+  /// The subject already has a different resource, or the name or UID is
+  /// taken by another subject.
+  #[error("resource conflict for subject {subject_id}: {reason}")]
+  ResourceConflict {
+    subject_id: uuid::Uuid,
+    reason:     String,
+  },
+
   #[error("cannot supersede a fact with itself")]
   SelfSupersession,
+
+  #[error("cannot merge a subject into itself")]
+  SelfMerge,
+
+  #[error("subject {0} is already merged into another subject")]
+  AlreadyMerged(uuid::Uuid),
+
+  #[error("subject {absorbed} is not merged into subject {survivor}")]
+  NotMerged {
+    survivor: uuid::Uuid,
+    absorbed: uuid::Uuid,
+  },
+
+  /// The database was written by a newer build with a schema this one does
+  /// not understand.
+  #[error(
+    "database schema version {found} is newer than supported version \
+     {supported}"
+  )]
+  SchemaTooNew { found: i64, supported: i64 },
+
+  #[error(
+    "schema migration to version {version} ({description}) failed: {source}"
+  )]
+  Migration {
+    version:     i64,
+    description: &'static str,
+    source:      Box<Error>,
+  },
+}
+
+impl From<rusqlite::Error> for Error {
+  fn from(e: rusqlite::Error) -> Self { Self::Database(e.into()) }
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -1,13 +1,123 @@
-//! SQL schema for the Kith SQLite store.
+//! SQL schema and migrations for the Kith SQLite store.
 //!
-//! Executed once at connection startup via `PRAGMA user_version`. Future
-//! migrations will be gated on that version number.
+//! The schema version lives in `PRAGMA user_version`. On open, every
+//! [`Migration`] newer than the stored version is applied in order, each in
+//! its own transaction together with the `user_version` bump, so a failed step
+//! leaves the database at the previous version. A database whose version is
+//! newer than [`LATEST_VERSION`] is refused rather than silently misread.
+//!
+//! Migrations are append-only: once released, a step's SQL never changes.
+//! New schema changes go in a new step at the end of [`MIGRATIONS`].
+
+use kith_core::fact::{EffectiveDate, FactValue};
+
+use crate::{
+  Error, Result,
+  encode::{decode_effective_date, encode_dt},
+};
 
-/// Full schema DDL; idempotent thanks to `CREATE TABLE IF NOT EXISTS`.
-pub const SCHEMA: &str = "
+/// Connection-level settings, applied on every open outside any transaction
+/// (`journal_mode` cannot be changed inside one).
+pub const PRAGMAS: &str = "
 PRAGMA journal_mode = WAL;
 PRAGMA foreign_keys = ON;
+";
+
+/// A single forward-only schema step.
+pub struct Migration {
+  /// The `user_version` the database is at once this step has run.
+  pub version:     i64,
+  pub description: &'static str,
+  pub up:          fn(&rusqlite::Transaction<'_>) -> Result<()>,
+}
+
+/// All migrations, in order. `MIGRATIONS[i].version == i + 1`.
+pub const MIGRATIONS: &[Migration] = &[
+  Migration {
+    version:     1,
+    description: "core tables",
+    up:          |tx| Ok(tx.execute_batch(V1_CORE)?),
+  },
+  Migration {
+    version:     2,
+    description: "full-text index over fact values",
+    up:          v2_facts_fts,
+  },
+  Migration {
+    version:     3,
+    description: "sortable effective_at / effective_until companions",
+    up:          v3_effective_bounds,
+  },
+  Migration {
+    version:     4,
+    description: "address books and membership",
+    up:          |tx| Ok(tx.execute_batch(V4_ADDRESSBOOKS)?),
+  },
+  Migration {
+    version:     5,
+    description: "CardDAV resource names and UIDs",
+    up:          |tx| Ok(tx.execute_batch(V5_RESOURCES)?),
+  },
+  Migration {
+    version:     6,
+    description: "app passwords",
+    up:          |tx| Ok(tx.execute_batch(V6_APP_PASSWORDS)?),
+  },
+  Migration {
+    version:     7,
+    description: "subject merges",
+    up:          |tx| Ok(tx.execute_batch(V7_SUBJECT_MERGES)?),
+  },
+  Migration {
+    version:     8,
+    description: "fact reinstatements",
+    up:          |tx| Ok(tx.execute_batch(V8_REINSTATEMENTS)?),
+  },
+  Migration {
+    version:     9,
+    description: "supersession lineage index",
+    up:          |tx| Ok(tx.execute_batch(V9_LINEAGE)?),
+  },
+  Migration {
+    version:     10,
+    description: "import source index",
+    up:          |tx| Ok(tx.execute_batch(V10_IMPORT_SOURCES)?),
+  },
+];
+
+/// The schema version this build writes and understands.
+pub const LATEST_VERSION: i64 = MIGRATIONS.len() as i64;
+
+/// Bring the database behind `conn` up to [`LATEST_VERSION`].
+pub fn migrate(conn: &mut rusqlite::Connection) -> Result<()> {
+  conn.execute_batch(PRAGMAS)?;
+
+  let current: i64 = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+  if current > LATEST_VERSION {
+    return Err(Error::SchemaTooNew {
+      found:     current,
+      supported: LATEST_VERSION,
+    });
+  }
 
+  for migration in MIGRATIONS.iter().filter(|m| m.version > current) {
+    let tx = conn.transaction()?;
+    (migration.up)(&tx).map_err(|e| Error::Migration {
+      version:     migration.version,
+      description: migration.description,
+      source:      Box::new(e),
+    })?;
+    // PRAGMA does not accept bound parameters; the value is a constant.
+    tx.execute_batch(&format!("PRAGMA user_version = {}", migration.version))?;
+    tx.commit()?;
+  }
+
+  Ok(())
+}
+
+// ─── v1 ──────────────────────────────────────────────────────────────────────
+
+const V1_CORE: &str = "
 CREATE TABLE IF NOT EXISTS subjects (
     subject_id  TEXT PRIMARY KEY,
     created_at  TEXT NOT NULL,
@@ -15,7 +125,8 @@ CREATE TABLE IF NOT EXISTS subjects (
 );
 
 -- Facts are strictly append-only.
--- No UPDATE or DELETE is ever issued against this table.
+-- No UPDATE or DELETE is ever issued against this table (bar the one-off
+-- backfill of derived columns in migrations).
 CREATE TABLE IF NOT EXISTS facts (
     fact_id           TEXT PRIMARY KEY,
     subject_id        TEXT NOT NULL REFERENCES subjects(subject_id),
@@ -52,6 +163,336 @@ CREATE TABLE IF NOT EXISTS retractions (
 CREATE INDEX IF NOT EXISTS facts_subject_idx  ON facts(subject_id);
 CREATE INDEX IF NOT EXISTS facts_type_idx     ON facts(fact_type);
 CREATE INDEX IF NOT EXISTS facts_recorded_idx ON facts(recorded_at);
+";
+
+// ─── v2 ──────────────────────────────────────────────────────────────────────
+
+/// Full-text index over [`FactValue::search_text`], one row per fact. Written
+/// in the same transaction as the fact itself; never updated.
+fn v2_facts_fts(tx: &rusqlite::Transaction<'_>) -> Result<()> {
+  tx.execute_batch(
+    "CREATE VIRTUAL TABLE IF NOT EXISTS facts_fts USING fts5(
+         fact_id    UNINDEXED,
+         subject_id UNINDEXED,
+         text,
+         tokenize = 'unicode61 remove_diacritics 2'
+     );",
+  )?;
+
+  let mut select = tx.prepare(
+    "SELECT fact_id, subject_id, fact_type, value_json FROM facts
+     WHERE fact_id NOT IN (SELECT fact_id FROM facts_fts)",
+  )?;
+  let mut insert = tx.prepare(
+    "INSERT INTO facts_fts (fact_id, subject_id, text) VALUES (?1, ?2, ?3)",
+  )?;
+  let mut rows = select.query([])?;
+  while let Some(row) = rows.next()? {
+    let fact_id: String = row.get(0)?;
+    let subject_id: String = row.get(1)?;
+    let fact_type: String = row.get(2)?;
+    let value_json: String = row.get(3)?;
+    let value =
+      FactValue::from_parts(&fact_type, serde_json::from_str(&value_json)?)?;
+    insert.execute(rusqlite::params![
+      fact_id,
+      subject_id,
+      value.search_text()
+    ])?;
+  }
+  Ok(())
+}
+
+// ─── v3 ──────────────────────────────────────────────────────────────────────
+
+/// Sortable companions of `effective_at` / `effective_until` (RFC 3339 UTC,
+/// see [`EffectiveDate::start_instant`] / [`EffectiveDate::end_instant`]).
+/// NULL is an open bound; `effective_end` is exclusive.
+fn v3_effective_bounds(tx: &rusqlite::Transaction<'_>) -> Result<()> {
+  // Builds that predate versioning may already have added the columns while
+  // still reporting version 1.
+  let has_columns: bool = tx.query_row(
+    "SELECT COUNT(*) > 0 FROM pragma_table_info('facts')
+     WHERE name = 'effective_start'",
+    [],
+    |r| r.get(0),
+  )?;
+  if !has_columns {
+    tx.execute_batch(
+      "ALTER TABLE facts ADD COLUMN effective_start TEXT;
+       ALTER TABLE facts ADD COLUMN effective_end   TEXT;",
+    )?;
+  }
+  tx.execute_batch(
+    "CREATE INDEX IF NOT EXISTS facts_effective_idx
+         ON facts(effective_start, effective_end);",
+  )?;
+
+  let mut select = tx.prepare(
+    "SELECT fact_id, effective_at, effective_until FROM facts
+     WHERE effective_at IS NOT NULL OR effective_until IS NOT NULL",
+  )?;
+  let mut update = tx.prepare(
+    "UPDATE facts SET effective_start = ?2, effective_end = ?3
+     WHERE fact_id = ?1",
+  )?;
+  let mut rows = select.query([])?;
+  while let Some(row) = rows.next()? {
+    let fact_id: String = row.get(0)?;
+    let effective_at: Option<String> = row.get(1)?;
+    let effective_until: Option<String> = row.get(2)?;
+    let start = effective_at
+      .as_deref()
+      .map(decode_effective_date)
+      .transpose()?
+      .as_ref()
+      .and_then(EffectiveDate::start_instant)
+      .map(encode_dt);
+    let end = effective_until
+      .as_deref()
+      .map(decode_effective_date)
+      .transpose()?
+      .as_ref()
+      .and_then(EffectiveDate::end_instant)
+      .map(encode_dt);
+    update.execute(rusqlite::params![fact_id, start, end])?;
+  }
+  Ok(())
+}
+
+// ─── v4 ──────────────────────────────────────────────────────────────────────
+
+const V4_ADDRESSBOOKS: &str = "
+CREATE TABLE IF NOT EXISTS addressbooks (
+    addressbook_id TEXT PRIMARY KEY,
+    name           TEXT NOT NULL UNIQUE,   -- URL path segment
+    display_name   TEXT,
+    description    TEXT,
+    is_default     INTEGER NOT NULL DEFAULT 0,
+    created_at     TEXT NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS addressbooks_default_idx
+    ON addressbooks(is_default) WHERE is_default;
+
+-- Membership changes, append-only. A subject is in a book while its latest
+-- event there is an 'add'.
+CREATE TABLE IF NOT EXISTS addressbook_membership (
+    seq            INTEGER PRIMARY KEY,
+    addressbook_id TEXT NOT NULL REFERENCES addressbooks(addressbook_id),
+    subject_id     TEXT NOT NULL REFERENCES subjects(subject_id),
+    action         TEXT NOT NULL CHECK (action IN ('add', 'remove')),
+    recorded_at    TEXT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS addressbook_membership_book_idx
+    ON addressbook_membership(addressbook_id, subject_id);
+CREATE INDEX IF NOT EXISTS addressbook_membership_subject_idx
+    ON addressbook_membership(subject_id);
+
+-- Current membership. The default book also holds every person that has
+-- never had a membership event, so contacts created outside CardDAV (and
+-- everything recorded before this migration) stay visible.
+CREATE VIEW IF NOT EXISTS addressbook_members AS
+SELECT addressbook_id, subject_id FROM (
+    -- SQLite takes bare columns from the row that supplies MAX(seq).
+    SELECT addressbook_id, subject_id, action, MAX(seq)
+    FROM addressbook_membership
+    GROUP BY addressbook_id, subject_id
+) WHERE action = 'add'
+UNION
+SELECT b.addressbook_id, s.subject_id
+FROM addressbooks b, subjects s
+WHERE b.is_default AND s.kind = 'person'
+  AND NOT EXISTS (
+      SELECT 1 FROM addressbook_membership m WHERE m.subject_id = s.subject_id
+  );
+";
+
+// ─── v5 ──────────────────────────────────────────────────────────────────────
+
+const V5_RESOURCES: &str = "
+-- The href name and vCard UID a CardDAV client gave a subject. Written once
+-- per subject; never updated.
+CREATE TABLE IF NOT EXISTS carddav_resources (
+    subject_id    TEXT PRIMARY KEY REFERENCES subjects(subject_id),
+    resource_name TEXT NOT NULL UNIQUE,   -- last href segment
+    vcard_uid     TEXT UNIQUE,            -- verbatim UID property
+    recorded_at   TEXT NOT NULL
+);
+";
+
+// ─── v6 ──────────────────────────────────────────────────────────────────────
+
+const V6_APP_PASSWORDS: &str = "
+-- Revocable per-device credentials. Only a hash of each secret is kept.
+-- Credentials are not contact history, so rows are updated in place when a
+-- password is used or revoked.
+CREATE TABLE IF NOT EXISTS app_passwords (
+    app_password_id TEXT PRIMARY KEY,
+    label           TEXT NOT NULL,
+    secret_hash     TEXT NOT NULL UNIQUE,  -- hex SHA-256 of the secret
+    created_at      TEXT NOT NULL,
+    last_used_at    TEXT,
+    revoked_at      TEXT
+);
+";
+
+// ─── v7 ──────────────────────────────────────────────────────────────────────
+
+const V7_SUBJECT_MERGES: &str = "
+-- Subject merges, append-only. A subject is absorbed into its survivor while
+-- its latest event here is a 'merge'. Facts keep their subject_id; reads
+-- follow the merges instead.
+CREATE TABLE IF NOT EXISTS subject_merges (
+    seq         INTEGER PRIMARY KEY,
+    survivor_id TEXT NOT NULL REFERENCES subjects(subject_id),
+    absorbed_id TEXT NOT NULL REFERENCES subjects(subject_id),
+    action      TEXT NOT NULL CHECK (action IN ('merge', 'unmerge')),
+    recorded_at TEXT NOT NULL,
+    CHECK (survivor_id != absorbed_id)
+);
+
+CREATE INDEX IF NOT EXISTS subject_merges_absorbed_idx
+    ON subject_merges(absorbed_id);
+CREATE INDEX IF NOT EXISTS subject_merges_survivor_idx
+    ON subject_merges(survivor_id);
+
+-- Current merges. Neither side of a merge may already be absorbed, so these
+-- form a forest.
+CREATE VIEW IF NOT EXISTS merged_subjects AS
+SELECT absorbed_id, survivor_id FROM (
+    SELECT absorbed_id, survivor_id, action, MAX(seq)
+    FROM subject_merges
+    GROUP BY absorbed_id
+) WHERE action = 'merge';
+
+-- Every subject with the root of its merge tree: itself unless absorbed.
+CREATE VIEW IF NOT EXISTS subject_roots AS
+WITH RECURSIVE roots (subject_id, root_id) AS (
+    SELECT subject_id, subject_id FROM subjects
+    WHERE subject_id NOT IN (SELECT absorbed_id FROM merged_subjects)
+    UNION ALL
+    SELECT m.absorbed_id, r.root_id
+    FROM merged_subjects m
+    JOIN roots r ON r.subject_id = m.survivor_id
+)
+SELECT subject_id, root_id FROM roots;
+
+-- Absorbed subjects belong to no address book; the survivor keeps its own
+-- memberships.
+DROP VIEW IF EXISTS addressbook_members;
+CREATE VIEW addressbook_members AS
+SELECT addressbook_id, subject_id FROM (
+    SELECT addressbook_id, subject_id FROM (
+        SELECT addressbook_id, subject_id, action, MAX(seq)
+        FROM addressbook_membership
+        GROUP BY addressbook_id, subject_id
+    ) WHERE action = 'add'
+    UNION
+    SELECT b.addressbook_id, s.subject_id
+    FROM addressbooks b, subjects s
+    WHERE b.is_default AND s.kind = 'person'
+      AND NOT EXISTS (
+          SELECT 1 FROM addressbook_membership m
+          WHERE m.subject_id = s.subject_id
+      )
+) WHERE subject_id NOT IN (SELECT absorbed_id FROM merged_subjects);
+";
+
+// ─── v8 ──────────────────────────────────────────────────────────────────────
+
+const V8_REINSTATEMENTS: &str = "
+-- A fact may now be superseded or retracted again once reinstated, so drop
+-- the one-event-per-fact constraints. Rowids are kept: sync tokens and event
+-- ids refer to them.
+CREATE TABLE supersessions_v8 (
+    supersession_id TEXT PRIMARY KEY,
+    old_fact_id     TEXT NOT NULL REFERENCES facts(fact_id),
+    new_fact_id     TEXT NOT NULL REFERENCES facts(fact_id),
+    recorded_at     TEXT NOT NULL,
+    CHECK  (old_fact_id != new_fact_id)
+);
+INSERT INTO supersessions_v8
+    (rowid, supersession_id, old_fact_id, new_fact_id, recorded_at)
+SELECT rowid, supersession_id, old_fact_id, new_fact_id, recorded_at
+FROM supersessions;
+DROP TABLE supersessions;
+ALTER TABLE supersessions_v8 RENAME TO supersessions;
+CREATE INDEX supersessions_old_fact_idx ON supersessions(old_fact_id);
+
+CREATE TABLE retractions_v8 (
+    retraction_id TEXT PRIMARY KEY,
+    fact_id       TEXT NOT NULL REFERENCES facts(fact_id),
+    reason        TEXT,
+    recorded_at   TEXT NOT NULL
+);
+INSERT INTO retractions_v8 (rowid, retraction_id, fact_id, reason, recorded_at)
+SELECT rowid, retraction_id, fact_id, reason, recorded_at FROM retractions;
+DROP TABLE retractions;
+ALTER TABLE retractions_v8 RENAME TO retractions;
+CREATE INDEX retractions_fact_idx ON retractions(fact_id);
+
+-- A superseded or retracted fact made active again.
+CREATE TABLE IF NOT EXISTS reinstatements (
+    reinstatement_id TEXT PRIMARY KEY,
+    fact_id          TEXT NOT NULL REFERENCES facts(fact_id),
+    recorded_at      TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS reinstatements_fact_idx
+    ON reinstatements(fact_id);
+
+-- One sequence across all three lifecycle tables, in the order the events
+-- were written. Wall-clock time cannot order them: the clock may step back,
+-- and restored events keep the time they were first recorded.
+CREATE TABLE IF NOT EXISTS lifecycle_log (
+    seq      INTEGER PRIMARY KEY AUTOINCREMENT,
+    event_id TEXT NOT NULL UNIQUE  -- supersession, retraction or reinstatement
+);
+
+-- Each fact had at most one event until now, so their order only needs to
+-- be stable.
+INSERT INTO lifecycle_log (event_id)
+SELECT event_id FROM (
+    SELECT supersession_id AS event_id, recorded_at, rowid AS n
+    FROM supersessions
+    UNION ALL
+    SELECT retraction_id, recorded_at, rowid FROM retractions
+)
+ORDER BY recorded_at, n;
+
+-- Every lifecycle event, with the status it leaves its fact in. A fact's
+-- status is that of its event with the highest seq; a fact with no events is
+-- active.
+CREATE VIEW IF NOT EXISTS fact_events AS
+SELECT s.old_fact_id AS fact_id, 'superseded' AS status,
+       s.new_fact_id AS superseded_by, NULL AS reason, s.recorded_at, l.seq
+FROM supersessions s
+JOIN lifecycle_log l ON l.event_id = s.supersession_id
+UNION ALL
+SELECT r.fact_id, 'retracted', NULL, r.reason, r.recorded_at, l.seq
+FROM retractions r
+JOIN lifecycle_log l ON l.event_id = r.retraction_id
+UNION ALL
+SELECT ri.fact_id, 'active', NULL, NULL, ri.recorded_at, l.seq
+FROM reinstatements ri
+JOIN lifecycle_log l ON l.event_id = ri.reinstatement_id;
+";
+
+// ─── v9 ──────────────────────────────────────────────────────────────────────
+
+const V9_LINEAGE: &str = "
+-- Walking a fact's lineage backwards looks supersessions up by the fact they
+-- created.
+CREATE INDEX IF NOT EXISTS supersessions_new_fact_idx
+    ON supersessions(new_fact_id);
+";
+
+// ─── v10 ─────────────────────────────────────────────────────────────────────
 
-PRAGMA user_version = 1;
+const V10_IMPORT_SOURCES: &str = "
+-- Undoing an import looks its facts up by the source named in their
+-- recording context.
+CREATE INDEX IF NOT EXISTS facts_source_name_idx
+    ON facts(json_extract(recording_context, '$.source_name'));
 ";
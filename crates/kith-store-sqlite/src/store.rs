@@ -1,113 +1,797 @@
 //! [`SqliteStore`] — the SQLite implementation of [`ContactStore`].
 
-use std::path::Path;
+use std::{collections::VecDeque, path::Path};
 
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use kith_core::{
-  fact::{Fact, NewFact},
-  lifecycle::{ContactView, ResolvedFact, Retraction, Supersession},
-  store::{ContactStore, FactQuery},
+  addressbook::{
+    AddressBook, AddressBookChanges, MembershipAction, NewAddressBook,
+    SyncToken,
+  },
+  app_password::{AppPassword, NewAppPassword},
+  event::{Change, EventId, StoreEvent},
+  fact::{EffectiveDate, Fact, NewFact},
+  history::History,
+  lifecycle::{
+    ContactView, LifecycleEvent, MergeAction, Reinstatement, ResolvedFact,
+    Retraction, SubjectMerge, Supersession,
+  },
+  resource::{NewResource, Resource},
+  store::{AppliedChangeset, Changeset, ContactStore, FactQuery, ImportSource},
   subject::{Subject, SubjectKind},
 };
 use rusqlite::OptionalExtension as _;
+use tokio::sync::broadcast;
 use uuid::Uuid;
 
 use crate::{
   Error, Result,
   encode::{
-    RawResolvedFact, RawSubject, encode_dt, encode_effective_date,
-    encode_recording_context, encode_tags, encode_uuid,
+    RawAddressBook, RawAppPassword, RawMembership, RawMerge, RawResolvedFact,
+    RawResource, RawSubject, decode_dt, decode_uuid, encode_dt,
+    encode_effective_date, encode_recording_context, encode_tags, encode_uuid,
   },
-  schema::SCHEMA,
+  schema,
 };
 
 // ─── Store ───────────────────────────────────────────────────────────────────
 
+/// How many events a subscriber may fall behind before it lags.
+const EVENT_CAPACITY: usize = 1024;
+
 /// A Kith contact store backed by a single SQLite file.
 ///
-/// Cloning is cheap — the inner connection is reference-counted.
+/// Cloning is cheap — the inner connection is reference-counted, and clones
+/// share one event channel.
 #[derive(Clone)]
 pub struct SqliteStore {
-  conn: tokio_rusqlite::Connection,
+  conn:   tokio_rusqlite::Connection,
+  /// Publishes a [`StoreEvent`] for every write to the fact log.
+  events: broadcast::Sender<StoreEvent>,
 }
 
 impl SqliteStore {
   /// Open (or create) a store at `path` and run schema initialisation.
   pub async fn open(path: impl AsRef<Path>) -> Result<Self> {
     let conn = tokio_rusqlite::Connection::open(path).await?;
-    let store = Self { conn };
-    store.init_schema().await?;
-    Ok(store)
+    Self::with_connection(conn).await
   }
 
   /// Open an in-memory store — useful for testing.
   pub async fn open_in_memory() -> Result<Self> {
     let conn = tokio_rusqlite::Connection::open_in_memory().await?;
-    let store = Self { conn };
+    Self::with_connection(conn).await
+  }
+
+  async fn with_connection(conn: tokio_rusqlite::Connection) -> Result<Self> {
+    let (events, _) = broadcast::channel(EVENT_CAPACITY);
+    let store = Self { conn, events };
     store.init_schema().await?;
     Ok(store)
   }
 
   async fn init_schema(&self) -> Result<()> {
+    // The closure's own error type is tokio_rusqlite's; carry the richer
+    // migration result out through it instead.
+    self.conn.call(|conn| Ok(schema::migrate(conn))).await??;
+    Ok(())
+  }
+
+  /// The resource whose `column` equals `value`, if any. `column` is one of
+  /// the table's unique columns and never user input.
+  async fn resource_where(
+    &self,
+    column: &'static str,
+    value: String,
+  ) -> Result<Option<Resource>> {
+    let raw: Option<RawResource> = self
+      .conn
+      .call(move |conn| {
+        Ok(
+          conn
+            .query_row(
+              &format!(
+                "SELECT {} FROM carddav_resources WHERE {column} = ?1",
+                RawResource::COLUMNS
+              ),
+              rusqlite::params![value],
+              RawResource::from_row,
+            )
+            .optional()?,
+        )
+      })
+      .await?;
+
+    raw.map(RawResource::into_resource).transpose()
+  }
+
+  /// Append a membership event unless it would not change anything.
+  async fn record_membership(
+    &self,
+    addressbook_id: Uuid,
+    subject_id: Uuid,
+    action: MembershipAction,
+  ) -> Result<()> {
+    let at_str = encode_dt(Utc::now());
+
     self
       .conn
-      .call(|conn| {
-        conn.execute_batch(SCHEMA)?;
-        Ok(())
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+        if let Err(e) =
+          insert_membership(&tx, addressbook_id, subject_id, action, &at_str)?
+        {
+          return Ok(Err(e));
+        }
+        tx.commit()?;
+        Ok(Ok(()))
+      })
+      .await?
+  }
+
+  /// The subject `id` resolved to at `as_of`: itself, or the subject it had
+  /// been merged into by then.
+  async fn resolve_subject(
+    &self,
+    id: Uuid,
+    as_of: DateTime<Utc>,
+  ) -> Result<Option<Subject>> {
+    let id_str = encode_uuid(id);
+    let as_of_str = encode_dt(as_of);
+
+    let raw: Option<RawSubject> = self
+      .conn
+      .call(move |conn| {
+        Ok(
+          conn
+            .query_row(
+              &format!(
+                "{MERGE_TREE}
+                 SELECT subject_id, created_at, kind FROM subjects
+                 WHERE subject_id = (SELECT subject_id FROM root)"
+              ),
+              rusqlite::params![id_str, as_of_str],
+              |row| {
+                Ok(RawSubject {
+                  subject_id: row.get(0)?,
+                  created_at: row.get(1)?,
+                  kind:       row.get(2)?,
+                })
+              },
+            )
+            .optional()?,
+        )
       })
       .await?;
-    Ok(())
+
+    raw.map(RawSubject::into_subject).transpose()
   }
 
-  /// Insert a fully-built [`Fact`] into the `facts` table.
-  async fn insert_fact(&self, fact: &Fact) -> Result<()> {
-    let fact_id_str = encode_uuid(fact.fact_id);
-    let subject_id_str = encode_uuid(fact.subject_id);
-    let fact_type = fact.value.discriminant().to_owned();
-    let value_json_str = fact.value.to_json()?.to_string();
-    let recorded_at_str = encode_dt(fact.recorded_at);
-    let effective_at_str = fact
-      .effective_at
-      .as_ref()
-      .map(encode_effective_date)
-      .transpose()?;
-    let effective_until_str = fact
-      .effective_until
-      .as_ref()
-      .map(encode_effective_date)
-      .transpose()?;
-    let confidence_str = fact.confidence.to_string();
-    let recording_ctx_str = encode_recording_context(&fact.recording_context)?;
-    let tags_str = encode_tags(&fact.tags)?;
-    let source = fact.source.clone();
+  /// Append a merge or unmerge event if the subjects' current state allows
+  /// it.
+  async fn record_merge(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+    action: MergeAction,
+  ) -> Result<SubjectMerge> {
+    if survivor == absorbed {
+      return Err(Error::SelfMerge);
+    }
+    let merge = SubjectMerge {
+      survivor_id: survivor,
+      absorbed_id: absorbed,
+      action,
+      recorded_at: Utc::now(),
+    };
+    let survivor_str = encode_uuid(survivor);
+    let absorbed_str = encode_uuid(absorbed);
+    let at_str = encode_dt(merge.recorded_at);
 
     self
       .conn
       .call(move |conn| {
-        conn.execute(
-          "INSERT INTO facts (
-             fact_id, subject_id, fact_type, value_json, recorded_at,
-             effective_at, effective_until, source,
-             confidence, recording_context, tags
-           ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+        let tx = conn.transaction()?;
+        let (survivor_exists, absorbed_exists, survivor_into, absorbed_into): (
+          bool,
+          bool,
+          Option<String>,
+          Option<String>,
+        ) = tx.query_row(
+          "SELECT \
+             EXISTS (SELECT 1 FROM subjects WHERE subject_id = ?1), \
+             EXISTS (SELECT 1 FROM subjects WHERE subject_id = ?2), \
+             (SELECT survivor_id FROM merged_subjects \
+              WHERE absorbed_id = ?1), \
+             (SELECT survivor_id FROM merged_subjects \
+              WHERE absorbed_id = ?2)",
+          rusqlite::params![survivor_str, absorbed_str],
+          |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+        )?;
+
+        if !survivor_exists {
+          return Ok(Err(Error::SubjectNotFound(survivor)));
+        }
+        if !absorbed_exists {
+          return Ok(Err(Error::SubjectNotFound(absorbed)));
+        }
+        match action {
+          // Only roots merge, so the merges always form a forest.
+          MergeAction::Merge if survivor_into.is_some() => {
+            return Ok(Err(Error::AlreadyMerged(survivor)));
+          }
+          MergeAction::Merge if absorbed_into.is_some() => {
+            return Ok(Err(Error::AlreadyMerged(absorbed)));
+          }
+          MergeAction::Unmerge
+            if absorbed_into.as_deref() != Some(survivor_str.as_str()) =>
+          {
+            return Ok(Err(Error::NotMerged { survivor, absorbed }));
+          }
+          _ => {}
+        }
+
+        tx.execute(
+          "INSERT INTO subject_merges \
+             (survivor_id, absorbed_id, action, recorded_at) \
+             VALUES (?1, ?2, ?3, ?4)",
           rusqlite::params![
-            fact_id_str,
-            subject_id_str,
-            fact_type,
-            value_json_str,
-            recorded_at_str,
-            effective_at_str,
-            effective_until_str,
-            source,
-            confidence_str,
-            recording_ctx_str,
-            tags_str,
+            survivor_str,
+            absorbed_str,
+            merge_action_str(action),
+            at_str
           ],
         )?;
-        Ok(())
+        tx.commit()?;
+        Ok(Ok(merge))
       })
-      .await?;
+      .await?
+  }
+}
+
+/// Common table expressions over the merges recorded at or before `?2`:
+/// `merges` holds those in force then, `root` the subject `?1` had been
+/// merged into (or `?1` itself), and `tree` that root with every subject
+/// merged into it, directly or not.
+const MERGE_TREE: &str = "
+WITH RECURSIVE
+  merges AS (
+    SELECT absorbed_id, survivor_id FROM (
+      SELECT absorbed_id, survivor_id, action, MAX(seq)
+      FROM subject_merges
+      WHERE recorded_at <= ?2
+      GROUP BY absorbed_id
+    ) WHERE action = 'merge'
+  ),
+  up (subject_id, depth) AS (
+    SELECT ?1, 0
+    UNION ALL
+    SELECT m.survivor_id, up.depth + 1
+    FROM merges m JOIN up ON m.absorbed_id = up.subject_id
+  ),
+  root AS (SELECT subject_id FROM up ORDER BY depth DESC LIMIT 1),
+  tree (subject_id) AS (
+    SELECT subject_id FROM root
+    UNION ALL
+    SELECT m.absorbed_id
+    FROM merges m JOIN tree t ON m.survivor_id = t.subject_id
+  )";
+
+/// The status left by the latest lifecycle event of fact `f`, or NULL if it
+/// has none (see the `fact_events` view).
+const LATEST_STATUS: &str = "(
+  SELECT e.status FROM fact_events e WHERE e.fact_id = f.fact_id
+  ORDER BY e.seq DESC LIMIT 1
+)";
+
+/// Matches facts `f` recorded from the import source `?1`. The first term is
+/// the expression `facts_source_name_idx` covers.
+const FROM_IMPORT_SOURCE: &str = "\
+  json_extract(f.recording_context, '$.source_name') = ?1
+  AND json_extract(f.recording_context, '$.kind') = 'imported'";
+
+// ─── Row encoding ────────────────────────────────────────────────────────────
+
+/// A [`Fact`] pre-encoded into column strings, ready to be inserted inside a
+/// `conn.call` closure. Encoding can fail, so it happens before the closure.
+struct FactRow {
+  fact_id:           String,
+  subject_id:        String,
+  fact_type:         String,
+  value_json:        String,
+  recorded_at:       String,
+  effective_at:      Option<String>,
+  effective_until:   Option<String>,
+  source:            Option<String>,
+  confidence:        String,
+  recording_context: String,
+  tags:              String,
+  effective_start:   Option<String>,
+  effective_end:     Option<String>,
+  search_text:       String,
+}
+
+impl FactRow {
+  fn encode(fact: &Fact) -> Result<Self> {
+    Ok(Self {
+      fact_id:           encode_uuid(fact.fact_id),
+      subject_id:        encode_uuid(fact.subject_id),
+      fact_type:         fact.value.discriminant().to_owned(),
+      value_json:        fact.value.to_json()?.to_string(),
+      recorded_at:       encode_dt(fact.recorded_at),
+      effective_at:      fact
+        .effective_at
+        .as_ref()
+        .map(encode_effective_date)
+        .transpose()?,
+      effective_until:   fact
+        .effective_until
+        .as_ref()
+        .map(encode_effective_date)
+        .transpose()?,
+      source:            fact.source.clone(),
+      confidence:        fact.confidence.to_string(),
+      recording_context: encode_recording_context(&fact.recording_context)?,
+      tags:              encode_tags(&fact.tags)?,
+      effective_start:   fact
+        .effective_at
+        .as_ref()
+        .and_then(EffectiveDate::start_instant)
+        .map(encode_dt),
+      effective_end:     fact
+        .effective_until
+        .as_ref()
+        .and_then(EffectiveDate::end_instant)
+        .map(encode_dt),
+      search_text:       fact.value.search_text(),
+    })
+  }
+
+  /// Insert into `facts` and `facts_fts`, returning the `facts` rowid.
+  /// Callers wrap this in a transaction so the two tables never disagree.
+  fn insert(&self, conn: &rusqlite::Connection) -> rusqlite::Result<i64> {
+    conn.execute(
+      "INSERT INTO facts (
+         fact_id, subject_id, fact_type, value_json, recorded_at,
+         effective_at, effective_until, source,
+         confidence, recording_context, tags,
+         effective_start, effective_end
+       ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+      rusqlite::params![
+        self.fact_id,
+        self.subject_id,
+        self.fact_type,
+        self.value_json,
+        self.recorded_at,
+        self.effective_at,
+        self.effective_until,
+        self.source,
+        self.confidence,
+        self.recording_context,
+        self.tags,
+        self.effective_start,
+        self.effective_end,
+      ],
+    )?;
+    let rowid = conn.last_insert_rowid();
+    conn.execute(
+      "INSERT INTO facts_fts (fact_id, subject_id, text) VALUES (?1, ?2, ?3)",
+      rusqlite::params![self.fact_id, self.subject_id, self.search_text],
+    )?;
+    Ok(rowid)
+  }
+}
+
+// ─── Transaction helpers ─────────────────────────────────────────────────────
+
+/// Build the stored [`Fact`] for `input`, assigning a fresh ID and the
+/// store-controlled `recorded_at`.
+fn stamp_fact(input: NewFact, recorded_at: DateTime<Utc>) -> Fact {
+  Fact {
+    fact_id: Uuid::new_v4(),
+    subject_id: input.subject_id,
+    value: input.value,
+    recorded_at,
+    effective_at: input.effective_at,
+    effective_until: input.effective_until,
+    source: input.source,
+    confidence: input.confidence,
+    recording_context: input.recording_context,
+    tags: input.tags,
+  }
+}
+
+/// Why a lifecycle event cannot be recorded against a fact.
+enum Conflict {
+  NotFound,
+  AlreadySuperseded,
+  AlreadyRetracted,
+  AlreadyActive,
+}
+
+impl Conflict {
+  fn into_error(self, fact_id: Uuid) -> Error {
+    match self {
+      Self::NotFound => Error::FactNotFound(fact_id),
+      Self::AlreadySuperseded => Error::AlreadySuperseded(fact_id),
+      Self::AlreadyRetracted => Error::AlreadyRetracted(fact_id),
+      Self::AlreadyActive => Error::AlreadyActive(fact_id),
+    }
+  }
+}
+
+/// Check that `fact_id` exists and is active, or is not if `active` is
+/// false.
+fn check_status(
+  conn: &rusqlite::Connection,
+  fact_id: &str,
+  active: bool,
+) -> rusqlite::Result<Result<(), Conflict>> {
+  let (exists_flag, status): (Option<i64>, Option<String>) = conn.query_row(
+    &format!(
+      "SELECT \
+         (SELECT 1 FROM facts WHERE fact_id = ?1), \
+         (SELECT {LATEST_STATUS} FROM facts f WHERE f.fact_id = ?1)"
+    ),
+    rusqlite::params![fact_id],
+    |r| Ok((r.get(0)?, r.get(1)?)),
+  )?;
+
+  let is_active = status.as_deref().is_none_or(|s| s == "active");
+  Ok(if exists_flag.is_none() {
+    Err(Conflict::NotFound)
+  } else if is_active == active {
     Ok(())
+  } else {
+    match status.as_deref() {
+      Some("superseded") => Err(Conflict::AlreadySuperseded),
+      Some("retracted") => Err(Conflict::AlreadyRetracted),
+      _ => Err(Conflict::AlreadyActive),
+    }
+  })
+}
+
+/// Record `sup` if its old fact is active. The replacement fact must already
+/// be inserted.
+fn insert_supersession(
+  conn: &rusqlite::Connection,
+  sup: &Supersession,
+) -> rusqlite::Result<Result<(), Conflict>> {
+  let old_id_str = encode_uuid(sup.old_fact_id);
+  if let Err(conflict) = check_status(conn, &old_id_str, true)? {
+    return Ok(Err(conflict));
+  }
+  conn.execute(
+    "INSERT INTO supersessions \
+       (supersession_id, old_fact_id, new_fact_id, recorded_at) \
+       VALUES (?1, ?2, ?3, ?4)",
+    rusqlite::params![
+      encode_uuid(sup.supersession_id),
+      old_id_str,
+      encode_uuid(sup.new_fact_id),
+      encode_dt(sup.recorded_at),
+    ],
+  )?;
+  Ok(Ok(()))
+}
+
+/// Record `ret` if its fact is active.
+fn insert_retraction(
+  conn: &rusqlite::Connection,
+  ret: &Retraction,
+) -> rusqlite::Result<Result<(), Conflict>> {
+  let fact_id_str = encode_uuid(ret.fact_id);
+  if let Err(conflict) = check_status(conn, &fact_id_str, true)? {
+    return Ok(Err(conflict));
+  }
+  conn.execute(
+    "INSERT INTO retractions \
+       (retraction_id, fact_id, reason, recorded_at) \
+       VALUES (?1, ?2, ?3, ?4)",
+    rusqlite::params![
+      encode_uuid(ret.retraction_id),
+      fact_id_str,
+      ret.reason,
+      encode_dt(ret.recorded_at),
+    ],
+  )?;
+  Ok(Ok(()))
+}
+
+/// Record `rein` if its fact is superseded or retracted.
+fn insert_reinstatement(
+  conn: &rusqlite::Connection,
+  rein: &Reinstatement,
+) -> rusqlite::Result<Result<(), Conflict>> {
+  let fact_id_str = encode_uuid(rein.fact_id);
+  if let Err(conflict) = check_status(conn, &fact_id_str, false)? {
+    return Ok(Err(conflict));
+  }
+  conn.execute(
+    "INSERT INTO reinstatements (reinstatement_id, fact_id, recorded_at) \
+       VALUES (?1, ?2, ?3)",
+    rusqlite::params![
+      encode_uuid(rein.reinstatement_id),
+      fact_id_str,
+      encode_dt(rein.recorded_at),
+    ],
+  )?;
+  Ok(Ok(()))
+}
+
+/// Insert `facts` (pre-encoded as `rows`), then the `lifecycle` events in
+/// order, inside `tx`. Returns the events to publish once `tx` commits, or
+/// the first lifecycle event that conflicts, with the fact it targets.
+fn write_log(
+  tx: &rusqlite::Transaction<'_>,
+  rows: &[FactRow],
+  facts: Vec<Fact>,
+  lifecycle: Vec<LifecycleEvent>,
+) -> rusqlite::Result<Result<Vec<StoreEvent>, (Uuid, Conflict)>> {
+  let mut id = current_event_id(tx)?;
+  let mut events = Vec::new();
+
+  // Facts go in first so a supersession may target a fact written in the
+  // same transaction.
+  for (row, fact) in rows.iter().zip(facts) {
+    id.facts = row.insert(tx)?;
+    events.push(StoreEvent {
+      id,
+      subject_id: fact.subject_id,
+      change: Change::FactRecorded {
+        fact: Box::new(fact),
+      },
+    });
+  }
+  for event in lifecycle {
+    let fact_id = event.fact_id();
+    let inserted = match &event {
+      LifecycleEvent::Supersession(sup) => insert_supersession(tx, sup)?,
+      LifecycleEvent::Retraction(ret) => insert_retraction(tx, ret)?,
+      LifecycleEvent::Reinstatement(rein) => insert_reinstatement(tx, rein)?,
+    };
+    if let Err(conflict) = inserted {
+      return Ok(Err((fact_id, conflict)));
+    }
+    let rowid = tx.last_insert_rowid();
+    tx.execute(
+      "INSERT INTO lifecycle_log (event_id) VALUES (?1)",
+      rusqlite::params![encode_uuid(event.id())],
+    )?;
+    let change = match event {
+      LifecycleEvent::Supersession(supersession) => {
+        id.supersessions = rowid;
+        Change::FactSuperseded { supersession }
+      }
+      LifecycleEvent::Retraction(retraction) => {
+        id.retractions = rowid;
+        Change::FactRetracted { retraction }
+      }
+      LifecycleEvent::Reinstatement(reinstatement) => {
+        id.reinstatements = rowid;
+        Change::FactReinstated { reinstatement }
+      }
+    };
+    events.push(StoreEvent {
+      id,
+      subject_id: fact_subject(tx, fact_id)?,
+      change,
+    });
+  }
+  Ok(Ok(events))
+}
+
+/// Record `action` on a subject's membership of an address book inside
+/// `conn`, unless it would change nothing.
+fn insert_membership(
+  conn: &rusqlite::Connection,
+  addressbook_id: Uuid,
+  subject_id: Uuid,
+  action: MembershipAction,
+  at_str: &str,
+) -> rusqlite::Result<Result<()>> {
+  let book_str = encode_uuid(addressbook_id);
+  let subject_str = encode_uuid(subject_id);
+  let (book_exists, subject_exists, is_member, explicit): (
+    bool,
+    bool,
+    bool,
+    bool,
+  ) = conn.query_row(
+    "SELECT \
+       EXISTS (SELECT 1 FROM addressbooks WHERE addressbook_id = ?1), \
+       EXISTS (SELECT 1 FROM subjects WHERE subject_id = ?2), \
+       EXISTS (SELECT 1 FROM addressbook_members \
+               WHERE addressbook_id = ?1 AND subject_id = ?2), \
+       EXISTS (SELECT 1 FROM addressbook_membership \
+               WHERE addressbook_id = ?1 AND subject_id = ?2 \
+                 AND action = 'add' \
+                 AND seq = (SELECT MAX(seq) \
+                            FROM addressbook_membership \
+                            WHERE addressbook_id = ?1 \
+                              AND subject_id = ?2))",
+    rusqlite::params![book_str, subject_str],
+    |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)),
+  )?;
+
+  if !book_exists {
+    return Ok(Err(Error::AddressBookNotFound(addressbook_id)));
+  }
+  if !subject_exists {
+    return Ok(Err(Error::SubjectNotFound(subject_id)));
+  }
+  // An implicit member of the default book is still recorded as added, so
+  // that it stays put once it joins another book.
+  let changes = match action {
+    MembershipAction::Add => !explicit,
+    MembershipAction::Remove => is_member,
+  };
+  if changes {
+    conn.execute(
+      "INSERT INTO addressbook_membership \
+         (addressbook_id, subject_id, action, recorded_at) \
+         VALUES (?1, ?2, ?3, ?4)",
+      rusqlite::params![
+        book_str,
+        subject_str,
+        membership_action_str(action),
+        at_str
+      ],
+    )?;
+  }
+  Ok(Ok(()))
+}
+
+/// Insert `subject` inside `conn`.
+fn insert_subject(
+  conn: &rusqlite::Connection,
+  subject: &Subject,
+) -> rusqlite::Result<()> {
+  conn.execute(
+    "INSERT INTO subjects (subject_id, created_at, kind) VALUES (?1, ?2, ?3)",
+    rusqlite::params![
+      encode_uuid(subject.subject_id),
+      encode_dt(subject.created_at),
+      subject.kind.to_string(),
+    ],
+  )?;
+  Ok(())
+}
+
+/// Record `resource` inside `conn`. Returns the stored resource, which is the
+/// existing one if the subject already had an identical resource.
+fn insert_resource(
+  conn: &rusqlite::Connection,
+  resource: Resource,
+) -> rusqlite::Result<Result<Resource>> {
+  let subject_str = encode_uuid(resource.subject_id);
+  let subject_exists: bool = conn.query_row(
+    "SELECT EXISTS (SELECT 1 FROM subjects WHERE subject_id = ?1)",
+    rusqlite::params![subject_str],
+    |r| r.get(0),
+  )?;
+  if !subject_exists {
+    return Ok(Err(Error::SubjectNotFound(resource.subject_id)));
+  }
+
+  let existing = conn
+    .query_row(
+      &format!(
+        "SELECT {} FROM carddav_resources WHERE subject_id = ?1",
+        RawResource::COLUMNS
+      ),
+      rusqlite::params![subject_str],
+      RawResource::from_row,
+    )
+    .optional()?;
+  if let Some(existing) = existing {
+    // Recording the same resource twice is harmless.
+    if existing.name == resource.name && existing.uid == resource.uid {
+      return Ok(existing.into_resource());
+    }
+    return Ok(Err(Error::ResourceConflict {
+      subject_id: resource.subject_id,
+      reason:     format!("already named {:?}", existing.name),
+    }));
+  }
+
+  match conn.execute(
+    "INSERT INTO carddav_resources \
+       (subject_id, resource_name, vcard_uid, recorded_at) \
+       VALUES (?1, ?2, ?3, ?4)",
+    rusqlite::params![
+      subject_str,
+      resource.name,
+      resource.uid,
+      encode_dt(resource.recorded_at),
+    ],
+  ) {
+    Ok(_) => {}
+    Err(rusqlite::Error::SqliteFailure(ref err, _))
+      if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+    {
+      return Ok(Err(Error::ResourceConflict {
+        subject_id: resource.subject_id,
+        reason:     "name or UID belongs to another subject".into(),
+      }));
+    }
+    Err(e) => return Err(e),
+  }
+  Ok(Ok(resource))
+}
+
+/// Publish the events of a committed transaction. Called on the connection's
+/// thread, which keeps events in commit order. Having no subscribers is not
+/// an error.
+fn publish(sender: &broadcast::Sender<StoreEvent>, events: Vec<StoreEvent>) {
+  for event in events {
+    let _ = sender.send(event);
+  }
+}
+
+/// Read the high-water mark of every append-only log. Nothing is ever deleted
+/// from them, so rowids only grow.
+fn current_sync_token(
+  conn: &rusqlite::Connection,
+) -> rusqlite::Result<SyncToken> {
+  conn.query_row(
+    "SELECT (SELECT COALESCE(MAX(rowid), 0) FROM facts),
+            (SELECT COALESCE(MAX(rowid), 0) FROM supersessions),
+            (SELECT COALESCE(MAX(rowid), 0) FROM retractions),
+            (SELECT COALESCE(MAX(seq), 0) FROM addressbook_membership),
+            (SELECT COALESCE(MAX(seq), 0) FROM subject_merges),
+            (SELECT COALESCE(MAX(rowid), 0) FROM reinstatements)",
+    [],
+    |r| {
+      Ok(SyncToken {
+        facts:          r.get(0)?,
+        supersessions:  r.get(1)?,
+        retractions:    r.get(2)?,
+        memberships:    r.get(3)?,
+        merges:         r.get(4)?,
+        reinstatements: r.get(5)?,
+      })
+    },
+  )
+}
+
+/// The position of the latest write to the fact log.
+fn current_event_id(conn: &rusqlite::Connection) -> rusqlite::Result<EventId> {
+  Ok(current_sync_token(conn)?.into())
+}
+
+/// The subject of fact `fact_id`, which must exist.
+fn fact_subject(
+  conn: &rusqlite::Connection,
+  fact_id: Uuid,
+) -> rusqlite::Result<Uuid> {
+  let subject_id: String = conn.query_row(
+    "SELECT subject_id FROM facts WHERE fact_id = ?1",
+    rusqlite::params![encode_uuid(fact_id)],
+    |r| r.get(0),
+  )?;
+  Uuid::parse_str(&subject_id).map_err(|e| {
+    rusqlite::Error::FromSqlConversionFailure(
+      0,
+      rusqlite::types::Type::Text,
+      Box::new(e),
+    )
+  })
+}
+
+/// How `action` is stored in `addressbook_membership.action`.
+fn membership_action_str(action: MembershipAction) -> &'static str {
+  match action {
+    MembershipAction::Add => "add",
+    MembershipAction::Remove => "remove",
+  }
+}
+
+/// How `action` is stored in `subject_merges.action`.
+fn merge_action_str(action: MergeAction) -> &'static str {
+  match action {
+    MergeAction::Merge => "merge",
+    MergeAction::Unmerge => "unmerge",
   }
 }
 
@@ -133,51 +817,17 @@ impl ContactStore for SqliteStore {
       kind,
     };
 
-    let id_str = encode_uuid(subject.subject_id);
-    let at_str = encode_dt(subject.created_at);
-    let kind_str = kind.to_string();
-
+    let inserted = subject.clone();
     self
       .conn
-      .call(move |conn| {
-        conn.execute(
-          "INSERT INTO subjects (subject_id, created_at, kind) VALUES (?1, \
-           ?2, ?3)",
-          rusqlite::params![id_str, at_str, kind_str],
-        )?;
-        Ok(())
-      })
+      .call(move |conn| Ok(insert_subject(conn, &inserted)?))
       .await?;
 
     Ok(subject)
   }
 
   async fn get_subject(&self, id: Uuid) -> Result<Option<Subject>> {
-    let id_str = encode_uuid(id);
-
-    let raw: Option<RawSubject> = self
-      .conn
-      .call(move |conn| {
-        Ok(
-          conn
-            .query_row(
-              "SELECT subject_id, created_at, kind FROM subjects WHERE \
-               subject_id = ?1",
-              rusqlite::params![id_str],
-              |row| {
-                Ok(RawSubject {
-                  subject_id: row.get(0)?,
-                  created_at: row.get(1)?,
-                  kind:       row.get(2)?,
-                })
-              },
-            )
-            .optional()?,
-        )
-      })
-      .await?;
-
-    raw.map(RawSubject::into_subject).transpose()
+    self.resolve_subject(id, Utc::now()).await
   }
 
   async fn list_subjects(
@@ -191,7 +841,8 @@ impl ContactStore for SqliteStore {
       .call(move |conn| {
         let mut stmt = conn.prepare(
           "SELECT subject_id, created_at, kind FROM subjects \
-           WHERE (?1 IS NULL OR kind = ?1)",
+           WHERE (?1 IS NULL OR kind = ?1) \
+             AND subject_id NOT IN (SELECT absorbed_id FROM merged_subjects)",
         )?;
         let rows = stmt
           .query_map(rusqlite::params![kind_str.as_deref()], |row| {
@@ -212,21 +863,13 @@ impl ContactStore for SqliteStore {
   // ── Facts — append-only writes ────────────────────────────────────────────
 
   async fn record_fact(&self, input: NewFact) -> Result<Fact> {
-    let fact = Fact {
-      fact_id:           Uuid::new_v4(),
-      subject_id:        input.subject_id,
-      value:             input.value,
-      recorded_at:       Utc::now(),
-      effective_at:      input.effective_at,
-      effective_until:   input.effective_until,
-      source:            input.source,
-      confidence:        input.confidence,
-      recording_context: input.recording_context,
-      tags:              input.tags,
-    };
-
-    self.insert_fact(&fact).await?;
-    Ok(fact)
+    let mut applied = self
+      .apply_changeset(Changeset {
+        new_facts: vec![input],
+        ..Default::default()
+      })
+      .await?;
+    Ok(applied.facts.remove(0))
   }
 
   // ── Single-fact lookup ────────────────────────────────────────────────────
@@ -240,17 +883,22 @@ impl ContactStore for SqliteStore {
         Ok(
           conn
             .query_row(
-              "SELECT
+              "WITH latest AS (
+                 SELECT * FROM fact_events WHERE fact_id = ?1
+                 ORDER BY seq DESC LIMIT 1
+               )
+               SELECT
                  f.fact_id, f.subject_id, f.fact_type, f.value_json,
                  f.recorded_at, f.effective_at, f.effective_until,
                  f.source, f.confidence, f.recording_context, f.tags,
-                 s.new_fact_id   AS superseded_by,
-                 s.recorded_at   AS superseded_at,
-                 r.reason        AS retraction_reason,
-                 r.recorded_at   AS retracted_at
+                 st.superseded_by AS superseded_by,
+                 CASE st.status WHEN 'superseded' THEN st.recorded_at END
+                                  AS superseded_at,
+                 st.reason        AS retraction_reason,
+                 CASE st.status WHEN 'retracted' THEN st.recorded_at END
+                                  AS retracted_at
                FROM facts f
-               LEFT JOIN supersessions s ON s.old_fact_id = f.fact_id
-               LEFT JOIN retractions   r ON r.fact_id     = f.fact_id
+               LEFT JOIN latest st ON st.fact_id = f.fact_id
                WHERE f.fact_id = ?1",
               rusqlite::params![id_str],
               |row| {
@@ -281,6 +929,104 @@ impl ContactStore for SqliteStore {
     raw.map(RawResolvedFact::into_resolved).transpose()
   }
 
+  async fn fact_lineage(&self, id: Uuid) -> Result<Vec<ResolvedFact>> {
+    let id_str = encode_uuid(id);
+
+    let raws: Vec<RawResolvedFact> = self
+      .conn
+      .call(move |conn| {
+        // `back` walks the supersessions that created each version, `fwd`
+        // the one each version is currently superseded by (its latest
+        // event). Each carries the IDs it has visited, so a cycle written
+        // by an import cannot recurse forever.
+        let mut stmt = conn.prepare(
+          "WITH RECURSIVE
+             back (fact_id, depth, path) AS (
+               SELECT ?1, 0, ?1
+               UNION ALL
+               SELECT s.old_fact_id, b.depth - 1,
+                      b.path || ' ' || s.old_fact_id
+               FROM back b
+               JOIN supersessions s ON s.new_fact_id = b.fact_id
+               WHERE instr(b.path, s.old_fact_id) = 0
+             ),
+             fwd (fact_id, depth, path) AS (
+               SELECT ?1, 0, ?1
+               UNION ALL
+               SELECT e.superseded_by, w.depth + 1,
+                      w.path || ' ' || e.superseded_by
+               FROM fwd w
+               JOIN fact_events e ON e.fact_id = w.fact_id
+               WHERE e.status = 'superseded'
+                 AND instr(w.path, e.superseded_by) = 0
+                 AND NOT EXISTS (
+                   SELECT 1 FROM fact_events l
+                   WHERE l.fact_id = e.fact_id
+                     AND l.seq > e.seq
+                 )
+             ),
+             lineage (fact_id, depth) AS (
+               SELECT fact_id, depth FROM back
+               UNION
+               SELECT fact_id, depth FROM fwd
+             ),
+             events AS (
+               SELECT e.*, ROW_NUMBER() OVER (
+                 PARTITION BY e.fact_id
+                 ORDER BY e.seq DESC
+               ) AS n
+               FROM fact_events e
+               WHERE e.fact_id IN (SELECT fact_id FROM lineage)
+             ),
+             latest AS (SELECT * FROM events WHERE n = 1)
+           SELECT
+             f.fact_id, f.subject_id, f.fact_type, f.value_json,
+             f.recorded_at, f.effective_at, f.effective_until,
+             f.source, f.confidence, f.recording_context, f.tags,
+             st.superseded_by AS superseded_by,
+             CASE st.status WHEN 'superseded' THEN st.recorded_at END
+                              AS superseded_at,
+             st.reason        AS retraction_reason,
+             CASE st.status WHEN 'retracted' THEN st.recorded_at END
+                              AS retracted_at
+           FROM lineage l
+           JOIN facts f ON f.fact_id = l.fact_id
+           LEFT JOIN latest st ON st.fact_id = f.fact_id
+           ORDER BY l.depth",
+        )?;
+
+        let rows = stmt
+          .query_map(rusqlite::params![id_str], |row| {
+            Ok(RawResolvedFact {
+              fact_id:           row.get(0)?,
+              subject_id:        row.get(1)?,
+              fact_type:         row.get(2)?,
+              value_json:        row.get(3)?,
+              recorded_at:       row.get(4)?,
+              effective_at:      row.get(5)?,
+              effective_until:   row.get(6)?,
+              source:            row.get(7)?,
+              confidence:        row.get(8)?,
+              recording_context: row.get(9)?,
+              tags:              row.get(10)?,
+              superseded_by:     row.get(11)?,
+              superseded_at:     row.get(12)?,
+              retraction_reason: row.get(13)?,
+              retracted_at:      row.get(14)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+      })
+      .await?;
+
+    raws
+      .into_iter()
+      .map(RawResolvedFact::into_resolved)
+      .collect()
+  }
+
   // ── Lifecycle events ──────────────────────────────────────────────────────
 
   async fn supersede(
@@ -288,244 +1034,350 @@ impl ContactStore for SqliteStore {
     old_id: Uuid,
     replacement: NewFact,
   ) -> Result<(Supersession, Fact)> {
-    // Build the replacement fact outside the closure so encoding errors
-    // surface here, not inside conn.call.
-    let new_fact = Fact {
-      fact_id:           Uuid::new_v4(),
-      subject_id:        replacement.subject_id,
-      value:             replacement.value,
-      recorded_at:       Utc::now(),
-      effective_at:      replacement.effective_at,
-      effective_until:   replacement.effective_until,
-      source:            replacement.source,
-      confidence:        replacement.confidence,
-      recording_context: replacement.recording_context,
-      tags:              replacement.tags,
-    };
+    let mut applied = self
+      .apply_changeset(Changeset {
+        supersessions: vec![(old_id, replacement)],
+        ..Default::default()
+      })
+      .await?;
+    Ok((applied.supersessions.remove(0), applied.facts.remove(0)))
+  }
 
-    if new_fact.fact_id == old_id {
-      return Err(Error::SelfSupersession);
+  async fn retract(
+    &self,
+    fact_id: Uuid,
+    reason: Option<String>,
+  ) -> Result<Retraction> {
+    let mut applied = self
+      .apply_changeset(Changeset {
+        retractions: vec![(fact_id, reason)],
+        ..Default::default()
+      })
+      .await?;
+    Ok(applied.retractions.remove(0))
+  }
+
+  async fn reinstate(&self, fact_id: Uuid) -> Result<Reinstatement> {
+    let mut applied = self
+      .apply_changeset(Changeset {
+        reinstatements: vec![fact_id],
+        ..Default::default()
+      })
+      .await?;
+    Ok(applied.reinstatements.remove(0))
+  }
+
+  async fn apply_changeset(
+    &self,
+    changeset: Changeset,
+  ) -> Result<AppliedChangeset> {
+    let recorded_at = Utc::now();
+
+    // Build and pre-encode everything (encoding can fail) before moving into
+    // the closure.
+    let mut facts = Vec::new();
+    let mut rows = Vec::new();
+    for input in changeset.new_facts {
+      let fact = stamp_fact(input, recorded_at);
+      rows.push(FactRow::encode(&fact)?);
+      facts.push(fact);
     }
 
-    // Pre-encode all strings (encoding can fail) before moving into the closure.
-    let new_fact_id_str = encode_uuid(new_fact.fact_id);
-    let new_subject_id_str = encode_uuid(new_fact.subject_id);
-    let fact_type = new_fact.value.discriminant().to_owned();
-    let value_json_str = new_fact.value.to_json()?.to_string();
-    let new_recorded_at_str = encode_dt(new_fact.recorded_at);
-    let new_effective_at_str = new_fact
-      .effective_at
-      .as_ref()
-      .map(encode_effective_date)
-      .transpose()?;
-    let new_effective_until_str = new_fact
-      .effective_until
-      .as_ref()
-      .map(encode_effective_date)
-      .transpose()?;
-    let new_confidence_str = new_fact.confidence.to_string();
-    let new_recording_ctx_str =
-      encode_recording_context(&new_fact.recording_context)?;
-    let new_tags_str = encode_tags(&new_fact.tags)?;
-    let new_source = new_fact.source.clone();
-
-    let supersession_id = Uuid::new_v4();
-    let sup_recorded_at = Utc::now();
-    let sup_id_str = encode_uuid(supersession_id);
-    let old_id_str = encode_uuid(old_id);
-    let sup_at_str = encode_dt(sup_recorded_at);
-
-    enum SupersedeOutcome {
-      NotFound,
-      AlreadySuperseded,
-      AlreadyRetracted,
-      Done,
+    let mut supersessions = Vec::new();
+    for (old_fact_id, replacement) in changeset.supersessions {
+      let fact = stamp_fact(replacement, recorded_at);
+      if fact.fact_id == old_fact_id {
+        return Err(Error::SelfSupersession);
+      }
+      rows.push(FactRow::encode(&fact)?);
+      supersessions.push(Supersession {
+        supersession_id: Uuid::new_v4(),
+        old_fact_id,
+        new_fact_id: fact.fact_id,
+        recorded_at,
+      });
+      facts.push(fact);
     }
 
-    let outcome = self
-      .conn
-      .call(move |conn| {
-        let tx = conn.transaction()?;
+    let retractions: Vec<Retraction> = changeset
+      .retractions
+      .into_iter()
+      .map(|(fact_id, reason)| Retraction {
+        retraction_id: Uuid::new_v4(),
+        fact_id,
+        reason,
+        recorded_at,
+      })
+      .collect();
 
-        // Lifecycle check — all three sub-queries inside the same transaction.
-        let (exists_flag, sup_str, ret_str): (
-          Option<i64>,
-          Option<String>,
-          Option<String>,
-        ) = tx.query_row(
-          "SELECT \
-             (SELECT 1 FROM facts WHERE fact_id = ?1), \
-             (SELECT new_fact_id FROM supersessions WHERE old_fact_id = ?1), \
-             (SELECT retraction_id FROM retractions WHERE fact_id = ?1)",
-          rusqlite::params![&old_id_str],
-          |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
-        )?;
+    let reinstatements: Vec<Reinstatement> = changeset
+      .reinstatements
+      .into_iter()
+      .map(|fact_id| Reinstatement {
+        reinstatement_id: Uuid::new_v4(),
+        fact_id,
+        recorded_at,
+      })
+      .collect();
 
-        if exists_flag.is_none() {
-          return Ok(SupersedeOutcome::NotFound);
+    let subjects: Vec<Subject> = changeset
+      .new_subjects
+      .into_iter()
+      .map(|(subject_id, kind)| Subject {
+        subject_id,
+        created_at: recorded_at,
+        kind,
+      })
+      .collect();
+    let resources: Vec<Resource> = changeset
+      .resources
+      .into_iter()
+      .map(|input| Resource {
+        subject_id: input.subject_id,
+        name: input.name,
+        uid: input.uid,
+        recorded_at,
+      })
+      .collect();
+    let memberships: Vec<_> = changeset
+      .addressbook_adds
+      .into_iter()
+      .map(|(book, subject)| (book, subject, MembershipAction::Add))
+      .collect();
+    let removals: Vec<_> = changeset
+      .addressbook_removals
+      .into_iter()
+      .map(|(book, subject)| (book, subject, MembershipAction::Remove))
+      .collect();
+    let at_str = encode_dt(recorded_at);
+
+    let written = facts.clone();
+    let lifecycle = supersessions
+      .iter()
+      .cloned()
+      .map(LifecycleEvent::Supersession)
+      .chain(retractions.iter().cloned().map(LifecycleEvent::Retraction))
+      .chain(
+        reinstatements
+          .iter()
+          .cloned()
+          .map(LifecycleEvent::Reinstatement),
+      )
+      .collect();
+    let sender = self.events.clone();
+    self
+      .conn
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+        // Returning early drops `tx`, which rolls back everything written so
+        // far.
+        for subject in &subjects {
+          insert_subject(&tx, subject)?;
         }
-        if sup_str.is_some() {
-          return Ok(SupersedeOutcome::AlreadySuperseded);
+        for resource in resources {
+          if let Err(e) = insert_resource(&tx, resource)? {
+            return Ok(Err(e));
+          }
         }
-        if ret_str.is_some() {
-          return Ok(SupersedeOutcome::AlreadyRetracted);
+        for (book, subject, action) in memberships {
+          if let Err(e) =
+            insert_membership(&tx, book, subject, action, &at_str)?
+          {
+            return Ok(Err(e));
+          }
         }
-
-        // Insert the replacement fact inside the transaction.
-        tx.execute(
-          "INSERT INTO facts (
-             fact_id, subject_id, fact_type, value_json, recorded_at,
-             effective_at, effective_until, source,
-             confidence, recording_context, tags
-           ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
-          rusqlite::params![
-            &new_fact_id_str,
-            &new_subject_id_str,
-            &fact_type,
-            &value_json_str,
-            &new_recorded_at_str,
-            &new_effective_at_str,
-            &new_effective_until_str,
-            &new_source,
-            &new_confidence_str,
-            &new_recording_ctx_str,
-            &new_tags_str,
-          ],
-        )?;
-
-        // Insert the supersession record.  A UNIQUE constraint violation on
-        // old_fact_id means a concurrent task already superseded this fact.
-        // Rolling back the transaction prevents an orphan replacement fact.
-        match tx.execute(
-          "INSERT INTO supersessions \
-             (supersession_id, old_fact_id, new_fact_id, recorded_at) \
-             VALUES (?1, ?2, ?3, ?4)",
-          rusqlite::params![
-            &sup_id_str,
-            &old_id_str,
-            &new_fact_id_str,
-            &sup_at_str,
-          ],
-        ) {
-          Ok(_) => {}
-          Err(rusqlite::Error::SqliteFailure(ref err, _))
-            if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+        let events = match write_log(&tx, &rows, written, lifecycle)? {
+          Ok(events) => events,
+          Err((fact_id, conflict)) => {
+            return Ok(Err(conflict.into_error(fact_id)));
+          }
+        };
+        for (book, subject, action) in removals {
+          if let Err(e) =
+            insert_membership(&tx, book, subject, action, &at_str)?
           {
-            // tx drops here → rolls back the fact INSERT (no orphan fact).
-            return Ok(SupersedeOutcome::AlreadySuperseded);
+            return Ok(Err(e));
           }
-          Err(e) => return Err(e.into()),
         }
-
         tx.commit()?;
-        Ok(SupersedeOutcome::Done)
+        publish(&sender, events);
+        Ok(Ok(()))
+      })
+      .await??;
+
+    Ok(AppliedChangeset {
+      facts,
+      supersessions,
+      retractions,
+      reinstatements,
+    })
+  }
+
+  // ── Import sources ────────────────────────────────────────────────────────
+
+  async fn import_sources(&self) -> Result<Vec<ImportSource>> {
+    let rows: Vec<(String, i64, i64)> = self
+      .conn
+      .call(|conn| {
+        let mut stmt = conn.prepare(&format!(
+          "SELECT json_extract(f.recording_context, '$.source_name') AS name,
+                  COUNT(*),
+                  SUM(COALESCE({LATEST_STATUS}, 'active') = 'active')
+           FROM facts f
+           WHERE json_extract(f.recording_context, '$.kind') = 'imported'
+           GROUP BY name
+           ORDER BY name"
+        ))?;
+        let rows = stmt
+          .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
       })
       .await?;
 
-    match outcome {
-      SupersedeOutcome::NotFound => Err(Error::FactNotFound(old_id)),
-      SupersedeOutcome::AlreadySuperseded => {
-        Err(Error::AlreadySuperseded(old_id))
-      }
-      SupersedeOutcome::AlreadyRetracted => {
-        Err(Error::AlreadyRetracted(old_id))
-      }
-      SupersedeOutcome::Done => {
-        let supersession = Supersession {
-          supersession_id,
-          old_fact_id: old_id,
-          new_fact_id: new_fact.fact_id,
-          recorded_at: sup_recorded_at,
-        };
-        Ok((supersession, new_fact))
-      }
-    }
+    Ok(
+      rows
+        .into_iter()
+        .map(|(source_name, facts, active_facts)| ImportSource {
+          source_name,
+          facts: facts as usize,
+          active_facts: active_facts as usize,
+        })
+        .collect(),
+    )
   }
 
-  async fn retract(
+  async fn import_facts(&self, source_name: &str) -> Result<Vec<ResolvedFact>> {
+    let source_name = source_name.to_string();
+
+    let raws: Vec<RawResolvedFact> = self
+      .conn
+      .call(move |conn| {
+        let mut stmt = conn.prepare(&format!(
+          "SELECT f.fact_id, f.subject_id, f.fact_type, f.value_json,
+                  f.recorded_at, f.effective_at, f.effective_until,
+                  f.source, f.confidence, f.recording_context, f.tags
+           FROM facts f
+           WHERE {FROM_IMPORT_SOURCE}
+             AND COALESCE({LATEST_STATUS}, 'active') = 'active'
+           ORDER BY f.rowid"
+        ))?;
+
+        // Only active facts are returned, so there is no status to join.
+        let rows = stmt
+          .query_map(rusqlite::params![source_name], |row| {
+            Ok(RawResolvedFact {
+              fact_id:           row.get(0)?,
+              subject_id:        row.get(1)?,
+              fact_type:         row.get(2)?,
+              value_json:        row.get(3)?,
+              recorded_at:       row.get(4)?,
+              effective_at:      row.get(5)?,
+              effective_until:   row.get(6)?,
+              source:            row.get(7)?,
+              confidence:        row.get(8)?,
+              recording_context: row.get(9)?,
+              tags:              row.get(10)?,
+              superseded_by:     None,
+              superseded_at:     None,
+              retraction_reason: None,
+              retracted_at:      None,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+      })
+      .await?;
+
+    raws
+      .into_iter()
+      .map(RawResolvedFact::into_resolved)
+      .collect()
+  }
+
+  async fn retract_import(
     &self,
-    fact_id: Uuid,
+    source_name: String,
     reason: Option<String>,
-  ) -> Result<Retraction> {
-    let retraction_id = Uuid::new_v4();
-    let ret_recorded_at = Utc::now();
-    let ret_id_str = encode_uuid(retraction_id);
-    let fact_id_str = encode_uuid(fact_id);
-    let at_str = encode_dt(ret_recorded_at);
-    let reason_for_insert = reason.clone();
-
-    enum RetractOutcome {
-      NotFound,
-      AlreadySuperseded,
-      AlreadyRetracted,
-      Done,
-    }
+  ) -> Result<Vec<Retraction>> {
+    let recorded_at = Utc::now();
+    let sender = self.events.clone();
 
-    let outcome = self
+    let (retractions, conflict) = self
       .conn
       .call(move |conn| {
         let tx = conn.transaction()?;
+        // The facts are picked inside the transaction, so none can change
+        // status between being picked and retracted.
+        let fact_ids = tx
+          .prepare(&format!(
+            "SELECT f.fact_id FROM facts f
+             WHERE {FROM_IMPORT_SOURCE}
+               AND COALESCE({LATEST_STATUS}, 'active') = 'active'
+             ORDER BY f.rowid"
+          ))?
+          .query_map(rusqlite::params![source_name], |r| r.get::<_, String>(0))?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
 
-        let (exists_flag, sup_str, ret_str): (
-          Option<i64>,
-          Option<String>,
-          Option<String>,
-        ) = tx.query_row(
-          "SELECT \
-             (SELECT 1 FROM facts WHERE fact_id = ?1), \
-             (SELECT new_fact_id FROM supersessions WHERE old_fact_id = ?1), \
-             (SELECT retraction_id FROM retractions WHERE fact_id = ?1)",
-          rusqlite::params![&fact_id_str],
-          |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
-        )?;
-
-        if exists_flag.is_none() {
-          return Ok(RetractOutcome::NotFound);
-        }
-        if sup_str.is_some() {
-          return Ok(RetractOutcome::AlreadySuperseded);
-        }
-        if ret_str.is_some() {
-          return Ok(RetractOutcome::AlreadyRetracted);
-        }
-
-        // A UNIQUE constraint violation on fact_id means a concurrent task
-        // already retracted this fact.
-        match tx.execute(
-          "INSERT INTO retractions \
-             (retraction_id, fact_id, reason, recorded_at) \
-             VALUES (?1, ?2, ?3, ?4)",
-          rusqlite::params![&ret_id_str, &fact_id_str, &reason_for_insert, &at_str],
-        ) {
-          Ok(_) => {}
-          Err(rusqlite::Error::SqliteFailure(ref err, _))
-            if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
-          {
-            return Ok(RetractOutcome::AlreadyRetracted);
-          }
-          Err(e) => return Err(e.into()),
+        let mut retractions = Vec::with_capacity(fact_ids.len());
+        for fact_id in fact_ids {
+          let fact_id = Uuid::parse_str(&fact_id).map_err(|e| {
+            rusqlite::Error::FromSqlConversionFailure(
+              0,
+              rusqlite::types::Type::Text,
+              Box::new(e),
+            )
+          })?;
+          retractions.push(Retraction {
+            retraction_id: Uuid::new_v4(),
+            fact_id,
+            reason: reason.clone(),
+            recorded_at,
+          });
         }
 
+        let lifecycle = retractions
+          .iter()
+          .cloned()
+          .map(LifecycleEvent::Retraction)
+          .collect();
+        let events = match write_log(&tx, &[], Vec::new(), lifecycle)? {
+          Ok(events) => events,
+          Err(conflict) => return Ok((Vec::new(), Some(conflict))),
+        };
         tx.commit()?;
-        Ok(RetractOutcome::Done)
+        publish(&sender, events);
+        Ok((retractions, None))
       })
       .await?;
 
-    match outcome {
-      RetractOutcome::NotFound => Err(Error::FactNotFound(fact_id)),
-      RetractOutcome::AlreadySuperseded => {
-        Err(Error::AlreadySuperseded(fact_id))
-      }
-      RetractOutcome::AlreadyRetracted => {
-        Err(Error::AlreadyRetracted(fact_id))
-      }
-      RetractOutcome::Done => Ok(Retraction {
-        retraction_id,
-        fact_id,
-        reason,
-        recorded_at: ret_recorded_at,
-      }),
+    if let Some((fact_id, conflict)) = conflict {
+      return Err(conflict.into_error(fact_id));
     }
+    Ok(retractions)
+  }
+
+  // ── Subject merges ────────────────────────────────────────────────────────
+
+  async fn merge_subjects(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+  ) -> Result<SubjectMerge> {
+    self
+      .record_merge(survivor, absorbed, MergeAction::Merge)
+      .await
+  }
+
+  async fn unmerge_subjects(
+    &self,
+    survivor: Uuid,
+    absorbed: Uuid,
+  ) -> Result<SubjectMerge> {
+    self
+      .record_merge(survivor, absorbed, MergeAction::Unmerge)
+      .await
   }
 
   // ── Reads ─────────────────────────────────────────────────────────────────
@@ -534,50 +1386,77 @@ impl ContactStore for SqliteStore {
     &self,
     subject_id: Uuid,
     as_of: Option<chrono::DateTime<Utc>>,
+    valid_at: Option<chrono::DateTime<Utc>>,
     include_inactive: bool,
   ) -> Result<Vec<ResolvedFact>> {
     let subject_id_str = encode_uuid(subject_id);
     let as_of_str = encode_dt(as_of.unwrap_or_else(Utc::now));
+    let valid_at_str = valid_at.map(encode_dt);
 
     let raws: Vec<RawResolvedFact> = self
       .conn
       .call(move |conn| {
-        let mut stmt = conn.prepare(
-          "SELECT
+        // Lifecycle events recorded after `as_of` were not yet known then,
+        // so they are left out before picking each fact's latest rather than
+        // filtered from the result. Merges recorded after it are left out of
+        // the tree likewise.
+        let mut stmt = conn.prepare(&format!(
+          "{MERGE_TREE},
+           events AS (
+             SELECT e.*, ROW_NUMBER() OVER (
+               PARTITION BY e.fact_id
+               ORDER BY e.seq DESC
+             ) AS n
+             FROM fact_events e
+             JOIN facts ef ON ef.fact_id = e.fact_id
+             WHERE e.recorded_at <= ?2
+               AND ef.subject_id IN (SELECT subject_id FROM tree)
+           ),
+           latest AS (SELECT * FROM events WHERE n = 1)
+           SELECT
              f.fact_id, f.subject_id, f.fact_type, f.value_json,
              f.recorded_at, f.effective_at, f.effective_until,
              f.source, f.confidence, f.recording_context, f.tags,
-             s.new_fact_id   AS superseded_by,
-             s.recorded_at   AS superseded_at,
-             r.reason        AS retraction_reason,
-             r.recorded_at   AS retracted_at
+             st.superseded_by AS superseded_by,
+             CASE st.status WHEN 'superseded' THEN st.recorded_at END
+                              AS superseded_at,
+             st.reason        AS retraction_reason,
+             CASE st.status WHEN 'retracted' THEN st.recorded_at END
+                              AS retracted_at
            FROM facts f
-           LEFT JOIN supersessions s ON s.old_fact_id = f.fact_id
-           LEFT JOIN retractions   r ON r.fact_id     = f.fact_id
-           WHERE f.subject_id = ?1
-             AND f.recorded_at <= ?2",
-        )?;
+           LEFT JOIN latest st ON st.fact_id = f.fact_id
+           WHERE f.subject_id IN (SELECT subject_id FROM tree)
+             AND f.recorded_at <= ?2
+             AND (?3 IS NULL OR (
+               (f.effective_start IS NULL OR f.effective_start <= ?3)
+               AND (f.effective_end IS NULL OR f.effective_end > ?3)
+             ))
+           ORDER BY f.rowid"
+        ))?;
 
         let rows = stmt
-          .query_map(rusqlite::params![subject_id_str, as_of_str], |row| {
-            Ok(RawResolvedFact {
-              fact_id:           row.get(0)?,
-              subject_id:        row.get(1)?,
-              fact_type:         row.get(2)?,
-              value_json:        row.get(3)?,
-              recorded_at:       row.get(4)?,
-              effective_at:      row.get(5)?,
-              effective_until:   row.get(6)?,
-              source:            row.get(7)?,
-              confidence:        row.get(8)?,
-              recording_context: row.get(9)?,
-              tags:              row.get(10)?,
-              superseded_by:     row.get(11)?,
-              superseded_at:     row.get(12)?,
-              retraction_reason: row.get(13)?,
-              retracted_at:      row.get(14)?,
-            })
-          })?
+          .query_map(
+            rusqlite::params![subject_id_str, as_of_str, valid_at_str],
+            |row| {
+              Ok(RawResolvedFact {
+                fact_id:           row.get(0)?,
+                subject_id:        row.get(1)?,
+                fact_type:         row.get(2)?,
+                value_json:        row.get(3)?,
+                recorded_at:       row.get(4)?,
+                effective_at:      row.get(5)?,
+                effective_until:   row.get(6)?,
+                source:            row.get(7)?,
+                confidence:        row.get(8)?,
+                recording_context: row.get(9)?,
+                tags:              row.get(10)?,
+                superseded_by:     row.get(11)?,
+                superseded_at:     row.get(12)?,
+                retraction_reason: row.get(13)?,
+                retracted_at:      row.get(14)?,
+              })
+            },
+          )?
           .collect::<rusqlite::Result<Vec<_>>>()?;
 
         Ok(rows)
@@ -600,115 +1479,1268 @@ impl ContactStore for SqliteStore {
     &self,
     subject_id: Uuid,
     as_of: Option<chrono::DateTime<Utc>>,
+    valid_at: Option<chrono::DateTime<Utc>>,
   ) -> Result<Option<ContactView>> {
-    let subject = match self.get_subject(subject_id).await? {
+    let as_of_resolved = as_of.unwrap_or_else(Utc::now);
+    let subject = match self.resolve_subject(subject_id, as_of_resolved).await?
+    {
       Some(s) => s,
       None => return Ok(None),
     };
 
-    let as_of_resolved = as_of.unwrap_or_else(Utc::now);
     let active_facts = self
-      .get_facts(subject_id, Some(as_of_resolved), false)
+      .get_facts(subject.subject_id, Some(as_of_resolved), valid_at, false)
       .await?;
 
     Ok(Some(ContactView {
       subject,
       as_of: as_of_resolved,
+      valid_at,
       active_facts,
     }))
   }
 
-  async fn collection_ctag(
+  async fn search(&self, query: &FactQuery) -> Result<Vec<Subject>> {
+    use rusqlite::types::Value;
+
+    // Fact-level conditions all apply to the same fact row. Matching facts
+    // are grouped per subject, counting an absorbed subject's facts towards
+    // the subject it was merged into; with a text query each subject is scored
+    // by its best-ranked fact (bm25: lower is more relevant). FTS5 refuses
+    // bm25() inside an aggregate, so hits are scored in a materialised CTE
+    // first. Parameters are positional and pushed in the same order as their
+    // placeholders.
+    let fts = match query.text.as_deref().map(fts_query) {
+      // Text with no terms to look for matches nothing, not everything.
+      Some(None) => return Ok(vec![]),
+      Some(fts) => fts,
+      None => None,
+    };
+    let mut fact_conds: Vec<String> = vec![];
+    let mut fact_params: Vec<Value> = vec![];
+
+    if !query.fact_types.is_empty() {
+      let marks = vec!["?"; query.fact_types.len()].join(", ");
+      fact_conds.push(format!("f.fact_type IN ({marks})"));
+      fact_params.extend(query.fact_types.iter().cloned().map(Value::Text));
+    }
+    for tag in &query.tags {
+      fact_conds.push(
+        "EXISTS (SELECT 1 FROM json_each(f.tags) t WHERE t.value = ?)"
+          .to_owned(),
+      );
+      fact_params.push(Value::Text(tag.clone()));
+    }
+    if let Some(confidence) = query.confidence {
+      fact_conds.push("f.confidence = ?".to_owned());
+      fact_params.push(Value::Text(confidence.to_string()));
+    }
+    if let Some(after) = query.recorded_after {
+      fact_conds.push("f.recorded_at > ?".to_owned());
+      fact_params.push(Value::Text(encode_dt(after)));
+    }
+    if let Some(before) = query.recorded_before {
+      fact_conds.push("f.recorded_at < ?".to_owned());
+      fact_params.push(Value::Text(encode_dt(before)));
+    }
+    if query.active_only {
+      fact_conds
+        .push(format!("COALESCE({LATEST_STATUS}, 'active') = 'active'"));
+    }
+
+    let mut params: Vec<Value> = vec![];
+    let mut hits_cte = String::new();
+    let (from, score) = match fts {
+      Some(fts) => {
+        hits_cte.push_str(
+          "WITH hits AS MATERIALIZED (
+             SELECT fact_id, bm25(facts_fts) AS score
+             FROM facts_fts WHERE facts_fts MATCH ?
+           )",
+        );
+        params.push(Value::Text(fts));
+        ("hits JOIN facts f ON f.fact_id = hits.fact_id", "MIN(hits.score)")
+      }
+      None => ("facts f", "0"),
+    };
+
+    let (matches_join, order_by) = if hits_cte.is_empty()
+      && fact_conds.is_empty()
+    {
+      (String::new(), "s.created_at, s.subject_id")
+    } else {
+      let fact_where = if fact_conds.is_empty() {
+        String::new()
+      } else {
+        format!("WHERE {}", fact_conds.join(" AND "))
+      };
+      params.extend(fact_params);
+      (
+        format!(
+          "JOIN (SELECT r.root_id AS subject_id, {score} AS score
+                 FROM {from}
+                 JOIN subject_roots r ON r.subject_id = f.subject_id
+                 {fact_where}
+                 GROUP BY r.root_id) m
+             ON m.subject_id = s.subject_id"
+        ),
+        "m.score, s.created_at, s.subject_id",
+      )
+    };
+
+    let mut where_clause = String::from(
+      "WHERE s.subject_id NOT IN (SELECT absorbed_id FROM merged_subjects)",
+    );
+    if let Some(kind) = query.kind {
+      where_clause.push_str(" AND s.kind = ?");
+      params.push(Value::Text(kind.to_string()));
+    }
+    params.push(Value::Integer(query.limit.unwrap_or(100) as i64));
+    params.push(Value::Integer(query.offset.unwrap_or(0) as i64));
+
+    let sql = format!(
+      "{hits_cte}
+       SELECT s.subject_id, s.created_at, s.kind
+       FROM subjects s
+       {matches_join}
+       {where_clause}
+       ORDER BY {order_by}
+       LIMIT ? OFFSET ?"
+    );
+
+    let raws: Vec<RawSubject> = self
+      .conn
+      .call(move |conn| {
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt
+          .query_map(rusqlite::params_from_iter(params), |row| {
+            Ok(RawSubject {
+              subject_id: row.get(0)?,
+              created_at: row.get(1)?,
+              kind:       row.get(2)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok(rows)
+      })
+      .await?;
+
+    raws.into_iter().map(RawSubject::into_subject).collect()
+  }
+
+  // ── Address books ─────────────────────────────────────────────────────────
+
+  async fn create_addressbook(
     &self,
-  ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
-    let raw: Option<Option<String>> = self
+    input: NewAddressBook,
+  ) -> Result<AddressBook> {
+    let book = AddressBook {
+      addressbook_id: Uuid::new_v4(),
+      name:           input.name,
+      display_name:   input.display_name,
+      description:    input.description,
+      is_default:     false,
+      created_at:     Utc::now(),
+    };
+
+    let id_str = encode_uuid(book.addressbook_id);
+    let at_str = encode_dt(book.created_at);
+    let name = book.name.clone();
+    let display_name = book.display_name.clone();
+    let description = book.description.clone();
+
+    let inserted = self
       .conn
-      .call(|conn| {
+      .call(move |conn| {
+        match conn.execute(
+          "INSERT INTO addressbooks \
+             (addressbook_id, name, display_name, description, created_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+          rusqlite::params![id_str, name, display_name, description, at_str],
+        ) {
+          Ok(_) => Ok(true),
+          Err(rusqlite::Error::SqliteFailure(ref err, _))
+            if err.extended_code == rusqlite::ffi::SQLITE_CONSTRAINT_UNIQUE =>
+          {
+            Ok(false)
+          }
+          Err(e) => Err(e.into()),
+        }
+      })
+      .await?;
+
+    if !inserted {
+      return Err(Error::AddressBookExists(book.name));
+    }
+    Ok(book)
+  }
+
+  async fn get_addressbook(&self, name: &str) -> Result<Option<AddressBook>> {
+    let name = name.to_owned();
+
+    let raw: Option<RawAddressBook> = self
+      .conn
+      .call(move |conn| {
         Ok(
           conn
             .query_row(
-              "SELECT MAX(ts) FROM (
-                 SELECT f.recorded_at AS ts
-                 FROM facts f
-                 JOIN subjects s ON s.subject_id = f.subject_id
-                 WHERE s.kind = 'person'
-                 UNION ALL
-                 SELECT r.recorded_at AS ts
-                 FROM retractions r
-                 JOIN facts f ON f.fact_id = r.fact_id
-                 JOIN subjects s ON s.subject_id = f.subject_id
-                 WHERE s.kind = 'person'
-               )",
-              [],
-              |row| row.get::<_, Option<String>>(0),
+              &format!(
+                "SELECT {} FROM addressbooks WHERE name = ?1",
+                RawAddressBook::COLUMNS
+              ),
+              rusqlite::params![name],
+              RawAddressBook::from_row,
             )
             .optional()?,
         )
       })
       .await?;
 
-    raw
-      .flatten()
-      .map(|s| crate::encode::decode_dt(&s))
-      .transpose()
+    raw.map(RawAddressBook::into_addressbook).transpose()
   }
 
-  async fn search(&self, query: &FactQuery) -> Result<Vec<Subject>> {
-    // Phase 1: SQL LIKE over value_json + optional subject-kind filter.
-    let text_pattern = query.text.as_deref().map(|t| format!("%{t}%"));
-    let kind_str = query.kind.map(|k| k.to_string());
-    let limit_val = query.limit.unwrap_or(100) as i64;
-    let offset_val = query.offset.unwrap_or(0) as i64;
-
-    let raws: Vec<RawSubject> = self
+  async fn list_addressbooks(&self) -> Result<Vec<AddressBook>> {
+    let raws: Vec<RawAddressBook> = self
       .conn
-      .call(move |conn| {
-        // Build WHERE clause dynamically.
-        let mut conds: Vec<&'static str> = vec![];
-        if text_pattern.is_some() {
-          conds.push("f.value_json LIKE ?1");
-        }
-        if kind_str.is_some() {
-          conds.push("s.kind = ?2");
-        }
-
-        let where_clause = if conds.is_empty() {
-          String::new()
-        } else {
-          format!("WHERE {}", conds.join(" AND "))
-        };
-
-        let sql = format!(
-          "SELECT DISTINCT s.subject_id, s.created_at, s.kind
-           FROM subjects s
-           LEFT JOIN facts f ON f.subject_id = s.subject_id
-           {where_clause}
-           LIMIT ?3 OFFSET ?4"
-        );
-
-        let mut stmt = conn.prepare(&sql)?;
+      .call(|conn| {
+        let mut stmt = conn.prepare(&format!(
+          "SELECT {} FROM addressbooks ORDER BY name",
+          RawAddressBook::COLUMNS
+        ))?;
         let rows = stmt
-          .query_map(
-            rusqlite::params![
-              text_pattern.as_deref(),
-              kind_str.as_deref(),
-              limit_val,
-              offset_val,
-            ],
-            |row| {
-              Ok(RawSubject {
-                subject_id: row.get(0)?,
-                created_at: row.get(1)?,
-                kind:       row.get(2)?,
-              })
-            },
-          )?
+          .query_map([], RawAddressBook::from_row)?
           .collect::<rusqlite::Result<Vec<_>>>()?;
-
         Ok(rows)
       })
       .await?;
 
-    raws.into_iter().map(RawSubject::into_subject).collect()
+    raws
+      .into_iter()
+      .map(RawAddressBook::into_addressbook)
+      .collect()
   }
+
+  async fn set_default_addressbook(&self, addressbook_id: Uuid) -> Result<()> {
+    let id_str = encode_uuid(addressbook_id);
+
+    let found = self
+      .conn
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+        // Clear first: the partial unique index allows one default at a time.
+        tx.execute(
+          "UPDATE addressbooks SET is_default = 0 \
+           WHERE is_default AND addressbook_id != ?1",
+          rusqlite::params![id_str],
+        )?;
+        let updated = tx.execute(
+          "UPDATE addressbooks SET is_default = 1 WHERE addressbook_id = ?1",
+          rusqlite::params![id_str],
+        )?;
+        if updated == 0 {
+          // Dropping the transaction rolls back the clear.
+          return Ok(false);
+        }
+        tx.commit()?;
+        Ok(true)
+      })
+      .await?;
+
+    if !found {
+      return Err(Error::AddressBookNotFound(addressbook_id));
+    }
+    Ok(())
+  }
+
+  async fn add_to_addressbook(
+    &self,
+    addressbook_id: Uuid,
+    subject_id: Uuid,
+  ) -> Result<()> {
+    self
+      .record_membership(addressbook_id, subject_id, MembershipAction::Add)
+      .await
+  }
+
+  async fn remove_from_addressbook(
+    &self,
+    addressbook_id: Uuid,
+    subject_id: Uuid,
+  ) -> Result<()> {
+    self
+      .record_membership(addressbook_id, subject_id, MembershipAction::Remove)
+      .await
+  }
+
+  async fn addressbook_members(
+    &self,
+    addressbook_id: Uuid,
+  ) -> Result<Vec<Subject>> {
+    let id_str = encode_uuid(addressbook_id);
+
+    let raws: Vec<RawSubject> = self
+      .conn
+      .call(move |conn| {
+        let mut stmt = conn.prepare(
+          "SELECT s.subject_id, s.created_at, s.kind
+           FROM subjects s
+           JOIN addressbook_members m ON m.subject_id = s.subject_id
+           WHERE m.addressbook_id = ?1
+           ORDER BY s.created_at, s.subject_id",
+        )?;
+        let rows = stmt
+          .query_map(rusqlite::params![id_str], |row| {
+            Ok(RawSubject {
+              subject_id: row.get(0)?,
+              created_at: row.get(1)?,
+              kind:       row.get(2)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+      })
+      .await?;
+
+    raws.into_iter().map(RawSubject::into_subject).collect()
+  }
+
+  async fn subject_addressbooks(
+    &self,
+    subject_id: Uuid,
+  ) -> Result<Vec<AddressBook>> {
+    let id_str = encode_uuid(subject_id);
+
+    let raws: Vec<RawAddressBook> = self
+      .conn
+      .call(move |conn| {
+        let mut stmt = conn.prepare(&format!(
+          "SELECT {} FROM addressbooks
+           WHERE addressbook_id IN (
+             SELECT addressbook_id FROM addressbook_members
+             WHERE subject_id = ?1
+           )
+           ORDER BY name",
+          RawAddressBook::COLUMNS
+        ))?;
+        let rows = stmt
+          .query_map(rusqlite::params![id_str], RawAddressBook::from_row)?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+      })
+      .await?;
+
+    raws
+      .into_iter()
+      .map(RawAddressBook::into_addressbook)
+      .collect()
+  }
+
+  async fn sync_token(&self) -> Result<SyncToken> {
+    Ok(self.conn.call(|conn| Ok(current_sync_token(conn)?)).await?)
+  }
+
+  async fn addressbook_changes(
+    &self,
+    addressbook_id: Uuid,
+    since: Option<SyncToken>,
+  ) -> Result<AddressBookChanges> {
+    let book_str = encode_uuid(addressbook_id);
+    let from = since.unwrap_or_default();
+
+    // One closure on the single connection, so no write can land between
+    // reading the token and listing the changes it covers.
+    let (token, rows) = self
+      .conn
+      .call(move |conn| {
+        let token = current_sync_token(conn)?;
+        let mut stmt = conn.prepare(&format!(
+          "WITH raw AS (
+             SELECT subject_id FROM facts WHERE rowid > ?2
+             UNION
+             SELECT f.subject_id
+             FROM supersessions sp
+             JOIN facts f ON f.fact_id = sp.old_fact_id
+             WHERE sp.rowid > ?3
+             UNION
+             SELECT f.subject_id
+             FROM retractions r
+             JOIN facts f ON f.fact_id = r.fact_id
+             WHERE r.rowid > ?4
+             UNION
+             SELECT f.subject_id
+             FROM reinstatements ri
+             JOIN facts f ON f.fact_id = ri.fact_id
+             WHERE ri.rowid > ?7
+             UNION
+             -- Any membership event can change the default book's
+             -- implicit members.
+             SELECT e.subject_id
+             FROM addressbook_membership e
+             WHERE e.seq > ?5
+               AND (e.addressbook_id = ?1
+                    OR (SELECT is_default FROM addressbooks
+                        WHERE addressbook_id = ?1))
+             UNION
+             -- A merge or unmerge changes both of its subjects.
+             SELECT survivor_id FROM subject_merges WHERE seq > ?6
+             UNION
+             SELECT absorbed_id FROM subject_merges WHERE seq > ?6
+           ),
+           touched AS (
+             -- A change to an absorbed subject shows on its root.
+             SELECT subject_id FROM raw
+             UNION
+             SELECT r.root_id
+             FROM raw JOIN subject_roots r ON r.subject_id = raw.subject_id
+           )
+           SELECT t.subject_id,
+                  EXISTS (
+                    SELECT 1 FROM addressbook_members m
+                    WHERE m.addressbook_id = ?1
+                      AND m.subject_id = t.subject_id
+                  )
+                  AND EXISTS (
+                    SELECT 1 FROM subject_roots r
+                    JOIN facts f ON f.subject_id = r.subject_id
+                    WHERE r.root_id = t.subject_id
+                      AND COALESCE({LATEST_STATUS}, 'active') = 'active'
+                  ) AS visible
+           FROM touched t
+           ORDER BY t.subject_id",
+        ))?;
+        let rows = stmt
+          .query_map(
+            rusqlite::params![
+              book_str,
+              from.facts,
+              from.supersessions,
+              from.retractions,
+              from.memberships,
+              from.merges,
+              from.reinstatements,
+            ],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+          )?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok((token, rows))
+      })
+      .await?;
+
+    if let Some(since) = since
+      && !since.is_at_or_before(&token)
+    {
+      return Err(kith_core::Error::InvalidSyncToken(since.to_string()).into());
+    }
+
+    let mut changes = AddressBookChanges {
+      token,
+      changed: Vec::new(),
+      removed: Vec::new(),
+    };
+    for (subject_id, visible) in rows {
+      let subject_id = crate::encode::decode_uuid(&subject_id)?;
+      if visible {
+        changes.changed.push(subject_id);
+      } else if since.is_some() {
+        changes.removed.push(subject_id);
+      }
+    }
+    Ok(changes)
+  }
+
+  async fn collection_ctag(
+    &self,
+    addressbook_id: Uuid,
+  ) -> Result<Option<chrono::DateTime<chrono::Utc>>> {
+    let id_str = encode_uuid(addressbook_id);
+
+    let raw: Option<Option<String>> = self
+      .conn
+      .call(move |conn| {
+        Ok(
+          conn
+            .query_row(
+              // The default book also changes when a visible contact is
+              // first assigned elsewhere and so leaves its implicit members.
+              // Facts written with the assignment were never visible there.
+              // Merges are rare, so every book counts all of them rather
+              // than working out which books each one touched.
+              "SELECT MAX(ts) FROM (
+                 SELECT f.recorded_at AS ts
+                 FROM facts f
+                 JOIN subject_roots sr ON sr.subject_id = f.subject_id
+                 JOIN addressbook_members m ON m.subject_id = sr.root_id
+                 WHERE m.addressbook_id = ?1
+                 UNION ALL
+                 SELECT r.recorded_at AS ts
+                 FROM retractions r
+                 JOIN facts f ON f.fact_id = r.fact_id
+                 JOIN subject_roots sr ON sr.subject_id = f.subject_id
+                 JOIN addressbook_members m ON m.subject_id = sr.root_id
+                 WHERE m.addressbook_id = ?1
+                 UNION ALL
+                 SELECT ri.recorded_at AS ts
+                 FROM reinstatements ri
+                 JOIN facts f ON f.fact_id = ri.fact_id
+                 JOIN subject_roots sr ON sr.subject_id = f.subject_id
+                 JOIN addressbook_members m ON m.subject_id = sr.root_id
+                 WHERE m.addressbook_id = ?1
+                 UNION ALL
+                 SELECT e.recorded_at AS ts
+                 FROM addressbook_membership e
+                 WHERE e.addressbook_id = ?1
+                    OR ((SELECT is_default FROM addressbooks
+                         WHERE addressbook_id = ?1)
+                        AND e.seq = (SELECT MIN(seq)
+                                     FROM addressbook_membership
+                                     WHERE subject_id = e.subject_id)
+                        AND EXISTS (SELECT 1 FROM facts f
+                                    WHERE f.subject_id = e.subject_id
+                                      AND f.recorded_at < e.recorded_at))
+                 UNION ALL
+                 SELECT recorded_at AS ts FROM subject_merges
+               )",
+              rusqlite::params![id_str],
+              |row| row.get::<_, Option<String>>(0),
+            )
+            .optional()?,
+        )
+      })
+      .await?;
+
+    raw
+      .flatten()
+      .map(|s| crate::encode::decode_dt(&s))
+      .transpose()
+  }
+
+  // ── Change events ─────────────────────────────────────────────────────────
+
+  fn subscribe(&self) -> broadcast::Receiver<StoreEvent> {
+    self.events.subscribe()
+  }
+
+  async fn events_since(
+    &self,
+    after: EventId,
+    limit: usize,
+  ) -> Result<Vec<StoreEvent>> {
+    let limit = i64::try_from(limit).unwrap_or(i64::MAX);
+    let (fact_rows, sup_rows, ret_rows, rein_rows) = self
+      .conn
+      .call(move |conn| {
+        let facts = conn
+          .prepare(
+            "SELECT rowid, fact_id, subject_id, fact_type, value_json,
+                    recorded_at, effective_at, effective_until,
+                    source, confidence, recording_context, tags
+             FROM facts WHERE rowid > ?1 ORDER BY rowid LIMIT ?2",
+          )?
+          .query_map(rusqlite::params![after.facts, limit], |row| {
+            Ok((row.get::<_, i64>(0)?, RawResolvedFact {
+              fact_id:           row.get(1)?,
+              subject_id:        row.get(2)?,
+              fact_type:         row.get(3)?,
+              value_json:        row.get(4)?,
+              recorded_at:       row.get(5)?,
+              effective_at:      row.get(6)?,
+              effective_until:   row.get(7)?,
+              source:            row.get(8)?,
+              confidence:        row.get(9)?,
+              recording_context: row.get(10)?,
+              tags:              row.get(11)?,
+              superseded_by:     None,
+              superseded_at:     None,
+              retraction_reason: None,
+              retracted_at:      None,
+            }))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let sups = conn
+          .prepare(
+            "SELECT s.rowid, s.supersession_id, s.old_fact_id, s.new_fact_id,
+                    s.recorded_at, f.subject_id
+             FROM supersessions s JOIN facts f ON f.fact_id = s.old_fact_id
+             WHERE s.rowid > ?1 ORDER BY s.rowid LIMIT ?2",
+          )?
+          .query_map(rusqlite::params![after.supersessions, limit], |row| {
+            Ok((
+              row.get::<_, i64>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, String>(2)?,
+              row.get::<_, String>(3)?,
+              row.get::<_, String>(4)?,
+              row.get::<_, String>(5)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let rets = conn
+          .prepare(
+            "SELECT r.rowid, r.retraction_id, r.fact_id, r.reason,
+                    r.recorded_at, f.subject_id
+             FROM retractions r JOIN facts f ON f.fact_id = r.fact_id
+             WHERE r.rowid > ?1 ORDER BY r.rowid LIMIT ?2",
+          )?
+          .query_map(rusqlite::params![after.retractions, limit], |row| {
+            Ok((
+              row.get::<_, i64>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, String>(2)?,
+              row.get::<_, Option<String>>(3)?,
+              row.get::<_, String>(4)?,
+              row.get::<_, String>(5)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let reins = conn
+          .prepare(
+            "SELECT ri.rowid, ri.reinstatement_id, ri.fact_id, ri.recorded_at,
+                    f.subject_id
+             FROM reinstatements ri JOIN facts f ON f.fact_id = ri.fact_id
+             WHERE ri.rowid > ?1 ORDER BY ri.rowid LIMIT ?2",
+          )?
+          .query_map(rusqlite::params![after.reinstatements, limit], |row| {
+            Ok((
+              row.get::<_, i64>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, String>(2)?,
+              row.get::<_, String>(3)?,
+              row.get::<_, String>(4)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok((facts, sups, rets, reins))
+      })
+      .await?;
+
+    // One queue per log, each in rowid order: (rowid, subject, change).
+    let mut queues: [VecDeque<(i64, Uuid, Change)>; 4] = Default::default();
+    for (rowid, raw) in fact_rows {
+      let fact = Box::new(raw.into_resolved()?.fact);
+      let subject_id = fact.subject_id;
+      queues[0].push_back((rowid, subject_id, Change::FactRecorded { fact }));
+    }
+    for (rowid, id, old, new, at, subject) in sup_rows {
+      let supersession = Supersession {
+        supersession_id: decode_uuid(&id)?,
+        old_fact_id:     decode_uuid(&old)?,
+        new_fact_id:     decode_uuid(&new)?,
+        recorded_at:     decode_dt(&at)?,
+      };
+      let change = Change::FactSuperseded { supersession };
+      queues[1].push_back((rowid, decode_uuid(&subject)?, change));
+    }
+    for (rowid, id, fact_id, reason, at, subject) in ret_rows {
+      let retraction = Retraction {
+        retraction_id: decode_uuid(&id)?,
+        fact_id: decode_uuid(&fact_id)?,
+        reason,
+        recorded_at: decode_dt(&at)?,
+      };
+      let change = Change::FactRetracted { retraction };
+      queues[2].push_back((rowid, decode_uuid(&subject)?, change));
+    }
+    for (rowid, id, fact_id, at, subject) in rein_rows {
+      let reinstatement = Reinstatement {
+        reinstatement_id: decode_uuid(&id)?,
+        fact_id:          decode_uuid(&fact_id)?,
+        recorded_at:      decode_dt(&at)?,
+      };
+      let change = Change::FactReinstated { reinstatement };
+      queues[3].push_back((rowid, decode_uuid(&subject)?, change));
+    }
+
+    // Interleave the logs by time, facts first on ties, as a changeset
+    // writes them. Taking each log in rowid order keeps every id a valid
+    // place to resume from.
+    let recorded_at = |change: &Change| match change {
+      Change::FactRecorded { fact } => fact.recorded_at,
+      Change::FactSuperseded { supersession } => supersession.recorded_at,
+      Change::FactRetracted { retraction } => retraction.recorded_at,
+      Change::FactReinstated { reinstatement } => reinstatement.recorded_at,
+    };
+    let mut id = after;
+    let mut events = Vec::new();
+    while (events.len() as i64) < limit {
+      let next = queues
+        .iter()
+        .enumerate()
+        .filter_map(|(log, q)| q.front().map(|(_, _, c)| (recorded_at(c), log)))
+        .min();
+      let Some((_, log)) = next else { break };
+      let (rowid, subject_id, change) = queues[log].pop_front().unwrap();
+      match log {
+        0 => id.facts = rowid,
+        1 => id.supersessions = rowid,
+        2 => id.retractions = rowid,
+        _ => id.reinstatements = rowid,
+      }
+      events.push(StoreEvent {
+        id,
+        subject_id,
+        change,
+      });
+    }
+    Ok(events)
+  }
+
+  // ── History ───────────────────────────────────────────────────────────────
+
+  async fn history(&self, subject_id: Option<Uuid>) -> Result<History> {
+    let subject_str = subject_id.map(encode_uuid);
+    let (
+      subject_rows,
+      book_rows,
+      fact_rows,
+      sup_rows,
+      ret_rows,
+      rein_rows,
+      membership_rows,
+      merge_rows,
+    ) = self
+      .conn
+      .call(move |conn| {
+        // A subject's merges refer to the subjects it was merged with.
+        let subjects = conn
+          .prepare(
+            "SELECT subject_id, created_at, kind FROM subjects
+             WHERE ?1 IS NULL OR subject_id = ?1
+                OR subject_id IN (SELECT survivor_id FROM subject_merges
+                                  WHERE absorbed_id = ?1)
+                OR subject_id IN (SELECT absorbed_id FROM subject_merges
+                                  WHERE survivor_id = ?1)
+             ORDER BY rowid",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok(RawSubject {
+              subject_id: row.get(0)?,
+              created_at: row.get(1)?,
+              kind:       row.get(2)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let facts = conn
+          .prepare(
+            "SELECT fact_id, subject_id, fact_type, value_json,
+                    recorded_at, effective_at, effective_until,
+                    source, confidence, recording_context, tags
+             FROM facts WHERE ?1 IS NULL OR subject_id = ?1 ORDER BY rowid",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok(RawResolvedFact {
+              fact_id:           row.get(0)?,
+              subject_id:        row.get(1)?,
+              fact_type:         row.get(2)?,
+              value_json:        row.get(3)?,
+              recorded_at:       row.get(4)?,
+              effective_at:      row.get(5)?,
+              effective_until:   row.get(6)?,
+              source:            row.get(7)?,
+              confidence:        row.get(8)?,
+              recording_context: row.get(9)?,
+              tags:              row.get(10)?,
+              superseded_by:     None,
+              superseded_at:     None,
+              retraction_reason: None,
+              retracted_at:      None,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        // Lifecycle events belong to the subject of the fact they end or
+        // reinstate.
+        let sups = conn
+          .prepare(
+            "SELECT s.supersession_id, s.old_fact_id, s.new_fact_id,
+                    s.recorded_at
+             FROM supersessions s JOIN facts f ON f.fact_id = s.old_fact_id
+             WHERE ?1 IS NULL OR f.subject_id = ?1 ORDER BY s.rowid",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok((
+              row.get::<_, String>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, String>(2)?,
+              row.get::<_, String>(3)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let rets = conn
+          .prepare(
+            "SELECT r.retraction_id, r.fact_id, r.reason, r.recorded_at
+             FROM retractions r JOIN facts f ON f.fact_id = r.fact_id
+             WHERE ?1 IS NULL OR f.subject_id = ?1 ORDER BY r.rowid",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok((
+              row.get::<_, String>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, Option<String>>(2)?,
+              row.get::<_, String>(3)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let reins = conn
+          .prepare(
+            "SELECT ri.reinstatement_id, ri.fact_id, ri.recorded_at
+             FROM reinstatements ri JOIN facts f ON f.fact_id = ri.fact_id
+             WHERE ?1 IS NULL OR f.subject_id = ?1 ORDER BY ri.rowid",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok((
+              row.get::<_, String>(0)?,
+              row.get::<_, String>(1)?,
+              row.get::<_, String>(2)?,
+            ))
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let books = conn
+          .prepare(&format!(
+            "SELECT {} FROM addressbooks
+             WHERE ?1 IS NULL OR addressbook_id IN (
+                 SELECT addressbook_id FROM addressbook_membership
+                 WHERE subject_id = ?1
+             )
+             ORDER BY rowid",
+            RawAddressBook::COLUMNS
+          ))?
+          .query_map(rusqlite::params![subject_str], RawAddressBook::from_row)?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let memberships = conn
+          .prepare(
+            "SELECT addressbook_id, subject_id, action, recorded_at
+             FROM addressbook_membership
+             WHERE ?1 IS NULL OR subject_id = ?1 ORDER BY seq",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok(RawMembership {
+              addressbook_id: row.get(0)?,
+              subject_id:     row.get(1)?,
+              action:         row.get(2)?,
+              recorded_at:    row.get(3)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let merges = conn
+          .prepare(
+            "SELECT survivor_id, absorbed_id, action, recorded_at
+             FROM subject_merges
+             WHERE ?1 IS NULL OR ?1 IN (survivor_id, absorbed_id)
+             ORDER BY seq",
+          )?
+          .query_map(rusqlite::params![subject_str], |row| {
+            Ok(RawMerge {
+              survivor_id: row.get(0)?,
+              absorbed_id: row.get(1)?,
+              action:      row.get(2)?,
+              recorded_at: row.get(3)?,
+            })
+          })?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        Ok((
+          subjects,
+          books,
+          facts,
+          sups,
+          rets,
+          reins,
+          memberships,
+          merges,
+        ))
+      })
+      .await?;
+
+    Ok(History {
+      subjects:       subject_rows
+        .into_iter()
+        .map(RawSubject::into_subject)
+        .collect::<Result<_>>()?,
+      addressbooks:   book_rows
+        .into_iter()
+        .map(RawAddressBook::into_addressbook)
+        .collect::<Result<_>>()?,
+      facts:          fact_rows
+        .into_iter()
+        .map(|raw| Ok(raw.into_resolved()?.fact))
+        .collect::<Result<_>>()?,
+      supersessions:  sup_rows
+        .into_iter()
+        .map(|(id, old, new, at)| {
+          Ok(Supersession {
+            supersession_id: decode_uuid(&id)?,
+            old_fact_id:     decode_uuid(&old)?,
+            new_fact_id:     decode_uuid(&new)?,
+            recorded_at:     decode_dt(&at)?,
+          })
+        })
+        .collect::<Result<_>>()?,
+      retractions:    ret_rows
+        .into_iter()
+        .map(|(id, fact_id, reason, at)| {
+          Ok(Retraction {
+            retraction_id: decode_uuid(&id)?,
+            fact_id: decode_uuid(&fact_id)?,
+            reason,
+            recorded_at: decode_dt(&at)?,
+          })
+        })
+        .collect::<Result<_>>()?,
+      reinstatements: rein_rows
+        .into_iter()
+        .map(|(id, fact_id, at)| {
+          Ok(Reinstatement {
+            reinstatement_id: decode_uuid(&id)?,
+            fact_id:          decode_uuid(&fact_id)?,
+            recorded_at:      decode_dt(&at)?,
+          })
+        })
+        .collect::<Result<_>>()?,
+      memberships:    membership_rows
+        .into_iter()
+        .map(RawMembership::into_event)
+        .collect::<Result<_>>()?,
+      merges:         merge_rows
+        .into_iter()
+        .map(RawMerge::into_merge)
+        .collect::<Result<_>>()?,
+    })
+  }
+
+  async fn restore(&self, history: History) -> Result<()> {
+    if history
+      .supersessions
+      .iter()
+      .any(|sup| sup.old_fact_id == sup.new_fact_id)
+    {
+      return Err(Error::SelfSupersession);
+    }
+
+    let subjects: Vec<(String, String, String)> = history
+      .subjects
+      .iter()
+      .map(|s| {
+        (
+          encode_uuid(s.subject_id),
+          encode_dt(s.created_at),
+          s.kind.to_string(),
+        )
+      })
+      .collect();
+    let books: Vec<_> = history
+      .addressbooks
+      .iter()
+      .map(|b| {
+        (
+          encode_uuid(b.addressbook_id),
+          b.name.clone(),
+          b.display_name.clone(),
+          b.description.clone(),
+          b.is_default,
+          encode_dt(b.created_at),
+        )
+      })
+      .collect();
+    let rows = history
+      .facts
+      .iter()
+      .map(FactRow::encode)
+      .collect::<Result<Vec<_>>>()?;
+    let lifecycle = history.lifecycle_events();
+    let memberships: Vec<_> = history
+      .memberships
+      .iter()
+      .map(|m| {
+        (
+          encode_uuid(m.addressbook_id),
+          encode_uuid(m.subject_id),
+          membership_action_str(m.action),
+          encode_dt(m.recorded_at),
+        )
+      })
+      .collect();
+    let merges: Vec<_> = history
+      .merges
+      .iter()
+      .map(|m| {
+        (
+          encode_uuid(m.survivor_id),
+          encode_uuid(m.absorbed_id),
+          merge_action_str(m.action),
+          encode_dt(m.recorded_at),
+        )
+      })
+      .collect();
+
+    let sender = self.events.clone();
+    let conflict = self
+      .conn
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+        for (id, created_at, kind) in &subjects {
+          tx.execute(
+            "INSERT INTO subjects (subject_id, created_at, kind) VALUES (?1, \
+             ?2, ?3)",
+            rusqlite::params![id, created_at, kind],
+          )?;
+        }
+        for (id, name, display_name, description, is_default, created_at) in
+          &books
+        {
+          tx.execute(
+            "INSERT INTO addressbooks \
+               (addressbook_id, name, display_name, description, is_default, \
+                created_at) \
+               VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![
+              id,
+              name,
+              display_name,
+              description,
+              is_default,
+              created_at
+            ],
+          )?;
+        }
+        let events = match write_log(&tx, &rows, history.facts, lifecycle)? {
+          Ok(events) => events,
+          Err(conflict) => return Ok(Some(conflict)),
+        };
+        for (book, subject, action, at) in &memberships {
+          tx.execute(
+            "INSERT INTO addressbook_membership \
+               (addressbook_id, subject_id, action, recorded_at) \
+               VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![book, subject, action, at],
+          )?;
+        }
+        for (survivor, absorbed, action, at) in &merges {
+          tx.execute(
+            "INSERT INTO subject_merges \
+               (survivor_id, absorbed_id, action, recorded_at) \
+               VALUES (?1, ?2, ?3, ?4)",
+            rusqlite::params![survivor, absorbed, action, at],
+          )?;
+        }
+        tx.commit()?;
+        publish(&sender, events);
+        Ok(None)
+      })
+      .await?;
+
+    match conflict {
+      Some((fact_id, conflict)) => Err(conflict.into_error(fact_id)),
+      None => Ok(()),
+    }
+  }
+
+  // ── CardDAV resources ─────────────────────────────────────────────────────
+
+  async fn record_resource(&self, input: NewResource) -> Result<Resource> {
+    let resource = Resource {
+      subject_id:  input.subject_id,
+      name:        input.name,
+      uid:         input.uid,
+      recorded_at: Utc::now(),
+    };
+
+    self
+      .conn
+      .call(move |conn| {
+        let tx = conn.transaction()?;
+        let stored = match insert_resource(&tx, resource)? {
+          Ok(stored) => stored,
+          Err(e) => return Ok(Err(e)),
+        };
+        tx.commit()?;
+        Ok(Ok(stored))
+      })
+      .await?
+  }
+
+  async fn resource_by_name(&self, name: &str) -> Result<Option<Resource>> {
+    self.resource_where("resource_name", name.to_owned()).await
+  }
+
+  async fn resource_by_uid(&self, uid: &str) -> Result<Option<Resource>> {
+    self.resource_where("vcard_uid", uid.to_owned()).await
+  }
+
+  async fn subject_resource(
+    &self,
+    subject_id: Uuid,
+  ) -> Result<Option<Resource>> {
+    self
+      .resource_where("subject_id", encode_uuid(subject_id))
+      .await
+  }
+
+  async fn create_app_password(
+    &self,
+    input: NewAppPassword,
+  ) -> Result<AppPassword> {
+    let password = AppPassword {
+      app_password_id: Uuid::new_v4(),
+      label:           input.label,
+      created_at:      Utc::now(),
+      last_used_at:    None,
+      revoked_at:      None,
+    };
+
+    let id_str = encode_uuid(password.app_password_id);
+    let label = password.label.clone();
+    let at_str = encode_dt(password.created_at);
+
+    self
+      .conn
+      .call(move |conn| {
+        conn.execute(
+          "INSERT INTO app_passwords \
+             (app_password_id, label, secret_hash, created_at) \
+             VALUES (?1, ?2, ?3, ?4)",
+          rusqlite::params![id_str, label, input.secret_hash, at_str],
+        )?;
+        Ok(())
+      })
+      .await?;
+
+    Ok(password)
+  }
+
+  async fn list_app_passwords(&self) -> Result<Vec<AppPassword>> {
+    let raws: Vec<RawAppPassword> = self
+      .conn
+      .call(|conn| {
+        let mut stmt = conn.prepare(&format!(
+          "SELECT {} FROM app_passwords ORDER BY created_at, app_password_id",
+          RawAppPassword::COLUMNS
+        ))?;
+        let rows = stmt
+          .query_map([], RawAppPassword::from_row)?
+          .collect::<rusqlite::Result<Vec<_>>>()?;
+        Ok(rows)
+      })
+      .await?;
+
+    raws
+      .into_iter()
+      .map(RawAppPassword::into_app_password)
+      .collect()
+  }
+
+  async fn revoke_app_password(
+    &self,
+    app_password_id: Uuid,
+  ) -> Result<Option<AppPassword>> {
+    let id_str = encode_uuid(app_password_id);
+    let at_str = encode_dt(Utc::now());
+
+    let raw: Option<RawAppPassword> = self
+      .conn
+      .call(move |conn| {
+        Ok(
+          conn
+            .query_row(
+              &format!(
+                "UPDATE app_passwords \
+                 SET revoked_at = COALESCE(revoked_at, ?1) \
+                 WHERE app_password_id = ?2 \
+                 RETURNING {}",
+                RawAppPassword::COLUMNS
+              ),
+              rusqlite::params![at_str, id_str],
+              RawAppPassword::from_row,
+            )
+            .optional()?,
+        )
+      })
+      .await?;
+
+    raw.map(RawAppPassword::into_app_password).transpose()
+  }
+
+  async fn use_app_password(
+    &self,
+    secret_hash: &str,
+  ) -> Result<Option<AppPassword>> {
+    let hash = secret_hash.to_owned();
+    let at_str = encode_dt(Utc::now());
+
+    let raw: Option<RawAppPassword> = self
+      .conn
+      .call(move |conn| {
+        Ok(
+          conn
+            .query_row(
+              &format!(
+                "UPDATE app_passwords SET last_used_at = ?1 \
+                 WHERE secret_hash = ?2 AND revoked_at IS NULL \
+                 RETURNING {}",
+                RawAppPassword::COLUMNS
+              ),
+              rusqlite::params![at_str, hash],
+              RawAppPassword::from_row,
+            )
+            .optional()?,
+        )
+      })
+      .await?;
+
+    raw.map(RawAppPassword::into_app_password).transpose()
+  }
+}
+
+// ─── Helpers ─────────────────────────────────────────────────────────────────
+
+/// Translate user search text into an FTS5 `MATCH` expression.
+///
+/// - `"a phrase"` in double quotes matches those tokens consecutively.
+/// - A bare term ending in `*` is a prefix match (`ali*`).
+/// - Every other bare term must match as a whole token.
+///
+/// All terms are implicitly AND-ed. Everything is quoted before it reaches
+/// FTS5, so operators and punctuation in user input (`-`, `:`, `@`, `OR`)
+/// are never interpreted as query syntax. Returns `None` if the text contains
+/// no terms, so that nothing can match it.
+fn fts_query(text: &str) -> Option<String> {
+  // Terms never contain `"`: the input is split on it below.
+  fn quote(term: &str) -> String { format!("\"{term}\"") }
+
+  let mut terms = Vec::new();
+  for (i, chunk) in text.split('"').enumerate() {
+    if i % 2 == 1 {
+      // Inside a quoted phrase.
+      if !chunk.trim().is_empty() {
+        terms.push(quote(chunk.trim()));
+      }
+      continue;
+    }
+    for word in chunk.split_whitespace() {
+      let (word, prefix) = match word.strip_suffix('*') {
+        Some(stem) => (stem.trim_end_matches('*'), true),
+        None => (word, false),
+      };
+      if word.is_empty() {
+        continue;
+      }
+      let mut term = quote(word);
+      if prefix {
+        term.push('*');
+      }
+      terms.push(term);
+    }
+  }
+
+  (!terms.is_empty()).then(|| terms.join(" "))
 }
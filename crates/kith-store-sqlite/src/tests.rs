@@ -1,11 +1,18 @@
 //! Integration tests for `SqliteStore` against an in-memory database.
 
 use kith_core::{
+  addressbook::{NewAddressBook, SyncToken},
+  app_password::{NewAppPassword, hash_secret},
+  duplicates,
+  event::{Change, EventId},
   fact::{
-    Confidence, ContactLabel, EmailValue, FactValue, NameValue, NewFact,
-    RecordingContext,
+    Confidence, ContactLabel, EffectiveDate, EmailValue, FactValue, NameValue,
+    NewFact, OrgMembershipValue, PhoneKind, PhoneValue, RecordingContext,
   },
-  store::ContactStore,
+  history::{self, History, ImportError, ImportReport},
+  lifecycle::MergeAction,
+  resource::NewResource,
+  store::{Changeset, ContactStore, FactQuery, ImportSource},
   subject::SubjectKind,
 };
 use uuid::Uuid;
@@ -100,7 +107,10 @@ async fn record_fact_and_retrieve() {
   assert_eq!(fact.subject_id, subject.subject_id);
 
   // get_facts should return it as Active.
-  let facts = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let facts = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   assert_eq!(facts.len(), 1);
   assert!(facts[0].status.is_active());
   assert_eq!(facts[0].fact.fact_id, fact.fact_id);
@@ -119,7 +129,10 @@ async fn record_multiple_facts() {
     .await
     .unwrap();
 
-  let facts = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let facts = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   assert_eq!(facts.len(), 3);
   assert!(facts.iter().all(|rf| rf.status.is_active()));
 }
@@ -136,7 +149,10 @@ async fn fact_confidence_and_source_roundtrip() {
 
   let fact = s.record_fact(input).await.unwrap();
 
-  let facts = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let facts = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   let rf = facts
     .into_iter()
     .find(|rf| rf.fact.fact_id == fact.fact_id)
@@ -159,7 +175,10 @@ async fn recording_context_imported_roundtrip() {
   };
 
   let fact = s.record_fact(input).await.unwrap();
-  let facts = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let facts = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   let rf = facts
     .into_iter()
     .find(|rf| rf.fact.fact_id == fact.fact_id)
@@ -191,13 +210,19 @@ async fn supersede_marks_old_inactive() {
   assert_eq!(sup.new_fact_id, new_fact.fact_id);
 
   // Active-only view: only the new fact.
-  let active = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let active = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   assert_eq!(active.len(), 1);
   assert_eq!(active[0].fact.fact_id, new_fact.fact_id);
   assert!(active[0].status.is_active());
 
   // Full history: both facts, old marked Superseded.
-  let all = s.get_facts(subject.subject_id, None, true).await.unwrap();
+  let all = s
+    .get_facts(subject.subject_id, None, None, true)
+    .await
+    .unwrap();
   assert_eq!(all.len(), 2);
 
   let old_rf = all
@@ -263,10 +288,16 @@ async fn retract_removes_from_active_view() {
   assert_eq!(ret.fact_id, fact.fact_id);
   assert_eq!(ret.reason.as_deref(), Some("wrong address"));
 
-  let active = s.get_facts(subject.subject_id, None, false).await.unwrap();
+  let active = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
   assert!(active.is_empty());
 
-  let all = s.get_facts(subject.subject_id, None, true).await.unwrap();
+  let all = s
+    .get_facts(subject.subject_id, None, None, true)
+    .await
+    .unwrap();
   assert_eq!(all.len(), 1);
   assert!(
     matches!(&all[0].status, kith_core::lifecycle::FactStatus::Retracted { reason, .. }
@@ -313,12 +344,498 @@ async fn cannot_retract_superseded_fact() {
   assert!(matches!(err, crate::Error::AlreadySuperseded(_)));
 }
 
+// ─── Reinstatement ───────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn reinstate_undoes_retraction() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let fact = s
+    .record_fact(email_fact(subject.subject_id, "back@example.com"))
+    .await
+    .unwrap();
+  s.retract(fact.fact_id, Some("deleted by mistake".into()))
+    .await
+    .unwrap();
+  let rein = s.reinstate(fact.fact_id).await.unwrap();
+  assert_eq!(rein.fact_id, fact.fact_id);
+
+  let active = s
+    .get_facts(subject.subject_id, None, None, false)
+    .await
+    .unwrap();
+  assert_eq!(active.len(), 1);
+  let resolved = s.get_fact(fact.fact_id).await.unwrap().unwrap();
+  assert!(resolved.status.is_active());
+
+  // The latest event wins, so the fact can be retracted again.
+  s.retract(fact.fact_id, Some("gone for real".into()))
+    .await
+    .unwrap();
+  let resolved = s.get_fact(fact.fact_id).await.unwrap().unwrap();
+  assert!(
+    matches!(&resolved.status, kith_core::lifecycle::FactStatus::Retracted { reason, .. }
+      if reason.as_deref() == Some("gone for real"))
+  );
+}
+
+#[tokio::test]
+async fn reinstate_undoes_supersession() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let old = s
+    .record_fact(email_fact(id, "a@example.com"))
+    .await
+    .unwrap();
+  s.supersede(old.fact_id, email_fact(id, "b@example.com"))
+    .await
+    .unwrap();
+  s.reinstate(old.fact_id).await.unwrap();
+
+  // The replacement stays active alongside the reinstated fact.
+  let active = s.get_facts(id, None, None, false).await.unwrap();
+  assert_eq!(active.len(), 2);
+
+  let (_, newest) = s
+    .supersede(old.fact_id, email_fact(id, "c@example.com"))
+    .await
+    .unwrap();
+  let old = s.get_fact(old.fact_id).await.unwrap().unwrap();
+  assert!(matches!(
+    old.status,
+    kith_core::lifecycle::FactStatus::Superseded { by, .. }
+      if by == newest.fact_id
+  ));
+}
+
+#[tokio::test]
+async fn reinstate_active_fact_errors() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let fact = s
+    .record_fact(email_fact(subject.subject_id, "x@example.com"))
+    .await
+    .unwrap();
+  let err = s.reinstate(fact.fact_id).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadyActive(_)));
+
+  s.retract(fact.fact_id, None).await.unwrap();
+  s.reinstate(fact.fact_id).await.unwrap();
+  let err = s.reinstate(fact.fact_id).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadyActive(_)));
+
+  let err = s.reinstate(Uuid::new_v4()).await.unwrap_err();
+  assert!(matches!(err, crate::Error::FactNotFound(_)));
+}
+
+#[tokio::test]
+async fn get_facts_as_of_ignores_later_reinstatement() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let fact = s.record_fact(name_fact(id)).await.unwrap();
+  s.retract(fact.fact_id, None).await.unwrap();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  let while_retracted = chrono::Utc::now();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  s.reinstate(fact.fact_id).await.unwrap();
+
+  let then = s
+    .get_facts(id, Some(while_retracted), None, true)
+    .await
+    .unwrap();
+  assert!(matches!(
+    then[0].status,
+    kith_core::lifecycle::FactStatus::Retracted { .. }
+  ));
+  let now = s.get_facts(id, None, None, false).await.unwrap();
+  assert_eq!(now.len(), 1);
+
+  let hits = s
+    .search(&FactQuery {
+      text: Some("Alice".into()),
+      active_only: true,
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(hits.len(), 1);
+}
+
+// ─── Lineage ─────────────────────────────────────────────────────────────────
+
+fn lineage_ids(facts: &[kith_core::lifecycle::ResolvedFact]) -> Vec<Uuid> {
+  facts.iter().map(|rf| rf.fact.fact_id).collect()
+}
+
+#[tokio::test]
+async fn lineage_runs_from_original_to_head() {
+  let s = store().await;
+  let id = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+
+  let first = s
+    .record_fact(email_fact(id, "a@example.com"))
+    .await
+    .unwrap();
+  let (_, second) = s
+    .supersede(first.fact_id, email_fact(id, "b@example.com"))
+    .await
+    .unwrap();
+  let (_, third) = s
+    .supersede(second.fact_id, email_fact(id, "c@example.com"))
+    .await
+    .unwrap();
+  let chain = [first.fact_id, second.fact_id, third.fact_id];
+
+  // The same chain from any version in it.
+  for fact_id in chain {
+    let lineage = s.fact_lineage(fact_id).await.unwrap();
+    assert_eq!(lineage_ids(&lineage), chain);
+  }
+  let lineage = s.fact_lineage(first.fact_id).await.unwrap();
+  assert!(matches!(
+    lineage[0].status,
+    kith_core::lifecycle::FactStatus::Superseded { by, .. }
+      if by == second.fact_id
+  ));
+  assert!(lineage[2].status.is_active());
+
+  assert!(s.fact_lineage(Uuid::new_v4()).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn lineage_follows_the_supersession_in_force() {
+  let s = store().await;
+  let id = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+
+  let first = s
+    .record_fact(email_fact(id, "a@example.com"))
+    .await
+    .unwrap();
+  let (_, dropped) = s
+    .supersede(first.fact_id, email_fact(id, "b@example.com"))
+    .await
+    .unwrap();
+  s.reinstate(first.fact_id).await.unwrap();
+  let (_, kept) = s
+    .supersede(first.fact_id, email_fact(id, "c@example.com"))
+    .await
+    .unwrap();
+
+  let lineage = s.fact_lineage(first.fact_id).await.unwrap();
+  assert_eq!(lineage_ids(&lineage), [first.fact_id, kept.fact_id]);
+  // A replacement that was undone still descends from its original.
+  let lineage = s.fact_lineage(dropped.fact_id).await.unwrap();
+  assert_eq!(lineage_ids(&lineage), [first.fact_id, dropped.fact_id]);
+}
+
+// ─── Import sources ──────────────────────────────────────────────────────────
+
+fn imported(mut input: NewFact, source_name: &str) -> NewFact {
+  input.recording_context = RecordingContext::Imported {
+    source_name:  source_name.into(),
+    original_uid: None,
+  };
+  input
+}
+
+#[tokio::test]
+async fn import_sources_count_facts_per_source() {
+  let s = store().await;
+  let id = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+
+  let dropped = s
+    .record_fact(imported(email_fact(id, "a@example.com"), "Google"))
+    .await
+    .unwrap();
+  s.retract(dropped.fact_id, None).await.unwrap();
+  s.record_fact(imported(name_fact(id), "Google"))
+    .await
+    .unwrap();
+  s.record_fact(imported(email_fact(id, "b@example.com"), "Apple"))
+    .await
+    .unwrap();
+  s.record_fact(email_fact(id, "c@example.com"))
+    .await
+    .unwrap();
+
+  let sources = s.import_sources().await.unwrap();
+  assert_eq!(sources, [
+    ImportSource {
+      source_name:  "Apple".into(),
+      facts:        1,
+      active_facts: 1,
+    },
+    ImportSource {
+      source_name:  "Google".into(),
+      facts:        2,
+      active_facts: 1,
+    },
+  ]);
+}
+
+#[tokio::test]
+async fn retract_import_retracts_active_facts_of_one_source() {
+  let s = store().await;
+  let id = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+
+  let kept = s
+    .record_fact(imported(email_fact(id, "a@example.com"), "Google"))
+    .await
+    .unwrap();
+  let old = s
+    .record_fact(imported(email_fact(id, "b@example.com"), "Google"))
+    .await
+    .unwrap();
+  let (_, new) = s
+    .supersede(
+      old.fact_id,
+      imported(email_fact(id, "c@example.com"), "Google"),
+    )
+    .await
+    .unwrap();
+  let other = s
+    .record_fact(imported(email_fact(id, "d@example.com"), "Apple"))
+    .await
+    .unwrap();
+  let manual = s
+    .record_fact(email_fact(id, "e@example.com"))
+    .await
+    .unwrap();
+
+  // The preview is what the undo retracts.
+  let preview = s.import_facts("Google").await.unwrap();
+  let preview: Vec<_> = preview.iter().map(|rf| rf.fact.fact_id).collect();
+  assert_eq!(preview, [kept.fact_id, new.fact_id]);
+
+  let retractions = s
+    .retract_import("Google".into(), Some("bad import".into()))
+    .await
+    .unwrap();
+  let retracted: Vec<_> = retractions.iter().map(|r| r.fact_id).collect();
+  assert_eq!(retracted, preview);
+  assert!(retractions.iter().all(|r| {
+    r.reason.as_deref() == Some("bad import")
+      && r.recorded_at == retractions[0].recorded_at
+  }));
+
+  let active: Vec<_> = s
+    .get_facts(id, None, None, false)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|rf| rf.fact.fact_id)
+    .collect();
+  assert_eq!(active.len(), 2);
+  assert!(active.contains(&other.fact_id));
+  assert!(active.contains(&manual.fact_id));
+
+  // Nothing is left to undo.
+  assert!(s.import_facts("Google").await.unwrap().is_empty());
+  let again = s.retract_import("Google".into(), None).await.unwrap();
+  assert!(again.is_empty());
+  assert!(
+    s.retract_import("Unknown".into(), None)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+// ─── Changesets ──────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn apply_changeset_writes_everything_at_once() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let stale = s
+    .record_fact(email_fact(id, "old@example.com"))
+    .await
+    .unwrap();
+  let gone = s
+    .record_fact(email_fact(id, "gone@example.com"))
+    .await
+    .unwrap();
+
+  let applied = s
+    .apply_changeset(Changeset {
+      new_facts: vec![name_fact(id)],
+      supersessions: vec![(stale.fact_id, email_fact(id, "new@example.com"))],
+      retractions: vec![(gone.fact_id, Some("bounced".into()))],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(applied.facts.len(), 2);
+  assert_eq!(applied.supersessions.len(), 1);
+  assert_eq!(applied.retractions.len(), 1);
+  assert_eq!(
+    applied.supersessions[0].new_fact_id,
+    applied.facts[1].fact_id
+  );
+
+  // One shared timestamp for the whole batch.
+  let at = applied.facts[0].recorded_at;
+  assert!(applied.facts.iter().all(|f| f.recorded_at == at));
+  assert_eq!(applied.supersessions[0].recorded_at, at);
+  assert_eq!(applied.retractions[0].recorded_at, at);
+
+  let active = s.get_facts(id, None, None, false).await.unwrap();
+  let mut ids: Vec<_> = active.iter().map(|rf| rf.fact.fact_id).collect();
+  ids.sort();
+  let mut expected: Vec<_> = applied.facts.iter().map(|f| f.fact_id).collect();
+  expected.sort();
+  assert_eq!(ids, expected);
+}
+
+#[tokio::test]
+async fn apply_changeset_is_all_or_nothing() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let stale = s
+    .record_fact(email_fact(id, "old@example.com"))
+    .await
+    .unwrap();
+  let missing = Uuid::new_v4();
+
+  let err = s
+    .apply_changeset(Changeset {
+      new_facts: vec![note_fact(id, "should not persist")],
+      supersessions: vec![(stale.fact_id, email_fact(id, "new@example.com"))],
+      retractions: vec![(missing, None)],
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert!(matches!(err, crate::Error::FactNotFound(f) if f == missing));
+
+  // Nothing from the failed batch is visible, including in the FTS index.
+  let all = s.get_facts(id, None, None, true).await.unwrap();
+  assert_eq!(all.len(), 1);
+  assert!(all[0].status.is_active());
+  let hits = s
+    .search(&FactQuery {
+      text: Some("persist".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(hits.is_empty());
+}
+
+#[tokio::test]
+async fn apply_changeset_writes_the_envelope_with_the_facts() {
+  let s = store().await;
+  let book = s.create_addressbook(new_book("work")).await.unwrap();
+  let id = Uuid::new_v4();
+
+  // A failing lifecycle event undoes the subject, resource and membership
+  // written before it.
+  let missing = Uuid::new_v4();
+  let err = s
+    .apply_changeset(Changeset {
+      new_subjects: vec![(id, SubjectKind::Person)],
+      resources: vec![new_resource(id, "c.vcf", "c")],
+      addressbook_adds: vec![(book.addressbook_id, id)],
+      new_facts: vec![name_fact(id)],
+      retractions: vec![(missing, None)],
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert!(matches!(err, crate::Error::FactNotFound(f) if f == missing));
+  assert!(s.get_subject(id).await.unwrap().is_none());
+  assert!(s.subject_resource(id).await.unwrap().is_none());
+  assert!(
+    s.addressbook_members(book.addressbook_id)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  s.apply_changeset(Changeset {
+    new_subjects: vec![(id, SubjectKind::Person)],
+    resources: vec![new_resource(id, "c.vcf", "c")],
+    addressbook_adds: vec![(book.addressbook_id, id)],
+    new_facts: vec![name_fact(id)],
+    ..Default::default()
+  })
+  .await
+  .unwrap();
+  assert!(s.subject_resource(id).await.unwrap().is_some());
+  let members = s.addressbook_members(book.addressbook_id).await.unwrap();
+  assert_eq!(members.len(), 1);
+
+  // Removals come after the lifecycle events, in the same transaction.
+  let fact_id = s.get_facts(id, None, None, false).await.unwrap()[0]
+    .fact
+    .fact_id;
+  s.apply_changeset(Changeset {
+    retractions: vec![(fact_id, None)],
+    addressbook_removals: vec![(book.addressbook_id, id)],
+    ..Default::default()
+  })
+  .await
+  .unwrap();
+  assert!(
+    s.addressbook_members(book.addressbook_id)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn apply_changeset_rejects_double_lifecycle_events() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let fact = s
+    .record_fact(email_fact(id, "a@example.com"))
+    .await
+    .unwrap();
+
+  // Superseding and retracting the same fact in one batch conflicts with
+  // itself.
+  let err = s
+    .apply_changeset(Changeset {
+      supersessions: vec![(fact.fact_id, email_fact(id, "b@example.com"))],
+      retractions: vec![(fact.fact_id, None)],
+      ..Default::default()
+    })
+    .await
+    .unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadySuperseded(_)));
+
+  let all = s.get_facts(id, None, None, true).await.unwrap();
+  assert_eq!(all.len(), 1);
+  assert!(all[0].status.is_active());
+}
+
+#[tokio::test]
+async fn apply_empty_changeset_is_a_no_op() {
+  let s = store().await;
+  let applied = s.apply_changeset(Changeset::default()).await.unwrap();
+  assert!(applied.facts.is_empty());
+}
+
 // ─── Materialize ─────────────────────────────────────────────────────────────
 
 #[tokio::test]
 async fn materialize_returns_none_for_unknown_subject() {
   let s = store().await;
-  let view = s.materialize(Uuid::new_v4(), None).await.unwrap();
+  let view = s.materialize(Uuid::new_v4(), None, None).await.unwrap();
   assert!(view.is_none());
 }
 
@@ -344,7 +861,7 @@ async fn materialize_active_facts() {
   .unwrap();
 
   let view = s
-    .materialize(subject.subject_id, None)
+    .materialize(subject.subject_id, None, None)
     .await
     .unwrap()
     .unwrap();
@@ -364,10 +881,185 @@ async fn materialize_active_facts() {
   assert!(!ids.contains(&stale.fact_id));
 }
 
-// ─── Search ──────────────────────────────────────────────────────────────────
+// ─── Bitemporal reads ────────────────────────────────────────────────────────
+
+fn utc(y: i32, m: u32, d: u32) -> chrono::DateTime<chrono::Utc> {
+  use chrono::TimeZone as _;
+  chrono::Utc.with_ymd_and_hms(y, m, d, 0, 0, 0).unwrap()
+}
+
+fn employer_fact(
+  subject_id: Uuid,
+  org: &str,
+  from: Option<EffectiveDate>,
+  until: Option<EffectiveDate>,
+) -> NewFact {
+  let mut input = NewFact::new(
+    subject_id,
+    FactValue::OrgMembership(OrgMembershipValue {
+      org_name: org.into(),
+      org_id:   None,
+      title:    None,
+      role:     None,
+    }),
+  );
+  input.effective_at = from;
+  input.effective_until = until;
+  input
+}
+
+fn org_names(facts: &[kith_core::lifecycle::ResolvedFact]) -> Vec<String> {
+  let mut names: Vec<String> = facts
+    .iter()
+    .filter_map(|rf| match &rf.fact.value {
+      FactValue::OrgMembership(o) => Some(o.org_name.clone()),
+      _ => None,
+    })
+    .collect();
+  names.sort();
+  names
+}
 
 #[tokio::test]
-async fn search_by_text() {
+async fn get_facts_valid_at_filters_by_effective_range() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let date = |y, m, d| {
+    Some(EffectiveDate::DateOnly(
+      chrono::NaiveDate::from_ymd_opt(y, m, d).unwrap(),
+    ))
+  };
+
+  s.record_fact(employer_fact(id, "Initech", None, date(2018, 12, 31)))
+    .await
+    .unwrap();
+  s.record_fact(employer_fact(
+    id,
+    "Hooli",
+    Some(EffectiveDate::Instant(utc(2019, 1, 1))),
+    Some(EffectiveDate::Instant(utc(2022, 1, 1))),
+  ))
+  .await
+  .unwrap();
+  s.record_fact(employer_fact(id, "Acme", date(2022, 1, 1), None))
+    .await
+    .unwrap();
+  s.record_fact(employer_fact(
+    id,
+    "Unknown Corp",
+    Some(EffectiveDate::Unknown),
+    Some(EffectiveDate::Unknown),
+  ))
+  .await
+  .unwrap();
+
+  let cases = [
+    // DateOnly until covers the whole day; Unknown bounds are open.
+    (utc(2018, 12, 31), ["Initech", "Unknown Corp"]),
+    (utc(2019, 1, 1), ["Hooli", "Unknown Corp"]),
+    // Instant until is exclusive; DateOnly start is inclusive.
+    (utc(2022, 1, 1), ["Acme", "Unknown Corp"]),
+  ];
+  for (t, expected) in cases {
+    let facts = s.get_facts(id, None, Some(t), false).await.unwrap();
+    assert_eq!(org_names(&facts), expected, "valid_at {t}");
+  }
+
+  // Without valid_at, nothing is filtered.
+  let all = s.get_facts(id, None, None, false).await.unwrap();
+  assert_eq!(all.len(), 4);
+}
+
+#[tokio::test]
+async fn get_facts_as_of_ignores_later_lifecycle_events() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let old = s
+    .record_fact(employer_fact(id, "Initech", None, None))
+    .await
+    .unwrap();
+  let gone = s
+    .record_fact(employer_fact(id, "Hooli", None, None))
+    .await
+    .unwrap();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  let before_changes = chrono::Utc::now();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+  s.supersede(old.fact_id, employer_fact(id, "Acme", None, None))
+    .await
+    .unwrap();
+  s.retract(gone.fact_id, None).await.unwrap();
+
+  let then = s
+    .get_facts(id, Some(before_changes), None, false)
+    .await
+    .unwrap();
+  assert_eq!(org_names(&then), ["Hooli", "Initech"]);
+
+  let now = s.get_facts(id, None, None, false).await.unwrap();
+  assert_eq!(org_names(&now), ["Acme"]);
+}
+
+#[tokio::test]
+async fn materialize_combines_both_time_axes() {
+  let s = store().await;
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  // Originally believed to have worked at Initech throughout 2019...
+  let belief = s
+    .record_fact(employer_fact(
+      id,
+      "Initech",
+      Some(EffectiveDate::Instant(utc(2019, 1, 1))),
+      None,
+    ))
+    .await
+    .unwrap();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  let believed_at = chrono::Utc::now();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+  // ...later corrected: the Initech job only started in 2020.
+  s.supersede(
+    belief.fact_id,
+    employer_fact(
+      id,
+      "Initech",
+      Some(EffectiveDate::Instant(utc(2020, 1, 1))),
+      None,
+    ),
+  )
+  .await
+  .unwrap();
+
+  let mid_2019 = utc(2019, 6, 1);
+
+  let known_today = s
+    .materialize(id, None, Some(mid_2019))
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(known_today.valid_at, Some(mid_2019));
+  assert!(known_today.active_facts.is_empty());
+
+  let believed_then = s
+    .materialize(id, Some(believed_at), Some(mid_2019))
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(org_names(&believed_then.active_facts), ["Initech"]);
+}
+
+// ─── Search ──────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn search_by_text() {
   let s = store().await;
 
   let alice = s.add_subject(SubjectKind::Person).await.unwrap();
@@ -410,3 +1102,1795 @@ async fn search_by_kind() {
   assert_eq!(results.len(), 1);
   assert_eq!(results[0].kind, SubjectKind::Organization);
 }
+
+#[tokio::test]
+async fn search_by_fact_types() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  s.record_fact(name_fact(alice.subject_id)).await.unwrap();
+  s.record_fact(email_fact(bob.subject_id, "bob@example.com"))
+    .await
+    .unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      fact_types: vec!["email".into()],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].subject_id, bob.subject_id);
+}
+
+#[tokio::test]
+async fn search_by_tags_requires_all_tags() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+  let carol = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let mut both = name_fact(alice.subject_id);
+  both.tags = vec!["imported".into(), "work".into()];
+  s.record_fact(both).await.unwrap();
+
+  let mut one = name_fact(bob.subject_id);
+  one.tags = vec!["imported".into()];
+  s.record_fact(one).await.unwrap();
+
+  s.record_fact(name_fact(carol.subject_id)).await.unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      tags: vec!["imported".into(), "work".into()],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].subject_id, alice.subject_id);
+
+  let results = s
+    .search(&FactQuery {
+      tags: vec!["imported".into()],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 2);
+  assert!(results.iter().all(|r| r.subject_id != carol.subject_id));
+}
+
+#[tokio::test]
+async fn search_by_confidence() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let mut rumour = email_fact(alice.subject_id, "alice@example.com");
+  rumour.confidence = Confidence::Rumored;
+  s.record_fact(rumour).await.unwrap();
+  s.record_fact(email_fact(bob.subject_id, "bob@example.com"))
+    .await
+    .unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      confidence: Some(Confidence::Rumored),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].subject_id, alice.subject_id);
+}
+
+#[tokio::test]
+async fn search_by_recorded_range() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let early = s.record_fact(name_fact(alice.subject_id)).await.unwrap();
+  tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+  let late = s.record_fact(name_fact(bob.subject_id)).await.unwrap();
+
+  let after = s
+    .search(&FactQuery {
+      recorded_after: Some(early.recorded_at),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(after.len(), 1);
+  assert_eq!(after[0].subject_id, bob.subject_id);
+
+  let before = s
+    .search(&FactQuery {
+      recorded_before: Some(late.recorded_at),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(before.len(), 1);
+  assert_eq!(before[0].subject_id, alice.subject_id);
+}
+
+#[tokio::test]
+async fn search_active_only_skips_retracted_and_superseded() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let gone = s
+    .record_fact(email_fact(alice.subject_id, "old@example.com"))
+    .await
+    .unwrap();
+  s.retract(gone.fact_id, None).await.unwrap();
+
+  let stale = s
+    .record_fact(email_fact(bob.subject_id, "old@example.org"))
+    .await
+    .unwrap();
+  s.supersede(stale.fact_id, email_fact(bob.subject_id, "new@example.org"))
+    .await
+    .unwrap();
+
+  let query = FactQuery {
+    text: Some("old@".into()),
+    ..Default::default()
+  };
+  assert_eq!(s.search(&query).await.unwrap().len(), 2);
+
+  let active = s
+    .search(&FactQuery {
+      active_only: true,
+      ..query
+    })
+    .await
+    .unwrap();
+  assert!(active.is_empty());
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("new@".into()),
+      active_only: true,
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 1);
+  assert_eq!(results[0].subject_id, bob.subject_id);
+}
+
+#[tokio::test]
+async fn search_filters_apply_to_same_fact() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  // "alice" appears only in the name fact; the email fact is tagged.
+  s.record_fact(name_fact(alice.subject_id)).await.unwrap();
+  let mut email = email_fact(alice.subject_id, "liddell@example.com");
+  email.tags = vec!["work".into()];
+  s.record_fact(email).await.unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("Alice".into()),
+      tags: vec!["work".into()],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(results.is_empty());
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("liddell@".into()),
+      fact_types: vec!["email".into()],
+      tags: vec!["work".into()],
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 1);
+}
+
+#[tokio::test]
+async fn search_text_treats_wildcards_literally() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.record_fact(email_fact(alice.subject_id, "alice@example.com"))
+    .await
+    .unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("a_ice".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert!(results.is_empty());
+}
+
+#[tokio::test]
+async fn search_limit_and_offset() {
+  let s = store().await;
+
+  for _ in 0..5 {
+    s.add_subject(SubjectKind::Person).await.unwrap();
+  }
+
+  let first = s
+    .search(&FactQuery {
+      limit: Some(2),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  let rest = s
+    .search(&FactQuery {
+      offset: Some(2),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(first.len(), 2);
+  assert_eq!(rest.len(), 3);
+  assert!(
+    first
+      .iter()
+      .all(|f| rest.iter().all(|r| r.subject_id != f.subject_id))
+  );
+}
+
+// ─── Full-text search ────────────────────────────────────────────────────────
+
+fn note_fact(subject_id: Uuid, text: &str) -> NewFact {
+  NewFact::new(subject_id, FactValue::Note(text.into()))
+}
+
+#[tokio::test]
+async fn search_text_ignores_json_structure() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.record_fact(email_fact(alice.subject_id, "alice@example.com"))
+    .await
+    .unwrap();
+
+  // "address" and "work" only appear as a JSON key and a label value.
+  for text in ["address", "label", "work"] {
+    let results = s
+      .search(&FactQuery {
+        text: Some(text.into()),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    assert!(results.is_empty(), "{text:?} should not match");
+  }
+}
+
+#[tokio::test]
+async fn search_text_prefix_and_phrase() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  s.record_fact(note_fact(alice.subject_id, "met at the climbing gym"))
+    .await
+    .unwrap();
+  s.record_fact(note_fact(bob.subject_id, "the gym near climbing walls"))
+    .await
+    .unwrap();
+
+  let search = |text: &str| FactQuery {
+    text: Some(text.into()),
+    ..Default::default()
+  };
+
+  // Whole tokens only, unless a prefix is requested.
+  assert!(s.search(&search("climb")).await.unwrap().is_empty());
+  assert_eq!(s.search(&search("climb*")).await.unwrap().len(), 2);
+
+  // Bare terms are AND-ed regardless of order; phrases are not.
+  assert_eq!(s.search(&search("gym climbing")).await.unwrap().len(), 2);
+  let phrase = s.search(&search("\"climbing gym\"")).await.unwrap();
+  assert_eq!(phrase.len(), 1);
+  assert_eq!(phrase[0].subject_id, alice.subject_id);
+}
+
+#[tokio::test]
+async fn search_text_matches_phone_digits() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.record_fact(NewFact::new(
+    alice.subject_id,
+    FactValue::Phone(PhoneValue {
+      number:     "+1 (555) 867-5309".into(),
+      label:      ContactLabel::Home,
+      kind:       PhoneKind::Cell,
+      preference: 1,
+    }),
+  ))
+  .await
+  .unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("15558675309".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(results.len(), 1);
+}
+
+#[tokio::test]
+async fn search_text_ignores_query_syntax() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.record_fact(note_fact(alice.subject_id, "works at acme"))
+    .await
+    .unwrap();
+
+  for text in ["acme OR", "-acme", "text:acme", "\"", "*", "(acme"] {
+    s.search(&FactQuery {
+      text: Some(text.into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap_or_else(|e| panic!("{text:?} failed: {e}"));
+  }
+}
+
+#[tokio::test]
+async fn search_text_without_terms_matches_nothing() {
+  let s = store().await;
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.record_fact(note_fact(alice.subject_id, "works at acme"))
+    .await
+    .unwrap();
+
+  for text in ["", "   ", "*", "\"\"", "!!!", "-- ."] {
+    let hits = s
+      .search(&FactQuery {
+        text: Some(text.into()),
+        ..Default::default()
+      })
+      .await
+      .unwrap();
+    assert!(hits.is_empty(), "{text:?} matched {hits:?}");
+  }
+}
+
+#[tokio::test]
+async fn search_text_ranks_by_relevance() {
+  let s = store().await;
+
+  let weak = s.add_subject(SubjectKind::Person).await.unwrap();
+  let strong = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  s.record_fact(note_fact(
+    weak.subject_id,
+    "once mentioned sailing in a long conversation about many other topics",
+  ))
+  .await
+  .unwrap();
+  s.record_fact(note_fact(strong.subject_id, "sailing, sailing, sailing"))
+    .await
+    .unwrap();
+
+  let results = s
+    .search(&FactQuery {
+      text: Some("sailing".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+
+  assert_eq!(results.len(), 2);
+  assert_eq!(results[0].subject_id, strong.subject_id);
+}
+
+// ─── Address books ───────────────────────────────────────────────────────────
+
+fn new_book(name: &str) -> NewAddressBook {
+  NewAddressBook {
+    name:         name.to_string(),
+    display_name: None,
+    description:  None,
+  }
+}
+
+async fn member_ids(s: &SqliteStore, addressbook_id: Uuid) -> Vec<Uuid> {
+  s.addressbook_members(addressbook_id)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|m| m.subject_id)
+    .collect()
+}
+
+#[tokio::test]
+async fn create_get_and_list_addressbooks() {
+  let s = store().await;
+  let work = s
+    .create_addressbook(NewAddressBook {
+      display_name: Some("Work".into()),
+      ..new_book("work")
+    })
+    .await
+    .unwrap();
+  s.create_addressbook(new_book("family")).await.unwrap();
+
+  let fetched = s.get_addressbook("work").await.unwrap().unwrap();
+  assert_eq!(fetched.addressbook_id, work.addressbook_id);
+  assert_eq!(fetched.display_name.as_deref(), Some("Work"));
+  assert!(!fetched.is_default);
+  assert!(s.get_addressbook("missing").await.unwrap().is_none());
+
+  let names: Vec<String> = s
+    .list_addressbooks()
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|b| b.name)
+    .collect();
+  assert_eq!(names, ["family", "work"]);
+}
+
+#[tokio::test]
+async fn duplicate_addressbook_name_errors() {
+  let s = store().await;
+  s.create_addressbook(new_book("work")).await.unwrap();
+  let err = s.create_addressbook(new_book("work")).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AddressBookExists(ref n) if n == "work"));
+}
+
+#[tokio::test]
+async fn membership_is_per_book() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let family = s.create_addressbook(new_book("family")).await.unwrap();
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  s.add_to_addressbook(work.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+  s.add_to_addressbook(family.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+  s.add_to_addressbook(family.addressbook_id, bob.subject_id)
+    .await
+    .unwrap();
+  // Adding twice is a no-op.
+  s.add_to_addressbook(work.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+
+  assert_eq!(
+    member_ids(&s, work.addressbook_id).await,
+    [alice.subject_id]
+  );
+  assert_eq!(member_ids(&s, family.addressbook_id).await, [
+    alice.subject_id,
+    bob.subject_id
+  ]);
+
+  s.remove_from_addressbook(family.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+  assert_eq!(
+    member_ids(&s, family.addressbook_id).await,
+    [bob.subject_id]
+  );
+  let books: Vec<String> = s
+    .subject_addressbooks(alice.subject_id)
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|b| b.name)
+    .collect();
+  assert_eq!(books, ["work"]);
+
+  // Removing a non-member is a no-op; unknown IDs are errors.
+  s.remove_from_addressbook(work.addressbook_id, bob.subject_id)
+    .await
+    .unwrap();
+  let err = s
+    .add_to_addressbook(Uuid::new_v4(), bob.subject_id)
+    .await
+    .unwrap_err();
+  assert!(matches!(err, crate::Error::AddressBookNotFound(_)));
+  let err = s
+    .add_to_addressbook(work.addressbook_id, Uuid::new_v4())
+    .await
+    .unwrap_err();
+  assert!(matches!(err, crate::Error::SubjectNotFound(_)));
+}
+
+#[tokio::test]
+async fn default_addressbook_holds_unassigned_people() {
+  let s = store().await;
+  let personal = s.create_addressbook(new_book("personal")).await.unwrap();
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  s.set_default_addressbook(personal.addressbook_id)
+    .await
+    .unwrap();
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.add_subject(SubjectKind::Organization).await.unwrap();
+
+  assert_eq!(member_ids(&s, personal.addressbook_id).await, [
+    alice.subject_id,
+    bob.subject_id
+  ]);
+  assert!(member_ids(&s, work.addressbook_id).await.is_empty());
+
+  // Assigning a person anywhere takes them out of the implicit set, and
+  // removing them again does not put them back.
+  s.add_to_addressbook(work.addressbook_id, bob.subject_id)
+    .await
+    .unwrap();
+  assert_eq!(member_ids(&s, personal.addressbook_id).await, [
+    alice.subject_id
+  ]);
+  s.remove_from_addressbook(work.addressbook_id, bob.subject_id)
+    .await
+    .unwrap();
+  assert!(
+    s.subject_addressbooks(bob.subject_id)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+
+  // Removing an implicit member from the default book sticks.
+  s.remove_from_addressbook(personal.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+  assert!(member_ids(&s, personal.addressbook_id).await.is_empty());
+}
+
+#[tokio::test]
+async fn set_default_addressbook_moves_the_flag() {
+  let s = store().await;
+  let a = s.create_addressbook(new_book("a")).await.unwrap();
+  let b = s.create_addressbook(new_book("b")).await.unwrap();
+
+  s.set_default_addressbook(a.addressbook_id).await.unwrap();
+  s.set_default_addressbook(b.addressbook_id).await.unwrap();
+
+  let defaults: Vec<String> = s
+    .list_addressbooks()
+    .await
+    .unwrap()
+    .into_iter()
+    .filter(|book| book.is_default)
+    .map(|book| book.name)
+    .collect();
+  assert_eq!(defaults, ["b"]);
+
+  let err = s.set_default_addressbook(Uuid::new_v4()).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AddressBookNotFound(_)));
+  assert!(s.get_addressbook("b").await.unwrap().unwrap().is_default);
+}
+
+#[tokio::test]
+async fn ctag_tracks_each_book_separately() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let family = s.create_addressbook(new_book("family")).await.unwrap();
+  assert!(
+    s.collection_ctag(work.addressbook_id)
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  s.add_to_addressbook(work.addressbook_id, alice.subject_id)
+    .await
+    .unwrap();
+  let joined = s.collection_ctag(work.addressbook_id).await.unwrap();
+  assert!(joined.is_some());
+  assert!(
+    s.collection_ctag(family.addressbook_id)
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  let fact = s.record_fact(name_fact(alice.subject_id)).await.unwrap();
+  let written = s.collection_ctag(work.addressbook_id).await.unwrap();
+  assert_eq!(written, Some(fact.recorded_at));
+  assert!(
+    s.collection_ctag(family.addressbook_id)
+      .await
+      .unwrap()
+      .is_none()
+  );
+
+  s.retract(fact.fact_id, None).await.unwrap();
+  let retracted = s.collection_ctag(work.addressbook_id).await.unwrap();
+  assert!(retracted > written);
+}
+
+// ─── Sync ────────────────────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn initial_sync_lists_visible_members_only() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+  let carol = s.add_subject(SubjectKind::Person).await.unwrap();
+  for p in [&alice, &bob, &carol] {
+    s.add_to_addressbook(work.addressbook_id, p.subject_id)
+      .await
+      .unwrap();
+  }
+  s.record_fact(name_fact(alice.subject_id)).await.unwrap();
+  let gone = s.record_fact(name_fact(bob.subject_id)).await.unwrap();
+  s.retract(gone.fact_id, None).await.unwrap();
+
+  let changes = s
+    .addressbook_changes(work.addressbook_id, None)
+    .await
+    .unwrap();
+  assert_eq!(changes.changed, [alice.subject_id]);
+  assert!(changes.removed.is_empty());
+  assert_eq!(changes.token, s.sync_token().await.unwrap());
+}
+
+#[tokio::test]
+async fn incremental_sync_reports_changes_and_removals() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let mut ids = Vec::new();
+  for _ in 0..4 {
+    let p = s.add_subject(SubjectKind::Person).await.unwrap();
+    s.add_to_addressbook(work.addressbook_id, p.subject_id)
+      .await
+      .unwrap();
+    s.record_fact(name_fact(p.subject_id)).await.unwrap();
+    ids.push(p.subject_id);
+  }
+  let (edited, retracted, removed, untouched) =
+    (ids[0], ids[1], ids[2], ids[3]);
+  let token = s.sync_token().await.unwrap();
+
+  let nothing = s
+    .addressbook_changes(work.addressbook_id, Some(token))
+    .await
+    .unwrap();
+  assert!(nothing.changed.is_empty() && nothing.removed.is_empty());
+  assert_eq!(nothing.token, token);
+
+  s.record_fact(email_fact(edited, "a@example.com"))
+    .await
+    .unwrap();
+  let facts = s.get_facts(retracted, None, None, false).await.unwrap();
+  s.retract(facts[0].fact.fact_id, None).await.unwrap();
+  s.remove_from_addressbook(work.addressbook_id, removed)
+    .await
+    .unwrap();
+
+  let changes = s
+    .addressbook_changes(work.addressbook_id, Some(token))
+    .await
+    .unwrap();
+  assert_eq!(changes.changed, [edited]);
+  let mut expected_removed = vec![retracted, removed];
+  expected_removed.sort();
+  assert_eq!(changes.removed, expected_removed);
+  assert!(!changes.changed.contains(&untouched));
+  assert!(token.is_at_or_before(&changes.token));
+  assert_ne!(changes.token, token);
+}
+
+#[tokio::test]
+async fn sync_reports_reinstated_subject_as_changed() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let p = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  s.add_to_addressbook(work.addressbook_id, p).await.unwrap();
+  let fact = s.record_fact(name_fact(p)).await.unwrap();
+
+  s.retract(fact.fact_id, None).await.unwrap();
+  let token = s.sync_token().await.unwrap();
+  let ctag = s.collection_ctag(work.addressbook_id).await.unwrap();
+  let rein = s.reinstate(fact.fact_id).await.unwrap();
+
+  let changes = s
+    .addressbook_changes(work.addressbook_id, Some(token))
+    .await
+    .unwrap();
+  assert_eq!(changes.changed, [p]);
+  assert!(changes.removed.is_empty());
+  assert_eq!(changes.token.reinstatements, token.reinstatements + 1);
+  assert_eq!(
+    s.collection_ctag(work.addressbook_id).await.unwrap(),
+    Some(rein.recorded_at)
+  );
+  assert!(ctag < Some(rein.recorded_at));
+}
+
+#[tokio::test]
+async fn sync_token_ahead_of_store_is_rejected() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let token = s.sync_token().await.unwrap();
+  let future = SyncToken {
+    facts: token.facts + 1,
+    ..token
+  };
+  let err = s
+    .addressbook_changes(work.addressbook_id, Some(future))
+    .await
+    .unwrap_err();
+  assert!(matches!(
+    err,
+    crate::Error::Core(kith_core::Error::InvalidSyncToken(_))
+  ));
+}
+
+#[test]
+fn sync_token_round_trips_through_text() {
+  let token = SyncToken {
+    facts:          12,
+    supersessions:  3,
+    retractions:    4,
+    memberships:    5,
+    merges:         6,
+    reinstatements: 7,
+  };
+  assert_eq!(token.to_string().parse::<SyncToken>().unwrap(), token);
+  assert!("12.3.4.5.6".parse::<SyncToken>().is_err());
+  assert!("12.3.4.5.6.7.8".parse::<SyncToken>().is_err());
+  assert!("1.2.3".parse::<SyncToken>().is_err());
+  assert!("1.2.3.-4".parse::<SyncToken>().is_err());
+}
+
+// ─── Change events ───────────────────────────────────────────────────────────
+
+fn event_names(events: &[kith_core::event::StoreEvent]) -> Vec<&'static str> {
+  events.iter().map(|e| e.change.name()).collect()
+}
+
+#[tokio::test]
+async fn writes_are_published_in_commit_order() {
+  let s = store().await;
+  let mut rx = s.subscribe();
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let old = s
+    .record_fact(email_fact(id, "old@example.com"))
+    .await
+    .unwrap();
+  let (_, new) = s
+    .supersede(old.fact_id, email_fact(id, "new@example.com"))
+    .await
+    .unwrap();
+  s.retract(new.fact_id, None).await.unwrap();
+
+  let events: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+  assert_eq!(event_names(&events), [
+    "fact-recorded",
+    "fact-recorded",
+    "fact-superseded",
+    "fact-retracted",
+  ]);
+  assert!(events.iter().all(|e| e.subject_id == id));
+  assert!(matches!(
+    &events[2].change,
+    Change::FactSuperseded { supersession }
+      if supersession.old_fact_id == old.fact_id
+  ));
+  assert_eq!(events[3].id, EventId {
+    facts:          2,
+    supersessions:  1,
+    retractions:    1,
+    reinstatements: 0,
+  });
+  for pair in events.windows(2) {
+    assert!(pair[0].id.is_at_or_before(&pair[1].id));
+    assert_ne!(pair[0].id, pair[1].id);
+  }
+}
+
+#[tokio::test]
+async fn failed_changesets_publish_nothing() {
+  let s = store().await;
+  let mut rx = s.subscribe();
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let err = s
+    .apply_changeset(Changeset {
+      new_facts: vec![name_fact(id)],
+      retractions: vec![(Uuid::new_v4(), None)],
+      ..Default::default()
+    })
+    .await;
+  assert!(err.is_err());
+  assert!(rx.try_recv().is_err());
+  assert!(
+    s.events_since(EventId::default(), 10)
+      .await
+      .unwrap()
+      .is_empty()
+  );
+}
+
+#[tokio::test]
+async fn events_since_replays_what_was_published() {
+  let s = store().await;
+  let mut rx = s.subscribe();
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let stale = s
+    .record_fact(email_fact(id, "old@example.com"))
+    .await
+    .unwrap();
+  let gone = s
+    .record_fact(email_fact(id, "gone@example.com"))
+    .await
+    .unwrap();
+  s.apply_changeset(Changeset {
+    new_facts: vec![name_fact(id)],
+    supersessions: vec![(stale.fact_id, email_fact(id, "new@example.com"))],
+    retractions: vec![(gone.fact_id, Some("bounced".into()))],
+    ..Default::default()
+  })
+  .await
+  .unwrap();
+
+  let live: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+  let replayed = s.events_since(EventId::default(), 100).await.unwrap();
+  assert_eq!(replayed.len(), 6);
+  assert_eq!(event_names(&replayed), event_names(&live));
+  let ids = |events: &[kith_core::event::StoreEvent]| {
+    events.iter().map(|e| e.id).collect::<Vec<_>>()
+  };
+  assert_eq!(ids(&replayed), ids(&live));
+
+  // Resuming from any event returns exactly the ones after it, a page at a
+  // time.
+  for (i, event) in replayed.iter().enumerate() {
+    let rest = s.events_since(event.id, 100).await.unwrap();
+    assert_eq!(ids(&rest), ids(&replayed[i + 1..]));
+    let page = s.events_since(event.id, 2).await.unwrap();
+    assert_eq!(ids(&page), ids(&replayed[i + 1..(i + 3).min(6)]));
+  }
+}
+
+#[tokio::test]
+async fn reinstatements_are_published_and_replayed() {
+  let s = store().await;
+  let mut rx = s.subscribe();
+  let subject = s.add_subject(SubjectKind::Person).await.unwrap();
+  let id = subject.subject_id;
+
+  let fact = s.record_fact(name_fact(id)).await.unwrap();
+  s.retract(fact.fact_id, None).await.unwrap();
+  s.reinstate(fact.fact_id).await.unwrap();
+
+  let live: Vec<_> = std::iter::from_fn(|| rx.try_recv().ok()).collect();
+  assert_eq!(event_names(&live), [
+    "fact-recorded",
+    "fact-retracted",
+    "fact-reinstated",
+  ]);
+  assert!(matches!(
+    &live[2].change,
+    Change::FactReinstated { reinstatement }
+      if reinstatement.fact_id == fact.fact_id
+  ));
+  assert_eq!(live[2].id, EventId {
+    facts:          1,
+    supersessions:  0,
+    retractions:    1,
+    reinstatements: 1,
+  });
+
+  let replayed = s.events_since(EventId::default(), 100).await.unwrap();
+  assert_eq!(event_names(&replayed), event_names(&live));
+  assert_eq!(replayed[2].id, live[2].id);
+  assert_eq!(s.events_since(live[1].id, 100).await.unwrap().len(), 1);
+}
+
+// ─── History ─────────────────────────────────────────────────────────────────
+
+/// A store with two people whose histories include every kind of record.
+async fn store_with_history() -> (SqliteStore, Uuid, Uuid) {
+  let s = store().await;
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+
+  let stale = s
+    .record_fact(email_fact(alice, "old@example.com"))
+    .await
+    .unwrap();
+  let gone = s.record_fact(name_fact(alice)).await.unwrap();
+  s.apply_changeset(Changeset {
+    new_facts: vec![email_fact(bob, "bob@example.com")],
+    supersessions: vec![(stale.fact_id, email_fact(alice, "a@example.com"))],
+    retractions: vec![(gone.fact_id, Some("typo".into()))],
+    ..Default::default()
+  })
+  .await
+  .unwrap();
+  (s, alice, bob)
+}
+
+fn document(history: &History) -> String {
+  history.to_jsonl(chrono::Utc::now()).unwrap()
+}
+
+#[tokio::test]
+async fn history_round_trips_between_stores() {
+  let (a, alice, _) = store_with_history().await;
+  let exported = a.history(None).await.unwrap();
+  assert_eq!(exported.subjects.len(), 2);
+  assert_eq!(exported.facts.len(), 4);
+
+  let b = store().await;
+  let mut rx = b.subscribe();
+  let report = history::import(&b, &document(&exported)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    subjects:       2,
+    addressbooks:   0,
+    facts:          4,
+    supersessions:  1,
+    retractions:    1,
+    reinstatements: 0,
+    memberships:    0,
+    merges:         0,
+    skipped:        0,
+  });
+  assert_eq!(std::iter::from_fn(|| rx.try_recv().ok()).count(), 6);
+
+  // Every record comes back with its original IDs and timestamps.
+  let at = chrono::Utc::now();
+  let imported = b.history(None).await.unwrap();
+  assert_eq!(
+    imported.to_jsonl(at).unwrap(),
+    exported.to_jsonl(at).unwrap()
+  );
+  // Statuses, and so every past and present view, come out the same.
+  let statuses = |facts: Vec<kith_core::lifecycle::ResolvedFact>| {
+    facts
+      .into_iter()
+      .map(|rf| (rf.fact.fact_id, format!("{:?}", rf.status)))
+      .collect::<Vec<_>>()
+  };
+  assert_eq!(
+    statuses(b.get_facts(alice, None, None, true).await.unwrap()),
+    statuses(a.get_facts(alice, None, None, true).await.unwrap()),
+  );
+}
+
+#[tokio::test]
+async fn subject_history_holds_only_that_subject() {
+  let (s, alice, bob) = store_with_history().await;
+
+  let history = s.history(Some(bob)).await.unwrap();
+  assert_eq!(history.subjects.len(), 1);
+  assert_eq!(history.facts.len(), 1);
+  assert!(history.supersessions.is_empty() && history.retractions.is_empty());
+
+  let history = s.history(Some(alice)).await.unwrap();
+  assert_eq!(history.facts.len(), 3);
+  assert_eq!(history.supersessions.len(), 1);
+  assert_eq!(history.retractions.len(), 1);
+
+  assert!(s.history(Some(Uuid::new_v4())).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn reimporting_skips_existing_records() {
+  let (a, alice, _) = store_with_history().await;
+  let whole = document(&a.history(None).await.unwrap());
+
+  let b = store().await;
+  history::import(&b, &document(&a.history(Some(alice)).await.unwrap()))
+    .await
+    .unwrap();
+  let report = history::import(&b, &whole).await.unwrap();
+  assert_eq!(report, ImportReport {
+    subjects: 1,
+    facts: 1,
+    skipped: 6,
+    ..Default::default()
+  });
+
+  let report = history::import(&b, &whole).await.unwrap();
+  assert_eq!(report, ImportReport {
+    skipped: 8,
+    ..Default::default()
+  });
+}
+
+#[tokio::test]
+async fn invalid_documents_write_nothing() {
+  let (a, alice, _) = store_with_history().await;
+  let b = store().await;
+
+  // Without its subject, alice's first fact dangles.
+  let doc = document(&a.history(Some(alice)).await.unwrap());
+  let mut lines: Vec<&str> = doc.lines().collect();
+  lines.remove(1);
+  let err = history::import(&b, &lines.join("\n")).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 2, .. }), "{err}");
+  assert!(b.history(None).await.unwrap().is_empty());
+
+  // A lifecycle event that contradicts the store.
+  let fact = b
+    .record_fact(name_fact(
+      b.add_subject(SubjectKind::Person).await.unwrap().subject_id,
+    ))
+    .await
+    .unwrap();
+  b.retract(fact.fact_id, None).await.unwrap();
+  let mut history = b.history(None).await.unwrap();
+  history.retractions[0].retraction_id = Uuid::new_v4();
+  history.retractions[0].reason = Some("again".into());
+  let err = history::import(&b, &document(&history)).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 4, .. }), "{err}");
+
+  let doc =
+    document(&History::default()).replace("\"version\":1", "\"version\":9");
+  assert!(matches!(
+    history::import(&b, &doc).await,
+    Err(ImportError::UnsupportedVersion(9))
+  ));
+  assert!(matches!(
+    history::import(&b, "{}").await,
+    Err(ImportError::Parse { line: 1, .. })
+  ));
+}
+
+#[tokio::test]
+async fn history_replays_reinstatements_in_order() {
+  let a = store().await;
+  let id = a.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let fact = a.record_fact(name_fact(id)).await.unwrap();
+  a.retract(fact.fact_id, Some("first".into())).await.unwrap();
+  a.reinstate(fact.fact_id).await.unwrap();
+  a.retract(fact.fact_id, Some("second".into()))
+    .await
+    .unwrap();
+  let exported = a.history(None).await.unwrap();
+  assert_eq!(exported.reinstatements.len(), 1);
+
+  let b = store().await;
+  let report = history::import(&b, &document(&exported)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    subjects: 1,
+    facts: 1,
+    retractions: 2,
+    reinstatements: 1,
+    ..Default::default()
+  });
+  let resolved = b.get_fact(fact.fact_id).await.unwrap().unwrap();
+  assert!(
+    matches!(&resolved.status, kith_core::lifecycle::FactStatus::Retracted { reason, .. }
+      if reason.as_deref() == Some("second"))
+  );
+
+  let report = history::import(&b, &document(&exported)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    skipped: 5,
+    ..Default::default()
+  });
+
+  // Reinstating a fact that is already active contradicts the store.
+  let mut history = exported.clone();
+  history.retractions.clear();
+  history.reinstatements[0].reinstatement_id = Uuid::new_v4();
+  let c = store().await;
+  let err = history::import(&c, &document(&history)).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 4, .. }), "{err}");
+}
+
+#[tokio::test]
+async fn status_follows_write_order_not_timestamps() {
+  let a = store().await;
+  let id = a.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let fact = a.record_fact(name_fact(id)).await.unwrap();
+  a.retract(fact.fact_id, Some("first".into())).await.unwrap();
+  let first = a.history(None).await.unwrap();
+  a.reinstate(fact.fact_id).await.unwrap();
+  a.retract(fact.fact_id, Some("second".into()))
+    .await
+    .unwrap();
+  let mut second = a.history(None).await.unwrap();
+  second.retractions.remove(0);
+  second.reinstatements.clear();
+
+  // b reinstates the fact after `second` was recorded in a, then imports it:
+  // the imported retraction is older than the reinstatement but written
+  // later, so it is the one that counts.
+  let b = store().await;
+  history::import(&b, &document(&first)).await.unwrap();
+  b.reinstate(fact.fact_id).await.unwrap();
+  history::import(&b, &document(&second)).await.unwrap();
+
+  let resolved = b.get_fact(fact.fact_id).await.unwrap().unwrap();
+  assert!(
+    matches!(&resolved.status, kith_core::lifecycle::FactStatus::Retracted { reason, .. }
+      if reason.as_deref() == Some("second"))
+  );
+  assert!(b.get_facts(id, None, None, false).await.unwrap().is_empty());
+  assert!(b.reinstate(fact.fact_id).await.is_ok());
+}
+
+#[tokio::test]
+async fn history_carries_membership_and_merges() {
+  let a = store().await;
+  let alice = a.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let bob = a.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let work = a.create_addressbook(new_book("work")).await.unwrap();
+  a.add_to_addressbook(work.addressbook_id, alice)
+    .await
+    .unwrap();
+  a.merge_subjects(alice, bob).await.unwrap();
+  let exported = a.history(None).await.unwrap();
+
+  // The target's own "work" book stands in for the exported one.
+  let b = store().await;
+  let b_work = b.create_addressbook(new_book("work")).await.unwrap();
+  let report = history::import(&b, &document(&exported)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    subjects: 2,
+    memberships: 1,
+    merges: 1,
+    skipped: 1,
+    ..Default::default()
+  });
+  assert_eq!(member_ids(&b, b_work.addressbook_id).await, vec![alice]);
+  let resolved = b.get_subject(bob).await.unwrap().unwrap();
+  assert_eq!(resolved.subject_id, alice);
+
+  let report = history::import(&b, &document(&exported)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    skipped: 5,
+    ..Default::default()
+  });
+
+  // One subject's history brings the subject it was merged into along.
+  let c = store().await;
+  let bob_history = a.history(Some(bob)).await.unwrap();
+  let report = history::import(&c, &document(&bob_history)).await.unwrap();
+  assert_eq!(report, ImportReport {
+    subjects: 2,
+    merges: 1,
+    ..Default::default()
+  });
+
+  // Undoing a merge that never happened contradicts the store.
+  let mut history = exported.clone();
+  history.merges[0].action = kith_core::lifecycle::MergeAction::Unmerge;
+  let err = history::import(&store().await, &document(&history))
+    .await
+    .unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 6, .. }), "{err}");
+}
+
+#[tokio::test]
+async fn import_rejects_records_that_differ_under_a_held_id() {
+  let (a, alice, _) = store_with_history().await;
+  let exported = a.history(Some(alice)).await.unwrap();
+  let b = store().await;
+  history::import(&b, &document(&exported)).await.unwrap();
+
+  let mut changed = exported.clone();
+  changed.subjects[0].kind = SubjectKind::Organization;
+  let err = history::import(&b, &document(&changed)).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 2, .. }), "{err}");
+
+  let mut changed = exported.clone();
+  changed.facts[0].tags = vec!["moved".into()];
+  let err = history::import(&b, &document(&changed)).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { line: 3, .. }), "{err}");
+
+  let mut changed = exported.clone();
+  changed.retractions[0].reason = Some("other".into());
+  let err = history::import(&b, &document(&changed)).await.unwrap_err();
+  assert!(matches!(err, ImportError::Invalid { .. }), "{err}");
+
+  // The same goes for two records in one document.
+  let doc = document(&exported);
+  let mut lines: Vec<String> = doc.lines().map(String::from).collect();
+  assert!(lines[2].contains(r#""tags":[]"#), "{}", lines[2]);
+  lines.push(lines[2].replace(r#""tags":[]"#, r#""tags":["moved"]"#));
+  let err = history::import(&store().await, &lines.join("\n"))
+    .await
+    .unwrap_err();
+  assert!(
+    matches!(&err, ImportError::Invalid { line, reason }
+      if *line == lines.len() && reason.contains("differs")),
+    "{err}"
+  );
+}
+
+// ─── Merges ──────────────────────────────────────────────────────────────────
+
+fn fact_ids(facts: &[kith_core::lifecycle::ResolvedFact]) -> Vec<Uuid> {
+  let mut ids: Vec<Uuid> = facts.iter().map(|rf| rf.fact.fact_id).collect();
+  ids.sort();
+  ids
+}
+
+#[tokio::test]
+async fn merged_subject_reads_through_survivor() {
+  let s = store().await;
+  let survivor = s.add_subject(SubjectKind::Person).await.unwrap();
+  let absorbed = s.add_subject(SubjectKind::Person).await.unwrap();
+  let name = s.record_fact(name_fact(survivor.subject_id)).await.unwrap();
+  let email = s
+    .record_fact(email_fact(absorbed.subject_id, "alice@example.com"))
+    .await
+    .unwrap();
+
+  let merge = s
+    .merge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+  assert_eq!(merge.action, MergeAction::Merge);
+
+  let resolved = s.get_subject(absorbed.subject_id).await.unwrap().unwrap();
+  assert_eq!(resolved.subject_id, survivor.subject_id);
+  let view = s
+    .materialize(absorbed.subject_id, None, None)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(view.subject.subject_id, survivor.subject_id);
+  let mut both = vec![name.fact_id, email.fact_id];
+  both.sort();
+  assert_eq!(fact_ids(&view.active_facts), both);
+
+  // The fact rows themselves are untouched.
+  let stored = s.get_fact(email.fact_id).await.unwrap().unwrap();
+  assert_eq!(stored.fact.subject_id, absorbed.subject_id);
+
+  let listed = s.list_subjects(None).await.unwrap();
+  assert_eq!(listed.len(), 1);
+  assert_eq!(listed[0].subject_id, survivor.subject_id);
+  let found = s
+    .search(&FactQuery {
+      text: Some("alice@example.com".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(found.len(), 1);
+  assert_eq!(found[0].subject_id, survivor.subject_id);
+}
+
+#[tokio::test]
+async fn unmerge_restores_both_subjects() {
+  let s = store().await;
+  let survivor = s.add_subject(SubjectKind::Person).await.unwrap();
+  let absorbed = s.add_subject(SubjectKind::Person).await.unwrap();
+  let name = s.record_fact(name_fact(survivor.subject_id)).await.unwrap();
+  let email = s
+    .record_fact(email_fact(absorbed.subject_id, "alice@example.com"))
+    .await
+    .unwrap();
+
+  let before_merge = chrono::Utc::now();
+  s.merge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+  let while_merged = chrono::Utc::now();
+  let unmerge = s
+    .unmerge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+  assert_eq!(unmerge.action, MergeAction::Unmerge);
+
+  let view = s
+    .materialize(absorbed.subject_id, None, None)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(view.subject.subject_id, absorbed.subject_id);
+  assert_eq!(fact_ids(&view.active_facts), [email.fact_id]);
+  let facts = s
+    .get_facts(survivor.subject_id, None, None, false)
+    .await
+    .unwrap();
+  assert_eq!(fact_ids(&facts), [name.fact_id]);
+  assert_eq!(s.list_subjects(None).await.unwrap().len(), 2);
+
+  // Past reads see the merges in force at the time.
+  let then = s
+    .materialize(absorbed.subject_id, Some(while_merged), None)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(then.subject.subject_id, survivor.subject_id);
+  assert_eq!(then.active_facts.len(), 2);
+  let earlier = s
+    .get_facts(absorbed.subject_id, Some(before_merge), None, false)
+    .await
+    .unwrap();
+  assert_eq!(fact_ids(&earlier), [email.fact_id]);
+
+  // A subject can be merged again once it has been split out.
+  s.merge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn merges_follow_chains() {
+  let s = store().await;
+  let root = s.add_subject(SubjectKind::Person).await.unwrap();
+  let middle = s.add_subject(SubjectKind::Person).await.unwrap();
+  let leaf = s.add_subject(SubjectKind::Person).await.unwrap();
+  let fact = s.record_fact(name_fact(leaf.subject_id)).await.unwrap();
+
+  s.merge_subjects(middle.subject_id, leaf.subject_id)
+    .await
+    .unwrap();
+  s.merge_subjects(root.subject_id, middle.subject_id)
+    .await
+    .unwrap();
+
+  let resolved = s.get_subject(leaf.subject_id).await.unwrap().unwrap();
+  assert_eq!(resolved.subject_id, root.subject_id);
+  let facts = s
+    .get_facts(root.subject_id, None, None, false)
+    .await
+    .unwrap();
+  assert_eq!(fact_ids(&facts), [fact.fact_id]);
+}
+
+#[tokio::test]
+async fn invalid_merges_are_rejected() {
+  let s = store().await;
+  let a = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let b = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let c = s.add_subject(SubjectKind::Person).await.unwrap().subject_id;
+  let nobody = Uuid::new_v4();
+
+  let err = s.merge_subjects(a, a).await.unwrap_err();
+  assert!(matches!(err, crate::Error::SelfMerge));
+  let err = s.merge_subjects(a, nobody).await.unwrap_err();
+  assert!(matches!(err, crate::Error::SubjectNotFound(id) if id == nobody));
+  let err = s.unmerge_subjects(a, b).await.unwrap_err();
+  assert!(matches!(err, crate::Error::NotMerged { .. }));
+
+  s.merge_subjects(a, b).await.unwrap();
+  // Neither side of a merge may already be absorbed.
+  let err = s.merge_subjects(c, b).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadyMerged(id) if id == b));
+  let err = s.merge_subjects(b, c).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadyMerged(id) if id == b));
+  let err = s.merge_subjects(b, a).await.unwrap_err();
+  assert!(matches!(err, crate::Error::AlreadyMerged(id) if id == b));
+  let err = s.unmerge_subjects(c, b).await.unwrap_err();
+  assert!(matches!(err, crate::Error::NotMerged { .. }));
+}
+
+#[tokio::test]
+async fn sync_reports_absorbed_subject_as_removed() {
+  let s = store().await;
+  let work = s.create_addressbook(new_book("work")).await.unwrap();
+  let survivor = s.add_subject(SubjectKind::Person).await.unwrap();
+  let absorbed = s.add_subject(SubjectKind::Person).await.unwrap();
+  for subject in [&survivor, &absorbed] {
+    s.add_to_addressbook(work.addressbook_id, subject.subject_id)
+      .await
+      .unwrap();
+    s.record_fact(name_fact(subject.subject_id)).await.unwrap();
+  }
+  let token = s.sync_token().await.unwrap();
+  let ctag = s.collection_ctag(work.addressbook_id).await.unwrap();
+
+  s.merge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+  let merged = s
+    .addressbook_changes(work.addressbook_id, Some(token))
+    .await
+    .unwrap();
+  assert_eq!(merged.changed, [survivor.subject_id]);
+  assert_eq!(merged.removed, [absorbed.subject_id]);
+  assert_eq!(member_ids(&s, work.addressbook_id).await, [
+    survivor.subject_id
+  ]);
+  assert_ne!(s.collection_ctag(work.addressbook_id).await.unwrap(), ctag);
+
+  // Later writes to the absorbed subject's facts change the survivor.
+  s.record_fact(email_fact(absorbed.subject_id, "alice@example.com"))
+    .await
+    .unwrap();
+  let edited = s
+    .addressbook_changes(work.addressbook_id, Some(merged.token))
+    .await
+    .unwrap();
+  assert_eq!(edited.changed, [survivor.subject_id]);
+
+  s.unmerge_subjects(survivor.subject_id, absorbed.subject_id)
+    .await
+    .unwrap();
+  let unmerged = s
+    .addressbook_changes(work.addressbook_id, Some(edited.token))
+    .await
+    .unwrap();
+  let mut both = vec![survivor.subject_id, absorbed.subject_id];
+  both.sort();
+  assert_eq!(unmerged.changed, both);
+  assert!(unmerged.removed.is_empty());
+}
+
+#[tokio::test]
+async fn duplicates_are_found_until_merged() {
+  let s = store().await;
+  let a = s.add_subject(SubjectKind::Person).await.unwrap();
+  let b = s.add_subject(SubjectKind::Person).await.unwrap();
+  for id in [a.subject_id, b.subject_id] {
+    s.record_fact(email_fact(id, "alice@example.com"))
+      .await
+      .unwrap();
+  }
+
+  let found = duplicates::find(&s, duplicates::DEFAULT_MIN_SCORE)
+    .await
+    .unwrap();
+  assert_eq!(found.len(), 1);
+  assert!(found[0].subject_ids.contains(&a.subject_id));
+  assert!(found[0].subject_ids.contains(&b.subject_id));
+
+  s.merge_subjects(a.subject_id, b.subject_id).await.unwrap();
+  let found = duplicates::find(&s, 0.0).await.unwrap();
+  assert!(found.is_empty());
+}
+
+// ─── CardDAV resources ───────────────────────────────────────────────────────
+
+fn new_resource(subject_id: Uuid, name: &str, uid: &str) -> NewResource {
+  NewResource {
+    subject_id,
+    name: name.to_string(),
+    uid: Some(uid.to_string()),
+  }
+}
+
+#[tokio::test]
+async fn resources_are_found_by_name_uid_and_subject() {
+  let s = store().await;
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let recorded = s
+    .record_resource(new_resource(
+      alice.subject_id,
+      "alice@example.com.vcf",
+      "alice@example.com",
+    ))
+    .await
+    .unwrap();
+
+  let by_name = s.resource_by_name("alice@example.com.vcf").await.unwrap();
+  let by_uid = s.resource_by_uid("alice@example.com").await.unwrap();
+  let by_subject = s.subject_resource(alice.subject_id).await.unwrap();
+  assert_eq!(by_name.as_ref(), Some(&recorded));
+  assert_eq!(by_uid.as_ref(), Some(&recorded));
+  assert_eq!(by_subject.as_ref(), Some(&recorded));
+  assert!(s.resource_by_name("bob.vcf").await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn resources_are_write_once_and_unique() {
+  let s = store().await;
+  let alice = s.add_subject(SubjectKind::Person).await.unwrap();
+  let bob = s.add_subject(SubjectKind::Person).await.unwrap();
+
+  let first = new_resource(alice.subject_id, "alice.vcf", "alice");
+  s.record_resource(first.clone()).await.unwrap();
+  // The same resource again is a no-op...
+  s.record_resource(first).await.unwrap();
+  // ...but renaming it is not.
+  let renamed = new_resource(alice.subject_id, "alice2.vcf", "alice");
+  assert!(s.record_resource(renamed).await.is_err());
+
+  // Neither a name nor a UID can be shared.
+  let same_name = new_resource(bob.subject_id, "alice.vcf", "bob");
+  assert!(s.record_resource(same_name).await.is_err());
+  let same_uid = new_resource(bob.subject_id, "bob.vcf", "alice");
+  assert!(s.record_resource(same_uid).await.is_err());
+  assert!(s.subject_resource(bob.subject_id).await.unwrap().is_none());
+
+  let nobody = new_resource(Uuid::new_v4(), "nobody.vcf", "nobody");
+  assert!(s.record_resource(nobody).await.is_err());
+}
+
+// ─── App passwords ───────────────────────────────────────────────────────────
+
+fn new_app_password(label: &str, secret: &str) -> NewAppPassword {
+  NewAppPassword {
+    label:       label.into(),
+    secret_hash: hash_secret(secret),
+  }
+}
+
+#[tokio::test]
+async fn app_password_use_stamps_last_used() {
+  let s = store().await;
+  let phone = s
+    .create_app_password(new_app_password("phone", "kith_phone"))
+    .await
+    .unwrap();
+  assert!(phone.is_active());
+  assert!(phone.last_used_at.is_none());
+
+  let used = s
+    .use_app_password(&hash_secret("kith_phone"))
+    .await
+    .unwrap()
+    .expect("active password");
+  assert_eq!(used.app_password_id, phone.app_password_id);
+  assert!(used.last_used_at.is_some());
+
+  assert!(
+    s.use_app_password(&hash_secret("kith_wrong"))
+      .await
+      .unwrap()
+      .is_none()
+  );
+}
+
+#[tokio::test]
+async fn revoked_app_passwords_stop_working() {
+  let s = store().await;
+  let phone = s
+    .create_app_password(new_app_password("phone", "kith_phone"))
+    .await
+    .unwrap();
+  s.create_app_password(new_app_password("laptop", "kith_laptop"))
+    .await
+    .unwrap();
+
+  let revoked = s
+    .revoke_app_password(phone.app_password_id)
+    .await
+    .unwrap()
+    .expect("password exists");
+  assert!(!revoked.is_active());
+  assert!(
+    s.use_app_password(&hash_secret("kith_phone"))
+      .await
+      .unwrap()
+      .is_none()
+  );
+  assert!(
+    s.use_app_password(&hash_secret("kith_laptop"))
+      .await
+      .unwrap()
+      .is_some()
+  );
+
+  // Revoking again keeps the original timestamp.
+  let again = s
+    .revoke_app_password(phone.app_password_id)
+    .await
+    .unwrap()
+    .unwrap();
+  assert_eq!(again.revoked_at, revoked.revoked_at);
+  assert!(s.revoke_app_password(Uuid::new_v4()).await.unwrap().is_none());
+
+  let labels: Vec<_> = s
+    .list_app_passwords()
+    .await
+    .unwrap()
+    .into_iter()
+    .map(|p| (p.is_active(), p.label))
+    .collect();
+  assert_eq!(
+    labels,
+    [(false, "phone".to_string()), (true, "laptop".to_string())]
+  );
+}
+
+// ─── Migrations ──────────────────────────────────────────────────────────────
+
+const FIXTURE_SUBJECT: &str = "6f1c1c36-8d0e-4d8a-9a53-1d2b0c7f0a01";
+
+/// Materialise a fixture SQL script into a fresh database file.
+fn fixture_db(name: &str, sql: &str) -> std::path::PathBuf {
+  let path = std::env::temp_dir()
+    .join(format!("kith-fixture-{name}-{}.db", Uuid::new_v4()));
+  rusqlite::Connection::open(&path)
+    .unwrap()
+    .execute_batch(sql)
+    .unwrap();
+  path
+}
+
+fn user_version(path: &std::path::Path) -> i64 {
+  rusqlite::Connection::open(path)
+    .unwrap()
+    .query_row("PRAGMA user_version", [], |r| r.get(0))
+    .unwrap()
+}
+
+/// Check that a migrated fixture behaves like a freshly created store.
+async fn assert_fixture_migrated(path: &std::path::Path) {
+  let s = SqliteStore::open(path).await.unwrap();
+  let subject_id = Uuid::parse_str(FIXTURE_SUBJECT).unwrap();
+
+  // v2: pre-existing facts are in the full-text index.
+  let hits = s
+    .search(&FactQuery {
+      text: Some("analytical".into()),
+      ..Default::default()
+    })
+    .await
+    .unwrap();
+  assert_eq!(hits.len(), 1);
+
+  // v3: pre-existing effective dates are queryable.
+  let during = s
+    .get_facts(subject_id, None, Some(utc(2020, 6, 30)), false)
+    .await
+    .unwrap();
+  let after = s
+    .get_facts(subject_id, None, Some(utc(2020, 7, 1)), false)
+    .await
+    .unwrap();
+  assert_eq!(during.len(), after.len() + 1);
+
+  // v4: pre-existing people land in the default book.
+  let book = s.create_addressbook(new_book("personal")).await.unwrap();
+  s.set_default_addressbook(book.addressbook_id)
+    .await
+    .unwrap();
+  assert_eq!(member_ids(&s, book.addressbook_id).await, [subject_id]);
+
+  // New writes land in every table.
+  let fact = s
+    .record_fact(email_fact(subject_id, "ada@example.com"))
+    .await
+    .unwrap();
+
+  // v9: the lineage of a new version reaches back to the original.
+  let (_, fact) = s
+    .supersede(fact.fact_id, email_fact(subject_id, "ada@example.org"))
+    .await
+    .unwrap();
+  assert_eq!(s.fact_lineage(fact.fact_id).await.unwrap().len(), 2);
+
+  // v8: a fact can be retracted again once reinstated.
+  s.retract(fact.fact_id, None).await.unwrap();
+  s.reinstate(fact.fact_id).await.unwrap();
+  s.retract(fact.fact_id, None).await.unwrap();
+
+  // v10: imported facts are found by their source.
+  s.record_fact(imported(name_fact(subject_id), "vCard"))
+    .await
+    .unwrap();
+  assert_eq!(
+    s.retract_import("vCard".into(), None).await.unwrap().len(),
+    1
+  );
+  drop(s);
+
+  assert_eq!(user_version(path), crate::schema::LATEST_VERSION);
+}
+
+#[tokio::test]
+async fn new_database_is_at_latest_version() {
+  let path =
+    std::env::temp_dir().join(format!("kith-fresh-{}.db", Uuid::new_v4()));
+  drop(SqliteStore::open(&path).await.unwrap());
+  assert_eq!(user_version(&path), crate::schema::LATEST_VERSION);
+
+  // Re-opening an up-to-date database is a no-op.
+  drop(SqliteStore::open(&path).await.unwrap());
+  assert_eq!(user_version(&path), crate::schema::LATEST_VERSION);
+  let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn migrates_v1_fixture() {
+  let path = fixture_db("v1", include_str!("../fixtures/v1.sql"));
+  assert_fixture_migrated(&path).await;
+  let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn migrates_v2_fixture() {
+  let path = fixture_db("v2", include_str!("../fixtures/v2.sql"));
+  assert_fixture_migrated(&path).await;
+  let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn failed_migration_leaves_previous_version() {
+  let path = fixture_db("v1-broken", include_str!("../fixtures/v1.sql"));
+  // A row the v2 backfill cannot decode.
+  rusqlite::Connection::open(&path)
+    .unwrap()
+    .execute(
+      "INSERT INTO facts (fact_id, subject_id, fact_type, value_json, \
+       recorded_at) VALUES ('broken', ?1, 'no_such_type', '{}', \
+       '2024-01-01T00:00:00+00:00')",
+      rusqlite::params![FIXTURE_SUBJECT],
+    )
+    .unwrap();
+
+  let err = SqliteStore::open(&path)
+    .await
+    .err()
+    .expect("open must fail");
+  assert!(
+    matches!(err, crate::Error::Migration { version: 2, .. }),
+    "unexpected error: {err}"
+  );
+  assert_eq!(user_version(&path), 1);
+
+  let tables: i64 = rusqlite::Connection::open(&path)
+    .unwrap()
+    .query_row(
+      "SELECT COUNT(*) FROM sqlite_master WHERE name = 'facts_fts'",
+      [],
+      |r| r.get(0),
+    )
+    .unwrap();
+  assert_eq!(tables, 0, "partial migration was not rolled back");
+  let _ = std::fs::remove_file(&path);
+}
+
+#[tokio::test]
+async fn refuses_newer_database() {
+  let path =
+    std::env::temp_dir().join(format!("kith-future-{}.db", Uuid::new_v4()));
+  let future = crate::schema::LATEST_VERSION + 1;
+  rusqlite::Connection::open(&path)
+    .unwrap()
+    .execute_batch(&format!("PRAGMA user_version = {future}"))
+    .unwrap();
+
+  let err = SqliteStore::open(&path)
+    .await
+    .err()
+    .expect("open must fail");
+  assert!(
+    matches!(
+      err,
+      crate::Error::SchemaTooNew { found, supported }
+        if found == future && supported == crate::schema::LATEST_VERSION
+    ),
+    "unexpected error: {err}"
+  );
+  assert_eq!(user_version(&path), future);
+  let _ = std::fs::remove_file(&path);
+}
@@ -16,6 +16,9 @@ pub enum Error {
   #[error("invalid IMPP URI: {0}")]
   InvalidImppUri(String),
 
+  #[error("invalid inline PHOTO: {0}")]
+  InvalidPhoto(String),
+
   #[error("JSON error: {0}")]
   Json(#[from] serde_json::Error),
 }
@@ -17,22 +17,85 @@ pub mod error;
 mod parse;
 mod serialize;
 
+use std::collections::HashMap;
+
 pub use error::{Error, Result};
 use kith_core::{fact::NewFact, lifecycle::ContactView};
+pub use parse::{ContentLine, Param};
 
 // ─── Public types
 // ─────────────────────────────────────────────────────────────
 
+/// A vCard format version.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Version {
+  /// vCard 3.0 (RFC 2426).
+  V3,
+  /// vCard 4.0 (RFC 6350).
+  #[default]
+  V4,
+}
+
+impl Version {
+  /// The `VERSION` property value, e.g. `"4.0"`.
+  pub fn as_str(self) -> &'static str {
+    match self {
+      Version::V3 => "3.0",
+      Version::V4 => "4.0",
+    }
+  }
+
+  /// Parse a `VERSION` value; `None` for versions this codec cannot write.
+  pub fn parse(s: &str) -> Option<Self> {
+    match s.trim() {
+      "3.0" => Some(Version::V3),
+      "4.0" => Some(Version::V4),
+      _ => None,
+    }
+  }
+}
+
 /// The result of parsing a single vCard.
 ///
 /// All `facts[*].subject_id` are [`uuid::Uuid::nil()`]; the caller must
 /// replace them with the real subject UUID before persisting.
+#[derive(Debug, Clone)]
 pub struct ParsedVcard {
   /// The `UID` property from the vCard, if present.
-  pub uid:   Option<String>,
+  pub uid:     Option<String>,
+  /// The `VERSION` property, if present and one this codec can write.
+  pub version: Option<Version>,
   /// Facts decoded from the vCard properties.
   /// All use `RecordingContext::Imported { source_name, original_uid: uid }`.
-  pub facts: Vec<NewFact>,
+  pub facts:   Vec<NewFact>,
+  /// Inline (base64) `PHOTO` values, decoded. These produce no facts: the
+  /// caller stores the bytes and records a `Photo` fact pointing at them.
+  pub photos:  Vec<InlinePhoto>,
+}
+
+/// Image data embedded in a vCard `PHOTO` property.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InlinePhoto {
+  /// A lower-cased `type/subtype`, e.g. `image/jpeg`.
+  pub media_type: String,
+  pub data:       Vec<u8>,
+}
+
+/// Photo bytes to embed when serializing, keyed by
+/// [`PhotoValue::content_hash`].
+///
+/// [`PhotoValue::content_hash`]: kith_core::fact::PhotoValue::content_hash
+pub type PhotoData = HashMap<String, Vec<u8>>;
+
+/// What a serialized vCard carries beyond the facts of its view.
+#[derive(Debug, Clone, Default)]
+pub struct SerializeOptions {
+  /// The `UID` to write, e.g. the one a client chose; the subject id if
+  /// `None`.
+  pub uid:    Option<String>,
+  /// Bytes of the view's photos. `Photo` facts whose bytes are missing are
+  /// left out.
+  pub photos: PhotoData,
 }
 
 // ─── Public API
@@ -80,15 +143,40 @@ pub fn parse_many(input: &str, source_name: &str) -> Vec<Result<ParsedVcard>> {
   results
 }
 
+/// Split a single vCard into its content lines, without mapping them to
+/// facts. Useful for matching against properties as a client sees them.
+pub fn content_lines(input: &str) -> Result<Vec<ContentLine>> {
+  parse::content_lines(input)
+}
+
 /// Serialize `view` as a vCard 4.0 string (CRLF line endings, folded at 75
 /// octets).
 pub fn serialize(view: &ContactView) -> Result<String> {
-  serialize::serialize(view)
+  serialize::serialize(view, &SerializeOptions::default())
 }
 
 /// Serialize `view` as a vCard 3.0 string.
 pub fn serialize_v3(view: &ContactView) -> Result<String> {
-  serialize::serialize_v3(view)
+  serialize::serialize_v3(view, &SerializeOptions::default())
+}
+
+/// Serialize `view` as a vCard of the given version.
+pub fn serialize_as(view: &ContactView, version: Version) -> Result<String> {
+  serialize_with(view, version, &SerializeOptions::default())
+}
+
+/// Serialize `view` as a vCard of the given version, with the `UID` and
+/// photo bytes from `options`. Photos are embedded as a `data:` URI in 4.0
+/// and with `ENCODING=b` in 3.0.
+pub fn serialize_with(
+  view: &ContactView,
+  version: Version,
+  options: &SerializeOptions,
+) -> Result<String> {
+  match version {
+    Version::V3 => serialize::serialize_v3(view, options),
+    Version::V4 => serialize::serialize(view, options),
+  }
 }
 
 // ─── Round-trip test ─────────────────────────────────────────────────────────
@@ -97,7 +185,8 @@ pub fn serialize_v3(view: &ContactView) -> Result<String> {
 mod roundtrip_tests {
   use kith_core::fact::{
     AddressValue, ContactLabel, EmailValue, FactValue, NameValue,
-    OrgMembershipValue, PhoneKind, PhoneValue, RelationshipValue, SocialValue,
+    OrgMembershipValue, PhoneKind, PhoneValue, PhotoValue, RelationshipValue,
+    SocialValue,
   };
   use uuid::Uuid;
 
@@ -241,6 +330,50 @@ mod roundtrip_tests {
     assert_eq!(r.relation, "colleague");
     assert_eq!(r.other_id, Some(other_id));
   }
+
+  #[test]
+  fn photo_round_trip() {
+    let data = b"\x89PNG\r\n\x1a\nnot really a png".to_vec();
+    let view = make_view(vec![FactValue::Photo(PhotoValue {
+      path:         "subject/abc.png".to_string(),
+      content_hash: "abc".to_string(),
+      media_type:   "image/png".to_string(),
+    })]);
+    let options = SerializeOptions {
+      uid:    None,
+      photos: PhotoData::from([("abc".to_string(), data.clone())]),
+    };
+
+    for version in [Version::V3, Version::V4] {
+      let vcard = serialize_with(&view, version, &options).unwrap();
+      let parsed = parse(&vcard, "roundtrip").unwrap();
+      assert_eq!(parsed.photos, vec![InlinePhoto {
+        media_type: "image/png".to_string(),
+        data:       data.clone(),
+      }]);
+    }
+
+    // Without the bytes there is nothing worth writing.
+    let vcard = serialize(&view).unwrap();
+    assert!(!vcard.contains("PHOTO"));
+  }
+
+  #[test]
+  fn client_uid_round_trips() {
+    let view = make_view(vec![FactValue::Note("hi".to_string())]);
+    let options = SerializeOptions {
+      uid:    Some("{alice@example.com}".to_string()),
+      photos: PhotoData::new(),
+    };
+    for version in [Version::V3, Version::V4] {
+      let vcard = serialize_with(&view, version, &options).unwrap();
+      let parsed = parse(&vcard, "roundtrip").unwrap();
+      assert_eq!(parsed.uid, options.uid);
+    }
+    let vcard = serialize(&view).unwrap();
+    let parsed = parse(&vcard, "roundtrip").unwrap();
+    assert_eq!(parsed.uid, Some(view.subject.subject_id.to_string()));
+  }
 }
 
 // ─── Shared test helpers ──────────────────────────────────────────────────────
@@ -288,6 +421,7 @@ pub(crate) mod test_helpers {
     ContactView {
       subject,
       as_of,
+      valid_at: None,
       active_facts,
     }
   }
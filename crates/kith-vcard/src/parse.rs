@@ -7,6 +7,13 @@
 //!               └─ map_property()  → accumulate facts
 //!                    └─ flush accumulators → Vec<NewFact>
 
+use base64::{
+  Engine as _,
+  engine::{
+    DecodePaddingMode,
+    general_purpose::{GeneralPurpose, GeneralPurposeConfig},
+  },
+};
 use chrono::NaiveDate;
 use kith_core::fact::{
   AddressValue, AliasValue, ContactLabel, EmailValue, FactValue,
@@ -17,21 +24,35 @@ use kith_core::fact::{
 use uuid::Uuid;
 
 use crate::{
-  ParsedVcard,
+  InlinePhoto, ParsedVcard, Version,
   error::{Error, Result},
 };
 
 // ─── Content-line representation ─────────────────────────────────────────────
 
-struct ContentLine {
-  name:   String,
-  params: Vec<Param>,
-  value:  String,
+/// One `NAME;PARAM=VALUE:value` line of a vCard, after unfolding.
+#[derive(Debug, Clone)]
+pub struct ContentLine {
+  /// Upper-cased property name, with any group prefix (`ORG1.`) stripped.
+  pub name:   String,
+  pub params: Vec<Param>,
+  /// The raw value, still escaped. See [`ContentLine::text`].
+  pub value:  String,
+}
+
+/// A property parameter. Bare vCard 3.0 tokens (`TEL;CELL:…`) are read as
+/// `TYPE` parameters.
+#[derive(Debug, Clone)]
+pub struct Param {
+  /// Upper-cased parameter name.
+  pub name:  String,
+  pub value: String,
 }
 
-struct Param {
-  name:  String,
-  value: String,
+impl ContentLine {
+  /// The value with backslash escapes resolved. Structured values (`N`,
+  /// `ADR`) keep their `;` component separators.
+  pub fn text(&self) -> String { unescape_value(&self.value) }
 }
 
 // ─── Low-level helpers
@@ -229,6 +250,29 @@ fn parse_content_line(line: &str) -> Result<ContentLine> {
   })
 }
 
+/// The content lines between the first `BEGIN:VCARD` and the last
+/// `END:VCARD`, skipping malformed lines as [`parse_one`] does.
+pub fn content_lines(input: &str) -> Result<Vec<ContentLine>> {
+  let lines = unfold_lines(input);
+  let start = lines
+    .iter()
+    .position(|l| l.eq_ignore_ascii_case("BEGIN:VCARD"))
+    .ok_or(Error::MissingEnvelope)?;
+  let end = lines
+    .iter()
+    .rposition(|l| l.eq_ignore_ascii_case("END:VCARD"))
+    .ok_or(Error::MissingEnvelope)?;
+  if end <= start {
+    return Err(Error::MissingEnvelope);
+  }
+  Ok(
+    lines[start + 1..end]
+      .iter()
+      .filter_map(|l| parse_content_line(l).ok())
+      .collect(),
+  )
+}
+
 // ─── Accumulators
 // ─────────────────────────────────────────────────────────────
 
@@ -348,6 +392,100 @@ fn scheme_to_service(scheme: &str) -> String {
   }
 }
 
+// ─── Inline photos
+// ────────────────────────────────────────────────────────────
+
+/// Base64 as clients actually send it: standard alphabet, padding optional.
+const LENIENT_BASE64: GeneralPurpose = GeneralPurpose::new(
+  &base64::alphabet::STANDARD,
+  GeneralPurposeConfig::new()
+    .with_decode_padding_mode(DecodePaddingMode::Indifferent),
+);
+
+/// Decode an inline PHOTO: `ENCODING=b` / `ENCODING=BASE64` (3.0) or a
+/// base64 `data:` URI (4.0). `None` for a URI reference or an empty value.
+fn inline_photo(params: &[Param], value: &str) -> Result<Option<InlinePhoto>> {
+  let is_base64 = params.iter().any(|p| {
+    p.name.eq_ignore_ascii_case("ENCODING")
+      && (p.value.eq_ignore_ascii_case("BASE64")
+        || p.value.eq_ignore_ascii_case("b"))
+  });
+
+  let (declared, encoded) = if is_base64 {
+    let declared = params
+      .iter()
+      .find(|p| {
+        p.name.eq_ignore_ascii_case("TYPE")
+          || p.name.eq_ignore_ascii_case("MEDIATYPE")
+      })
+      .map(|p| p.value.as_str());
+    (declared, value)
+  } else if value
+    .get(..5)
+    .is_some_and(|scheme| scheme.eq_ignore_ascii_case("data:"))
+  {
+    let (header, encoded) = value[5..]
+      .split_once(',')
+      .ok_or_else(|| Error::InvalidPhoto("data URI without data".into()))?;
+    let mut header = header.split(';');
+    let media_type = header.next().filter(|m| !m.is_empty());
+    if !header.any(|p| p.eq_ignore_ascii_case("base64")) {
+      return Err(Error::InvalidPhoto(
+        "only base64 data URIs are supported".into(),
+      ));
+    }
+    (media_type, encoded)
+  } else {
+    return Ok(None);
+  };
+
+  let encoded: String = encoded
+    .chars()
+    .filter(|c| !c.is_ascii_whitespace())
+    .collect();
+  let data = LENIENT_BASE64
+    .decode(encoded)
+    .map_err(|e| Error::InvalidPhoto(e.to_string()))?;
+  if data.is_empty() {
+    return Ok(None);
+  }
+  let media_type = declared
+    .and_then(photo_media_type)
+    .unwrap_or_else(|| sniff_media_type(&data).to_string());
+  Ok(Some(InlinePhoto { media_type, data }))
+}
+
+/// Normalise a declared image type, either a full `image/png` or a bare 3.0
+/// `PNG`. `None` for anything but a plain `type/subtype` token.
+fn photo_media_type(declared: &str) -> Option<String> {
+  let declared = declared.trim().to_ascii_lowercase();
+  let media_type = if declared.contains('/') {
+    declared
+  } else {
+    format!("image/{declared}")
+  };
+  let plain = media_type.split_once('/').is_some_and(|(ty, sub)| {
+    !ty.is_empty() && !sub.is_empty() && !sub.contains('/')
+  }) && media_type
+    .chars()
+    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '+' | '.' | '-'));
+  plain.then_some(media_type)
+}
+
+/// Guess an image type from its magic bytes, defaulting to JPEG.
+fn sniff_media_type(data: &[u8]) -> &'static str {
+  if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+    "image/png"
+  } else if data.starts_with(b"GIF8") {
+    "image/gif"
+  } else if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP"
+  {
+    "image/webp"
+  } else {
+    "image/jpeg"
+  }
+}
+
 // ─── Core parser ─────────────────────────────────────────────────────────────
 
 /// Parse a single vCard from `input`.
@@ -355,31 +493,16 @@ fn scheme_to_service(scheme: &str) -> String {
 /// All returned [`NewFact`]s have `subject_id = Uuid::nil()`; the caller must
 /// replace this with the real subject UUID before persisting.
 pub fn parse_one(input: &str, source_name: &str) -> Result<ParsedVcard> {
-  let lines = unfold_lines(input);
-
-  let start = lines
-    .iter()
-    .position(|l| l.eq_ignore_ascii_case("BEGIN:VCARD"))
-    .ok_or(Error::MissingEnvelope)?;
-  let end = lines
-    .iter()
-    .rposition(|l| l.eq_ignore_ascii_case("END:VCARD"))
-    .ok_or(Error::MissingEnvelope)?;
-  if end <= start {
-    return Err(Error::MissingEnvelope);
-  }
+  let lines = content_lines(input)?;
 
   let mut uid: Option<String> = None;
+  let mut version: Option<Version> = None;
   let mut name_accum = NameAccum::default();
   let mut org_groups: Vec<OrgGroup> = Vec::new();
   let mut facts: Vec<FactValue> = Vec::new();
+  let mut photos: Vec<InlinePhoto> = Vec::new();
 
-  for line in &lines[start + 1..end] {
-    let cl = match parse_content_line(line) {
-      Ok(cl) => cl,
-      Err(_) => continue, // skip malformed lines
-    };
-
+  for cl in &lines {
     // Apply ENCODING=QUOTED-PRINTABLE if present
     let value = {
       let is_qp = cl.params.iter().any(|p| {
@@ -399,7 +522,9 @@ pub fn parse_one(input: &str, source_name: &str) -> Result<ParsedVcard> {
 
     match cl.name.as_str() {
       // ── Skip envelope / meta ──────────────────────────────────────────────
-      "VERSION" | "PRODID" | "REV" | "KIND" | "CATEGORIES" => {}
+      "PRODID" | "REV" | "KIND" | "CATEGORIES" => {}
+
+      "VERSION" => version = Version::parse(&value),
 
       "UID" => uid = opt_str(&value),
 
@@ -623,15 +748,11 @@ pub fn parse_one(input: &str, source_name: &str) -> Result<ParsedVcard> {
         }
       }
       "PHOTO" => {
-        let is_base64 = cl.params.iter().any(|p| {
-          p.name.eq_ignore_ascii_case("ENCODING")
-            && (p.value.eq_ignore_ascii_case("BASE64")
-              || p.value.eq_ignore_ascii_case("b"))
-        });
-        if !is_base64
-          && (value.starts_with("http")
-            || value.starts_with("file://")
-            || value.starts_with("cid:"))
+        if let Some(photo) = inline_photo(&cl.params, &value)? {
+          photos.push(photo);
+        } else if value.starts_with("http")
+          || value.starts_with("file://")
+          || value.starts_with("cid:")
         {
           let uri = value.trim().to_string();
           if !uri.is_empty() {
@@ -641,7 +762,6 @@ pub fn parse_one(input: &str, source_name: &str) -> Result<ParsedVcard> {
             });
           }
         }
-        // base64 photos silently dropped
       }
 
       // ── IM ────────────────────────────────────────────────────────────────
@@ -805,7 +925,9 @@ pub fn parse_one(input: &str, source_name: &str) -> Result<ParsedVcard> {
 
   Ok(ParsedVcard {
     uid,
+    version,
     facts: new_facts,
+    photos,
   })
 }
 
@@ -901,6 +1023,7 @@ mod tests {
     };
     assert_eq!(p.preference, 1);
     assert_eq!(p.label, ContactLabel::Work);
+    assert_eq!(card.version, Some(Version::V3));
   }
 
   // ── EMAIL preference roundtrip
@@ -1020,6 +1143,46 @@ mod tests {
     assert_eq!(orgs[1].org_name, "OSF");
   }
 
+  // ── PHOTO ───────────────────────────────────────────────────────────────────
+
+  #[test]
+  fn photo_v3_base64_decoded() {
+    #[rustfmt::skip]
+    let input = "BEGIN:VCARD\r\nVERSION:3.0\r\nPHOTO;ENCODING=b;TYPE=PNG:iVBO\r\n Rw0KGgo=\r\nEND:VCARD\r\n";
+    let card = parse_one(input, "test").unwrap();
+    assert!(card.facts.is_empty());
+    assert_eq!(card.photos, vec![InlinePhoto {
+      media_type: "image/png".to_string(),
+      data:       b"\x89PNG\r\n\x1a\n".to_vec(),
+    }]);
+  }
+
+  #[test]
+  fn photo_v4_data_uri_decoded() {
+    #[rustfmt::skip]
+    let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nPHOTO:data:image/jpeg;base64,/9j/4A\r\nEND:VCARD\r\n";
+    let card = parse_one(input, "test").unwrap();
+    assert_eq!(card.photos.len(), 1);
+    assert_eq!(card.photos[0].media_type, "image/jpeg");
+    assert_eq!(card.photos[0].data, [0xff, 0xd8, 0xff, 0xe0]);
+  }
+
+  #[test]
+  fn photo_type_sniffed_when_undeclared() {
+    #[rustfmt::skip]
+    let input = "BEGIN:VCARD\r\nVERSION:3.0\r\nPHOTO;ENCODING=BASE64:R0lGODlh\r\nEND:VCARD\r\n";
+    let card = parse_one(input, "test").unwrap();
+    assert_eq!(card.photos[0].media_type, "image/gif");
+  }
+
+  #[test]
+  fn photo_invalid_base64_is_error() {
+    let input =
+      "BEGIN:VCARD\r\nVERSION:3.0\r\nPHOTO;ENCODING=b:!!!\r\nEND:VCARD\r\n";
+    let r = parse_one(input, "test");
+    assert!(matches!(r, Err(Error::InvalidPhoto(_))));
+  }
+
   // ── IMPP ────────────────────────────────────────────────────────────────────
 
   #[test]
@@ -1155,6 +1318,21 @@ mod tests {
     }
   }
 
+  // ── content_lines
+  // ────────────────────────────────────────────────────────────
+
+  #[test]
+  fn content_lines_strip_groups_and_unescape() {
+    let input = "BEGIN:VCARD\r\nVERSION:4.0\r\nORG1.ORG:Acme\\, \
+                 Inc.\r\nTEL;TYPE=work,voice:+1555\r\nEND:VCARD\r\n";
+    let lines = content_lines(input).unwrap();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[1].name, "ORG");
+    assert_eq!(lines[1].text(), "Acme, Inc.");
+    assert_eq!(lines[2].params[0].name, "TYPE");
+    assert_eq!(lines[2].params[0].value, "work,voice");
+  }
+
   // ── parse_many
   // ───────────────────────────────────────────────────────────────
 
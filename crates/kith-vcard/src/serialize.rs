@@ -2,13 +2,14 @@
 //!
 //! Produces CRLF line endings and folds at 75 octets per RFC 6350 §3.2.
 
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use kith_core::{
-  fact::{ContactLabel, FactValue, PhoneKind, UrlContext},
+  fact::{ContactLabel, FactValue, PhoneKind, PhotoValue, UrlContext},
   lifecycle::ContactView,
   subject::SubjectKind,
 };
 
-use crate::error::Result;
+use crate::{SerializeOptions, error::Result};
 
 // ─── RFC 6350 line folding
 // ────────────────────────────────────────────────────
@@ -139,10 +140,33 @@ fn service_to_x_prop(service: &str) -> &'static str {
   }
 }
 
+// ─── PHOTO
+// ───────────────────────────────────────────────────────────────────
+
+/// An inline PHOTO property: a `data:` URI in v4, `ENCODING=b` with the
+/// image subtype as `TYPE` in v3.
+fn photo_line(photo: &PhotoValue, data: &[u8], v4: bool) -> String {
+  let encoded = BASE64.encode(data);
+  if v4 {
+    format!("PHOTO:data:{};base64,{}", photo.media_type, encoded)
+  } else {
+    let subtype = photo
+      .media_type
+      .split_once('/')
+      .map_or(photo.media_type.as_str(), |(_, sub)| sub);
+    let ty = subtype.to_uppercase();
+    format!("PHOTO;ENCODING=b;TYPE={ty}:{encoded}")
+  }
+}
+
 // ─── Inner serializer (shared between v3 / v4)
 // ────────────────────────────────
 
-fn serialize_body(view: &ContactView, v4: bool) -> Result<String> {
+fn serialize_body(
+  view: &ContactView,
+  v4: bool,
+  photos: &crate::PhotoData,
+) -> Result<String> {
   let facts: Vec<&FactValue> =
     view.active_facts.iter().map(|rf| &rf.fact.value).collect();
 
@@ -204,7 +228,11 @@ fn serialize_body(view: &ContactView, v4: bool) -> Result<String> {
       }
 
       FactValue::Photo(p) => {
-        lines.push(fold_line(&format!("PHOTO;VALUE=URI:{}", p.path)));
+        // A path under `photo_dir` means nothing to a client; without the
+        // bytes there is no useful PHOTO to write.
+        if let Some(data) = photos.get(&p.content_hash) {
+          lines.push(fold_line(&photo_line(p, data, v4)));
+        }
       }
 
       FactValue::Birthday(d) => {
@@ -426,8 +454,20 @@ fn serialize_body(view: &ContactView, v4: bool) -> Result<String> {
 // ─── Public API
 // ───────────────────────────────────────────────────────────────
 
+/// The `UID` line: the client's own UID if it has one, else the subject id.
+fn uid_line(view: &ContactView, options: &SerializeOptions) -> String {
+  let uid = match &options.uid {
+    Some(uid) => uid.clone(),
+    None => view.subject.subject_id.to_string(),
+  };
+  fold_line(&format!("UID:{uid}"))
+}
+
 /// Serialize `view` as a vCard 4.0 string.
-pub fn serialize(view: &ContactView) -> Result<String> {
+pub fn serialize(
+  view: &ContactView,
+  options: &SerializeOptions,
+) -> Result<String> {
   let kind_str = match view.subject.kind {
     SubjectKind::Person => "individual",
     SubjectKind::Organization => "org",
@@ -438,27 +478,30 @@ pub fn serialize(view: &ContactView) -> Result<String> {
   let mut out = String::new();
   out.push_str("BEGIN:VCARD\r\n");
   out.push_str("VERSION:4.0\r\n");
-  out.push_str(&fold_line(&format!("UID:{}", view.subject.subject_id)));
+  out.push_str(&uid_line(view, options));
   out.push_str("PRODID:-//Kith//Kith vCard//EN\r\n");
   out.push_str(&fold_line(&format!("REV:{}", rev)));
   out.push_str(&fold_line(&format!("KIND:{}", kind_str)));
-  out.push_str(&serialize_body(view, true)?);
+  out.push_str(&serialize_body(view, true, &options.photos)?);
   out.push_str("END:VCARD\r\n");
   Ok(out)
 }
 
 /// Serialize `view` as a vCard 3.0 string.
-pub fn serialize_v3(view: &ContactView) -> Result<String> {
+pub fn serialize_v3(
+  view: &ContactView,
+  options: &SerializeOptions,
+) -> Result<String> {
   let rev = view.as_of.format("%Y%m%dT%H%M%SZ").to_string();
 
   let mut out = String::new();
   out.push_str("BEGIN:VCARD\r\n");
   out.push_str("VERSION:3.0\r\n");
-  out.push_str(&fold_line(&format!("UID:{}", view.subject.subject_id)));
+  out.push_str(&uid_line(view, options));
   out.push_str("PRODID:-//Kith//Kith vCard//EN\r\n");
   out.push_str(&fold_line(&format!("REV:{}", rev)));
   // KIND is omitted in vCard 3.0
-  out.push_str(&serialize_body(view, false)?);
+  out.push_str(&serialize_body(view, false, &options.photos)?);
   out.push_str("END:VCARD\r\n");
   Ok(out)
 }
@@ -474,7 +517,8 @@ mod tests {
   };
 
   use crate::test_helpers::make_view;
-  use super::*;
+  // The public wrappers, which serialize without options.
+  use crate::{serialize, serialize_v3};
 
   // ── Envelope
   // ────────────────────────────────────────────────────────────────